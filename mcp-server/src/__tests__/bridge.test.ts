@@ -38,6 +38,7 @@ class MockRuneBridge {
   async getStats() {
     return JSON.stringify({
       indexed_files: 150,
+      tantivy_documents: 150,
       total_symbols: 750,
       index_size_bytes: 2048000,
       cache_size_bytes: 1024000,
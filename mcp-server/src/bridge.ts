@@ -11,8 +11,12 @@ export interface RuneBridgeInstance {
   start(): Promise<void>;
   stop(): Promise<void>;
   search(queryJson: string): Promise<string>;
+  nextPage(token: string, pageBytes: number): Promise<string>;
+  parseQuery(query: string): string;
   getStats(): Promise<string>;
-  reindex(): Promise<void>;
+  getEmbeddingStatus(): Promise<string>;
+  reindex(): Promise<string>;
+  indexNow(path: string): Promise<void>;
 }
 
 export interface RuneBridgeConstructor {
@@ -91,18 +95,54 @@ const createMockBridge = (): RuneBridgeConstructor => {
       });
     }
 
+    async nextPage(token: string, pageBytes: number): Promise<string> {
+      console.error('Mock: Fetching page', token, pageBytes);
+      throw new Error(`Unknown or expired continuation token: ${token}`);
+    }
+
+    parseQuery(query: string): string {
+      console.error('Mock: Parsing query:', query);
+      return JSON.stringify({
+        original: query,
+        intent: 'Explore',
+        suggested_mode: 'Semantic',
+        search_text: query,
+        languages: [],
+        file_patterns: [],
+        symbol_kinds: [],
+      });
+    }
+
     async getStats(): Promise<string> {
       console.error('Mock: Getting stats');
       return JSON.stringify({
         indexed_files: 0,
+        tantivy_documents: 0,
         total_symbols: 0,
         index_size_bytes: 0,
         cache_size_bytes: 0,
+        embedding_fallback: false,
+        file_watching_active: false,
       });
     }
 
-    async reindex(): Promise<void> {
+    async getEmbeddingStatus(): Promise<string> {
+      console.error('Mock: Getting embedding status');
+      return JSON.stringify({
+        model_loaded: false,
+        fallback: false,
+        dimension: 0,
+        model_name: '',
+      });
+    }
+
+    async reindex(): Promise<string> {
       console.error('Mock: Reindexing');
+      return JSON.stringify('completed');
+    }
+
+    async indexNow(path: string): Promise<void> {
+      console.error('Mock: Indexing', path);
     }
   }
 
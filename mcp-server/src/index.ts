@@ -26,7 +26,19 @@ const ConfigSchema = z.object({
   enableSemantic: z.boolean().default(true),
   languages: z
     .array(z.string())
-    .default(['rust', 'javascript', 'typescript', 'python', 'go', 'java', 'cpp']),
+    .default([
+      'rust',
+      'javascript',
+      'typescript',
+      'python',
+      'go',
+      'java',
+      'cpp',
+      'markdown',
+      'yaml',
+      'toml',
+      'sql',
+    ]),
   fileWatchDebounceMs: z.number().default(500),
 });
 
@@ -193,6 +205,7 @@ When to use this tool:
 
 Returned Metrics:
 - indexed_files: Total number of files processed and searchable
+- tantivy_documents: Documents in the text index (should match indexed_files)
 - total_symbols: Count of all extracted code symbols (functions, classes, etc.)
 - index_size_bytes: Disk space used by search indices
 - cache_size_bytes: Memory used by search result cache
@@ -402,9 +415,9 @@ Example Configurations:
 
           case 'reindex': {
             await this.ensureInitialized();
-            await this.bridge.reindex();
+            const status = JSON.parse(await this.bridge.reindex());
             return {
-              content: [{ type: 'text', text: 'Reindexing started' }],
+              content: [{ type: 'text', text: `Reindexing finished (${status})` }],
             };
           }
 
@@ -612,6 +625,10 @@ Example Configurations:
         'go',
         'java',
         'cpp',
+        'markdown',
+        'yaml',
+        'toml',
+        'sql',
       ],
       fileWatchDebounceMs: parseInt(process.env.RUNE_FILE_WATCH_DEBOUNCE_MS ?? '500'),
     };
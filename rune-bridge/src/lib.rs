@@ -3,14 +3,18 @@
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use rune_core::{
-    Config, RuneEngine,
-    search::{SearchMode, SearchQuery},
+    Config, RuneEngine, RuneError,
+    search::{SearchMode, SearchQuery, SearchResponse, query_parser::QueryParser},
 };
+use std::collections::{HashMap, VecDeque};
 #[cfg(unix)]
 use std::os::unix::io::{AsRawFd, FromRawFd};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+
+/// Paged responses kept for `next_page` before the oldest are dropped
+const MAX_PENDING_PAGES: usize = 32;
 
 // Helper to suppress stdout during Qdrant operations (Unix only)
 #[cfg(unix)]
@@ -73,6 +77,48 @@ impl Drop for StdoutSuppressor {
 #[napi]
 pub struct RuneBridge {
     engine: Arc<RwLock<Option<RuneEngine>>>,
+    /// Remaining results of paged searches, by continuation token
+    pending_pages: Arc<Mutex<PendingPages>>,
+}
+
+#[derive(Default)]
+struct PendingPages {
+    next_token: u64,
+    responses: HashMap<String, SearchResponse>,
+    /// Tokens oldest first, for evicting past `MAX_PENDING_PAGES`
+    order: VecDeque<String>,
+}
+
+impl PendingPages {
+    /// Split the next page off `response`, keeping any remainder under a new token
+    fn page(&mut self, mut response: SearchResponse, max_bytes: usize) -> Result<String> {
+        let mut page = response
+            .take_page(max_bytes)
+            .map_err(|e| Error::from_reason(format!("Failed to page response: {}", e)))?;
+
+        if !response.results.is_empty() {
+            let token = self.next_token.to_string();
+            self.next_token += 1;
+            page.continuation_token = Some(token.clone());
+            self.responses.insert(token.clone(), response);
+            self.order.push_back(token);
+
+            while self.order.len() > MAX_PENDING_PAGES {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.responses.remove(&oldest);
+                }
+            }
+        }
+
+        serde_json::to_string(&page)
+            .map_err(|e| Error::from_reason(format!("Failed to serialize response: {}", e)))
+    }
+
+    fn take(&mut self, token: &str) -> Option<SearchResponse> {
+        let response = self.responses.remove(token)?;
+        self.order.retain(|t| t != token);
+        Some(response)
+    }
 }
 
 #[napi]
@@ -114,6 +160,7 @@ impl RuneBridge {
 
         Ok(Self {
             engine: Arc::new(RwLock::new(None)),
+            pending_pages: Arc::new(Mutex::new(PendingPages::default())),
         })
     }
 
@@ -131,19 +178,16 @@ impl RuneBridge {
             .map(PathBuf::from)
             .collect();
 
-        let rust_config = Config {
-            workspace_dir: workspace_roots
-                .first()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| ".".to_string()),
-            workspace_roots,
-            cache_dir: PathBuf::from(config.cache_dir),
-            max_file_size: config.max_file_size,
-            indexing_threads: config.indexing_threads,
-            enable_semantic: config.enable_semantic,
-            languages: config.languages,
-            file_watch_debounce_ms: config.file_watch_debounce_ms,
-        };
+        let rust_config = Config::builder()
+            .workspace_roots(workspace_roots)
+            .cache_dir(PathBuf::from(config.cache_dir))
+            .max_file_size(config.max_file_size)
+            .indexing_threads(config.indexing_threads)
+            .enable_semantic(config.enable_semantic)
+            .languages(config.languages)
+            .file_watch_debounce_ms(config.file_watch_debounce_ms)
+            .build()
+            .map_err(|e| Error::from_reason(e.to_string()))?;
 
         let engine = RuneEngine::new(rust_config)
             .await
@@ -170,8 +214,8 @@ impl RuneBridge {
             .await
             .map_err(|e| Error::from_reason(format!("Failed to start engine: {}", e)))?;
 
-        // Note: engine.start() already performs initial indexing via index_workspaces()
-        // No need to call reindex() again here
+        // Note: engine.start() is the single source of truth for the initial index
+        // (watchers first, then one index_workspaces() pass). Do not call reindex() here.
 
         Ok(())
     }
@@ -204,6 +248,9 @@ impl RuneBridge {
         let mode = match query.mode.to_lowercase().as_str() {
             "symbol" => SearchMode::Symbol,
             "semantic" => SearchMode::Semantic,
+            "literal" => SearchMode::Literal,
+            "regex" => SearchMode::Regex,
+            "hybrid" => SearchMode::Hybrid,
             _ => SearchMode::Semantic, // Default to semantic for any other mode
         };
 
@@ -214,13 +261,21 @@ impl RuneBridge {
             file_patterns: query.file_patterns.clone(),
             limit: query.limit,
             offset: query.offset,
+            ..Default::default()
         };
 
-        let response = engine
-            .search()
-            .search(rust_query)
-            .await
-            .map_err(|e| Error::from_reason(format!("Search failed: {}", e)))?;
+        let response = engine.search().search(rust_query).await.map_err(|e| {
+            match e.downcast_ref::<RuneError>() {
+                // A build without semantic search, not a failed query; JS sees `code` "InvalidArg"
+                Some(RuneError::SemanticDisabled) => Error::new(Status::InvalidArg, e.to_string()),
+                _ => Error::from_reason(format!("Search failed: {}", e)),
+            }
+        })?;
+
+        // Large result sets can be fetched in bounded pages instead of one string
+        if let Some(max_bytes) = query.page_bytes {
+            return self.pending_pages.lock().await.page(response, max_bytes);
+        }
 
         let json_response = serde_json::to_string(&response)
             .map_err(|e| Error::from_reason(format!("Failed to serialize response: {}", e)))?;
@@ -228,6 +283,27 @@ impl RuneBridge {
         Ok(json_response)
     }
 
+    /// Fetch the next page of a search made with `page_bytes`. The page carries a
+    /// `continuation_token` while more results remain; each token works once.
+    #[napi]
+    pub async fn next_page(&self, token: String, page_bytes: u32) -> Result<String> {
+        let mut pending = self.pending_pages.lock().await;
+        let response = pending.take(&token).ok_or_else(|| {
+            Error::from_reason(format!("Unknown or expired continuation token: {}", token))
+        })?;
+
+        pending.page(response, page_bytes as usize)
+    }
+
+    /// Preview how a natural-language query will be interpreted, without searching.
+    #[napi]
+    pub fn parse_query(&self, query: String) -> Result<String> {
+        let parsed = QueryParser::parse(&query);
+
+        serde_json::to_string(&parsed)
+            .map_err(|e| Error::from_reason(format!("Failed to serialize parsed query: {}", e)))
+    }
+
     #[napi]
     pub async fn get_stats(&self) -> Result<String> {
         let lock = self.engine.read().await;
@@ -236,39 +312,63 @@ impl RuneBridge {
             .ok_or_else(|| Error::from_reason("Engine not initialized"))?;
 
         let stats = engine
-            .stats()
+            .stats(false)
             .await
             .map_err(|e| Error::from_reason(format!("Failed to get stats: {}", e)))?;
 
-        // Add watching status to stats
-        let mut stats_json = serde_json::to_value(&stats)
-            .map_err(|e| Error::from_reason(format!("Failed to serialize stats: {}", e)))?;
+        // Serialized straight from the struct so keys keep their declared order
+        serde_json::to_string(&stats)
+            .map_err(|e| Error::from_reason(format!("Failed to serialize stats: {}", e)))
+    }
 
-        if let Some(obj) = stats_json.as_object_mut() {
-            obj.insert(
-                "file_watching_active".to_string(),
-                serde_json::Value::Bool(engine.is_watching()),
-            );
-        }
+    /// Which embedding model backs semantic search, as JSON. `fallback` means the
+    /// model failed to load and semantic results are degraded.
+    #[napi]
+    pub async fn get_embedding_status(&self) -> Result<String> {
+        let lock = self.engine.read().await;
+        let engine = lock
+            .as_ref()
+            .ok_or_else(|| Error::from_reason("Engine not initialized"))?;
 
-        serde_json::to_string(&stats_json)
-            .map_err(|e| Error::from_reason(format!("Failed to serialize stats: {}", e)))
+        serde_json::to_string(&engine.embedding_status())
+            .map_err(|e| Error::from_reason(format!("Failed to serialize embedding status: {}", e)))
     }
 
+    /// Index one file immediately, bypassing the file watcher's debounce. Editors call
+    /// this on save so the change is searchable straight away. Files a workspace walk
+    /// would skip (ignored, hidden, oversized or binary) are left out of the index.
     #[napi]
-    pub async fn reindex(&self) -> Result<()> {
+    pub async fn index_now(&self, path: String) -> Result<()> {
         let lock = self.engine.read().await;
         let engine = lock
             .as_ref()
             .ok_or_else(|| Error::from_reason("Engine not initialized"))?;
 
         engine
+            .indexer()
+            .index_single_file(std::path::Path::new(&path))
+            .await
+            .map_err(|e| Error::from_reason(format!("Failed to index {}: {}", path, e)))
+    }
+
+    /// Reindex all workspaces. Returns how the request was served as a JSON string
+    /// (`"completed"`, `"joined_in_flight"` or `"coalesced"`); overlapping requests
+    /// share passes instead of running them side by side.
+    #[napi]
+    pub async fn reindex(&self) -> Result<String> {
+        let lock = self.engine.read().await;
+        let engine = lock
+            .as_ref()
+            .ok_or_else(|| Error::from_reason("Engine not initialized"))?;
+
+        let status = engine
             .indexer()
             .reindex()
             .await
             .map_err(|e| Error::from_reason(format!("Reindex failed: {}", e)))?;
 
-        Ok(())
+        serde_json::to_string(&status)
+            .map_err(|e| Error::from_reason(format!("Failed to serialize status: {}", e)))
     }
 }
 
@@ -292,4 +392,7 @@ struct SearchQueryJs {
     file_patterns: Option<Vec<String>>,
     limit: usize,
     offset: usize,
+    /// Return results in pages of at most this many bytes, continued with `next_page`
+    #[serde(default)]
+    page_bytes: Option<usize>,
 }
@@ -0,0 +1,66 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rune_core::cache::{CacheConfig, MultiTierCache};
+use rune_core::search::{SEARCH_RESPONSE_SCHEMA_VERSION, SearchMode, SearchQuery, SearchResponse};
+use std::hint::black_box;
+use tokio::runtime::Runtime;
+
+fn query(text: String) -> SearchQuery {
+    SearchQuery {
+        query: text,
+        mode: SearchMode::Literal,
+        ..Default::default()
+    }
+}
+
+fn response(query: &SearchQuery) -> SearchResponse {
+    SearchResponse {
+        query: query.clone(),
+        results: Vec::new(),
+        total_matches: 0,
+        search_time_ms: 0,
+        from_cache: None,
+        suggestions: Vec::new(),
+        truncated_files: Vec::new(),
+        schema_version: SEARCH_RESPONSE_SCHEMA_VERSION,
+        continuation_token: None,
+        stale: false,
+    }
+}
+
+/// Cost of a put into a full L1 cache, each of which evicts the least recently used entry
+fn benchmark_l1_eviction(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("cache/l1_eviction");
+
+    for entries in [1_000, 10_000, 50_000] {
+        let config = CacheConfig {
+            l1_max_entries: entries,
+            ..Default::default()
+        };
+        // The cache spawns its cleanup task, so it has to be created on the runtime
+        let cache = rt.block_on(async {
+            let cache = MultiTierCache::new(config, None);
+            for i in 0..entries {
+                let query = query(format!("warm query {i}"));
+                cache.put(&query, response(&query), 0).await.unwrap();
+            }
+            cache
+        });
+
+        let mut next = 0usize;
+        group.bench_with_input(BenchmarkId::new("put", entries), &entries, |b, _| {
+            b.iter(|| {
+                next += 1;
+                let query = query(format!("new query {next}"));
+                let response = response(&query);
+                rt.block_on(cache.put(black_box(&query), response, 0))
+                    .unwrap();
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_l1_eviction);
+criterion_main!(benches);
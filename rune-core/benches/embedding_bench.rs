@@ -52,6 +52,7 @@ fn benchmark_ast_chunking(c: &mut Criterion) {
                         include_imports: true,
                         include_parent_context: true,
                         context_overlap: ContextOverlap::Moderate,
+                        ..Default::default()
                     };
                     let mut chunker = AstChunker::new(config);
                     let _ = black_box(chunker.chunk_file(content, file_name, language));
@@ -74,6 +75,7 @@ fn benchmark_code_chunking(c: &mut Criterion) {
         overlap: 225.0,
         max_chunk_size: 2000,
         preserve_structure: true,
+        ..Default::default()
     };
 
     let test_files = vec![
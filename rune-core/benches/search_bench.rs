@@ -40,6 +40,7 @@ fn benchmark_symbol_search(c: &mut Criterion) {
                             offset: 0,
                             repositories: None,
                             file_patterns: None,
+                            ..Default::default()
                         };
 
                         black_box(search_engine.search(query).await.unwrap());
@@ -91,6 +92,7 @@ fn benchmark_search_with_filters(c: &mut Criterion) {
                         offset: 0,
                         repositories: repos.clone(),
                         file_patterns: patterns.clone(),
+                        ..Default::default()
                     };
 
                     black_box(search_engine.search(query).await.unwrap());
@@ -129,6 +131,7 @@ fn benchmark_search_latency_percentiles(c: &mut Criterion) {
                     offset: 0,
                     repositories: None,
                     file_patterns: None,
+                    ..Default::default()
                 };
 
                 black_box(search_engine.search(query).await.unwrap());
@@ -24,6 +24,7 @@ fn benchmark_storage_write(c: &mut Criterion) {
             language: "rust".to_string(),
             hash: "abc123".to_string(),
             indexed_at: 1234567890,
+            symbol_count: 20,
         }),
         ("medium", FileMetadata {
             path: PathBuf::from("very/long/path/to/some/file/test.rs"),
@@ -32,6 +33,7 @@ fn benchmark_storage_write(c: &mut Criterion) {
             language: "rust".to_string(),
             hash: "abc123def456ghi789jkl012mno345pqr678stu901vwx234yz".to_string(), // pragma: allowlist secret
             indexed_at: 1234567890,
+            symbol_count: 20,
         }),
         ("large", FileMetadata {
             path: PathBuf::from("extremely/long/path/with/many/nested/directories/and/a/very/long/filename/that/goes/on/and/on/test.rs"),
@@ -40,6 +42,7 @@ fn benchmark_storage_write(c: &mut Criterion) {
             language: "rust".to_string(),
             hash: "very_long_hash_value_that_contains_lots_of_characters_to_test_storage_performance_with_larger_payloads".to_string(),
             indexed_at: 1234567890,
+            symbol_count: 20,
         }),
     ];
 
@@ -81,6 +84,7 @@ fn benchmark_storage_read(c: &mut Criterion) {
                 language: "rust".to_string(),
                 hash: format!("hash_{}", i),
                 indexed_at: 1234567890,
+                symbol_count: 20,
             };
             storage.store_file_metadata(&path, metadata).await.unwrap();
         }
@@ -140,6 +144,7 @@ fn benchmark_storage_batch(c: &mut Criterion) {
                                 language: "rust".to_string(),
                                 hash: format!("hash_{}_{}", counter, i),
                                 indexed_at: 1234567890,
+                                symbol_count: 20,
                             };
                             let storage_clone = storage.clone();
                             async move { storage_clone.store_file_metadata(&path, metadata).await }
@@ -183,6 +188,7 @@ fn benchmark_storage_list(c: &mut Criterion) {
                     language: "rust".to_string(),
                     hash: format!("hash_{}", i),
                     indexed_at: 1234567890,
+                    symbol_count: 20,
                 };
                 storage.store_file_metadata(&path, metadata).await.unwrap();
             }
@@ -224,6 +230,7 @@ fn benchmark_storage_stats(c: &mut Criterion) {
                 language: "rust".to_string(),
                 hash: format!("hash_{}", i),
                 indexed_at: 1234567890,
+                symbol_count: 20,
             };
             storage.store_file_metadata(&path, metadata).await.unwrap();
         }
@@ -274,6 +281,7 @@ fn benchmark_concurrent_access(c: &mut Criterion) {
                 language: "rust".to_string(),
                 hash: format!("hash_{}", i),
                 indexed_at: 1234567890,
+                symbol_count: 20,
             };
             storage.store_file_metadata(&path, metadata).await.unwrap();
         }
@@ -310,6 +318,7 @@ fn benchmark_concurrent_access(c: &mut Criterion) {
                         language: "rust".to_string(),
                         hash: format!("new_hash_{}", i),
                         indexed_at: 1234567891,
+                        symbol_count: 20,
                     };
                     storage_clone.store_file_metadata(&path, metadata).await
                 };
@@ -86,6 +86,7 @@ pub fn setup_benchmark_workspace(size: DatasetSize) -> (TempDir, PathBuf, Arc<Co
             "go".to_string(),
         ],
         file_watch_debounce_ms: 500,
+        ..Default::default()
     });
 
     (temp_dir, workspace, config)
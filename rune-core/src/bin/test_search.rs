@@ -24,6 +24,7 @@ async fn main() -> anyhow::Result<()> {
             "go".to_string(),
         ],
         file_watch_debounce_ms: 500,
+        ..Default::default()
     };
 
     // Create engine
@@ -34,7 +35,7 @@ async fn main() -> anyhow::Result<()> {
     engine.indexer().reindex().await?;
 
     // Get stats
-    let stats = engine.stats().await?;
+    let stats = engine.stats(true).await?;
     println!("Stats: {:?}", stats);
 
     // Test symbol search
@@ -45,6 +46,7 @@ async fn main() -> anyhow::Result<()> {
         file_patterns: None,
         limit: 10,
         offset: 0,
+        ..Default::default()
     };
 
     println!("Searching for 'main' with symbol mode...");
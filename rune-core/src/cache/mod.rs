@@ -1,18 +1,27 @@
+use std::collections::VecDeque;
 use std::hash::{Hash, Hasher};
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use dashmap::DashMap;
-use tracing::{debug, error, trace};
+use parking_lot::Mutex;
+use tracing::{debug, error, trace, warn};
 
-use crate::search::{SearchQuery, SearchResponse};
+use crate::search::{
+    ContextMode, SEARCH_RESPONSE_SCHEMA_VERSION, SearchQuery, SearchResponse, SearchResult,
+};
+use crate::storage::StorageBackend;
 
 /// Cache metrics for monitoring performance
 #[derive(Debug, Default)]
 pub struct CacheMetrics {
     pub l1_hits: std::sync::atomic::AtomicU64,
     pub l1_misses: std::sync::atomic::AtomicU64,
+    pub l2_hits: std::sync::atomic::AtomicU64,
+    pub l2_misses: std::sync::atomic::AtomicU64,
     pub total_queries: std::sync::atomic::AtomicU64,
     pub total_cache_time_us: std::sync::atomic::AtomicU64,
 }
@@ -28,6 +37,16 @@ impl CacheMetrics {
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
     }
 
+    pub fn record_l2_hit(&self) {
+        self.l2_hits
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn record_l2_miss(&self) {
+        self.l2_misses
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
     pub fn record_query(&self) {
         self.total_queries
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
@@ -60,7 +79,9 @@ impl CacheMetrics {
     }
 }
 
-/// Cache key derived from search query
+/// Cache key derived from search query: everything in a `SearchQuery` that affects
+/// its response. `bypass_cache` is left out on purpose so a bypassing query refreshes
+/// the entry normal queries read.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct CacheKey {
     query_hash: u64,
@@ -69,9 +90,34 @@ struct CacheKey {
     file_patterns_hash: u64,
     limit: usize,
     offset: usize,
+    context_mode: ContextMode,
+    dedup_by_content_hash: bool,
+    candidate_limit: Option<usize>,
+    normalize_scores: bool,
+    suggest_on_empty: bool,
+    search_paths: bool,
+    chunk_preview_lines: Option<usize>,
+    modified_since: Option<u64>,
+    exclude_vendored: bool,
+    include_references: bool,
+    min_line_length: Option<usize>,
+    include_symbol_body: bool,
+    include_imports: bool,
+    highlight_snippets: bool,
+    include_blame: bool,
+    include_enclosing_signature: bool,
 }
 
 impl CacheKey {
+    /// Key of the entry in the L2 store under `scope`. `DefaultHasher` uses fixed keys,
+    /// so this is stable across runs of the same build.
+    fn l2_key(&self, scope: u64) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        scope.hash(&mut hasher);
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
     fn from_query(query: &SearchQuery) -> Self {
         use std::collections::hash_map::DefaultHasher;
 
@@ -102,6 +148,22 @@ impl CacheKey {
             file_patterns_hash,
             limit: query.limit,
             offset: query.offset,
+            context_mode: query.context_mode,
+            dedup_by_content_hash: query.dedup_by_content_hash,
+            candidate_limit: query.candidate_limit,
+            normalize_scores: query.normalize_scores,
+            suggest_on_empty: query.suggest_on_empty,
+            search_paths: query.search_paths,
+            chunk_preview_lines: query.chunk_preview_lines,
+            modified_since: query.modified_since,
+            exclude_vendored: query.exclude_vendored,
+            include_references: query.include_references,
+            min_line_length: query.min_line_length,
+            include_symbol_body: query.include_symbol_body,
+            include_imports: query.include_imports,
+            highlight_snippets: query.highlight_snippets,
+            include_blame: query.include_blame,
+            include_enclosing_signature: query.include_enclosing_signature,
         }
     }
 }
@@ -111,23 +173,23 @@ struct CachedResult {
     response: SearchResponse,
     cached_at: Instant,
     access_count: u32,
-    last_accessed: Instant,
+    /// Position of the last access in `MultiTierCache::recency`
+    tick: u64,
 }
 
 impl CachedResult {
-    fn new(response: SearchResponse) -> Self {
-        let now = Instant::now();
+    fn new(response: SearchResponse, tick: u64) -> Self {
         Self {
             response,
-            cached_at: now,
+            cached_at: Instant::now(),
             access_count: 1,
-            last_accessed: now,
+            tick,
         }
     }
 
-    fn touch(&mut self) {
+    fn touch(&mut self, tick: u64) {
         self.access_count += 1;
-        self.last_accessed = Instant::now();
+        self.tick = tick;
     }
 
     fn is_expired(&self, ttl: Duration) -> bool {
@@ -135,11 +197,65 @@ impl CachedResult {
     }
 }
 
+/// Unix time in milliseconds, for L2 entries; `Instant` doesn't survive a restart
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Heap bytes owned by a response, counting strings and vectors by length
+fn estimated_response_bytes(response: &SearchResponse) -> usize {
+    let lines = |lines: &[String]| -> usize {
+        lines
+            .iter()
+            .map(|l| std::mem::size_of::<String>() + l.len())
+            .sum()
+    };
+
+    let results: usize = response
+        .results
+        .iter()
+        .map(|r| {
+            std::mem::size_of::<SearchResult>()
+                + r.file_path.as_os_str().len()
+                + r.repository.len()
+                + r.content.len()
+                + lines(&r.context_before)
+                + lines(&r.context_after)
+                + r.relative_path.as_ref().map_or(0, |p| p.as_os_str().len())
+        })
+        .sum();
+
+    response.query.query.len()
+        + results
+        + lines(&response.suggestions)
+        + response
+            .truncated_files
+            .iter()
+            .map(|p| std::mem::size_of::<std::path::PathBuf>() + p.as_os_str().len())
+            .sum::<usize>()
+}
+
 /// Multi-tier caching system for search results
 pub struct MultiTierCache {
     /// L1: In-memory cache using DashMap for concurrent access
     l1_cache: Arc<DashMap<CacheKey, CachedResult>>,
 
+    /// L1 keys from least to most recently used, each with the tick of that access.
+    /// Accesses append rather than move a key, so only the entry whose tick matches
+    /// the cached one is current; the others are skipped on eviction and compacted
+    /// away once they outnumber the live keys. Never locked while a `l1_cache` entry
+    /// is held.
+    recency: Mutex<VecDeque<(CacheKey, u64)>>,
+
+    /// Source of `CachedResult::tick`
+    next_tick: AtomicU64,
+
+    /// L2: Responses serialized into the metadata store, surviving restarts
+    l2: Option<Arc<StorageBackend>>,
+
     /// Cache configuration
     config: CacheConfig,
 
@@ -158,6 +274,13 @@ pub struct CacheConfig {
 
     /// Minimum query length to cache (avoid caching single character queries)
     pub min_query_length: usize,
+
+    /// How long entries are kept past `l1_ttl` for `get_stale`. Zero drops them on expiry.
+    pub stale_grace: Duration,
+
+    /// TTL for L2 entries, counted from when they were written. Zero (the default)
+    /// disables L2.
+    pub l2_ttl: Duration,
 }
 
 impl Default for CacheConfig {
@@ -166,17 +289,20 @@ impl Default for CacheConfig {
             l1_max_entries: 10000,
             l1_ttl: Duration::from_secs(300), // 5 minutes
             min_query_length: 2,
+            stale_grace: Duration::ZERO,
+            l2_ttl: Duration::ZERO,
         }
     }
 }
 
 impl MultiTierCache {
-    pub fn new(
-        config: CacheConfig,
-        _storage: Option<Arc<crate::storage::StorageBackend>>, // Reserved for future L2 implementation
-    ) -> Self {
+    /// Without `storage`, or with a zero `l2_ttl`, only the in-memory L1 tier is used
+    pub fn new(config: CacheConfig, storage: Option<Arc<StorageBackend>>) -> Self {
         let cache = Self {
             l1_cache: Arc::new(DashMap::with_capacity(config.l1_max_entries)),
+            recency: Mutex::new(VecDeque::new()),
+            next_tick: AtomicU64::new(0),
+            l2: storage.filter(|_| !config.l2_ttl.is_zero()),
             config,
             metrics: Arc::new(CacheMetrics::default()),
         };
@@ -187,8 +313,12 @@ impl MultiTierCache {
         cache
     }
 
-    /// Get cached result if available
-    pub async fn get(&self, query: &SearchQuery) -> Option<SearchResponse> {
+    /// Get cached result if available. L2 entries are only visible under the `scope`
+    /// they were stored with: callers pass a fingerprint of whatever responses depend
+    /// on besides the query, such as the index contents and configuration, so L2 never
+    /// serves results from before a change, including one made while the process
+    /// wasn't running.
+    pub async fn get(&self, query: &SearchQuery, scope: u64) -> Option<SearchResponse> {
         // Skip caching for very short queries
         if query.query.len() < self.config.min_query_length {
             return None;
@@ -202,13 +332,18 @@ impl MultiTierCache {
         // Check L1 cache
         if let Some(mut entry) = self.l1_cache.get_mut(&key) {
             if !entry.is_expired(self.config.l1_ttl) {
-                entry.touch();
+                let tick = self.next_tick.fetch_add(1, Ordering::Relaxed);
+                entry.touch(tick);
+                let response = entry.response.clone();
+                drop(entry);
+                self.record_access(key, tick);
+
                 self.metrics.record_hit();
                 self.metrics
                     .record_cache_time(start.elapsed().as_micros() as u64);
                 debug!("L1 cache hit for query: {}", query.query);
-                return Some(entry.response.clone());
-            } else {
+                return Some(response);
+            } else if entry.is_expired(self.config.l1_ttl + self.config.stale_grace) {
                 // Remove expired entry
                 drop(entry);
                 self.l1_cache.remove(&key);
@@ -217,13 +352,82 @@ impl MultiTierCache {
         }
 
         self.metrics.record_miss();
+
+        let promoted = match self.l2 {
+            Some(ref storage) => match self.get_l2(storage, &key, scope).await {
+                Some(response) => {
+                    self.metrics.record_l2_hit();
+                    debug!("L2 cache hit for query: {}", query.query);
+                    self.insert_l1(key, response.clone());
+                    Some(response)
+                },
+                None => {
+                    self.metrics.record_l2_miss();
+                    None
+                },
+            },
+            None => None,
+        };
+
         self.metrics
             .record_cache_time(start.elapsed().as_micros() as u64);
-        None
+        promoted
+    }
+
+    /// The L2 entry for `key` if it's unexpired and readable, removing it otherwise
+    async fn get_l2(
+        &self,
+        storage: &StorageBackend,
+        key: &CacheKey,
+        scope: u64,
+    ) -> Option<SearchResponse> {
+        let l2_key = key.l2_key(scope);
+        let (stored_at, bytes) = match storage.get_cached_response(l2_key).await {
+            Ok(entry) => entry?,
+            Err(e) => {
+                warn!("Failed to read L2 cache entry: {}", e);
+                return None;
+            },
+        };
+
+        // Entries from an older response schema, or that no longer parse, are dropped
+        let fresh = now_ms().saturating_sub(stored_at) <= self.config.l2_ttl.as_millis() as u64;
+        let response = serde_json::from_slice::<SearchResponse>(&bytes)
+            .ok()
+            .filter(|response| fresh && response.schema_version == SEARCH_RESPONSE_SCHEMA_VERSION);
+        if response.is_none() {
+            if let Err(e) = storage.delete_cached_response(l2_key).await {
+                warn!("Failed to remove L2 cache entry: {}", e);
+            }
+            trace!("Dropped expired or unreadable L2 entry");
+        }
+
+        response
+    }
+
+    /// A cached response for `query` that may have outlived `l1_ttl` by up to
+    /// `stale_grace`, for when it can't be recomputed. Not counted as a hit or miss.
+    pub async fn get_stale(&self, query: &SearchQuery) -> Option<SearchResponse> {
+        if query.query.len() < self.config.min_query_length {
+            return None;
+        }
+
+        let entry = self.l1_cache.get(&CacheKey::from_query(query))?;
+        if entry.is_expired(self.config.l1_ttl + self.config.stale_grace) {
+            return None;
+        }
+
+        debug!("Stale L1 entry available for query: {}", query.query);
+        Some(entry.response.clone())
     }
 
-    /// Store search result in cache
-    pub async fn put(&self, query: &SearchQuery, response: SearchResponse) -> Result<()> {
+    /// Store search result in cache, in L2 under `scope` (see `get`)
+    pub async fn put(
+        &self,
+        query: &SearchQuery,
+        response: SearchResponse,
+        scope: u64,
+    ) -> Result<()> {
         // Skip caching for very short queries
         if query.query.len() < self.config.min_query_length {
             return Ok(());
@@ -231,17 +435,50 @@ impl MultiTierCache {
 
         let key = CacheKey::from_query(query);
 
+        let Some(ref storage) = self.l2 else {
+            self.insert_l1(key, response);
+            debug!("Cached search result in L1 for query: {}", query.query);
+            return Ok(());
+        };
+
+        let l2_key = key.l2_key(scope);
+        let bytes = serde_json::to_vec(&response)?;
+        self.insert_l1(key, response);
+        storage
+            .store_cached_response(l2_key, now_ms(), &bytes)
+            .await?;
+        debug!(
+            "Cached search result in L1 and L2 for query: {}",
+            query.query
+        );
+
+        Ok(())
+    }
+
+    fn insert_l1(&self, key: CacheKey, response: SearchResponse) {
         // Evict LRU entry if at capacity
-        if self.l1_cache.len() >= self.config.l1_max_entries {
+        if !self.l1_cache.contains_key(&key) && self.l1_cache.len() >= self.config.l1_max_entries {
             self.evict_lru();
         }
 
-        // Store in L1
-        let result = CachedResult::new(response);
-        self.l1_cache.insert(key, result);
-        debug!("Cached search result in L1 for query: {}", query.query);
+        let tick = self.next_tick.fetch_add(1, Ordering::Relaxed);
+        self.l1_cache
+            .insert(key.clone(), CachedResult::new(response, tick));
+        self.record_access(key, tick);
+    }
 
-        Ok(())
+    /// Append an access to `recency`, compacting it if stale accesses dominate
+    fn record_access(&self, key: CacheKey, tick: u64) {
+        let mut recency = self.recency.lock();
+        recency.push_back((key, tick));
+
+        if recency.len() > 2 * self.config.l1_max_entries + 64 {
+            recency.retain(|(key, tick)| {
+                self.l1_cache
+                    .get(key)
+                    .is_some_and(|entry| entry.tick == *tick)
+            });
+        }
     }
 
     /// Invalidate cache entries matching a pattern
@@ -264,11 +501,58 @@ impl MultiTierCache {
         }
     }
 
+    /// Drop L1 entries whose results reference `path`, e.g. after it was deleted. L2
+    /// entries need no scan: they are scoped to the index version, which changes on
+    /// every commit. A created or edited file can start matching any query, so that
+    /// calls for `clear` instead.
+    pub fn invalidate_file(&self, path: &Path) -> usize {
+        let mut removed = 0;
+
+        self.l1_cache.retain(|_, cached| {
+            let references = cached
+                .response
+                .results
+                .iter()
+                .any(|result| result.file_path == path);
+            if references {
+                removed += 1;
+            }
+            !references
+        });
+
+        if removed > 0 {
+            debug!("Invalidated {} L1 cache entries for {:?}", removed, path);
+        }
+        removed
+    }
+
     /// Clear all cache entries
     pub async fn clear(&self) {
         let l1_size = self.l1_cache.len();
         self.l1_cache.clear();
+        self.recency.lock().clear();
         debug!("Cleared {} entries from L1 cache", l1_size);
+
+        if let Some(ref storage) = self.l2 {
+            match storage.clear_cached_responses().await {
+                Ok(l2_size) => debug!("Cleared {} entries from L2 cache", l2_size),
+                Err(e) => warn!("Failed to clear L2 cache: {}", e),
+            }
+        }
+    }
+
+    /// Number of L1 entries and an estimate of the bytes their responses occupy
+    pub fn l1_usage(&self) -> (usize, usize) {
+        let bytes = self
+            .l1_cache
+            .iter()
+            .map(|entry| {
+                std::mem::size_of::<CacheKey>()
+                    + std::mem::size_of::<CachedResult>()
+                    + estimated_response_bytes(&entry.value().response)
+            })
+            .sum();
+        (self.l1_cache.len(), bytes)
     }
 
     /// Get cache metrics
@@ -278,30 +562,33 @@ impl MultiTierCache {
 
     /// Evict least recently used entry from L1
     fn evict_lru(&self) {
-        let mut oldest_key = None;
-        let mut oldest_time = Instant::now();
-
-        // Find LRU entry
-        for entry in self.l1_cache.iter() {
-            if entry.value().last_accessed < oldest_time {
-                oldest_time = entry.value().last_accessed;
-                oldest_key = Some(entry.key().clone());
+        loop {
+            let Some((key, tick)) = self.recency.lock().pop_front() else {
+                return;
+            };
+
+            // Entries removed since, or accessed again, left this access stale
+            if self
+                .l1_cache
+                .remove_if(&key, |_, entry| entry.tick == tick)
+                .is_some()
+            {
+                trace!("Evicted LRU entry from L1 cache");
+                return;
             }
         }
-
-        if let Some(key) = oldest_key {
-            self.l1_cache.remove(&key);
-            trace!("Evicted LRU entry from L1 cache");
-        }
     }
 
     /// Start background task to clean up expired entries
     fn start_cleanup_task(&self) {
         let cache = Arc::clone(&self.l1_cache);
-        let ttl = self.config.l1_ttl;
+        let ttl = self.config.l1_ttl + self.config.stale_grace;
+        let l2 = self.l2.clone();
+        let l2_ttl = self.config.l2_ttl;
 
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(60));
+            let mut last_l2_prune: Option<Instant> = None;
 
             loop {
                 interval.tick().await;
@@ -332,6 +619,21 @@ impl MultiTierCache {
                         );
                     },
                 }
+
+                // Entries for superseded scopes are never read again, so expiry is the
+                // only thing that removes them. Pruning scans every entry, and none can
+                // expire sooner than `l2_ttl` after the last pass, so run it that often.
+                if let Some(ref storage) = l2
+                    && last_l2_prune.is_none_or(|pruned| pruned.elapsed() >= l2_ttl)
+                {
+                    last_l2_prune = Some(Instant::now());
+                    let cutoff = now_ms().saturating_sub(l2_ttl.as_millis() as u64);
+                    match storage.prune_cached_responses(cutoff).await {
+                        Ok(0) => {},
+                        Ok(pruned) => trace!("Pruned {} expired L2 cache entries", pruned),
+                        Err(e) => warn!("Failed to prune L2 cache: {}", e),
+                    }
+                }
             }
         });
     }
@@ -397,18 +699,23 @@ mod tests {
             total_matches: 0,
             search_time_ms: 0,
             from_cache: None,
+            suggestions: vec![],
+            truncated_files: vec![],
+            schema_version: SEARCH_RESPONSE_SCHEMA_VERSION,
+            continuation_token: None,
+            stale: false,
         };
 
-        cache.put(&query, response.clone()).await.unwrap();
+        cache.put(&query, response.clone(), 0).await.unwrap();
 
         // Should be in cache immediately
-        assert!(cache.get(&query).await.is_some());
+        assert!(cache.get(&query, 0).await.is_some());
 
         // Wait for expiration
         tokio::time::sleep(Duration::from_millis(150)).await;
 
         // Should be expired
-        assert!(cache.get(&query).await.is_none());
+        assert!(cache.get(&query, 0).await.is_none());
     }
 
     #[tokio::test]
@@ -427,10 +734,15 @@ mod tests {
             total_matches: 0,
             search_time_ms: 0,
             from_cache: None,
+            suggestions: vec![],
+            truncated_files: vec![],
+            schema_version: SEARCH_RESPONSE_SCHEMA_VERSION,
+            continuation_token: None,
+            stale: false,
         };
 
         // Initial miss
-        assert!(cache.get(&query).await.is_none());
+        assert!(cache.get(&query, 0).await.is_none());
         assert_eq!(
             cache
                 .metrics
@@ -440,10 +752,10 @@ mod tests {
         );
 
         // Store in cache
-        cache.put(&query, response).await.unwrap();
+        cache.put(&query, response, 0).await.unwrap();
 
         // Cache hit
-        assert!(cache.get(&query).await.is_some());
+        assert!(cache.get(&query, 0).await.is_some());
         assert_eq!(
             cache
                 .metrics
@@ -455,4 +767,120 @@ mod tests {
         // Hit rate should be 50% (1 hit, 1 miss)
         assert_eq!(cache.metrics.get_hit_rate(), 0.5);
     }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() {
+        let config = CacheConfig {
+            l1_max_entries: 3,
+            ..Default::default()
+        };
+        let cache = MultiTierCache::new(config, None);
+
+        let query = |text: &str| SearchQuery {
+            query: text.to_string(),
+            mode: SearchMode::Symbol,
+            ..Default::default()
+        };
+        let response = |query: &SearchQuery| SearchResponse {
+            query: query.clone(),
+            results: vec![],
+            total_matches: 0,
+            search_time_ms: 0,
+            from_cache: None,
+            suggestions: vec![],
+            truncated_files: vec![],
+            schema_version: SEARCH_RESPONSE_SCHEMA_VERSION,
+            continuation_token: None,
+            stale: false,
+        };
+
+        for text in ["alpha", "beta", "gamma"] {
+            cache
+                .put(&query(text), response(&query(text)), 0)
+                .await
+                .unwrap();
+        }
+
+        // Reading alpha makes beta the oldest; rewriting gamma doesn't add an entry
+        assert!(cache.get(&query("alpha"), 0).await.is_some());
+        cache
+            .put(&query("gamma"), response(&query("gamma")), 0)
+            .await
+            .unwrap();
+        cache
+            .put(&query("delta"), response(&query("delta")), 0)
+            .await
+            .unwrap();
+
+        assert!(cache.get(&query("beta"), 0).await.is_none());
+        for text in ["alpha", "gamma", "delta"] {
+            assert!(
+                cache.get(&query(text), 0).await.is_some(),
+                "{text} was evicted"
+            );
+        }
+
+        // Many repeated reads leave the access log bounded
+        for _ in 0..1000 {
+            cache.get(&query("alpha"), 0).await;
+        }
+        assert!(cache.recency.lock().len() <= 2 * 3 + 64);
+        assert_eq!(cache.l1_cache.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_l2_serves_after_l1_is_cleared() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(StorageBackend::new(temp_dir.path()).await.unwrap());
+        let config = CacheConfig {
+            l2_ttl: Duration::from_secs(3600),
+            ..Default::default()
+        };
+        let cache = MultiTierCache::new(config.clone(), Some(storage.clone()));
+
+        let query = SearchQuery {
+            query: "parse config".to_string(),
+            mode: SearchMode::Symbol,
+            ..Default::default()
+        };
+        let response = SearchResponse {
+            query: query.clone(),
+            results: vec![],
+            total_matches: 3,
+            search_time_ms: 0,
+            from_cache: None,
+            suggestions: vec![],
+            truncated_files: vec![],
+            schema_version: SEARCH_RESPONSE_SCHEMA_VERSION,
+            continuation_token: None,
+            stale: false,
+        };
+        cache.put(&query, response, 0).await.unwrap();
+
+        // Not in L1 any more, but still on disk; the hit is promoted back into L1
+        cache.l1_cache.clear();
+        let cached = cache.get(&query, 0).await.unwrap();
+        assert_eq!(cached.total_matches, 3);
+        assert_eq!(cache.metrics.l1_misses.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.metrics.l2_hits.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.l1_cache.len(), 1);
+
+        // A fresh cache over the same store, e.g. after a restart, hits too
+        let restarted = MultiTierCache::new(config, Some(storage.clone()));
+        assert!(restarted.get(&query, 0).await.is_some());
+
+        // Entries from another scope, e.g. before the index changed, are invisible
+        restarted.l1_cache.clear();
+        assert!(restarted.get(&query, 1).await.is_none());
+
+        // L2 is opt-in
+        let memory_only = MultiTierCache::new(CacheConfig::default(), Some(storage.clone()));
+        assert!(memory_only.get(&query, 0).await.is_none());
+
+        // Clearing the cache clears both tiers
+        restarted.clear().await;
+        cache.l1_cache.clear();
+        assert!(cache.get(&query, 0).await.is_none());
+        assert_eq!(cache.metrics.l2_misses.load(Ordering::Relaxed), 1);
+    }
 }
@@ -5,6 +5,7 @@ use tracing::{debug, warn};
 use tree_sitter::{Language as TSLanguage, Node, Parser, Query, QueryCursor};
 
 use crate::indexing::language_detector::Language;
+use crate::indexing::symbol_extractor::import_query_source;
 
 use super::chunker::{ChunkType, CodeChunk};
 
@@ -15,7 +16,8 @@ pub struct AstChunkerConfig {
     pub target_size: usize,
     /// Maximum chunk size (will split even semantic units if exceeded)
     pub max_size: usize,
-    /// Minimum chunk size (won't create chunks smaller than this)
+    /// Minimum chunk size. Small adjacent units are merged until a chunk reaches it,
+    /// even past `target_size`, but never past `max_size`.
     pub min_size: usize,
     /// Whether to include imports/headers as context
     pub include_imports: bool,
@@ -23,6 +25,30 @@ pub struct AstChunkerConfig {
     pub include_parent_context: bool,
     /// Overlap strategy for context preservation
     pub context_overlap: ContextOverlap,
+    /// Size targets for specific languages, replacing the sizes above
+    pub language_overrides: HashMap<Language, ChunkSizes>,
+}
+
+/// Chunk size targets, in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkSizes {
+    pub target_size: usize,
+    pub max_size: usize,
+    pub min_size: usize,
+}
+
+impl AstChunkerConfig {
+    /// Size targets for a language, falling back to the defaults
+    pub fn sizes_for(&self, language: Language) -> ChunkSizes {
+        self.language_overrides
+            .get(&language)
+            .copied()
+            .unwrap_or(ChunkSizes {
+                target_size: self.target_size,
+                max_size: self.max_size,
+                min_size: self.min_size,
+            })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +68,7 @@ impl Default for AstChunkerConfig {
             include_imports: true,
             include_parent_context: true,
             context_overlap: ContextOverlap::Moderate,
+            language_overrides: HashMap::new(),
         }
     }
 }
@@ -161,7 +188,7 @@ impl AstChunker {
                         body: (_) @trait.body) @trait
                     "#,
                 )?,
-                import_query: Query::new(&ts_language, r#"(use_declaration) @import"#)?,
+                import_query: Self::import_query(language, &ts_language, "")?,
                 _module_query: Some(Query::new(&ts_language, r#"(mod_item) @module"#)?),
             },
             Language::Python => ChunkingQueries {
@@ -182,13 +209,7 @@ impl AstChunker {
                         body: (_) @class.body) @class
                     "#,
                 )?,
-                import_query: Query::new(
-                    &ts_language,
-                    r#"
-                    (import_statement) @import
-                    (import_from_statement) @import
-                    "#,
-                )?,
+                import_query: Self::import_query(language, &ts_language, "")?,
                 _module_query: None,
             },
             Language::JavaScript | Language::TypeScript => ChunkingQueries {
@@ -218,12 +239,10 @@ impl AstChunker {
                         body: (class_body) @class.body) @class
                     "#,
                 )?,
-                import_query: Query::new(
+                import_query: Self::import_query(
+                    language,
                     &ts_language,
-                    r#"
-                    (import_statement) @import
-                    (export_statement) @export
-                    "#,
+                    "(export_statement) @export",
                 )?,
                 _module_query: None,
             },
@@ -252,7 +271,7 @@ impl AstChunker {
                             type: (_) @type.body)) @type
                     "#,
                 )?,
-                import_query: Query::new(&ts_language, r#"(import_declaration) @import"#)?,
+                import_query: Self::import_query(language, &ts_language, "")?,
                 _module_query: Some(Query::new(&ts_language, r#"(package_clause) @package"#)?),
             },
             _ => {
@@ -268,6 +287,14 @@ impl AstChunker {
         Ok(())
     }
 
+    /// The shared import query for `language` (see `import_query_source`), plus `extra`
+    /// patterns captured alongside imports
+    fn import_query(language: Language, ts_language: &TSLanguage, extra: &str) -> Result<Query> {
+        let source = import_query_source(language)
+            .ok_or_else(|| anyhow!("No import query for {:?}", language))?;
+        Ok(Query::new(ts_language, &format!("{source}\n{extra}"))?)
+    }
+
     /// Extract semantic units from the AST
     fn extract_semantic_units(
         &mut self,
@@ -365,6 +392,7 @@ impl AstChunker {
         file_path: &str,
         language: Language,
     ) -> Result<Vec<CodeChunk>> {
+        let sizes = self.config.sizes_for(language);
         let mut chunks = Vec::new();
         let mut current_chunk = ChunkBuilder::new(file_path, language);
 
@@ -390,7 +418,7 @@ impl AstChunker {
             let unit_size = unit_content.len();
 
             // Check if this unit alone exceeds max size
-            if unit_size > self.config.max_size {
+            if unit_size > sizes.max_size {
                 // Flush current chunk if not empty
                 if !current_chunk.is_empty() {
                     chunks.push(current_chunk.build());
@@ -402,10 +430,11 @@ impl AstChunker {
                 continue;
             }
 
-            // Check if adding this unit would exceed target size
-            if !current_chunk.is_empty()
-                && current_chunk.size() + unit_size > self.config.target_size
-            {
+            // Check if adding this unit would exceed target size. A chunk still under
+            // min_size keeps growing instead, as long as it stays within max size.
+            let combined = current_chunk.size() + unit_size;
+            let below_min = current_chunk.size() < sizes.min_size && combined <= sizes.max_size;
+            if !current_chunk.is_empty() && combined > sizes.target_size && !below_min {
                 // Flush current chunk
                 chunks.push(current_chunk.build());
 
@@ -420,9 +449,21 @@ impl AstChunker {
             current_chunk.add_unit(unit, unit_content);
         }
 
-        // Flush final chunk
+        // Flush final chunk, folding it into the previous one if it's too small to
+        // stand alone
         if !current_chunk.is_empty() {
-            chunks.push(current_chunk.build());
+            let last = current_chunk.build();
+            match chunks.last_mut() {
+                Some(previous)
+                    if last.content.len() < sizes.min_size
+                        && previous.content.len() + last.content.len() <= sizes.max_size =>
+                {
+                    previous.content.push_str("\n\n");
+                    previous.content.push_str(&last.content);
+                    previous.end_line = last.end_line;
+                },
+                _ => chunks.push(last),
+            }
         }
 
         // Ensure we have at least one chunk
@@ -448,9 +489,10 @@ impl AstChunker {
         file_path: &str,
         language: Language,
     ) -> Result<Vec<CodeChunk>> {
+        let sizes = self.config.sizes_for(language);
         warn!(
             "Splitting large {:?} unit ({}+ chars)",
-            unit.kind, self.config.max_size
+            unit.kind, sizes.max_size
         );
 
         let unit_content = &source[unit.start_byte..unit.end_byte];
@@ -458,7 +500,8 @@ impl AstChunker {
 
         // Try to split at logical boundaries within the unit
         let mut chunks = Vec::new();
-        let chunk_lines = self.config.target_size / 80; // Approximate chars per line
+        // Approximate chars per line; a target under one line still takes one
+        let chunk_lines = (sizes.target_size / 80).max(1);
 
         for chunk_start in (0..lines.len()).step_by(chunk_lines) {
             let chunk_end = (chunk_start + chunk_lines).min(lines.len());
@@ -699,5 +742,92 @@ def standalone_function(x: int) -> int:
 
         // Should split the large function into multiple chunks
         assert!(chunks.len() > 1);
+
+        // A target shorter than a line splits one line at a time
+        let config = AstChunkerConfig {
+            target_size: 40,
+            max_size: 100,
+            ..Default::default()
+        };
+        let chunks = AstChunker::new(config)
+            .chunk_file(&large_function, "test.rs", Language::Rust)
+            .unwrap();
+        assert!(chunks.len() >= 200);
+    }
+
+    #[test]
+    fn test_language_size_overrides() {
+        let mut code = String::new();
+        for i in 0..12 {
+            code.push_str(&format!(
+                "def handler_{i}(request):\n    value = request.get('field_{i}')\n    return value * {i}\n\n"
+            ));
+        }
+
+        let default_config = AstChunkerConfig {
+            target_size: 1500,
+            max_size: 3000,
+            ..Default::default()
+        };
+        let default_chunks = AstChunker::new(default_config.clone())
+            .chunk_file(&code, "handlers.py", Language::Python)
+            .unwrap();
+
+        let mut overridden = default_config;
+        overridden.language_overrides.insert(
+            Language::Python,
+            ChunkSizes {
+                target_size: 200,
+                max_size: 400,
+                min_size: 50,
+            },
+        );
+        let python_chunks = AstChunker::new(overridden.clone())
+            .chunk_file(&code, "handlers.py", Language::Python)
+            .unwrap();
+
+        assert_eq!(default_chunks.len(), 1);
+        assert!(python_chunks.len() > default_chunks.len());
+        assert!(python_chunks.iter().all(|c| c.content.len() <= 400));
+
+        // Other languages keep the defaults
+        assert_eq!(overridden.sizes_for(Language::Rust).target_size, 1500);
+    }
+
+    #[test]
+    fn test_small_units_merge_up_to_min_size() {
+        let mut code = String::new();
+        for i in 0..3 {
+            code.push_str(&format!("fn tiny_{i}() -> u8 {{ {i} }}\n\n"));
+        }
+        code.push_str("fn medium() -> u32 {\n");
+        for i in 0..6 {
+            code.push_str(&format!("    let v{i} = {i} * 2;\n"));
+        }
+        code.push_str("    v0 + v5\n}\n\n");
+        for i in 3..5 {
+            code.push_str(&format!("fn tiny_{i}() -> u8 {{ {i} }}\n\n"));
+        }
+
+        let config = AstChunkerConfig {
+            target_size: 150,
+            max_size: 400,
+            min_size: 120,
+            include_imports: false,
+            ..Default::default()
+        };
+        let chunks = AstChunker::new(config)
+            .chunk_file(&code, "tiny.rs", Language::Rust)
+            .unwrap();
+
+        assert!(!chunks.is_empty());
+        assert!(
+            chunks.iter().all(|c| c.content.len() >= 120),
+            "chunk sizes: {:?}",
+            chunks.iter().map(|c| c.content.len()).collect::<Vec<_>>()
+        );
+        assert!(chunks.iter().all(|c| c.content.len() <= 400));
+        assert!(chunks[0].content.contains("tiny_0") && chunks[0].content.contains("medium"));
+        assert!(chunks.last().unwrap().content.contains("tiny_4"));
     }
 }
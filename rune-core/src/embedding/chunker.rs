@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+use std::ops::Range;
 use std::str::FromStr;
 use tracing::{debug, warn};
 
-use super::ast_chunker::{AstChunker, AstChunkerConfig};
+use super::ast_chunker::{AstChunker, AstChunkerConfig, ChunkSizes};
 use crate::indexing::language_detector::Language;
 
 /// Configuration for the chunking strategy
@@ -15,6 +17,23 @@ pub struct ChunkerConfig {
     pub preserve_structure: bool,
     /// Maximum chunk size (even if preserving structure)
     pub max_chunk_size: usize,
+    /// Size targets for specific languages, replacing `chunk_size`/`max_chunk_size`
+    pub language_overrides: HashMap<Language, ChunkSizes>,
+    /// Chunk Markdown and plain text by heading sections and paragraphs instead of
+    /// code structure
+    pub prose_chunking: bool,
+}
+
+impl ChunkerConfig {
+    /// Target and maximum chunk size for a language name, falling back to the defaults
+    fn sizes_for(&self, language: Option<&str>) -> (usize, usize) {
+        language
+            .and_then(|l| Language::from_str(l).ok())
+            .and_then(|l| self.language_overrides.get(&l))
+            .map_or((self.chunk_size, self.max_chunk_size), |s| {
+                (s.target_size, s.max_size)
+            })
+    }
 }
 
 impl Default for ChunkerConfig {
@@ -24,6 +43,8 @@ impl Default for ChunkerConfig {
             overlap: 0.15,    // 15% overlap
             preserve_structure: true,
             max_chunk_size: 3000, // ~1024 tokens
+            language_overrides: HashMap::new(),
+            prose_chunking: true,
         }
     }
 }
@@ -45,6 +66,7 @@ impl CodeChunker {
                 include_imports: true,
                 include_parent_context: true,
                 context_overlap: super::ast_chunker::ContextOverlap::Moderate,
+                language_overrides: config.language_overrides.clone(),
             };
             Some(AstChunker::new(ast_config))
         } else {
@@ -66,6 +88,10 @@ impl CodeChunker {
         // Detect language from file extension
         let language = Self::detect_language(file_path);
 
+        if self.config.prose_chunking && matches!(language.as_deref(), Some("markdown" | "text")) {
+            return self.chunk_prose(content, file_path, language.as_deref());
+        }
+
         // Try AST-based chunking first if available
         if self.config.preserve_structure {
             if let Some(lang_str) = language.as_deref()
@@ -98,7 +124,9 @@ impl CodeChunker {
         let lines: Vec<&str> = content.lines().collect();
         let mut chunks = Vec::new();
 
-        let chunk_lines = self.config.chunk_size / 80; // Assume ~80 chars per line
+        let language = Self::detect_language(file_path);
+        let (chunk_size, _) = self.config.sizes_for(language.as_deref());
+        let chunk_lines = chunk_size / 80; // Assume ~80 chars per line
         let overlap_lines = (chunk_lines as f32 * self.config.overlap) as usize;
 
         let mut start_line = 0;
@@ -112,7 +140,7 @@ impl CodeChunker {
                 file_path: file_path.to_string(),
                 start_line: start_line + 1, // 1-indexed
                 end_line,
-                language: Self::detect_language(file_path),
+                language: language.clone(),
                 chunk_type: ChunkType::Block,
             });
 
@@ -146,6 +174,7 @@ impl CodeChunker {
         file_path: &str,
         language: Option<&str>,
     ) -> Vec<CodeChunk> {
+        let (chunk_size, max_chunk_size) = self.config.sizes_for(language);
         let lines: Vec<&str> = content.lines().collect();
         let mut chunks = Vec::new();
         let mut current_chunk = Vec::new();
@@ -195,9 +224,9 @@ impl CodeChunker {
             // Check if we should start a new chunk
             let should_split = is_boundary
                 && !current_chunk.is_empty()
-                && (current_size > self.config.chunk_size / 2 || brace_depth == 0);
+                && (current_size > chunk_size / 2 || brace_depth == 0);
 
-            if should_split || current_size > self.config.max_chunk_size {
+            if should_split || current_size > max_chunk_size {
                 // Save current chunk
                 if !current_chunk.is_empty() {
                     chunks.push(CodeChunk {
@@ -259,6 +288,109 @@ impl CodeChunker {
         chunks
     }
 
+    /// Split prose into heading sections, one chunk each. Sections over
+    /// `max_chunk_size` are packed paragraph by paragraph up to `chunk_size`, and a
+    /// single paragraph that is still too large is cut by lines. Headings inside
+    /// fenced code blocks don't start sections.
+    fn chunk_prose(
+        &self,
+        content: &str,
+        file_path: &str,
+        language: Option<&str>,
+    ) -> Vec<CodeChunk> {
+        let (chunk_size, max_chunk_size) = self.config.sizes_for(language);
+        let lines: Vec<&str> = content.lines().collect();
+        let size = |range: Range<usize>| lines[range].iter().map(|l| l.len() + 1).sum::<usize>();
+
+        // Section and paragraph boundaries as 0-based line ranges
+        let mut sections: Vec<Vec<Range<usize>>> = Vec::new();
+        let mut paragraph_start: Option<usize> = None;
+        let mut in_fence = false;
+        for (i, line) in lines.iter().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+            }
+            let is_heading = !in_fence && is_markdown_heading(trimmed);
+            let is_blank = !in_fence && trimmed.is_empty();
+
+            if (is_heading || is_blank)
+                && let Some(start) = paragraph_start.take()
+            {
+                sections
+                    .last_mut()
+                    .expect("paragraph belongs to a section")
+                    .push(start..i);
+            }
+            if is_heading || sections.is_empty() {
+                sections.push(Vec::new());
+            }
+            if !is_blank && paragraph_start.is_none() {
+                paragraph_start = Some(i);
+            }
+        }
+        if let Some(start) = paragraph_start {
+            sections
+                .last_mut()
+                .expect("paragraph belongs to a section")
+                .push(start..lines.len());
+        }
+
+        let mut ranges = Vec::new();
+        for paragraphs in sections.iter().filter(|p| !p.is_empty()) {
+            let section = paragraphs[0].start..paragraphs[paragraphs.len() - 1].end;
+            if size(section.clone()) <= max_chunk_size {
+                ranges.push(section);
+                continue;
+            }
+
+            let mut current: Option<Range<usize>> = None;
+            for paragraph in paragraphs {
+                // A heading stays with the paragraph that follows it
+                if let Some(ref range) = current
+                    && size(range.start..paragraph.end) > chunk_size
+                    && !(range.len() == 1 && is_markdown_heading(lines[range.start].trim()))
+                {
+                    ranges.push(current.take().expect("checked above"));
+                }
+                let range = match current.take() {
+                    Some(range) => range.start..paragraph.end,
+                    None => paragraph.clone(),
+                };
+                if size(range.clone()) <= max_chunk_size {
+                    current = Some(range);
+                    continue;
+                }
+
+                // One oversized paragraph: cut it into pieces of at most chunk_size
+                let mut start = range.start;
+                for end in range.clone() {
+                    if end > start && size(start..end + 1) > chunk_size {
+                        ranges.push(start..end);
+                        start = end;
+                    }
+                }
+                current = Some(start..range.end);
+            }
+            ranges.extend(current);
+        }
+
+        let chunks: Vec<CodeChunk> = ranges
+            .into_iter()
+            .map(|range| CodeChunk {
+                content: lines[range.clone()].join("\n"),
+                file_path: file_path.to_string(),
+                start_line: range.start + 1,
+                end_line: range.end,
+                language: language.map(str::to_string),
+                chunk_type: ChunkType::Documentation,
+            })
+            .collect();
+
+        debug!("Created {} prose chunks for {}", chunks.len(), file_path);
+        chunks
+    }
+
     /// Detect language from file extension
     fn detect_language(file_path: &str) -> Option<String> {
         let extension = file_path.rsplit('.').next()?;
@@ -278,7 +410,8 @@ impl CodeChunker {
             "scala" => "scala",
             "sh" | "bash" => "bash",
             "sql" => "sql",
-            "md" => "markdown",
+            "md" | "markdown" => "markdown",
+            "txt" => "text",
             _ => return None,
         };
 
@@ -286,6 +419,12 @@ impl CodeChunker {
     }
 }
 
+/// ATX heading such as `## Install`: one to six `#` followed by a space or nothing
+fn is_markdown_heading(line: &str) -> bool {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    (1..=6).contains(&level) && line[level..].chars().next().is_none_or(char::is_whitespace)
+}
+
 /// Represents a chunk of code
 #[derive(Debug, Clone)]
 pub struct CodeChunk {
@@ -306,3 +445,60 @@ pub enum ChunkType {
     Import,
     Documentation,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_chunks_follow_headings() {
+        let markdown = "\
+# Rune
+
+Fast code search.
+
+## Install
+
+Run the installer.
+
+```sh
+# not a heading
+cargo install rune
+```
+
+## Usage
+
+Start the server.
+
+Then query it.
+";
+        let mut chunker = CodeChunker::new(ChunkerConfig::default());
+        let chunks = chunker.chunk_file(markdown, "README.md");
+
+        let spans: Vec<(usize, usize)> =
+            chunks.iter().map(|c| (c.start_line, c.end_line)).collect();
+        assert_eq!(spans, vec![(1, 3), (5, 12), (14, 18)]);
+        assert!(chunks[1].content.starts_with("## Install"));
+        assert!(chunks[1].content.contains("cargo install rune"));
+        assert!(chunks[2].content.ends_with("Then query it."));
+        assert!(
+            chunks
+                .iter()
+                .all(|c| c.chunk_type == ChunkType::Documentation)
+        );
+
+        // Sections too large for one chunk are split between paragraphs
+        let config = ChunkerConfig {
+            chunk_size: 20,
+            max_chunk_size: 40,
+            ..Default::default()
+        };
+        let chunks = CodeChunker::new(config).chunk_file(markdown, "README.md");
+        let usage: Vec<(usize, usize)> = chunks
+            .iter()
+            .filter(|c| c.start_line >= 14)
+            .map(|c| (c.start_line, c.end_line))
+            .collect();
+        assert_eq!(usage, vec![(14, 16), (18, 18)]);
+    }
+}
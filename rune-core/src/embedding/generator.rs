@@ -5,82 +5,216 @@ use ort::{
     session::{Session, builder::GraphOptimizationLevel},
     value::Tensor,
 };
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use tokenizers::Tokenizer;
 use tracing::{debug, info, warn};
 
-use super::model_manager::ModelManager;
-use crate::Config;
+use super::model_manager::{DOWNLOAD_RETRY_DELAY, MODEL_NAME, ModelManager};
+use super::remote::RemoteEmbedder;
+use crate::{Config, EmbeddingProvider, EmbeddingStatus, OnnxOptimizationLevel};
 
-/// Manages embedding generation using ONNX Runtime with caching and batch processing
+/// Manages embedding generation using ONNX Runtime (or a remote endpoint, per
+/// `Config::embedding_provider`) with caching and batch processing
 pub struct EmbeddingGenerator {
     _config: Arc<Config>, // Kept for potential future configuration needs
-    session: Option<Arc<Mutex<Session>>>,
+    sessions: Option<Arc<SessionPool>>,
     tokenizer: Option<Arc<Tokenizer>>,
     /// Cache embeddings by content hash to avoid recomputation
     cache: Arc<DashMap<String, Vec<f32>>>,
     dimension: usize,
     fallback_mode: bool,
+    model_name: String,
+    /// Set when embeddings come from `EmbeddingProvider::Remote` instead of ONNX
+    remote: Option<RemoteEmbedder>,
+}
+
+/// One ONNX session per `Config::max_concurrent_embeddings`, so that many batches
+/// can run inference at once
+struct SessionPool {
+    sessions: Vec<Mutex<Session>>,
+    next: AtomicUsize,
+}
+
+impl SessionPool {
+    /// Run `f` on an idle session, or wait for the next one in turn if all are busy
+    fn with_session<T>(&self, f: impl FnOnce(&mut Session) -> Result<T>) -> Result<T> {
+        let mut session = match self.sessions.iter().find_map(|s| s.try_lock().ok()) {
+            Some(session) => session,
+            None => {
+                let turn = self.next.fetch_add(1, Ordering::Relaxed) % self.sessions.len();
+                self.sessions[turn]
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Session mutex poisoned: {:?}", e))?
+            },
+        };
+        f(&mut session)
+    }
 }
 
 impl EmbeddingGenerator {
+    /// Generator for the configured `embedding_provider`. A remote provider fails
+    /// with `RuneError::InvalidConfig` if its API key variable is unset.
     pub async fn new(config: Arc<Config>) -> Result<Self> {
-        let cache = Arc::new(DashMap::new());
+        match &config.embedding_provider {
+            EmbeddingProvider::Local => Self::for_model(config, MODEL_NAME).await,
+            EmbeddingProvider::Remote {
+                url,
+                model,
+                api_key_env,
+            } => {
+                let remote = RemoteEmbedder::new(
+                    url,
+                    model,
+                    api_key_env.as_deref(),
+                    config.embedding_dimension,
+                )?;
+                info!("Using remote embedding model {} at {}", model, url);
+                Ok(Self::with_remote(config, remote))
+            },
+        }
+    }
 
+    /// Generator that sends every text to `remote`. Vectors have the configured
+    /// `embedding_dimension`.
+    pub fn with_remote(config: Arc<Config>, remote: RemoteEmbedder) -> Self {
+        Self {
+            model_name: remote.model().to_string(),
+            remote: Some(remote),
+            fallback_mode: false,
+            ..Self::fallback(config)
+        }
+    }
+
+    /// Load a specific embedding model instead of the default `MODEL_NAME`
+    pub async fn for_model(config: Arc<Config>, model: &str) -> Result<Self> {
         // Try to initialize ONNX model
-        match Self::initialize_model(&config).await {
-            Ok((session, tokenizer)) => {
-                info!("Successfully initialized all-MiniLM-L6-v2 model (384 dimensions)");
+        match Self::initialize_model(&config, model).await {
+            Ok((sessions, tokenizer, dimension)) => {
+                info!(
+                    "Successfully initialized {} model with {} sessions ({} dimensions)",
+                    model,
+                    sessions.sessions.len(),
+                    dimension
+                );
                 Ok(Self {
                     _config: config,
-                    session: Some(Arc::new(Mutex::new(session))),
-                    tokenizer: Some(Arc::new(tokenizer)),
-                    cache,
-                    dimension: 384,
+                    sessions: Some(sessions),
+                    tokenizer: Some(tokenizer),
+                    cache: Arc::new(DashMap::new()),
+                    dimension,
                     fallback_mode: false,
+                    model_name: model.to_string(),
+                    remote: None,
                 })
             },
             Err(e) => {
                 warn!(
-                    "Failed to initialize ONNX model: {}. Using fallback mode.",
+                    "Failed to initialize ONNX model: {:#}. Using fallback mode.",
                     e
                 );
                 Ok(Self {
-                    _config: config,
-                    session: None,
-                    tokenizer: None,
-                    cache,
-                    dimension: 256, // Fallback dimension
-                    fallback_mode: true,
+                    model_name: model.to_string(),
+                    ..Self::fallback(config)
                 })
             },
         }
     }
 
-    async fn initialize_model(config: &Arc<Config>) -> Result<(Session, Tokenizer)> {
+    /// Fallback vectors from a generator that reports itself available, so tests can
+    /// exercise code that only runs once a model is loaded
+    #[cfg(test)]
+    pub(crate) fn stand_in(config: Arc<Config>) -> Self {
+        Self {
+            fallback_mode: false,
+            ..Self::fallback(config)
+        }
+    }
+
+    /// Hash-based generator used when the model can't be loaded. Vectors have the
+    /// configured `embedding_dimension` so they can still be stored alongside real ones.
+    pub(crate) fn fallback(config: Arc<Config>) -> Self {
+        Self {
+            dimension: config.embedding_dimension,
+            _config: config,
+            sessions: None,
+            tokenizer: None,
+            cache: Arc::new(DashMap::new()),
+            fallback_mode: true,
+            model_name: MODEL_NAME.to_string(),
+            remote: None,
+        }
+    }
+
+    /// Load the model's tokenizer and a session for each concurrent embedding batch,
+    /// sharing the CPU's threads between them. The embedding dimension is read from
+    /// the output of a probe inference, since models differ in hidden size.
+    async fn initialize_model(
+        config: &Arc<Config>,
+        model: &str,
+    ) -> Result<(Arc<SessionPool>, Arc<Tokenizer>, usize)> {
         // Get model path using ModelManager
-        let model_manager = ModelManager::with_cache_dir(config.cache_dir.clone());
+        let model_manager = ModelManager::for_model(config.cache_dir.clone(), model)
+            .with_retries(config.model_download_retries, DOWNLOAD_RETRY_DELAY);
 
         let model_path = model_manager
             .get_model_path()
             .await
             .context("Failed to get model path")?;
 
-        // Initialize ONNX session with ORT v2 API
-        let session = Session::builder()
-            .map_err(|e| anyhow::anyhow!("Failed to create session builder: {:?}", e))?
-            .with_optimization_level(GraphOptimizationLevel::Level3)
-            .map_err(|e| anyhow::anyhow!("Failed to set optimization level: {:?}", e))?
-            .with_intra_threads(num_cpus::get())
-            .map_err(|e| anyhow::anyhow!("Failed to set intra threads: {:?}", e))?
-            .commit_from_file(model_path.join("model.onnx"))
-            .map_err(|e| anyhow::anyhow!("Failed to load model from file: {:?}", e))?;
+        // Initialize ONNX sessions with ORT v2 API
+        let pool_size = config.max_concurrent_embeddings.max(1);
+        let intra_threads = (num_cpus::get() / pool_size).max(1);
+        let sessions = (0..pool_size)
+            .map(|_| {
+                Session::builder()
+                    .map_err(|e| anyhow::anyhow!("Failed to create session builder: {:?}", e))?
+                    .with_optimization_level(ort_optimization_level(config.onnx_optimization_level))
+                    .map_err(|e| anyhow::anyhow!("Failed to set optimization level: {:?}", e))?
+                    .with_intra_threads(intra_threads)
+                    .map_err(|e| anyhow::anyhow!("Failed to set intra threads: {:?}", e))?
+                    .commit_from_file(model_path.join("model.onnx"))
+                    .map_err(|e| anyhow::anyhow!("Failed to load model from file: {:?}", e))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         // Load tokenizer
-        let tokenizer = Tokenizer::from_file(model_path.join("tokenizer.json"))
-            .map_err(|e| anyhow::anyhow!("Failed to load tokenizer: {}", e))?;
+        let tokenizer = Arc::new(
+            Tokenizer::from_file(model_path.join("tokenizer.json"))
+                .map_err(|e| anyhow::anyhow!("Failed to load tokenizer: {}", e))?,
+        );
+
+        let sessions = Arc::new(SessionPool {
+            sessions: sessions.into_iter().map(Mutex::new).collect(),
+            next: AtomicUsize::new(0),
+        });
 
-        Ok((session, tokenizer))
+        let probe = (sessions.clone(), tokenizer.clone());
+        let dimension = tokio::task::spawn_blocking(move || {
+            Self::run_batch(&probe.0, &probe.1, &["fn main() {}".to_string()])
+        })
+        .await??
+        .first()
+        .map(Vec::len)
+        .filter(|&len| len > 0)
+        .context("Model produced no embedding dimension")?;
+
+        Ok((sessions, tokenizer, dimension))
+    }
+
+    /// Number of cached embeddings and an estimate of the bytes they occupy
+    pub fn cache_usage(&self) -> (usize, usize) {
+        let bytes = self
+            .cache
+            .iter()
+            .map(|entry| {
+                std::mem::size_of::<String>()
+                    + entry.key().len()
+                    + std::mem::size_of::<Vec<f32>>()
+                    + entry.value().len() * std::mem::size_of::<f32>()
+            })
+            .sum();
+        (self.cache.len(), bytes)
     }
 
     /// Generate embedding for a single text
@@ -92,128 +226,30 @@ impl EmbeddingGenerator {
             return Ok(cached.clone());
         }
 
-        let embedding = if self.fallback_mode {
-            self.generate_fallback_embedding(text)?
-        } else {
-            self.generate_onnx_embedding(text).await?
+        let embedding = match self.remote {
+            Some(ref remote) => remote
+                .embed(&[text.to_string()])
+                .await?
+                .pop()
+                .context("Embedding endpoint returned no vector")?,
+            None if self.sessions.is_none() => self.generate_fallback_embedding(text)?,
+            None => self.generate_onnx_embedding(text).await?,
         };
 
         self.cache.insert(hash, embedding.clone());
         Ok(embedding)
     }
 
-    /// Generate embedding using ONNX model
+    /// Generate embedding using ONNX model, as a batch of one
     async fn generate_onnx_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        let session = self
-            .session
-            .as_ref()
-            .context("ONNX session not initialized")?;
-        let tokenizer = self
-            .tokenizer
-            .as_ref()
-            .context("Tokenizer not initialized")?;
-
-        // Tokenize the text
-        let encoding = tokenizer
-            .encode(text, true)
-            .map_err(|e| anyhow::anyhow!("Tokenization failed: {}", e))?;
-
-        let input_ids = encoding.get_ids();
-        let attention_mask = encoding.get_attention_mask();
-        let seq_len = input_ids.len();
-
-        // Convert to ndarray for ONNX
-        let input_ids_array = Array2::from_shape_vec(
-            (1, seq_len),
-            input_ids.iter().map(|&id| id as i64).collect(),
-        )?;
-
-        let attention_mask_array = Array2::from_shape_vec(
-            (1, seq_len),
-            attention_mask.iter().map(|&m| m as i64).collect(),
-        )?;
-
-        // Prepare token_type_ids (all zeros for single sequence)
-        let token_type_ids_array = Array2::<i64>::zeros((1, seq_len));
-
-        // Create tensors from arrays (ORT v2 API)
-        let input_ids_tensor = Tensor::from_array(input_ids_array)
-            .map_err(|e| anyhow::anyhow!("Failed to create input_ids tensor: {:?}", e))?;
-        let attention_mask_tensor = Tensor::from_array(attention_mask_array)
-            .map_err(|e| anyhow::anyhow!("Failed to create attention_mask tensor: {:?}", e))?;
-        let token_type_ids_tensor = Tensor::from_array(token_type_ids_array)
-            .map_err(|e| anyhow::anyhow!("Failed to create token_type_ids tensor: {:?}", e))?;
-
-        // Run inference with named inputs and extract embeddings
-        let embeddings_view = {
-            let mut session_guard = session
-                .lock()
-                .map_err(|e| anyhow::anyhow!("Session mutex poisoned: {:?}", e))?;
-            let outputs = session_guard
-                .run(ort::inputs![
-                    "input_ids" => input_ids_tensor,
-                    "attention_mask" => attention_mask_tensor,
-                    "token_type_ids" => token_type_ids_tensor
-                ])
-                .map_err(|e| anyhow::anyhow!("Failed to run inference: {:?}", e))?;
-
-            // Extract embeddings - for all-MiniLM-L6-v2, the output is "last_hidden_state"
-            let embeddings_view: ndarray::ArrayViewD<f32> = outputs["last_hidden_state"]
-                .try_extract_array()
-                .map_err(|e| anyhow::anyhow!("Failed to extract embeddings tensor: {:?}", e))?;
-
-            // Clone the data to own it before dropping the lock
-            embeddings_view.to_owned()
-        };
-
-        // Apply mean pooling
-        let pooled = self.mean_pool_ndarray(embeddings_view.view(), attention_mask);
-
-        // L2 normalize for cosine similarity
-        let normalized = self.l2_normalize(pooled);
-
-        Ok(normalized)
-    }
-
-    /// Apply mean pooling to embeddings using ndarray
-    fn mean_pool_ndarray(
-        &self,
-        embeddings: ndarray::ArrayViewD<f32>,
-        attention_mask: &[u32],
-    ) -> Vec<f32> {
-        // embeddings shape should be [1, seq_len, hidden_size]
-        let shape = embeddings.shape();
-        if shape.len() != 3 {
-            // Fallback to zeros if unexpected shape
-            return vec![0.0; 384];
-        }
-
-        let seq_len = shape[1];
-        let hidden_size = shape[2];
-
-        let mut pooled = vec![0.0; hidden_size];
-        let mut valid_tokens = 0.0;
-
-        for i in 0..seq_len.min(attention_mask.len()) {
-            if attention_mask[i] == 1 {
-                valid_tokens += 1.0;
-                for j in 0..hidden_size {
-                    pooled[j] += embeddings[[0, i, j]];
-                }
-            }
-        }
-
-        if valid_tokens > 0.0 {
-            for val in &mut pooled {
-                *val /= valid_tokens;
-            }
-        }
-
-        pooled
+        self.batch_generate_onnx(&[text.to_string()])
+            .await?
+            .pop()
+            .context("ONNX model returned no embedding")
     }
 
     /// L2 normalize a vector
-    fn l2_normalize(&self, mut vec: Vec<f32>) -> Vec<f32> {
+    fn l2_normalize(mut vec: Vec<f32>) -> Vec<f32> {
         let norm: f32 = vec.iter().map(|x| x * x).sum::<f32>().sqrt();
         if norm > 0.0 {
             for val in &mut vec {
@@ -223,28 +259,21 @@ impl EmbeddingGenerator {
         vec
     }
 
-    /// Generate a fallback embedding (same as before for compatibility)
+    /// Generate a fallback embedding: the text's blake3 output stream stretched to
+    /// `dimension` values and L2-normalized. Deterministic, but carries no meaning.
     fn generate_fallback_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        let mut embedding = vec![0.0; self.dimension];
-        let text_hash = blake3::hash(text.as_bytes());
-        let hash_bytes = text_hash.as_bytes();
-
-        for (i, byte) in hash_bytes.iter().enumerate() {
-            if i >= self.dimension {
-                break;
-            }
-            embedding[i] = (*byte as f32) / 255.0 - 0.5;
-        }
-
-        // Normalize
-        let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
-        if norm > 0.0 {
-            for val in &mut embedding {
-                *val /= norm;
-            }
-        }
-
-        Ok(embedding)
+        let mut hash_bytes = vec![0u8; self.dimension];
+        blake3::Hasher::new()
+            .update(text.as_bytes())
+            .finalize_xof()
+            .fill(&mut hash_bytes);
+
+        let embedding = hash_bytes
+            .iter()
+            .map(|byte| (*byte as f32) / 255.0 - 0.5)
+            .collect();
+
+        Ok(Self::l2_normalize(embedding))
     }
 
     /// Generate embeddings for multiple texts with batch processing
@@ -253,7 +282,11 @@ impl EmbeddingGenerator {
             return Ok(Vec::new());
         }
 
-        if self.fallback_mode {
+        if let Some(ref remote) = self.remote {
+            return remote.embed(texts).await;
+        }
+
+        if self.sessions.is_none() {
             // Fallback mode: generate individually
             let mut embeddings = Vec::with_capacity(texts.len());
             for text in texts {
@@ -274,17 +307,28 @@ impl EmbeddingGenerator {
         Ok(all_embeddings)
     }
 
-    /// Batch generate embeddings using ONNX
+    /// Batch generate embeddings using ONNX. Tokenizing and inference run on the
+    /// blocking pool, so concurrent batches run on separate sessions without stalling
+    /// the async runtime.
     async fn batch_generate_onnx(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
-        let session = self
-            .session
-            .as_ref()
+        let sessions = self
+            .sessions
+            .clone()
             .context("ONNX session not initialized")?;
         let tokenizer = self
             .tokenizer
-            .as_ref()
+            .clone()
             .context("Tokenizer not initialized")?;
+        let texts = texts.to_vec();
+
+        tokio::task::spawn_blocking(move || Self::run_batch(&sessions, &tokenizer, &texts)).await?
+    }
 
+    fn run_batch(
+        sessions: &SessionPool,
+        tokenizer: &Tokenizer,
+        texts: &[String],
+    ) -> Result<Vec<Vec<f32>>> {
         // Tokenize all texts and find max length
         let mut encodings = Vec::new();
         let mut max_len = 0;
@@ -336,11 +380,8 @@ impl EmbeddingGenerator {
         })?;
 
         // Run batch inference and extract embeddings
-        let embeddings_array = {
-            let mut session_guard = session
-                .lock()
-                .map_err(|e| anyhow::anyhow!("Session mutex poisoned: {:?}", e))?;
-            let outputs = session_guard
+        let embeddings_array = sessions.with_session(|session| {
+            let outputs = session
                 .run(ort::inputs![
                     "input_ids" => input_ids_tensor,
                     "attention_mask" => attention_mask_tensor,
@@ -355,28 +396,37 @@ impl EmbeddingGenerator {
                     anyhow::anyhow!("Failed to extract batch embeddings tensor: {:?}", e)
                 })?;
 
-            // Clone the data to own it before dropping the lock
-            embeddings_view.to_owned()
-        };
-
-        let embeddings_view = embeddings_array.view();
+            // Clone the data to own it before releasing the session
+            Ok(embeddings_view.to_owned())
+        })?;
 
-        let hidden_size = 384;
-        let mut result = Vec::with_capacity(batch_size);
+        let masks: Vec<_> = encodings.iter().map(|e| e.get_attention_mask()).collect();
+        Self::mean_pool(embeddings_array.view(), &masks)
+    }
 
-        for (i, encoding) in encodings.iter().enumerate() {
-            let mask = encoding.get_attention_mask();
-            let text_len = encoding.len();
+    /// Mean-pool each text's token states over its attention mask and L2-normalize.
+    /// The hidden size is taken from the last axis of `hidden_states`
+    /// (batch x tokens x hidden).
+    fn mean_pool(
+        hidden_states: ndarray::ArrayViewD<f32>,
+        masks: &[&[u32]],
+    ) -> Result<Vec<Vec<f32>>> {
+        let hidden_size = match hidden_states.shape() {
+            [_, _, hidden] => *hidden,
+            shape => anyhow::bail!("Unexpected last_hidden_state shape {:?}", shape),
+        };
+        let mut result = Vec::with_capacity(masks.len());
 
+        for (i, mask) in masks.iter().enumerate() {
             // Extract embeddings for this text in the batch
             let mut pooled = vec![0.0; hidden_size];
             let mut valid_tokens = 0.0;
 
-            for j in 0..text_len {
-                if mask[j] == 1 {
+            for (j, &m) in mask.iter().enumerate() {
+                if m == 1 {
                     valid_tokens += 1.0;
                     for k in 0..hidden_size {
-                        pooled[k] += embeddings_view[[i, j, k]];
+                        pooled[k] += hidden_states[[i, j, k]];
                     }
                 }
             }
@@ -387,7 +437,7 @@ impl EmbeddingGenerator {
                 }
             }
 
-            let normalized = self.l2_normalize(pooled);
+            let normalized = Self::l2_normalize(pooled);
             result.push(normalized);
         }
 
@@ -403,11 +453,31 @@ impl EmbeddingGenerator {
     pub fn dimension(&self) -> usize {
         self.dimension
     }
+
+    /// Whether the model loaded or hash-based fallback vectors are being produced
+    pub fn status(&self) -> EmbeddingStatus {
+        EmbeddingStatus {
+            model_loaded: !self.fallback_mode,
+            fallback: self.fallback_mode,
+            dimension: self.dimension,
+            model_name: self.model_name.clone(),
+        }
+    }
+}
+
+fn ort_optimization_level(level: OnnxOptimizationLevel) -> GraphOptimizationLevel {
+    match level {
+        OnnxOptimizationLevel::Disable => GraphOptimizationLevel::Disable,
+        OnnxOptimizationLevel::Level1 => GraphOptimizationLevel::Level1,
+        OnnxOptimizationLevel::Level2 => GraphOptimizationLevel::Level2,
+        OnnxOptimizationLevel::Level3 => GraphOptimizationLevel::Level3,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embedding::remote::serve_embeddings;
     use tempfile::TempDir;
 
     #[tokio::test]
@@ -430,6 +500,81 @@ mod tests {
         assert!((norm - 1.0).abs() < 0.01);
     }
 
+    #[tokio::test]
+    async fn test_fallback_matches_configured_dimension() {
+        for dimension in [384, 512] {
+            let config = Arc::new(Config {
+                embedding_dimension: dimension,
+                ..Default::default()
+            });
+            let generator = EmbeddingGenerator::fallback(config);
+
+            let embedding = generator.generate_embedding("fn main() {}").await.unwrap();
+            assert_eq!(embedding.len(), dimension);
+            assert_eq!(generator.dimension(), dimension);
+
+            let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert!((norm - 1.0).abs() < 1e-4);
+
+            // Deterministic per text, distinct across texts
+            generator.cache.clear();
+            let again = generator.generate_embedding("fn main() {}").await.unwrap();
+            assert_eq!(embedding, again);
+            let other = generator.generate_embedding("struct Point;").await.unwrap();
+            assert_ne!(embedding, other);
+        }
+    }
+
+    #[test]
+    fn test_mean_pool_uses_model_hidden_size() {
+        // Two texts, three tokens, hidden size 8; the second text has one padding token
+        let states = ndarray::Array3::from_shape_fn((2, 3, 8), |(i, j, k)| {
+            if i == 1 && j == 2 {
+                100.0
+            } else {
+                (j + k) as f32
+            }
+        })
+        .into_dyn();
+        let pooled =
+            EmbeddingGenerator::mean_pool(states.view(), &[&[1, 1, 1], &[1, 1, 0]]).unwrap();
+
+        assert_eq!(pooled.len(), 2);
+        assert!(pooled.iter().all(|v| v.len() == 8));
+        // Padding is ignored, so the second text is the mean of tokens 0 and 1
+        let expected = EmbeddingGenerator::l2_normalize((0..8).map(|k| k as f32 + 0.5).collect());
+        for (got, want) in pooled[1].iter().zip(&expected) {
+            assert!((got - want).abs() < 1e-6);
+        }
+
+        let flat = ndarray::Array2::<f32>::zeros((1, 8)).into_dyn();
+        assert!(EmbeddingGenerator::mean_pool(flat.view(), &[&[1]]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cache_usage_scales_with_entries() {
+        let generator = EmbeddingGenerator::fallback(Arc::new(Config::default()));
+        assert_eq!(generator.cache_usage(), (0, 0));
+
+        for i in 0..10 {
+            generator
+                .generate_embedding(&format!("fn f{i}() {{}}"))
+                .await
+                .unwrap();
+        }
+        let (entries, ten) = generator.cache_usage();
+        assert_eq!(entries, 10);
+        assert!(ten >= 10 * 384 * std::mem::size_of::<f32>());
+
+        for i in 10..30 {
+            generator
+                .generate_embedding(&format!("fn f{i}() {{}}"))
+                .await
+                .unwrap();
+        }
+        assert_eq!(generator.cache_usage(), (30, ten * 3));
+    }
+
     #[tokio::test]
     async fn test_batch_generation() {
         let temp_dir = TempDir::new().unwrap();
@@ -458,6 +603,108 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_remote_provider_chunks_batches() {
+        let (url, batches) = serve_embeddings(8, true).await;
+        let config = Arc::new(Config {
+            embedding_dimension: 8,
+            embedding_provider: EmbeddingProvider::Remote {
+                url: url.clone(),
+                model: "text-embedding-3-small".to_string(),
+                api_key_env: None,
+            },
+            ..Default::default()
+        });
+        let remote = RemoteEmbedder::new(&url, "text-embedding-3-small", None, 8)
+            .unwrap()
+            .with_retries(2, std::time::Duration::from_millis(1));
+        let generator = EmbeddingGenerator::with_remote(config.clone(), remote);
+
+        let texts: Vec<String> = (1..=150).map(|len| "x".repeat(len)).collect();
+        let embeddings = generator.batch_generate(&texts).await.unwrap();
+        assert_eq!(embeddings.len(), 150);
+        for (i, embedding) in embeddings.iter().enumerate() {
+            assert_eq!(embedding, &vec![(i + 1) as f32; 8]);
+        }
+        // The first request failed with a 503 and was retried
+        assert_eq!(*batches.lock().unwrap(), vec![64, 64, 64, 22]);
+
+        let embedding = generator.generate_embedding("abc").await.unwrap();
+        assert_eq!(embedding, vec![3.0; 8]);
+        assert_eq!(batches.lock().unwrap().last(), Some(&1));
+
+        // `new` dispatches on the configured provider
+        let generator = EmbeddingGenerator::new(config).await.unwrap();
+        assert_eq!(generator.dimension(), 8);
+        assert!(generator.is_available());
+        assert_eq!(generator.status().model_name, "text-embedding-3-small");
+    }
+
+    #[tokio::test]
+    async fn test_remote_provider_rejects_wrong_dimension() {
+        let (url, _batches) = serve_embeddings(4, false).await;
+        let remote = RemoteEmbedder::new(&url, "small", None, 8)
+            .unwrap()
+            .with_retries(1, std::time::Duration::from_millis(1));
+        let generator = EmbeddingGenerator::with_remote(Arc::new(Config::default()), remote);
+
+        let err = generator.generate_embedding("abc").await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<crate::RuneError>(),
+            Some(crate::RuneError::Embedding(m)) if m.contains("4 dimensions")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_remote_timeout_is_retried() {
+        // Accepts connections but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let remote = RemoteEmbedder::new(&url, "small", None, 8)
+            .unwrap()
+            .with_retries(2, std::time::Duration::from_millis(1))
+            .with_timeout(std::time::Duration::from_millis(50));
+        let err = remote.embed(&["abc".to_string()]).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<crate::RuneError>(),
+            Some(crate::RuneError::Embedding(m)) if m.contains("after 3 attempts")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_lower_optimization_level() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Arc::new(Config {
+            workspace_dir: temp_dir.path().to_string_lossy().to_string(),
+            cache_dir: temp_dir.path().to_path_buf(),
+            onnx_optimization_level: OnnxOptimizationLevel::Level1,
+            ..Default::default()
+        });
+
+        let generator = EmbeddingGenerator::new(config).await.unwrap();
+        // Fallback vectors never touch ONNX Runtime, so there's nothing to check
+        if !generator.is_available() {
+            return;
+        }
+
+        let embedding = generator
+            .generate_embedding("fn main() { println!(\"hi\"); }")
+            .await
+            .unwrap();
+
+        assert_eq!(embedding.len(), generator.dimension());
+        assert!(embedding.iter().all(|x| x.is_finite()));
+        let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 0.01);
+    }
+
     #[tokio::test]
     #[ignore] // Run with --ignored to test actual model download
     async fn test_real_model_download() {
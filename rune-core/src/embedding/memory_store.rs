@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use parking_lot::RwLock;
+
+use super::qdrant::{EmbeddedChunk, SemanticSearchResult};
+use crate::VectorDistance;
+
+/// Brute-force vector store used when Qdrant is unreachable.
+///
+/// Scores follow the configured `VectorDistance` so rankings match what Qdrant
+/// would return for the same collection. Higher scores are always better.
+pub struct InMemoryVectorStore {
+    distance: VectorDistance,
+    chunks: RwLock<HashMap<String, EmbeddedChunk>>,
+    /// Largest number of chunks received in a single `store_embeddings` call
+    largest_batch: AtomicUsize,
+}
+
+impl InMemoryVectorStore {
+    pub fn new(distance: VectorDistance) -> Self {
+        Self {
+            distance,
+            chunks: RwLock::new(HashMap::new()),
+            largest_batch: AtomicUsize::new(0),
+        }
+    }
+
+    /// Insert or replace chunks by id
+    pub fn store_embeddings(&self, chunks: Vec<EmbeddedChunk>) {
+        self.largest_batch
+            .fetch_max(chunks.len(), Ordering::Relaxed);
+
+        let mut store = self.chunks.write();
+        for chunk in chunks {
+            store.insert(chunk.id.clone(), chunk);
+        }
+    }
+
+    /// Return the `limit` chunks most similar to `query_embedding`
+    pub fn search(&self, query_embedding: &[f32], limit: usize) -> Vec<SemanticSearchResult> {
+        self.search_where(query_embedding, limit, |_| true)
+    }
+
+    /// Like `search`, but only over chunks for which `keep` returns true
+    pub fn search_where(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        keep: impl Fn(&EmbeddedChunk) -> bool,
+    ) -> Vec<SemanticSearchResult> {
+        let store = self.chunks.read();
+        let mut scored: Vec<(f32, &EmbeddedChunk)> = store
+            .values()
+            .filter(|chunk| keep(chunk))
+            .map(|chunk| {
+                (
+                    similarity(self.distance, query_embedding, &chunk.embedding),
+                    chunk,
+                )
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(score, chunk)| SemanticSearchResult {
+                file_path: chunk.file_path.clone(),
+                content: chunk.content.clone(),
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+                language: chunk.language.clone(),
+                score,
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.read().is_empty()
+    }
+
+    /// Largest batch passed to `store_embeddings` so far
+    pub fn largest_batch(&self) -> usize {
+        self.largest_batch.load(Ordering::Relaxed)
+    }
+
+    pub fn clear(&self) {
+        self.chunks.write().clear();
+    }
+}
+
+/// Similarity between two vectors under `distance`, where higher means closer.
+///
+/// Cosine normalizes both vectors first (as Qdrant does on insert), dot product is
+/// used as-is, and euclidean distance `d` is mapped to `1 / (1 + d)`.
+fn similarity(distance: VectorDistance, a: &[f32], b: &[f32]) -> f32 {
+    match distance {
+        VectorDistance::Cosine => {
+            let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+            let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm_a == 0.0 || norm_b == 0.0 {
+                return 0.0;
+            }
+            dot(a, b) / (norm_a * norm_b)
+        },
+        VectorDistance::Dot => dot(a, b),
+        VectorDistance::Euclidean => {
+            let dist = a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f32>()
+                .sqrt();
+            1.0 / (1.0 + dist)
+        },
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &str, embedding: Vec<f32>) -> EmbeddedChunk {
+        EmbeddedChunk {
+            id: id.to_string(),
+            content: String::new(),
+            embedding,
+            file_path: format!("{}.rs", id),
+            start_line: 1,
+            end_line: 1,
+            language: None,
+        }
+    }
+
+    fn ranking(distance: VectorDistance) -> Vec<String> {
+        let store = InMemoryVectorStore::new(distance);
+        store.store_embeddings(vec![
+            // Large magnitude, pointing partly away from the query
+            chunk("long", vec![10.0, 5.0]),
+            // Close to the query, slightly off-axis
+            chunk("near", vec![0.99, 0.1]),
+            // Orthogonal to the query
+            chunk("ortho", vec![0.0, 1.0]),
+        ]);
+
+        store
+            .search(&[1.0, 0.0], 3)
+            .into_iter()
+            .map(|r| r.file_path.trim_end_matches(".rs").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_ranking_per_distance() {
+        assert_eq!(ranking(VectorDistance::Cosine), ["near", "long", "ortho"]);
+        assert_eq!(ranking(VectorDistance::Dot), ["long", "near", "ortho"]);
+        assert_eq!(
+            ranking(VectorDistance::Euclidean),
+            ["near", "ortho", "long"]
+        );
+    }
+
+    #[test]
+    fn test_cosine_ignores_magnitude() {
+        let a = similarity(VectorDistance::Cosine, &[1.0, 1.0], &[2.0, 2.0]);
+        assert!((a - 1.0).abs() < 1e-6);
+
+        let d = similarity(VectorDistance::Dot, &[1.0, 1.0], &[2.0, 2.0]);
+        assert!((d - 4.0).abs() < 1e-6);
+    }
+}
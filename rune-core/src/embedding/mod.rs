@@ -1,43 +1,129 @@
 pub mod ast_chunker;
 pub mod chunker;
 pub mod generator;
+pub mod memory_store;
 pub mod model_manager;
 pub mod qdrant;
 pub mod quantization;
+pub mod remote;
+pub mod reranker;
 
 pub use chunker::{ChunkType, ChunkerConfig, CodeChunk, CodeChunker};
 pub use generator::EmbeddingGenerator;
-pub use qdrant::{EmbeddedChunk, QdrantManager, SemanticSearchResult};
+pub use memory_store::InMemoryVectorStore;
+pub use qdrant::{EmbeddedChunk, QdrantManager, SemanticSearchResult, VectorFilter};
+pub use remote::RemoteEmbedder;
+pub use reranker::Reranker;
 
 use anyhow::Result;
+use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
 use tracing::{debug, info};
 
-use crate::Config;
+use crate::storage::StorageBackend;
+use crate::{Config, EmbeddingStatus, RuneError};
+
+/// Number of chunks embedded and written per batch
+const EMBED_BATCH_SIZE: usize = 32;
+
+/// Queued chunks that trigger a flush before the next coordinated one, so a bulk
+/// indexing pass doesn't hold every vector between commits
+const MAX_PENDING_CHUNKS: usize = 1024;
+
+/// Queued chunk content and vector bytes that trigger an early flush
+const MAX_PENDING_BYTES: usize = 32 * 1024 * 1024;
+
+/// Queued chunks written to the vector store per call when flushing
+const FLUSH_BATCH_SIZE: usize = 256;
+
+/// Embedded chunks waiting for the next coordinated flush, and the chunk ids to record
+/// for their files once the chunks are stored
+#[derive(Default)]
+struct PendingEmbeddings {
+    chunks: Vec<EmbeddedChunk>,
+    ids: HashMap<String, Vec<String>>,
+}
 
 /// High-level embedding pipeline that coordinates chunking, generation, and storage
 pub struct EmbeddingPipeline {
+    config: Arc<Config>,
     generator: Arc<EmbeddingGenerator>,
     qdrant: Arc<QdrantManager>,
+    /// Keeps vectors in process instead of Qdrant when set
+    memory_store: Option<Arc<InMemoryVectorStore>>,
     chunker: Arc<tokio::sync::Mutex<CodeChunker>>,
+    /// Embedded chunks waiting for the next coordinated flush
+    pending: tokio::sync::Mutex<PendingEmbeddings>,
+    /// Ids of the chunks each file produced the last time it was embedded, when
+    /// `storage` isn't set
+    embedded_ids: DashMap<String, HashSet<String>>,
+    /// Keeps those ids next to each file's metadata instead
+    storage: Option<StorageBackend>,
 }
 
 impl EmbeddingPipeline {
     pub async fn new(config: Arc<Config>) -> Result<Self> {
         let generator = Arc::new(EmbeddingGenerator::new(config.clone()).await?);
         let qdrant = Arc::new(QdrantManager::new(config.clone()).await?);
+        Ok(Self::with_parts(config, generator, qdrant, None))
+    }
+
+    /// A pipeline that stores vectors in an `InMemoryVectorStore` and never contacts
+    /// Qdrant. Nothing persists across restarts.
+    pub async fn in_memory(config: Arc<Config>) -> Result<Self> {
+        let generator = Arc::new(EmbeddingGenerator::new(config.clone()).await?);
+        Ok(Self::in_memory_with(config, generator))
+    }
+
+    /// `in_memory` with a stand-in generator, for tests that run without the model
+    #[cfg(test)]
+    pub(crate) fn in_memory_stand_in(config: Arc<Config>) -> Self {
+        let generator = Arc::new(EmbeddingGenerator::stand_in(config.clone()));
+        Self::in_memory_with(config, generator)
+    }
+
+    fn in_memory_with(config: Arc<Config>, generator: Arc<EmbeddingGenerator>) -> Self {
+        let qdrant = Arc::new(QdrantManager::disconnected(config.clone()));
+        let store = Arc::new(InMemoryVectorStore::new(config.vector_distance));
+        Self::with_parts(config, generator, qdrant, Some(store))
+    }
+
+    fn with_parts(
+        config: Arc<Config>,
+        generator: Arc<EmbeddingGenerator>,
+        qdrant: Arc<QdrantManager>,
+        memory_store: Option<Arc<InMemoryVectorStore>>,
+    ) -> Self {
         let chunker = Arc::new(tokio::sync::Mutex::new(CodeChunker::new(
             ChunkerConfig::default(),
         )));
 
-        Ok(Self {
+        Self {
+            config,
             generator,
             qdrant,
+            memory_store,
             chunker,
-        })
+            pending: tokio::sync::Mutex::new(PendingEmbeddings::default()),
+            embedded_ids: DashMap::new(),
+            storage: None,
+        }
+    }
+
+    /// Record each file's embedded chunk ids in `storage`, so unchanged chunks are
+    /// still skipped after a restart and the ids go away with the file's metadata.
+    /// Only for pipelines whose vectors persist too.
+    pub fn with_storage(mut self, storage: StorageBackend) -> Self {
+        self.storage = Some(storage);
+        self
     }
 
-    /// Process a file and store its embeddings
+    /// Process a file and store its embeddings.
+    ///
+    /// Chunks are embedded and upserted one batch at a time, so peak memory depends on
+    /// `EMBED_BATCH_SIZE` rather than on the size of the file.
     pub async fn process_file(&self, file_path: &str, content: &str) -> Result<()> {
         if !self.is_available() {
             debug!("Embedding pipeline not available, skipping file");
@@ -46,65 +132,201 @@ impl EmbeddingPipeline {
 
         info!("Processing file for embeddings: {}", file_path);
 
-        // Chunk the file
-        let chunks = {
-            let mut chunker = self.chunker.lock().await;
-            chunker.chunk_file(content, file_path)
-        };
-        if chunks.is_empty() {
+        let chunks = self.chunk_file(file_path, content).await;
+        let (_, ids) = self
+            .embed_in_batches(file_path, &chunks, |batch| self.store(batch))
+            .await?;
+        self.record_chunk_ids(file_path, ids).await?;
+
+        Ok(())
+    }
+
+    /// Embed a file and hold its chunks until the next `flush_pending` call. Callers
+    /// should flush early once `pending_full` reports the queue has grown too large.
+    pub async fn queue_file(&self, file_path: &str, content: &str) -> Result<()> {
+        if !self.is_available() {
+            debug!("Embedding pipeline not available, skipping file");
             return Ok(());
         }
 
-        info!("Processing {} chunks for {}", chunks.len(), file_path);
+        let chunks = self.chunk_file(file_path, content).await;
+        let (_, ids) = self
+            .embed_in_batches(file_path, &chunks, |batch| async move {
+                self.pending.lock().await.chunks.extend(batch);
+                Ok(())
+            })
+            .await?;
+        self.pending
+            .lock()
+            .await
+            .ids
+            .insert(file_path.to_string(), ids);
 
-        // Generate embeddings in batches
-        let batch_size = 32;
-        let mut embedded_chunks = Vec::new();
+        Ok(())
+    }
 
-        for batch in chunks.chunks(batch_size) {
-            let texts: Vec<String> = batch.iter().map(|c| c.content.clone()).collect();
-            let embeddings = self.generator.batch_generate(&texts).await?;
+    /// Whether `MAX_PENDING_CHUNKS` or `MAX_PENDING_BYTES` are queued, so a bulk pass
+    /// should flush before its next scheduled flush
+    pub async fn pending_full(&self) -> bool {
+        let pending = self.pending.lock().await;
+        pending.chunks.len() >= MAX_PENDING_CHUNKS
+            || pending
+                .chunks
+                .iter()
+                .map(|c| c.content.len() + c.embedding.len() * size_of::<f32>())
+                .sum::<usize>()
+                >= MAX_PENDING_BYTES
+    }
 
-            for (chunk, embedding) in batch.iter().zip(embeddings.iter()) {
-                // Generate a deterministic UUID based on file path and content
-                // This ensures the same chunk always gets the same ID, preventing duplicates
-                let file_hash = blake3::hash(file_path.as_bytes());
-                let content_hash = blake3::hash(chunk.content.as_bytes());
-                let line_info = format!("{:08x}{:08x}", chunk.start_line, chunk.end_line);
-                let combined = format!(
-                    "{}{}{}",
-                    &file_hash.to_hex()[..16],
-                    line_info,
-                    &content_hash.to_hex()[..8]
-                );
+    /// Store all queued chunks, then record the chunk ids of the files they came from.
+    /// Chunks that fail to store stay queued for the next flush. Returns the number of
+    /// chunks written.
+    pub async fn flush_pending(&self) -> Result<usize> {
+        let PendingEmbeddings { mut chunks, ids } = std::mem::take(&mut *self.pending.lock().await);
+        let count = chunks.len();
+        if count > 0 {
+            debug!("Flushing {} pending chunks to the vector store", count);
+        }
 
-                // Create a valid UUID format from our deterministic hash
-                // Format: xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx (8-4-4-4-12)
-                let id = format!(
-                    "{}-{}-{}-{}-{}",
-                    &combined[0..8],
-                    &combined[8..12],
-                    &combined[12..16],
-                    &combined[16..20],
-                    &combined[20..32]
-                );
+        let mut stored = 0;
+        while stored < count {
+            let end = (stored + FLUSH_BATCH_SIZE).min(count);
+            if let Err(e) = self.store(chunks[stored..end].to_vec()).await {
+                // Requeue what didn't land ahead of anything queued since; newer ids
+                // for the same file win
+                let mut pending = self.pending.lock().await;
+                let mut unstored = chunks.split_off(stored);
+                unstored.append(&mut pending.chunks);
+                pending.chunks = unstored;
+                for (file_path, file_ids) in ids {
+                    pending.ids.entry(file_path).or_insert(file_ids);
+                }
+                return Err(e);
+            }
+            stored = end;
+        }
+
+        for (file_path, file_ids) in ids {
+            self.record_chunk_ids(&file_path, file_ids).await?;
+        }
+
+        Ok(count)
+    }
+
+    /// Write embedded chunks to whichever vector store the pipeline uses
+    async fn store(&self, chunks: Vec<EmbeddedChunk>) -> Result<()> {
+        match self.memory_store {
+            Some(ref store) => {
+                store.store_embeddings(chunks);
+                Ok(())
+            },
+            None => self.qdrant.store_embeddings(chunks).await,
+        }
+    }
+
+    async fn chunk_file(&self, file_path: &str, content: &str) -> Vec<CodeChunk> {
+        let mut chunker = self.chunker.lock().await;
+        chunker.chunk_file(content, file_path)
+    }
+
+    /// Generate embeddings for `chunks` in batches of `EMBED_BATCH_SIZE`, handing each
+    /// batch to `store` before the next one is generated. Returns the number of chunks
+    /// embedded and the ids of all of the file's chunks, for the caller to record with
+    /// `record_chunk_ids` once the batches are actually stored.
+    ///
+    /// With `Config::incremental_embedding`, chunks whose id (path, span and content)
+    /// was already embedded by the last pass over this file are skipped, since their
+    /// vectors are still stored under that id.
+    async fn embed_in_batches<F, Fut>(
+        &self,
+        file_path: &str,
+        chunks: &[CodeChunk],
+        mut store: F,
+    ) -> Result<(usize, Vec<String>)>
+    where
+        F: FnMut(Vec<EmbeddedChunk>) -> Fut,
+        Fut: std::future::Future<Output = Result<()>>,
+    {
+        let ids: Vec<String> = chunks.iter().map(|c| chunk_id(file_path, c)).collect();
+        let known = if self.config.incremental_embedding {
+            self.known_chunk_ids(file_path).await?
+        } else {
+            HashSet::new()
+        };
+        let fresh: Vec<(&CodeChunk, &String)> = chunks
+            .iter()
+            .zip(&ids)
+            .filter(|(_, id)| !known.contains(*id))
+            .collect();
+
+        if fresh.is_empty() {
+            return Ok((0, ids));
+        }
+
+        info!(
+            "Processing {} chunks for {} ({} unchanged)",
+            fresh.len(),
+            file_path,
+            chunks.len() - fresh.len()
+        );
+
+        for batch in fresh.chunks(EMBED_BATCH_SIZE) {
+            let texts: Vec<String> = batch
+                .iter()
+                .map(|(c, _)| {
+                    if self.config.normalize_whitespace_for_embedding {
+                        normalize_indentation(&c.content)
+                    } else {
+                        c.content.clone()
+                    }
+                })
+                .collect();
+            let embeddings = self.generator.batch_generate(&texts).await?;
 
-                embedded_chunks.push(EmbeddedChunk {
-                    id,
+            let embedded_batch = batch
+                .iter()
+                .zip(embeddings)
+                .map(|((chunk, id), embedding)| EmbeddedChunk {
+                    id: (*id).clone(),
                     content: chunk.content.clone(),
-                    embedding: embedding.clone(),
+                    embedding,
                     file_path: chunk.file_path.clone(),
                     start_line: chunk.start_line,
                     end_line: chunk.end_line,
                     language: chunk.language.clone(),
-                });
-            }
+                })
+                .collect();
+
+            store(embedded_batch).await?;
         }
 
-        // Store in Qdrant
-        self.qdrant.store_embeddings(embedded_chunks).await?;
+        Ok((fresh.len(), ids))
+    }
 
-        Ok(())
+    async fn known_chunk_ids(&self, file_path: &str) -> Result<HashSet<String>> {
+        match self.storage {
+            Some(ref storage) => Ok(storage
+                .get_chunk_ids(Path::new(file_path))
+                .await?
+                .into_iter()
+                .collect()),
+            None => Ok(self
+                .embedded_ids
+                .get(file_path)
+                .map(|ids| ids.clone())
+                .unwrap_or_default()),
+        }
+    }
+
+    async fn record_chunk_ids(&self, file_path: &str, ids: Vec<String>) -> Result<()> {
+        match self.storage {
+            Some(ref storage) => storage.store_chunk_ids(Path::new(file_path), &ids).await,
+            None => {
+                self.embedded_ids
+                    .insert(file_path.to_string(), ids.into_iter().collect());
+                Ok(())
+            },
+        }
     }
 
     /// Search for semantically similar code
@@ -117,21 +339,137 @@ impl EmbeddingPipeline {
         // Generate query embedding
         let query_embedding = self.generator.generate_embedding(query).await?;
 
-        // Search in Qdrant
-        self.qdrant.search(query_embedding, limit, None).await
+        self.search_by_vector(query_embedding, limit, None).await
+    }
+
+    /// Search with a precomputed query vector, e.g. one from another embedding
+    /// service or a stored chunk's own vector to find its neighbors. The vector must
+    /// have `Config::embedding_dimension` entries.
+    pub async fn search_by_vector(
+        &self,
+        vector: Vec<f32>,
+        limit: usize,
+        filter: Option<VectorFilter>,
+    ) -> Result<Vec<SemanticSearchResult>> {
+        let expected = self.config.embedding_dimension;
+        if vector.len() != expected {
+            return Err(RuneError::InvalidQuery(format!(
+                "Query vector has {} dimensions, expected {}",
+                vector.len(),
+                expected
+            ))
+            .into());
+        }
+
+        match self.memory_store {
+            Some(ref store) => Ok(store.search_where(&vector, limit, |chunk| {
+                filter.as_ref().is_none_or(|f| f.matches(chunk))
+            })),
+            None => {
+                if !self.qdrant.is_available() {
+                    debug!("Vector store not available");
+                    return Ok(Vec::new());
+                }
+                self.qdrant
+                    .search(vector, limit, filter.map(|f| f.to_qdrant()))
+                    .await
+            },
+        }
+    }
+
+    /// Entries and estimated bytes held by the generator's embedding cache
+    pub fn embedding_cache_usage(&self) -> (usize, usize) {
+        self.generator.cache_usage()
+    }
+
+    pub fn embedding_status(&self) -> EmbeddingStatus {
+        self.generator.status()
     }
 
     /// Check if the pipeline is fully operational
     pub fn is_available(&self) -> bool {
-        self.generator.is_available() && self.qdrant.is_available()
+        self.generator.is_available() && (self.memory_store.is_some() || self.qdrant.is_available())
     }
 
     /// Clear all stored embeddings
     pub async fn clear(&self) -> Result<()> {
-        self.qdrant.clear_collection().await
+        self.embedded_ids.clear();
+        if let Some(ref storage) = self.storage {
+            storage.clear_chunk_ids().await?;
+        }
+        match self.memory_store {
+            Some(ref store) => {
+                store.clear();
+                Ok(())
+            },
+            None => self.qdrant.clear_collection().await,
+        }
     }
 }
 
+/// Columns a tab advances to when measuring indentation
+const TAB_WIDTH: usize = 4;
+
+/// Re-indent `content` with four spaces per level. The shallowest non-blank line sets
+/// level zero and the smallest step above it sets the unit, so tabs, 2-space and
+/// 4-space indentation of the same code come out identical. Trailing whitespace is
+/// dropped and blank lines are emptied.
+fn normalize_indentation(content: &str) -> String {
+    let width = |line: &str| {
+        line.chars()
+            .take_while(|c| c.is_whitespace())
+            .fold(0, |col, c| match c {
+                '\t' => (col / TAB_WIDTH + 1) * TAB_WIDTH,
+                _ => col + 1,
+            })
+    };
+
+    let widths: Vec<usize> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(width)
+        .collect();
+    let base = widths.iter().copied().min().unwrap_or(0);
+    let unit = widths
+        .iter()
+        .map(|w| w - base)
+        .filter(|w| *w > 0)
+        .min()
+        .unwrap_or(1);
+
+    content
+        .lines()
+        .map(|line| {
+            let text = line.trim();
+            if text.is_empty() {
+                return String::new();
+            }
+            let level = (width(line) - base) / unit;
+            format!("{}{}", "    ".repeat(level), text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Generate a deterministic UUID based on file path, line span and content.
+/// This ensures the same chunk always gets the same ID, preventing duplicates.
+/// All inputs are hashed in full (the path length-prefixed so fields can't run
+/// together), leaving 122 hash bits in the UUID so distinct chunks don't collide.
+fn chunk_id(file_path: &str, chunk: &CodeChunk) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&(file_path.len() as u64).to_le_bytes());
+    hasher.update(file_path.as_bytes());
+    hasher.update(&(chunk.start_line as u64).to_le_bytes());
+    hasher.update(&(chunk.end_line as u64).to_le_bytes());
+    hasher.update(chunk.content.as_bytes());
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hasher.finalize().as_bytes()[..16]);
+    uuid::Builder::from_custom_bytes(bytes)
+        .into_uuid()
+        .to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,6 +486,7 @@ mod tests {
             enable_semantic: true,
             languages: vec!["rust".to_string(), "python".to_string()],
             file_watch_debounce_ms: 500,
+            ..Default::default()
         })
     }
 
@@ -344,4 +683,355 @@ mod tests {
             std::env::remove_var("RUNE_ENABLE_SEMANTIC");
         }
     }
+
+    /// Rust source with `count` small functions, each its own chunk
+    fn functions(prefix: &str, count: usize) -> String {
+        let mut content = String::new();
+        for i in 0..count {
+            content.push_str(&format!("fn {prefix}_{i}(x: u32) -> u32 {{\n"));
+            for j in 0..20 {
+                content.push_str(&format!("    let v{j} = x.wrapping_mul({i} + {j});\n"));
+            }
+            content.push_str("    x\n}\n\n");
+        }
+        content
+    }
+
+    #[tokio::test]
+    async fn test_process_file_bounds_memory() {
+        let pipeline = EmbeddingPipeline::in_memory_stand_in(create_test_config());
+        let store = pipeline.memory_store.clone().unwrap();
+
+        let content = functions("function", 200);
+        let chunks = pipeline.chunk_file("large.rs", &content).await;
+        assert!(chunks.len() > EMBED_BATCH_SIZE * 2);
+
+        pipeline.process_file("large.rs", &content).await.unwrap();
+
+        // Every chunk is stored, but never more than one batch is held at a time
+        assert_eq!(store.len(), chunks.len());
+        assert!(store.largest_batch() <= EMBED_BATCH_SIZE);
+    }
+
+    #[tokio::test]
+    async fn test_queue_file_reports_when_pending_grows() {
+        let pipeline = EmbeddingPipeline::in_memory_stand_in(create_test_config());
+        let store = pipeline.memory_store.clone().unwrap();
+
+        // A bulk pass queueing more chunks than one flush should hold, flushing
+        // whenever the queue fills up
+        let mut total = 0;
+        let mut early_flushes = 0;
+        for i in 0..12 {
+            let path = format!("module_{i}.rs");
+            let content = functions(&format!("handler_{i}"), 100);
+            total += pipeline.chunk_file(&path, &content).await.len();
+            pipeline.queue_file(&path, &content).await.unwrap();
+            // Nothing is written until the caller flushes
+            assert_eq!(
+                store.len() + pipeline.pending.lock().await.chunks.len(),
+                total
+            );
+            if pipeline.pending_full().await {
+                pipeline.flush_pending().await.unwrap();
+                early_flushes += 1;
+            }
+        }
+        assert!(total > MAX_PENDING_CHUNKS);
+        assert!(early_flushes > 0);
+        assert!(store.largest_batch() <= FLUSH_BATCH_SIZE);
+
+        // The coordinated flush writes whatever is left
+        pipeline.flush_pending().await.unwrap();
+        assert_eq!(store.len(), total);
+    }
+
+    #[tokio::test]
+    async fn test_failed_flush_keeps_chunks_queued() {
+        let config = create_test_config();
+        let cache_dir = tempdir().unwrap();
+        let storage = StorageBackend::new(cache_dir.path()).await.unwrap();
+        let pipeline = EmbeddingPipeline::with_parts(
+            config.clone(),
+            Arc::new(EmbeddingGenerator::stand_in(config.clone())),
+            Arc::new(QdrantManager::disconnected(config.clone())),
+            None,
+        )
+        .with_storage(storage.clone());
+
+        // A vector of the wrong size makes the Qdrant write fail
+        let chunk = |id: &str, dimension: usize| EmbeddedChunk {
+            id: id.to_string(),
+            content: "fn queued() {}".to_string(),
+            embedding: vec![0.5; dimension],
+            file_path: "lib.rs".to_string(),
+            start_line: 1,
+            end_line: 1,
+            language: Some("rust".to_string()),
+        };
+        {
+            let mut pending = pipeline.pending.lock().await;
+            pending.chunks.push(chunk("a", config.embedding_dimension));
+            pending.chunks.push(chunk("b", 3));
+            pending
+                .ids
+                .insert("lib.rs".to_string(), vec!["a".to_string(), "b".to_string()]);
+        }
+
+        assert!(pipeline.flush_pending().await.is_err());
+        // The chunks wait for the next flush, and the file's ids aren't recorded yet
+        assert_eq!(pipeline.pending.lock().await.chunks.len(), 2);
+        assert!(
+            storage
+                .get_chunk_ids(Path::new("lib.rs"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        pipeline.pending.lock().await.chunks[1] = chunk("b", config.embedding_dimension);
+        assert_eq!(pipeline.flush_pending().await.unwrap(), 2);
+        assert!(pipeline.pending.lock().await.chunks.is_empty());
+        assert_eq!(
+            storage.get_chunk_ids(Path::new("lib.rs")).await.unwrap(),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn test_chunk_ids_do_not_collide() {
+        let chunk = |start_line: usize, end_line: usize| CodeChunk {
+            content: "fn shared() {}".to_string(),
+            file_path: String::new(),
+            start_line,
+            end_line,
+            language: Some("rust".to_string()),
+            chunk_type: ChunkType::Function,
+        };
+
+        // Identical content at identical lines across many files
+        let mut ids = std::collections::HashSet::new();
+        for i in 0..10_000 {
+            let id = chunk_id(&format!("/workspace/src/module_{i}/lib.rs"), &chunk(1, 20));
+            assert!(uuid::Uuid::parse_str(&id).is_ok());
+            assert!(ids.insert(id), "collision for file {}", i);
+        }
+
+        // Same file, different spans
+        let path = "/workspace/src/lib.rs";
+        assert_ne!(chunk_id(path, &chunk(1, 20)), chunk_id(path, &chunk(1, 21)));
+        assert_ne!(chunk_id(path, &chunk(1, 20)), chunk_id(path, &chunk(2, 20)));
+
+        // Field boundaries can't shift between path and lines
+        assert_ne!(chunk_id("a1", &chunk(1, 2)), chunk_id("a", &chunk(11, 2)));
+
+        // Deterministic across calls
+        assert_eq!(chunk_id(path, &chunk(1, 20)), chunk_id(path, &chunk(1, 20)));
+    }
+
+    #[tokio::test]
+    async fn test_search_by_vector_finds_stored_chunk() {
+        let config = Arc::new(Config {
+            embedding_dimension: 4,
+            ..Default::default()
+        });
+        let store = Arc::new(InMemoryVectorStore::new(config.vector_distance));
+        let pipeline = EmbeddingPipeline::with_parts(
+            config.clone(),
+            Arc::new(EmbeddingGenerator::fallback(config.clone())),
+            Arc::new(QdrantManager::disconnected(config)),
+            Some(store.clone()),
+        );
+
+        let chunk = |name: &str, language: &str, embedding: Vec<f32>| EmbeddedChunk {
+            id: name.to_string(),
+            content: format!("fn {name}() {{}}"),
+            embedding,
+            file_path: format!("{name}.rs"),
+            start_line: 1,
+            end_line: 1,
+            language: Some(language.to_string()),
+        };
+        pipeline
+            .store(vec![
+                chunk("parse", "rust", vec![0.9, 0.1, 0.0, 0.1]),
+                chunk("render", "rust", vec![0.1, 0.9, 0.2, 0.0]),
+                chunk("connect", "go", vec![0.0, 0.2, 0.9, 0.3]),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(store.len(), 3);
+
+        // Searching with a stored chunk's own vector finds that chunk first
+        let results = pipeline
+            .search_by_vector(vec![0.1, 0.9, 0.2, 0.0], 3, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].file_path, "render.rs");
+        assert!((results[0].score - 1.0).abs() < 1e-5);
+
+        // Filters restrict the candidates, not just the ranking
+        let filter = VectorFilter {
+            language: Some("go".to_string()),
+            ..Default::default()
+        };
+        let results = pipeline
+            .search_by_vector(vec![0.1, 0.9, 0.2, 0.0], 3, Some(filter))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_path, "connect.rs");
+
+        let err = pipeline
+            .search_by_vector(vec![1.0; 3], 3, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RuneError>(),
+            Some(RuneError::InvalidQuery(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_normalized_indentation_embeds_alike() {
+        let chunk = |content: &str| CodeChunk {
+            content: content.to_string(),
+            file_path: "lib.rs".to_string(),
+            start_line: 1,
+            end_line: 4,
+            language: Some("rust".to_string()),
+            chunk_type: ChunkType::Function,
+        };
+        let chunks = [
+            chunk(
+                "fn total(xs: &[u32]) -> u32 {\n\tlet mut sum = 0;\n\tfor x in xs {\n\t\tsum += x;\n\t}\n\tsum\n}",
+            ),
+            chunk(
+                "fn total(xs: &[u32]) -> u32 {\n  let mut sum = 0;\n  for x in xs {\n    sum += x;\n  }\n  sum\n}",
+            ),
+        ];
+
+        let similarity = |normalize: bool| {
+            let chunks = chunks.clone();
+            async move {
+                let config = Arc::new(Config {
+                    normalize_whitespace_for_embedding: normalize,
+                    ..Default::default()
+                });
+                let pipeline = EmbeddingPipeline::with_parts(
+                    config.clone(),
+                    Arc::new(EmbeddingGenerator::fallback(config.clone())),
+                    Arc::new(QdrantManager::disconnected(config)),
+                    None,
+                );
+
+                let embedded = std::sync::Mutex::new(Vec::new());
+                pipeline
+                    .embed_in_batches("lib.rs", &chunks, |batch| {
+                        embedded.lock().unwrap().extend(batch);
+                        async { Ok(()) }
+                    })
+                    .await
+                    .unwrap();
+                let embedded = embedded.into_inner().unwrap();
+
+                // The stored payload keeps the original indentation for display
+                assert_eq!(embedded[0].content, chunks[0].content);
+                assert_eq!(embedded[1].content, chunks[1].content);
+
+                // Fallback vectors are unit length, so the dot product is the cosine
+                let (a, b) = (&embedded[0].embedding, &embedded[1].embedding);
+                a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+            }
+        };
+
+        let raw = similarity(false).await;
+        let normalized = similarity(true).await;
+        assert!(normalized > raw, "{normalized} <= {raw}");
+        assert!((normalized - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_normalize_indentation() {
+        let spaces = "    fn a() {\n        b();\n    }  \n\n";
+        let tabs = "\tfn a() {\n\t\tb();\n\t}";
+        let expected = "fn a() {\n    b();\n}\n";
+        assert_eq!(normalize_indentation(spaces), expected);
+        assert_eq!(normalize_indentation(tabs), expected.trim_end());
+    }
+
+    #[tokio::test]
+    async fn test_only_changed_chunks_are_reembedded() {
+        let (url, requests) = remote::serve_embeddings(8, false).await;
+        let config = Arc::new(Config {
+            embedding_dimension: 8,
+            ..Default::default()
+        });
+        let cache_dir = tempdir().unwrap();
+        let storage = StorageBackend::new(cache_dir.path()).await.unwrap();
+        let store = Arc::new(InMemoryVectorStore::new(config.vector_distance));
+        let pipeline = || {
+            let remote = RemoteEmbedder::new(&url, "test", None, 8).unwrap();
+            EmbeddingPipeline::with_parts(
+                config.clone(),
+                Arc::new(EmbeddingGenerator::with_remote(config.clone(), remote)),
+                Arc::new(QdrantManager::disconnected(config.clone())),
+                Some(store.clone()),
+            )
+            .with_storage(storage.clone())
+        };
+        // Texts sent to the generator since the last check
+        let generated = || requests.lock().unwrap().drain(..).sum::<usize>();
+
+        let source = |scale: u32| {
+            let mut content = String::new();
+            for (i, name) in ["parse", "render", "connect"].iter().enumerate() {
+                let factor = if *name == "render" { scale } else { i as u32 };
+                content.push_str(&format!("pub fn {name}(input: &[u8]) -> usize {{\n"));
+                for j in 0..40 {
+                    content.push_str(&format!("    let step_{j} = input.len() * {j};\n"));
+                }
+                content.push_str(&format!("    step_39 * {factor}\n}}\n\n"));
+            }
+            content
+        };
+        async fn embed(pipeline: &EmbeddingPipeline, content: &str) -> (usize, usize) {
+            let chunks = pipeline.chunk_file("lib.rs", content).await;
+            let (embedded, ids) = pipeline
+                .embed_in_batches("lib.rs", &chunks, |batch| pipeline.store(batch))
+                .await
+                .unwrap();
+            pipeline.record_chunk_ids("lib.rs", ids).await.unwrap();
+            (chunks.len(), embedded)
+        }
+
+        let first = pipeline();
+        let (chunk_count, embedded) = embed(&first, &source(1)).await;
+        assert!(chunk_count >= 3, "expected a chunk per function");
+        assert_eq!(embedded, chunk_count);
+        assert_eq!(generated(), chunk_count);
+
+        // Nothing changed: nothing is embedded again
+        assert_eq!(embed(&first, &source(1)).await.1, 0);
+        assert_eq!(generated(), 0);
+
+        // Editing one function body re-embeds just that chunk
+        assert_eq!(embed(&first, &source(7)).await.1, 1);
+        assert_eq!(generated(), 1);
+        assert!(store.len() > chunk_count);
+
+        // The ids outlive the pipeline, stored with the file's metadata
+        let restarted = pipeline();
+        assert_eq!(embed(&restarted, &source(7)).await.1, 0);
+        assert_eq!(generated(), 0);
+
+        // and go away with it
+        storage
+            .delete_file_metadata(Path::new("lib.rs"))
+            .await
+            .unwrap();
+        assert_eq!(embed(&restarted, &source(7)).await.1, chunk_count);
+        assert_eq!(generated(), chunk_count);
+    }
 }
@@ -1,28 +1,62 @@
 use anyhow::{Context, Result};
+use reqwest::StatusCode;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::io::AsyncWriteExt;
-use tracing::info;
+use tracing::{info, warn};
 
-const MODEL_NAME: &str = "all-MiniLM-L6-v2";
+use crate::RuneError;
+
+/// Embedding model used when no other model is named
+pub const MODEL_NAME: &str = "all-MiniLM-L6-v2";
+
+/// HuggingFace organization assumed for model names without one
+const DEFAULT_MODEL_ORG: &str = "sentence-transformers";
+
+/// Where model files are downloaded from
+const DEFAULT_BASE_URL: &str = "https://huggingface.co";
+
+/// Delay before the first download retry; doubles on each further attempt
+pub const DOWNLOAD_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait for a connection to the download server
+const DOWNLOAD_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest wait for more bytes of a download before the attempt is retried
+const DOWNLOAD_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Local file name and its path inside the HuggingFace model repository
 const MODEL_FILES: &[(&str, &str)] = &[
-    (
-        "model.onnx",
-        "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/onnx/model.onnx",
-    ),
-    (
-        "tokenizer.json",
-        "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/tokenizer.json",
-    ),
-    (
-        "tokenizer_config.json",
-        "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/tokenizer_config.json",
-    ),
+    ("model.onnx", "onnx/model.onnx"),
+    ("tokenizer.json", "tokenizer.json"),
+    ("tokenizer_config.json", "tokenizer_config.json"),
 ];
 
 /// Manages embedding model downloads and caching
 pub struct ModelManager {
     cache_dir: PathBuf,
+    /// HuggingFace repository id, e.g. `sentence-transformers/all-MiniLM-L6-v2`
+    repo: String,
+    base_url: String,
+    /// Extra attempts for a file whose download fails with a retryable error
+    retries: u32,
+    retry_delay: Duration,
+}
+
+/// A failed download attempt. Client errors such as a 404 won't go away on retry.
+struct AttemptError {
+    error: anyhow::Error,
+    retryable: bool,
+}
+
+impl From<anyhow::Error> for AttemptError {
+    fn from(error: anyhow::Error) -> Self {
+        Self {
+            error,
+            retryable: true,
+        }
+    }
 }
 
 impl ModelManager {
@@ -34,13 +68,51 @@ impl ModelManager {
             .join("models")
             .join(MODEL_NAME);
 
-        Ok(Self { cache_dir })
+        Ok(Self {
+            cache_dir,
+            repo: Self::repo_id(MODEL_NAME),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            retries: 0,
+            retry_delay: DOWNLOAD_RETRY_DELAY,
+        })
     }
 
     /// Create a model manager with a custom cache directory (for testing)
     pub fn with_cache_dir(cache_dir: PathBuf) -> Self {
+        Self::for_model(cache_dir, MODEL_NAME)
+    }
+
+    /// Manage `model` (a HuggingFace repo id, or a bare `sentence-transformers` model
+    /// name) under `cache_dir/models`
+    pub fn for_model(cache_dir: PathBuf, model: &str) -> Self {
         Self {
-            cache_dir: cache_dir.join("models").join(MODEL_NAME),
+            cache_dir: cache_dir.join("models").join(model.replace('/', "--")),
+            repo: Self::repo_id(model),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            retries: 0,
+            retry_delay: DOWNLOAD_RETRY_DELAY,
+        }
+    }
+
+    /// Retry each failed file download up to `retries` more times, waiting
+    /// `initial_delay` before the first retry and doubling the wait after that
+    pub fn with_retries(mut self, retries: u32, initial_delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = initial_delay;
+        self
+    }
+
+    /// Download from a HuggingFace mirror instead of `huggingface.co`
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    fn repo_id(model: &str) -> String {
+        if model.contains('/') {
+            model.to_string()
+        } else {
+            format!("{}/{}", DEFAULT_MODEL_ORG, model)
         }
     }
 
@@ -70,8 +142,14 @@ impl ModelManager {
         // Create cache directory
         fs::create_dir_all(&self.cache_dir).context("Failed to create model cache directory")?;
 
+        let client = reqwest::Client::builder()
+            .connect_timeout(DOWNLOAD_CONNECT_TIMEOUT)
+            .read_timeout(DOWNLOAD_READ_TIMEOUT)
+            .build()
+            .context("Failed to build HTTP client")?;
+
         // Download each file
-        for (filename, url) in MODEL_FILES {
+        for (filename, repo_path) in MODEL_FILES {
             let file_path = self.cache_dir.join(filename);
 
             if file_path.exists() {
@@ -79,45 +157,131 @@ impl ModelManager {
                 continue;
             }
 
+            let url = format!("{}/{}/resolve/main/{}", self.base_url, self.repo, repo_path);
             info!("Downloading {} from {}", filename, url);
-            self.download_file(url, &file_path)
-                .await
-                .with_context(|| format!("Failed to download {}", filename))?;
+            self.download_with_retry(&client, filename, &url, &file_path)
+                .await?;
         }
 
         info!("Model download complete");
         Ok(())
     }
 
-    /// Download a single file
-    async fn download_file(&self, url: &str, dest: &Path) -> Result<()> {
-        let client = reqwest::Client::new();
-        let response = client
-            .get(url)
+    /// Download a file, retrying server errors, failed connections, timeouts and
+    /// dropped transfers with exponential backoff. Persistent failure becomes `RuneError::ModelDownloadFailed`.
+    async fn download_with_retry(
+        &self,
+        client: &reqwest::Client,
+        filename: &str,
+        url: &str,
+        dest: &Path,
+    ) -> Result<()> {
+        let max_attempts = self.retries + 1;
+        let mut delay = self.retry_delay;
+        let mut attempt = 1;
+
+        loop {
+            match self.download_file(client, url, dest).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= max_attempts || !e.retryable => {
+                    return Err(RuneError::ModelDownloadFailed {
+                        file: filename.to_string(),
+                        attempts: attempt,
+                        cause: format!("{:#}", e.error),
+                    }
+                    .into());
+                },
+                Err(e) => {
+                    warn!(
+                        "Downloading {} failed (attempt {}/{}): {:#}, retrying in {:?}",
+                        filename, attempt, max_attempts, e.error, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                },
+            }
+        }
+    }
+
+    /// Download a single file. Bytes left in the temporary file by an interrupted
+    /// attempt are kept and only the rest is requested, if the server supports ranges.
+    async fn download_file(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        dest: &Path,
+    ) -> std::result::Result<(), AttemptError> {
+        let temp_path = dest.with_extension("tmp");
+        let partial_len = tokio::fs::metadata(&temp_path)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0);
+
+        let mut request = client.get(url);
+        if partial_len > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", partial_len));
+        }
+        // Refused connections and timeouts are often transient, so they're retried
+        let response = request
             .send()
             .await
             .context("Failed to send download request")?;
 
-        if !response.status().is_success() {
-            anyhow::bail!("Download failed with status: {}", response.status());
+        let status = response.status();
+        if status == StatusCode::RANGE_NOT_SATISFIABLE {
+            // The partial file doesn't match the remote one; start over next time
+            let _ = tokio::fs::remove_file(&temp_path).await;
+        }
+        if !status.is_success() {
+            return Err(AttemptError {
+                error: anyhow::anyhow!("Download failed with status: {}", status),
+                // Other client errors such as a 404 won't change on retry. A 416 was
+                // caused by the partial file removed above.
+                retryable: !status.is_client_error()
+                    || status == StatusCode::REQUEST_TIMEOUT
+                    || status == StatusCode::TOO_MANY_REQUESTS
+                    || status == StatusCode::RANGE_NOT_SATISFIABLE,
+            });
         }
 
-        // Get total size for progress tracking
-        let total_size = response.content_length().unwrap_or(0);
+        // A plain 200 means the server ignored the range and is sending everything
+        let resuming = partial_len > 0 && status == StatusCode::PARTIAL_CONTENT;
+        let mut downloaded = if resuming { partial_len } else { 0 };
+        if resuming {
+            info!("Resuming download at byte {}", partial_len);
+        }
 
-        // Create temporary file
-        let temp_path = dest.with_extension("tmp");
-        let mut file = tokio::fs::File::create(&temp_path)
-            .await
-            .context("Failed to create temporary file")?;
+        // Get total size for progress tracking
+        let total_size = response
+            .content_length()
+            .map_or(0, |remaining| remaining + downloaded);
+
+        let mut file = if resuming {
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&temp_path)
+                .await
+                .context("Failed to open partial download")?
+        } else {
+            tokio::fs::File::create(&temp_path)
+                .await
+                .context("Failed to create temporary file")?
+        };
 
         // Download with progress tracking
-        let mut downloaded = 0u64;
         let mut stream = response.bytes_stream();
 
         use futures::StreamExt;
         while let Some(chunk) = stream.next().await {
-            let chunk = chunk.context("Failed to read chunk")?;
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    // Keep what arrived so the next attempt can resume from it
+                    file.flush().await.context("Failed to flush file")?;
+                    return Err(anyhow::Error::new(e).context("Failed to read chunk").into());
+                },
+            };
             file.write_all(&chunk)
                 .await
                 .context("Failed to write chunk")?;
@@ -185,4 +349,121 @@ mod tests {
 
         assert!(manager.is_model_cached());
     }
+
+    /// Serve HTTP on localhost, answering the n-th request (0-based) with
+    /// `respond(n, request)`. Returns the base URL.
+    async fn serve<F>(respond: F) -> String
+    where
+        F: Fn(usize, &str) -> (u16, Vec<u8>, Option<usize>) + Send + Sync + 'static,
+    {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let respond = std::sync::Arc::new(respond);
+
+        tokio::spawn(async move {
+            let mut served = 0;
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+
+                // `content_length` overrides the body length to simulate a dropped connection
+                let (status, body, content_length) =
+                    respond(served, &String::from_utf8_lossy(&request));
+                served += 1;
+                let head = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    content_length.unwrap_or(body.len())
+                );
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(&body).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_flaky_download_retries_and_resumes() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_url = serve(|n, request| {
+            if !request.contains("model.onnx") {
+                return (200, b"{}".to_vec(), None);
+            }
+            match n {
+                0 => (503, Vec::new(), None),
+                // Connection drops after the first 4 of 10 bytes
+                1 => (200, b"ONNX".to_vec(), Some(10)),
+                _ => {
+                    assert!(request.to_lowercase().contains("range: bytes=4-"));
+                    (206, b"-MODEL".to_vec(), None)
+                },
+            }
+        })
+        .await;
+
+        let manager = ModelManager::with_cache_dir(temp_dir.path().to_path_buf())
+            .with_base_url(base_url)
+            .with_retries(3, Duration::from_millis(1));
+        let path = manager.get_model_path().await.unwrap();
+
+        assert!(manager.is_model_cached());
+        assert_eq!(fs::read(path.join("model.onnx")).unwrap(), b"ONNX-MODEL");
+    }
+
+    #[tokio::test]
+    async fn test_permanent_download_failure_is_typed() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_url = serve(|_, _| (404, Vec::new(), None)).await;
+
+        let manager = ModelManager::with_cache_dir(temp_dir.path().to_path_buf())
+            .with_base_url(base_url)
+            .with_retries(3, Duration::from_millis(1));
+        let err = manager.get_model_path().await.unwrap_err();
+
+        match err.downcast_ref::<RuneError>() {
+            Some(RuneError::ModelDownloadFailed {
+                file,
+                attempts,
+                cause,
+            }) => {
+                assert_eq!(file, "model.onnx");
+                // A 404 isn't retried
+                assert_eq!(*attempts, 1);
+                assert!(cause.contains("404"));
+            },
+            other => panic!("expected ModelDownloadFailed, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refused_connection_is_retried() {
+        let temp_dir = TempDir::new().unwrap();
+        let addr = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap()
+        };
+
+        let manager = ModelManager::with_cache_dir(temp_dir.path().to_path_buf())
+            .with_base_url(format!("http://{}", addr))
+            .with_retries(2, Duration::from_millis(1));
+        let err = manager.get_model_path().await.unwrap_err();
+
+        match err.downcast_ref::<RuneError>() {
+            Some(RuneError::ModelDownloadFailed { attempts, .. }) => assert_eq!(*attempts, 3),
+            other => panic!("expected ModelDownloadFailed, got {:?}", other),
+        }
+    }
 }
@@ -7,18 +7,64 @@ use tracing::{debug, error, info, warn};
 use qdrant_client::{
     Qdrant,
     qdrant::{
-        CreateCollectionBuilder, Distance, Filter, PointStruct, QuantizationType,
+        Condition, CreateCollectionBuilder, Distance, Filter, PointStruct, QuantizationType,
         ScalarQuantization, SearchParamsBuilder, SearchPointsBuilder, UpsertPointsBuilder,
         VectorParamsBuilder,
     },
 };
 
 use super::quantization::{QuantizationConfig, QuantizationMode};
-use crate::Config;
+use crate::{Config, RuneError, VectorDistance};
+
+/// Delay before the first retry of a failed search; doubles on each further attempt
+const SEARCH_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Run a Qdrant operation up to `max_attempts` times with exponential backoff.
+/// Persistent failure becomes `RuneError::QdrantUnavailable` so callers can tell a
+/// down backend apart from an empty result.
+async fn with_retry<T, F, Fut>(
+    operation: &str,
+    max_attempts: u32,
+    initial_delay: Duration,
+    mut op: F,
+) -> std::result::Result<T, RuneError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let max_attempts = max_attempts.max(1);
+    let mut delay = initial_delay;
+    let mut attempt = 1;
+
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt >= max_attempts => {
+                error!(
+                    "[QDRANT] {} failed after {} attempts: {:#}",
+                    operation, attempt, e
+                );
+                return Err(RuneError::QdrantUnavailable {
+                    attempts: attempt,
+                    message: format!("{:#}", e),
+                });
+            },
+            Err(e) => {
+                warn!(
+                    "[QDRANT] {} failed (attempt {}/{}): {:#}, retrying in {:?}",
+                    operation, attempt, max_attempts, e, delay
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            },
+        }
+    }
+}
 
 /// Manages Qdrant vector database operations
 pub struct QdrantManager {
-    _config: Arc<Config>, // Kept for potential future configuration needs
+    config: Arc<Config>,
     #[cfg(feature = "semantic")]
     client: Option<Qdrant>,
     collection_name: String,
@@ -27,17 +73,7 @@ pub struct QdrantManager {
 
 impl QdrantManager {
     pub async fn new(config: Arc<Config>) -> Result<Self> {
-        // Generate collection name based on workspace path hash
-        // Use RUNE_WORKSPACE_ID if set (for Docker), otherwise hash the workspace_dir
-        let workspace_identifier =
-            std::env::var("RUNE_WORKSPACE_ID").unwrap_or_else(|_| config.workspace_dir.clone());
-
-        let workspace_hash = blake3::hash(workspace_identifier.as_bytes())
-            .to_hex()
-            .chars()
-            .take(16)
-            .collect::<String>();
-        let collection_name = format!("rune_{}", workspace_hash);
+        let collection_name = Self::collection_name(&config);
 
         #[cfg(feature = "semantic")]
         {
@@ -49,7 +85,7 @@ impl QdrantManager {
             if !enable_semantic {
                 info!("Semantic search disabled by configuration");
                 return Ok(Self {
-                    _config: config,
+                    config,
                     client: None,
                     collection_name,
                     quantization_config: QuantizationConfig::default(),
@@ -98,12 +134,18 @@ impl QdrantManager {
                     quantization_config.log_config();
 
                     // Initialize collection with quantization
-                    if let Err(e) =
-                        Self::init_collection(&client, &collection_name, &quantization_config).await
+                    if let Err(e) = Self::init_collection(
+                        &client,
+                        &collection_name,
+                        &quantization_config,
+                        config.vector_distance,
+                        config.embedding_dimension,
+                    )
+                    .await
                     {
                         error!("[QDRANT] Failed to initialize collection: {}", e);
                         return Ok(Self {
-                            _config: config,
+                            config,
                             client: None,
                             collection_name,
                             quantization_config,
@@ -111,7 +153,7 @@ impl QdrantManager {
                     }
 
                     Ok(Self {
-                        _config: config,
+                        config,
                         client: Some(client),
                         collection_name,
                         quantization_config,
@@ -125,7 +167,7 @@ impl QdrantManager {
                         "[QDRANT] Please ensure Qdrant is running: docker run -p 6333:6333 -p 6334:6334 qdrant/qdrant"
                     );
                     Ok(Self {
-                        _config: config,
+                        config,
                         client: None,
                         collection_name,
                         quantization_config: QuantizationConfig::default(),
@@ -138,13 +180,38 @@ impl QdrantManager {
         {
             debug!("Semantic feature not enabled at compile time");
             Ok(Self {
-                _config: config,
+                config,
                 collection_name,
                 quantization_config: QuantizationConfig::default(),
             })
         }
     }
 
+    /// A manager that never connects, for pipelines that keep vectors in memory
+    pub fn disconnected(config: Arc<Config>) -> Self {
+        Self {
+            collection_name: Self::collection_name(&config),
+            config,
+            #[cfg(feature = "semantic")]
+            client: None,
+            quantization_config: QuantizationConfig::default(),
+        }
+    }
+
+    /// Collection name based on a hash of the workspace. Uses RUNE_WORKSPACE_ID if set
+    /// (for Docker), otherwise hashes the workspace_dir.
+    fn collection_name(config: &Config) -> String {
+        let workspace_identifier =
+            std::env::var("RUNE_WORKSPACE_ID").unwrap_or_else(|_| config.workspace_dir.clone());
+
+        let workspace_hash = blake3::hash(workspace_identifier.as_bytes())
+            .to_hex()
+            .chars()
+            .take(16)
+            .collect::<String>();
+        format!("rune_{}", workspace_hash)
+    }
+
     #[cfg(feature = "semantic")]
     async fn connect_with_retry(url: &str, strategy: &str, max_retries: u32) -> Option<Qdrant> {
         let mut retry_count = 0;
@@ -200,6 +267,8 @@ impl QdrantManager {
         client: &Qdrant,
         collection_name: &str,
         quant_config: &QuantizationConfig,
+        distance: VectorDistance,
+        dimension: usize,
     ) -> Result<()> {
         // Check if collection exists
         let collections = client.list_collections().await?;
@@ -215,8 +284,9 @@ impl QdrantManager {
             );
 
             // Build the collection with quantization config
-            let mut builder = CreateCollectionBuilder::new(collection_name)
-                .vectors_config(VectorParamsBuilder::new(384, Distance::Cosine));
+            let mut builder = CreateCollectionBuilder::new(collection_name).vectors_config(
+                VectorParamsBuilder::new(dimension as u64, qdrant_distance(distance)),
+            );
 
             // Add quantization configuration based on mode
             match quant_config.mode {
@@ -265,6 +335,17 @@ impl QdrantManager {
 
     /// Store embeddings with metadata
     pub async fn store_embeddings(&self, chunks: Vec<EmbeddedChunk>) -> Result<()> {
+        // Catch a wrong-sized vector here rather than as an opaque upsert failure
+        let expected = self.config.embedding_dimension;
+        if let Some(chunk) = chunks.iter().find(|c| c.embedding.len() != expected) {
+            return Err(RuneError::DimensionMismatch {
+                file_path: chunk.file_path.clone(),
+                expected,
+                actual: chunk.embedding.len(),
+            }
+            .into());
+        }
+
         #[cfg(feature = "semantic")]
         {
             if let Some(ref client) = self.client {
@@ -409,10 +490,18 @@ impl QdrantManager {
                     search_builder = search_builder.filter(filter);
                 }
 
-                let results = client
-                    .search_points(search_builder)
-                    .await
-                    .context("Failed to search points")?;
+                let results = with_retry(
+                    "search",
+                    self.config.qdrant_search_retries + 1,
+                    SEARCH_RETRY_DELAY,
+                    || async {
+                        client
+                            .search_points(search_builder.clone())
+                            .await
+                            .context("Failed to search points")
+                    },
+                )
+                .await?;
 
                 let mut search_results = Vec::new();
                 for result in results.result {
@@ -515,14 +604,61 @@ impl QdrantManager {
             if let Some(ref client) = self.client {
                 info!("[QDRANT] Clearing collection '{}'", self.collection_name);
                 client.delete_collection(&self.collection_name).await?;
-                Self::init_collection(client, &self.collection_name, &self.quantization_config)
-                    .await?;
+                Self::init_collection(
+                    client,
+                    &self.collection_name,
+                    &self.quantization_config,
+                    self.config.vector_distance,
+                    self.config.embedding_dimension,
+                )
+                .await?;
             }
         }
         Ok(())
     }
 }
 
+#[cfg(feature = "semantic")]
+fn qdrant_distance(distance: VectorDistance) -> Distance {
+    match distance {
+        VectorDistance::Cosine => Distance::Cosine,
+        VectorDistance::Dot => Distance::Dot,
+        VectorDistance::Euclidean => Distance::Euclid,
+    }
+}
+
+/// Restricts a vector search to chunks with matching payload fields
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VectorFilter {
+    /// Only chunks from one of these files (all files when empty)
+    pub file_paths: Vec<String>,
+    /// Only chunks in this language, as stored (e.g. "rust")
+    pub language: Option<String>,
+}
+
+impl VectorFilter {
+    pub fn matches(&self, chunk: &EmbeddedChunk) -> bool {
+        (self.file_paths.is_empty() || self.file_paths.contains(&chunk.file_path))
+            && self
+                .language
+                .as_ref()
+                .is_none_or(|language| chunk.language.as_ref() == Some(language))
+    }
+
+    /// Equivalent Qdrant payload filter
+    #[cfg(feature = "semantic")]
+    pub fn to_qdrant(&self) -> Filter {
+        let mut conditions = Vec::new();
+        if !self.file_paths.is_empty() {
+            conditions.push(Condition::matches("file_path", self.file_paths.clone()));
+        }
+        if let Some(ref language) = self.language {
+            conditions.push(Condition::matches("language", language.clone()));
+        }
+        Filter::must(conditions)
+    }
+}
+
 /// Represents a chunk of code with its embedding
 #[derive(Debug, Clone)]
 pub struct EmbeddedChunk {
@@ -562,9 +698,43 @@ mod tests {
             enable_semantic: true,
             languages: vec!["rust".to_string()],
             file_watch_debounce_ms: 500,
+            ..Default::default()
         })
     }
 
+    #[tokio::test]
+    async fn test_with_retry_reports_unavailable() {
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result: std::result::Result<(), RuneError> =
+            with_retry("search", 3, Duration::from_millis(1), || async {
+                attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Err(anyhow::anyhow!("connection refused"))
+            })
+            .await;
+
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+        match result {
+            Err(RuneError::QdrantUnavailable { attempts, message }) => {
+                assert_eq!(attempts, 3);
+                assert!(message.contains("connection refused"));
+            },
+            other => panic!("expected QdrantUnavailable, got {:?}", other),
+        }
+
+        // A transient failure is retried and then succeeds
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result = with_retry("search", 3, Duration::from_millis(1), || async {
+            if attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                Err(anyhow::anyhow!("timeout"))
+            } else {
+                Ok(42)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
     #[tokio::test]
     async fn test_qdrant_manager_new_with_disabled_semantic() {
         // Set env var to disable semantic
@@ -636,6 +806,47 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_store_embeddings_rejects_wrong_dimension() {
+        unsafe {
+            std::env::set_var("RUNE_ENABLE_SEMANTIC", "false");
+        }
+
+        let config = create_test_config();
+        let manager = QdrantManager::new(config).await.unwrap();
+
+        let chunk = |file_path: &str, dimension: usize| EmbeddedChunk {
+            id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+            content: "test content".to_string(),
+            embedding: vec![0.1; dimension],
+            file_path: file_path.to_string(),
+            start_line: 1,
+            end_line: 10,
+            language: Some("rust".to_string()),
+        };
+
+        let err = manager
+            .store_embeddings(vec![chunk("ok.rs", 384), chunk("fallback.rs", 256)])
+            .await
+            .unwrap_err();
+        match err.downcast_ref::<RuneError>() {
+            Some(RuneError::DimensionMismatch {
+                file_path,
+                expected,
+                actual,
+            }) => {
+                assert_eq!(file_path, "fallback.rs");
+                assert_eq!(*expected, 384);
+                assert_eq!(*actual, 256);
+            },
+            other => panic!("expected DimensionMismatch, got {:?}", other),
+        }
+
+        unsafe {
+            std::env::remove_var("RUNE_ENABLE_SEMANTIC");
+        }
+    }
+
     #[tokio::test]
     async fn test_search_without_client() {
         unsafe {
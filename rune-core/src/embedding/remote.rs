@@ -0,0 +1,312 @@
+use anyhow::{Context, Result};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::RuneError;
+
+/// Most texts sent to the endpoint in one request
+pub const REMOTE_BATCH_SIZE: usize = 64;
+
+/// Extra attempts for a request that fails with a transient error
+const REMOTE_RETRIES: u32 = 3;
+
+/// Delay before the first retry; doubles on each further attempt
+const REMOTE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest wait for a connection to the endpoint
+const REMOTE_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest wait for a whole request, after which it's retried
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Client for an OpenAI-compatible `/v1/embeddings` endpoint
+pub struct RemoteEmbedder {
+    client: reqwest::Client,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    dimension: usize,
+    retries: u32,
+    retry_delay: Duration,
+    timeout: Duration,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// A failed request. Client errors such as a rejected API key won't go away on retry.
+struct AttemptError {
+    error: anyhow::Error,
+    retryable: bool,
+}
+
+impl RemoteEmbedder {
+    /// Embeddings from `model` at `url`, expected to have `dimension` values. Fails
+    /// with `RuneError::InvalidConfig` if `api_key_env` names an unset variable.
+    pub fn new(
+        url: &str,
+        model: &str,
+        api_key_env: Option<&str>,
+        dimension: usize,
+    ) -> Result<Self> {
+        let api_key = api_key_env
+            .map(|var| {
+                std::env::var(var).map_err(|_| {
+                    RuneError::InvalidConfig(format!(
+                        "embedding API key variable {} is not set",
+                        var
+                    ))
+                })
+            })
+            .transpose()?;
+
+        let client = reqwest::Client::builder()
+            .connect_timeout(REMOTE_CONNECT_TIMEOUT)
+            .timeout(REMOTE_TIMEOUT)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self {
+            client,
+            endpoint: format!("{}/v1/embeddings", url.trim_end_matches('/')),
+            model: model.to_string(),
+            api_key,
+            dimension,
+            retries: REMOTE_RETRIES,
+            retry_delay: REMOTE_RETRY_DELAY,
+            timeout: REMOTE_TIMEOUT,
+        })
+    }
+
+    /// Abandon and retry a request that hasn't completed within `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retry each failed request up to `retries` more times, waiting `initial_delay`
+    /// before the first retry and doubling the wait after that
+    pub fn with_retries(mut self, retries: u32, initial_delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = initial_delay;
+        self
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// One embedding per text, in order, requested `REMOTE_BATCH_SIZE` texts at a time.
+    /// Persistent failure becomes `RuneError::Embedding`.
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(REMOTE_BATCH_SIZE) {
+            embeddings.extend(self.embed_with_retry(batch).await?);
+        }
+        Ok(embeddings)
+    }
+
+    /// Retry server errors, rate limiting, timeouts and dropped connections with
+    /// exponential backoff
+    async fn embed_with_retry(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let max_attempts = self.retries + 1;
+        let mut delay = self.retry_delay;
+        let mut attempt = 1;
+
+        loop {
+            match self.request(texts).await {
+                Ok(embeddings) => return Ok(embeddings),
+                Err(e) if attempt >= max_attempts || !e.retryable => {
+                    return Err(RuneError::Embedding(format!(
+                        "{} failed after {} attempts: {:#}",
+                        self.endpoint, attempt, e.error
+                    ))
+                    .into());
+                },
+                Err(e) => {
+                    warn!(
+                        "Embedding request failed (attempt {}/{}): {:#}, retrying in {:?}",
+                        attempt, max_attempts, e.error, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                },
+            }
+        }
+    }
+
+    async fn request(&self, texts: &[String]) -> std::result::Result<Vec<Vec<f32>>, AttemptError> {
+        let mut request =
+            self.client
+                .post(&self.endpoint)
+                .timeout(self.timeout)
+                .json(&EmbeddingRequest {
+                    model: &self.model,
+                    input: texts,
+                });
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        // Includes refused connections and timeouts
+        let retryable = |error: anyhow::Error| AttemptError {
+            error,
+            retryable: true,
+        };
+        let response = request
+            .send()
+            .await
+            .context("Failed to send embedding request")
+            .map_err(retryable)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AttemptError {
+                error: anyhow::anyhow!("Embedding request failed with status: {}", status),
+                retryable: status.is_server_error()
+                    || status == StatusCode::REQUEST_TIMEOUT
+                    || status == StatusCode::TOO_MANY_REQUESTS,
+            });
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .context("Failed to read embedding response")
+            .map_err(retryable)?;
+        let fatal = |error: anyhow::Error| AttemptError {
+            error,
+            retryable: false,
+        };
+        let mut parsed: EmbeddingResponse = serde_json::from_slice(&body)
+            .context("Malformed embedding response")
+            .map_err(fatal)?;
+
+        // The API doesn't promise to answer in input order
+        parsed.data.sort_by_key(|data| data.index);
+        if parsed.data.len() != texts.len()
+            || parsed
+                .data
+                .iter()
+                .enumerate()
+                .any(|(i, data)| data.index != i)
+        {
+            return Err(fatal(anyhow::anyhow!(
+                "Expected {} embeddings, got {}",
+                texts.len(),
+                parsed.data.len()
+            )));
+        }
+        if let Some(data) = parsed
+            .data
+            .iter()
+            .find(|data| data.embedding.len() != self.dimension)
+        {
+            return Err(fatal(anyhow::anyhow!(
+                "Model {} returned {} dimensions (expected {})",
+                self.model,
+                data.embedding.len(),
+                self.dimension
+            )));
+        }
+
+        Ok(parsed.data.into_iter().map(|data| data.embedding).collect())
+    }
+}
+
+/// Serve an OpenAI-style embeddings endpoint on localhost, answering the first
+/// request with a 503 if `fail_first` is set. Each vector is the input's length
+/// repeated `dimension` times, listed in reverse order. Returns the base URL and the
+/// number of inputs in each request received.
+#[cfg(test)]
+pub(crate) async fn serve_embeddings(
+    dimension: usize,
+    fail_first: bool,
+) -> (String, std::sync::Arc<std::sync::Mutex<Vec<usize>>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let batches = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let received = batches.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                return;
+            };
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body_start = loop {
+                if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end + 4;
+                }
+                match socket.read(&mut buf).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            };
+            let head = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+            let content_length: usize = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map_or(0, |len| len.trim().parse().unwrap());
+            while request.len() < body_start + content_length {
+                match socket.read(&mut buf).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+
+            let body: serde_json::Value = serde_json::from_slice(&request[body_start..]).unwrap();
+            let inputs = body["input"].as_array().unwrap();
+            let first = {
+                let mut batches = received.lock().unwrap();
+                batches.push(inputs.len());
+                fail_first && batches.len() == 1
+            };
+
+            let (status, body) = if first {
+                (503, String::new())
+            } else {
+                let data: Vec<_> = inputs
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, input)| {
+                        let len = input.as_str().unwrap().len() as f32;
+                        serde_json::json!({ "index": index, "embedding": vec![len; dimension] })
+                    })
+                    .collect();
+                (200, serde_json::json!({ "data": data }).to_string())
+            };
+            let response = format!(
+                "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+            let _ = socket.shutdown().await;
+        }
+    });
+
+    (format!("http://{}", addr), batches)
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+use std::sync::Arc;
+
+use super::{EmbeddingGenerator, SemanticSearchResult};
+use crate::Config;
+
+/// Second-stage scorer: re-embeds the query and the top candidates with a separate
+/// (usually larger) model and orders them by similarity under that model.
+pub struct Reranker {
+    generator: EmbeddingGenerator,
+}
+
+impl Reranker {
+    pub async fn new(config: Arc<Config>, model: &str) -> Result<Self> {
+        Ok(Self::with_generator(
+            EmbeddingGenerator::for_model(config, model).await?,
+        ))
+    }
+
+    pub fn with_generator(generator: EmbeddingGenerator) -> Self {
+        Self { generator }
+    }
+
+    /// Whether the rerank model loaded (rather than running in hash fallback mode)
+    pub fn is_available(&self) -> bool {
+        self.generator.is_available()
+    }
+
+    /// Replace the scores of the first `top_k` results with their similarity to `query`
+    /// and sort them by it. Results past `top_k` keep their primary scores and order
+    /// and stay after the re-ranked ones.
+    pub async fn rerank(
+        &self,
+        query: &str,
+        mut results: Vec<SemanticSearchResult>,
+        top_k: usize,
+    ) -> Result<Vec<SemanticSearchResult>> {
+        let rest = results.split_off(top_k.min(results.len()));
+        if results.is_empty() {
+            results.extend(rest);
+            return Ok(results);
+        }
+
+        let query_embedding = self.generator.generate_embedding(query).await?;
+        let contents: Vec<String> = results.iter().map(|r| r.content.clone()).collect();
+        let embeddings = self.generator.batch_generate(&contents).await?;
+
+        // Embeddings are L2-normalized, so the dot product is the cosine similarity
+        for (result, embedding) in results.iter_mut().zip(embeddings) {
+            result.score = query_embedding
+                .iter()
+                .zip(&embedding)
+                .map(|(a, b)| a * b)
+                .sum();
+        }
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        results.extend(rest);
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(file_path: &str, content: &str, score: f32) -> SemanticSearchResult {
+        SemanticSearchResult {
+            file_path: file_path.to_string(),
+            content: content.to_string(),
+            start_line: 1,
+            end_line: 1,
+            language: Some("rust".to_string()),
+            score,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rerank_reorders_by_rerank_scores() {
+        // Hash-based embeddings: identical text scores 1.0, anything else far less
+        let reranker =
+            Reranker::with_generator(EmbeddingGenerator::fallback(Arc::new(Config::default())));
+
+        let query = "fn parse_config(path: &Path) -> Config";
+        let candidates = vec![
+            candidate("a.rs", "fn unrelated() {}", 0.9),
+            candidate("b.rs", "struct Other;", 0.8),
+            candidate("c.rs", query, 0.7),
+            candidate("d.rs", query, 0.1),
+        ];
+
+        let reranked = reranker.rerank(query, candidates, 3).await.unwrap();
+        let order: Vec<&str> = reranked.iter().map(|r| r.file_path.as_str()).collect();
+
+        // The exact match had the lowest primary score among the top three
+        assert_eq!(order[0], "c.rs");
+        assert!((reranked[0].score - 1.0).abs() < 1e-4);
+        assert!(reranked[0].score > reranked[1].score);
+        assert!(reranked[1].score >= reranked[2].score);
+
+        // Candidates past top_k are left alone
+        assert_eq!(order[3], "d.rs");
+        assert_eq!(reranked[3].score, 0.1);
+    }
+}
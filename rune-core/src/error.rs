@@ -26,6 +26,9 @@ pub enum RuneError {
     #[error("Configuration error: {0}")]
     Config(String),
 
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
     #[error("Invalid query: {0}")]
     InvalidQuery(String),
 
@@ -41,9 +44,40 @@ pub enum RuneError {
     #[error("Network error: {0}")]
     Network(String),
 
+    #[error("Semantic search is unavailable: rune-core was built without the `semantic` feature")]
+    SemanticDisabled,
+
+    #[error("Too many concurrent searches (limit: {max_concurrent})")]
+    Overloaded { max_concurrent: usize },
+
+    #[error("Qdrant unavailable after {attempts} attempts: {message}")]
+    QdrantUnavailable { attempts: u32, message: String },
+
+    #[error("Embedding for {file_path} has {actual} dimensions (expected {expected})")]
+    DimensionMismatch {
+        file_path: String,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error(
+        "Storage at {path} is locked by another Rune instance; open it with `read_only` or use a different cache_dir"
+    )]
+    StorageLocked { path: String },
+
+    #[error("Engine is read-only, cannot {0}")]
+    ReadOnly(String),
+
     #[error("Model not found: {0}")]
     ModelNotFound(String),
 
+    #[error("Failed to download model file {file} after {attempts} attempts: {cause}")]
+    ModelDownloadFailed {
+        file: String,
+        attempts: u32,
+        cause: String,
+    },
+
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -0,0 +1,176 @@
+use std::path::{Path, PathBuf};
+
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// Marker word that opens a task comment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Encode, Decode)]
+pub enum AnnotationKind {
+    Todo,
+    Fixme,
+    Hack,
+    Xxx,
+}
+
+impl AnnotationKind {
+    const ALL: [AnnotationKind; 4] = [Self::Todo, Self::Fixme, Self::Hack, Self::Xxx];
+
+    /// The marker as written in source
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Self::Todo => "TODO",
+            Self::Fixme => "FIXME",
+            Self::Hack => "HACK",
+            Self::Xxx => "XXX",
+        }
+    }
+}
+
+/// A TODO/FIXME/HACK/XXX comment found while indexing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub struct Annotation {
+    pub kind: AnnotationKind,
+    pub file_path: PathBuf,
+    /// 1-based line of the marker
+    pub line: usize,
+    /// Text following the marker, without an `(owner)` tag or separator
+    pub message: String,
+}
+
+/// Tokens that start a comment in one of the indexed languages
+const COMMENT_OPENERS: &[&str] = &["//", "/*", "#", "--", "<!--"];
+
+/// Extensions of Lisp, assembly and INI files, where `;` also starts a comment
+const SEMICOLON_COMMENT_EXTENSIONS: &[&str] = &[
+    "lisp", "lsp", "cl", "el", "clj", "cljs", "cljc", "edn", "scm", "ss", "rkt", "asm", "s",
+    "nasm", "ini",
+];
+
+/// Task comments in `content`. A marker only counts at the start of a comment's
+/// text, so prose like "see the TODO list" is ignored.
+pub fn extract_annotations(file_path: &Path, content: &str) -> Vec<Annotation> {
+    let semicolon_comments = file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            SEMICOLON_COMMENT_EXTENSIONS
+                .iter()
+                .any(|e| ext.eq_ignore_ascii_case(e))
+        });
+
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let (kind, message) = line_annotation(line, semicolon_comments)?;
+            Some(Annotation {
+                kind,
+                file_path: file_path.to_path_buf(),
+                line: i + 1,
+                message,
+            })
+        })
+        .collect()
+}
+
+fn line_annotation(line: &str, semicolon_comments: bool) -> Option<(AnnotationKind, String)> {
+    // Continuation lines of a block comment (" * TODO: ...") open with a bare `*`
+    let trimmed = line.trim_start();
+    if let Some(rest) = trimmed.strip_prefix('*')
+        && let Some(found) = comment_annotation(rest)
+    {
+        return Some(found);
+    }
+
+    line.char_indices().find_map(|(i, _)| {
+        let rest = &line[i..];
+        let opener_len = if semicolon_comments && rest.starts_with(';') {
+            1
+        } else {
+            COMMENT_OPENERS
+                .iter()
+                .find(|o| rest.starts_with(**o))?
+                .len()
+        };
+        comment_annotation(&rest[opener_len..])
+    })
+}
+
+/// Parse the text after a comment opener, e.g. " TODO(alice): handle errors */"
+fn comment_annotation(comment: &str) -> Option<(AnnotationKind, String)> {
+    let text = comment.trim_start_matches(|c: char| c.is_whitespace() || "/*!#-;".contains(c));
+
+    let kind = AnnotationKind::ALL.into_iter().find(|kind| {
+        text.strip_prefix(kind.marker())
+            .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+    })?;
+
+    let mut rest = &text[kind.marker().len()..];
+    if rest.starts_with('(')
+        && let Some(end) = rest.find(')')
+    {
+        rest = &rest[end + 1..];
+    }
+
+    let message = rest
+        .trim_start_matches(|c: char| c.is_whitespace() || c == ':' || c == '-')
+        .trim_end()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim_end();
+
+    Some((kind, message.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(line: &str) -> Option<(AnnotationKind, String)> {
+        line_annotation(line, false)
+    }
+
+    #[test]
+    fn test_comment_styles() {
+        assert_eq!(
+            parsed("    // TODO: x"),
+            Some((AnnotationKind::Todo, "x".to_string()))
+        );
+        assert_eq!(
+            parsed("# FIXME: y"),
+            Some((AnnotationKind::Fixme, "y".to_string()))
+        );
+        assert_eq!(
+            parsed("let a = 1; /* HACK(bob): skip the check */"),
+            Some((AnnotationKind::Hack, "skip the check".to_string()))
+        );
+        assert_eq!(
+            parsed(" * XXX - revisit"),
+            Some((AnnotationKind::Xxx, "revisit".to_string()))
+        );
+        assert_eq!(
+            parsed("-- TODO"),
+            Some((AnnotationKind::Todo, String::new()))
+        );
+    }
+
+    #[test]
+    fn test_non_markers_ignored() {
+        assert_eq!(parsed("// see the TODO list"), None);
+        assert_eq!(parsed("// TODOS are tracked elsewhere"), None);
+        assert_eq!(parsed("let TODO = 1;"), None);
+        assert_eq!(parsed("#[derive(Debug)]"), None);
+    }
+
+    #[test]
+    fn test_semicolon_comments_by_extension() {
+        let line = "(setq width 80) ; TODO: read from the config";
+        let found = extract_annotations(Path::new("init.el"), line);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].message, "read from the config");
+        assert_eq!(extract_annotations(Path::new("boot.S"), "; FIXME").len(), 1);
+
+        // Elsewhere `;` ends a statement
+        assert!(extract_annotations(Path::new("main.rs"), "let x = 1; TODO: x").is_empty());
+    }
+}
@@ -1,9 +1,12 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
 use anyhow::Result;
+use dashmap::DashMap;
 use ignore::WalkBuilder;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use notify::{Config as NotifyConfig, RecursiveMode};
 use notify_debouncer_full::{DebounceEventResult, Debouncer, FileIdMap, new_debouncer_opt};
 use tokio::sync::mpsc;
@@ -13,11 +16,125 @@ use crate::Config;
 
 pub struct FileWalker {
     config: Arc<Config>,
+    /// Ignore rules for each workspace root, shared by watchers and `admits`
+    ignore_rules: DashMap<PathBuf, Arc<IgnoreRules>>,
+}
+
+/// The ignore rules the walker applies under one workspace root, for checking single
+/// paths. Ignore files are parsed on first use and again whenever they change.
+struct IgnoreRules {
+    root: PathBuf,
+    /// `Config::ignore_patterns`
+    configured: Gitignore,
+    /// The global git excludes file, read once
+    global: Gitignore,
+    files: Mutex<HashMap<PathBuf, ParsedIgnoreFile>>,
+}
+
+/// An ignore file's rules, with the modification time and size they were read at
+struct ParsedIgnoreFile {
+    modified: SystemTime,
+    len: u64,
+    rules: Arc<Gitignore>,
+}
+
+impl IgnoreRules {
+    fn new(root: &Path, configured: Gitignore) -> Self {
+        let (global, error) = GitignoreBuilder::new(root).build_global();
+        if let Some(e) = error {
+            warn!("Failed to read global git excludes: {}", e);
+        }
+
+        Self {
+            root: root.to_path_buf(),
+            configured,
+            global,
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `path` is ignored by `Config::ignore_patterns`, by the nearest
+    /// `.ignore`, `.gitignore` or `.git/info/exclude` rule between the root and the
+    /// path (so nested files and `!` negations apply), or by the global git excludes
+    fn is_ignored(&self, path: &Path) -> bool {
+        if !path.starts_with(&self.root) {
+            return false;
+        }
+        let is_dir = path.is_dir();
+        if self
+            .configured
+            .matched_path_or_any_parents(path, is_dir)
+            .is_ignore()
+        {
+            return true;
+        }
+
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            for name in [".ignore", ".gitignore", ".git/info/exclude"] {
+                let Some(rules) = self.rules(current, &current.join(name)) else {
+                    continue;
+                };
+                let matched = rules.matched_path_or_any_parents(path, is_dir);
+                if matched.is_ignore() {
+                    return true;
+                }
+                if matched.is_whitelist() {
+                    return false;
+                }
+            }
+            if current == self.root {
+                break;
+            }
+            dir = current.parent();
+        }
+
+        self.global
+            .matched_path_or_any_parents(path, is_dir)
+            .is_ignore()
+    }
+
+    /// The rules in ignore `file`, matched relative to `dir`, or `None` if there is
+    /// no such file. Reparsed only when the file's modification time or size changes.
+    fn rules(&self, dir: &Path, file: &Path) -> Option<Arc<Gitignore>> {
+        let stamp = std::fs::metadata(file)
+            .ok()
+            .filter(|m| m.is_file())
+            .and_then(|m| Some((m.modified().ok()?, m.len())));
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        let Some((modified, len)) = stamp else {
+            files.remove(file);
+            return None;
+        };
+        if let Some(parsed) = files.get(file)
+            && (parsed.modified, parsed.len) == (modified, len)
+        {
+            return Some(parsed.rules.clone());
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        if let Some(e) = builder.add(file) {
+            warn!("Failed to read ignore file {:?}: {}", file, e);
+        }
+        let rules = Arc::new(builder.build().unwrap_or_else(|_| Gitignore::empty()));
+        files.insert(
+            file.to_path_buf(),
+            ParsedIgnoreFile {
+                modified,
+                len,
+                rules: rules.clone(),
+            },
+        );
+        Some(rules)
+    }
 }
 
 impl FileWalker {
     pub fn new(config: Arc<Config>) -> Self {
-        Self { config }
+        Self {
+            config,
+            ignore_rules: DashMap::new(),
+        }
     }
 
     pub async fn walk_workspaces(&self) -> Result<Vec<PathBuf>> {
@@ -37,17 +154,29 @@ impl FileWalker {
         let (tx, mut rx) = mpsc::channel(1000);
         let root = root.to_path_buf();
         let max_file_size = self.config.max_file_size;
+        let index_hidden = self.config.index_hidden;
+        let index_unknown = self.config.index_unknown_languages;
+        let excluded = self.excluded_dirs(&root);
+        let ignored = self.ignore_patterns(&root);
+        let contained_in = self.containment_roots();
 
         // Spawn blocking task for file walking
         let handle = tokio::task::spawn_blocking(move || {
+            let ignored_dirs = ignored.clone();
             let walker = WalkBuilder::new(&root)
-                .hidden(false) // Include hidden files
+                .hidden(!index_hidden) // Skip dotfiles and hidden directories unless enabled
                 .git_ignore(true) // Respect .gitignore
                 .git_global(true) // Respect global gitignore
                 .git_exclude(true) // Respect .git/info/exclude
                 .require_git(false) // Don't require git repo
                 .ignore(true) // Respect .ignore files
                 .max_filesize(Some(max_file_size as u64))
+                // With `max_filesize` set, the walker only filters directories
+                .filter_entry(move |entry| {
+                    let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                    !Self::is_excluded(&excluded, entry.path())
+                        && !ignored_dirs.matched(entry.path(), is_dir).is_ignore()
+                })
                 .build();
 
             for entry in walker {
@@ -60,8 +189,16 @@ impl FileWalker {
                             continue;
                         }
 
+                        if ignored.matched(path, false).is_ignore() {
+                            continue;
+                        }
+
                         // Skip binary files and non-text files
-                        if !Self::is_indexable_file(path) {
+                        if !Self::should_index(path, index_unknown) {
+                            continue;
+                        }
+
+                        if !Self::is_contained(contained_in.as_deref(), path) {
                             continue;
                         }
 
@@ -97,6 +234,11 @@ impl FileWalker {
         use std::sync::mpsc as std_mpsc;
 
         let root_path = root.to_path_buf();
+        let index_hidden = self.config.index_hidden;
+        let index_unknown = self.config.index_unknown_languages;
+        let excluded = self.excluded_dirs(&root_path);
+        let ignore_rules = self.ignore_rules(&root_path);
+        let contained_in = self.containment_roots();
         let (event_tx, event_rx) = std_mpsc::channel();
 
         // Create a debounced watcher with FileIdMap cache
@@ -123,17 +265,39 @@ impl FileWalker {
         );
 
         // Process debounced events in a separate thread
+        let event_root = root_path.clone();
         std::thread::spawn(move || {
             while let Ok(event) = event_rx.recv() {
                 let paths = event.paths.clone();
                 let kind = event.kind;
 
                 for path in paths {
-                    if !Self::is_indexable_file(&path) {
+                    use notify::EventKind;
+
+                    if Self::is_excluded(&excluded, &path) || ignore_rules.is_ignored(&path) {
+                        continue;
+                    }
+
+                    // Removed files can't be sniffed, so admit any that could have been indexed
+                    let admitted = match kind {
+                        EventKind::Remove(_) => Self::is_indexable_file(&path) || index_unknown,
+                        _ => Self::should_index(&path, index_unknown),
+                    };
+                    if !admitted {
+                        continue;
+                    }
+
+                    if !index_hidden && Self::is_hidden_path(&event_root, &path) {
+                        continue;
+                    }
+
+                    // Removed files no longer resolve, and dropping them is always safe
+                    if !matches!(kind, EventKind::Remove(_))
+                        && !Self::is_contained(contained_in.as_deref(), &path)
+                    {
                         continue;
                     }
 
-                    use notify::EventKind;
                     let file_event = match kind {
                         EventKind::Create(_) => FileEvent::Created(path),
                         EventKind::Modify(_) => FileEvent::Modified(path),
@@ -154,6 +318,148 @@ impl FileWalker {
         Ok(debouncer)
     }
 
+    /// Directories under `root` that must never be indexed or watched: the cache
+    /// directory (RocksDB, Tantivy and downloaded models) when it sits inside the
+    /// workspace. Paths are given both as seen from `root` and canonicalized, so they
+    /// match walker entries and watcher events alike.
+    pub fn excluded_dirs(&self, root: &Path) -> Vec<PathBuf> {
+        let canonical = |path: &Path| {
+            path.canonicalize()
+                .or_else(|_| std::path::absolute(path))
+                .unwrap_or_else(|_| path.to_path_buf())
+        };
+
+        let root_canonical = canonical(root);
+        let cache_dir = canonical(&self.config.cache_dir);
+        let Ok(relative) = cache_dir.strip_prefix(&root_canonical) else {
+            return Vec::new();
+        };
+
+        let mut excluded = vec![root.join(relative)];
+        if cache_dir != excluded[0] {
+            excluded.push(cache_dir);
+        }
+        excluded
+    }
+
+    /// `Config::ignore_patterns` anchored at `root`. Patterns that don't parse are
+    /// skipped; `Config::validate` reports them.
+    fn ignore_patterns(&self, root: &Path) -> Gitignore {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in &self.config.ignore_patterns {
+            if let Err(e) = builder.add_line(None, pattern) {
+                warn!("Skipping ignore pattern {:?}: {}", pattern, e);
+            }
+        }
+        builder.build().unwrap_or_else(|e| {
+            warn!("Failed to build ignore patterns: {}", e);
+            Gitignore::empty()
+        })
+    }
+
+    /// The ignore rules for `root`, loaded on first use
+    fn ignore_rules(&self, root: &Path) -> Arc<IgnoreRules> {
+        self.ignore_rules
+            .entry(root.to_path_buf())
+            .or_insert_with(|| Arc::new(IgnoreRules::new(root, self.ignore_patterns(root))))
+            .clone()
+    }
+
+    /// Whether a walk would pick up the existing file `path`: it isn't excluded or
+    /// ignored, hidden (unless `Config::index_hidden`), larger than
+    /// `Config::max_file_size` or binary, and it resolves under a workspace root
+    pub fn admits(&self, path: &Path) -> bool {
+        if let Some(root) = self
+            .config
+            .workspace_roots
+            .iter()
+            .find(|root| path.starts_with(root))
+            && (Self::is_excluded(&self.excluded_dirs(root), path)
+                || self.ignore_rules(root).is_ignored(path)
+                || (!self.config.index_hidden && Self::is_hidden_path(root, path)))
+        {
+            return false;
+        }
+
+        std::fs::metadata(path)
+            .is_ok_and(|m| m.is_file() && m.len() <= self.config.max_file_size as u64)
+            && Self::should_index(path, self.config.index_unknown_languages)
+            && self.is_within_roots(path)
+    }
+
+    /// Whether `path`, with symlinks and `..` resolved, lies under a workspace root.
+    /// Always true when `Config::enforce_root_containment` is off.
+    pub fn is_within_roots(&self, path: &Path) -> bool {
+        Self::is_contained(self.containment_roots().as_deref(), path)
+    }
+
+    /// Canonicalized workspace roots, or `None` when containment isn't enforced
+    fn containment_roots(&self) -> Option<Vec<PathBuf>> {
+        self.config.enforce_root_containment.then(|| {
+            self.config
+                .workspace_roots
+                .iter()
+                .map(|root| root.canonicalize().unwrap_or_else(|_| root.clone()))
+                .collect()
+        })
+    }
+
+    fn is_contained(roots: Option<&[PathBuf]>, path: &Path) -> bool {
+        let Some(roots) = roots else {
+            return true;
+        };
+
+        let contained = path
+            .canonicalize()
+            .is_ok_and(|resolved| roots.iter().any(|root| resolved.starts_with(root)));
+        if !contained {
+            warn!(
+                "Skipping {:?}: it resolves outside the workspace roots",
+                path
+            );
+        }
+        contained
+    }
+
+    fn is_excluded(excluded: &[PathBuf], path: &Path) -> bool {
+        excluded.iter().any(|dir| path.starts_with(dir))
+    }
+
+    /// Known source/text extensions are always admitted. Other files are admitted as
+    /// plain text only when `index_unknown` is set and they don't look binary.
+    fn should_index(path: &Path, index_unknown: bool) -> bool {
+        Self::is_indexable_file(path) || (index_unknown && Self::looks_like_text(path))
+    }
+
+    /// Sniff the start of a file for NUL bytes, the usual sign of binary content
+    fn looks_like_text(path: &Path) -> bool {
+        use std::io::Read;
+
+        let Ok(file) = std::fs::File::open(path) else {
+            return false;
+        };
+        let mut buf = Vec::with_capacity(8192);
+        if file.take(8192).read_to_end(&mut buf).is_err() {
+            return false;
+        }
+        if buf.contains(&0) {
+            return false;
+        }
+        // A multi-byte character cut off at the end of the sample is still text
+        match std::str::from_utf8(&buf) {
+            Ok(_) => true,
+            Err(e) => e.error_len().is_none(),
+        }
+    }
+
+    /// Whether any component of `path` below `root` is a dotfile or hidden directory
+    fn is_hidden_path(root: &Path, path: &Path) -> bool {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+    }
+
     fn is_indexable_file(path: &Path) -> bool {
         // Check if file has a text extension
         if let Some(extension) = path.extension() {
@@ -270,6 +576,210 @@ mod tests {
         assert_eq!(files[0], test_file);
     }
 
+    #[tokio::test]
+    async fn test_cache_dir_inside_workspace_is_excluded() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+
+        let cache_dir = root.join("rune-cache");
+        let storage = crate::storage::StorageBackend::new(&cache_dir)
+            .await
+            .unwrap();
+        std::fs::create_dir_all(cache_dir.join("models")).unwrap();
+        std::fs::write(cache_dir.join("models/config.json"), "{}").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![root.to_path_buf()],
+            cache_dir: cache_dir.clone(),
+            ..Default::default()
+        });
+        let walker = FileWalker::new(config);
+        let files = walker.walk_workspaces().await.unwrap();
+        assert_eq!(files, vec![root.join("main.rs")]);
+
+        let (tx, mut rx) = mpsc::channel(100);
+        let _debouncer = walker.watch_directory(root, tx, 50).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        std::fs::write(cache_dir.join("notes.md"), "# cache").unwrap();
+        storage
+            .record_access(&[root.join("main.rs")])
+            .await
+            .unwrap();
+        std::fs::write(root.join("lib.rs"), "pub fn lib() {}").unwrap();
+
+        let mut paths = Vec::new();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(3);
+        while let Ok(Some(event)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+            let (FileEvent::Created(path) | FileEvent::Modified(path) | FileEvent::Deleted(path)) =
+                event;
+            paths.push(path);
+        }
+
+        assert!(paths.iter().any(|p| p.ends_with("lib.rs")), "{:?}", paths);
+        assert!(
+            paths
+                .iter()
+                .all(|p| !p.to_string_lossy().contains("rune-cache")),
+            "{:?}",
+            paths
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlinks_outside_workspace_are_skipped() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("workspace");
+        let outside = temp_dir.path().join("outside");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        std::fs::write(outside.join("secret.rs"), "const KEY: &str = \"\";").unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.rs"), root.join("link.rs")).unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![root.clone()],
+            ..Default::default()
+        });
+        let walker = FileWalker::new(config);
+        let files = walker.walk_workspaces().await.unwrap();
+        assert_eq!(files, vec![root.join("main.rs")]);
+        assert!(walker.is_within_roots(&root.join("main.rs")));
+        assert!(!walker.is_within_roots(&root.join("../outside/secret.rs")));
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![root.clone()],
+            enforce_root_containment: false,
+            ..Default::default()
+        });
+        let mut files = FileWalker::new(config).walk_workspaces().await.unwrap();
+        files.sort();
+        assert_eq!(files, vec![root.join("link.rs"), root.join("main.rs")]);
+    }
+
+    #[tokio::test]
+    async fn test_hidden_entries_respect_config() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join(".git/config"), "[core]").unwrap();
+        std::fs::create_dir_all(root.join(".vscode")).unwrap();
+        std::fs::write(root.join(".vscode/settings.json"), "{}").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![root.to_path_buf()],
+            ..Default::default()
+        });
+        let files = FileWalker::new(config).walk_workspaces().await.unwrap();
+        assert_eq!(files, vec![root.join("main.rs")]);
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![root.to_path_buf()],
+            index_hidden: true,
+            index_unknown_languages: false,
+            ..Default::default()
+        });
+        let files = FileWalker::new(config).walk_workspaces().await.unwrap();
+        assert!(files.contains(&root.join(".vscode/settings.json")));
+        // Included by the hidden check, still filtered by extension
+        assert!(!files.contains(&root.join(".git/config")));
+
+        assert!(FileWalker::is_hidden_path(root, &root.join(".git/config")));
+        assert!(!FileWalker::is_hidden_path(root, &root.join("src/main.rs")));
+    }
+
+    #[tokio::test]
+    async fn test_gitignore_and_ignore_patterns() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(".gitignore", "build/\ngen/*\n!gen/keep.rs\n");
+        write("src/.gitignore", "*.generated.rs\n");
+        write("main.rs", "fn main() {}");
+        write("build/out.rs", "fn out() {}");
+        write("gen/drop.rs", "fn drop() {}");
+        write("gen/keep.rs", "fn keep() {}");
+        write("src/lib.rs", "pub fn lib() {}");
+        write("src/api.generated.rs", "pub fn api() {}");
+        write("fixtures/big.rs", "fn big() {}");
+        write("fixtures/small.rs", "fn small() {}");
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![root.to_path_buf()],
+            ignore_patterns: vec!["fixtures/*".to_string(), "!fixtures/small.rs".to_string()],
+            ..Default::default()
+        });
+        let walker = FileWalker::new(config);
+        let mut files = walker.walk_workspaces().await.unwrap();
+        files.sort();
+        let expected: Vec<PathBuf> = ["fixtures/small.rs", "gen/keep.rs", "main.rs", "src/lib.rs"]
+            .iter()
+            .map(|p| root.join(p))
+            .collect();
+        assert_eq!(files, expected);
+
+        // The watcher drops the same paths
+        let (tx, mut rx) = mpsc::channel(100);
+        let _debouncer = walker.watch_directory(root, tx, 50).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        write("build/next.rs", "fn next() {}");
+        write("src/db.generated.rs", "pub fn db() {}");
+        write("fixtures/other.rs", "fn other() {}");
+        write("gen/keep.rs", "fn keep() { }");
+
+        let mut paths = Vec::new();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(3);
+        while let Ok(Some(event)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+            let (FileEvent::Created(path) | FileEvent::Modified(path) | FileEvent::Deleted(path)) =
+                event;
+            paths.push(path);
+        }
+        assert!(
+            paths.iter().any(|p| p.ends_with("gen/keep.rs")),
+            "{:?}",
+            paths
+        );
+        assert!(
+            paths.iter().all(|p| p.ends_with("gen/keep.rs")),
+            "{:?}",
+            paths
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ignore_rules_follow_git_exclude_and_edits() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join(".git/info")).unwrap();
+        std::fs::write(root.join(".git/info/exclude"), "local.rs\n").unwrap();
+        std::fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        std::fs::write(root.join("local.rs"), "fn local() {}").unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+
+        let walker = FileWalker::new(Arc::new(Config {
+            workspace_roots: vec![root.to_path_buf()],
+            ..Default::default()
+        }));
+        let files = walker.walk_workspaces().await.unwrap();
+        assert_eq!(files, vec![root.join("main.rs")]);
+        assert!(!walker.admits(&root.join("local.rs")));
+        assert!(walker.admits(&root.join("main.rs")));
+
+        // An edited ignore file is read again
+        std::fs::write(root.join(".gitignore"), "build/\nmain.rs\n").unwrap();
+        assert!(!walker.admits(&root.join("main.rs")));
+        std::fs::remove_file(root.join(".gitignore")).unwrap();
+        assert!(walker.admits(&root.join("main.rs")));
+    }
+
     #[test]
     fn test_is_indexable_file() {
         assert!(FileWalker::is_indexable_file(Path::new("test.rs")));
@@ -27,6 +27,7 @@ pub enum Language {
     Toml,
     Xml,
     Shell,
+    Sql,
     Unknown,
 }
 
@@ -74,6 +75,7 @@ impl Language {
             "toml" => Language::Toml,
             "xml" => Language::Xml,
             "sh" | "bash" | "zsh" | "fish" => Language::Shell,
+            "sql" => Language::Sql,
             _ => Language::Unknown,
         }
     }
@@ -118,6 +120,7 @@ impl Language {
             Language::Toml => "toml",
             Language::Xml => "xml",
             Language::Shell => "shell",
+            Language::Sql => "sql",
             Language::Unknown => "unknown",
         }
     }
@@ -152,13 +155,34 @@ impl FromStr for Language {
             "toml" => Ok(Language::Toml),
             "xml" => Ok(Language::Xml),
             "shell" | "bash" | "sh" => Ok(Language::Shell),
+            "sql" => Ok(Language::Sql),
             "unknown" => Ok(Language::Unknown),
             _ => Err(format!("Unknown language: {}", s)),
         }
     }
 }
 
+/// Language tag stored for files indexed without a detected language
+pub const PLAIN_TEXT_LANGUAGE: &str = "plaintext";
+
 impl Language {
+    /// Language tag written to the index. Undetected files are tagged as plain text.
+    pub fn index_label(&self) -> &'static str {
+        match self {
+            Language::Unknown => PLAIN_TEXT_LANGUAGE,
+            _ => self.to_str(),
+        }
+    }
+
+    /// Data and documentation formats that are indexed as plain content (no symbols).
+    /// Files in these formats are only indexed when listed in `Config::languages`.
+    pub fn is_text_format(&self) -> bool {
+        matches!(
+            self,
+            Language::Markdown | Language::Yaml | Language::Toml | Language::Sql
+        )
+    }
+
     /// Returns true if this language has tree-sitter grammar support in the codebase.
     /// Only languages with actual tree-sitter grammars in symbol_extractor.rs are listed.
     pub fn supports_tree_sitter(&self) -> bool {
@@ -261,4 +285,20 @@ mod tests {
         assert!(Language::Python.supports_tree_sitter());
         assert!(!Language::Unknown.supports_tree_sitter());
     }
+
+    #[test]
+    fn test_text_formats() {
+        assert_eq!(Language::from_extension("sql"), Language::Sql);
+        assert_eq!("sql".parse::<Language>(), Ok(Language::Sql));
+        assert!(Language::Sql.is_text_format());
+        assert!(Language::Markdown.is_text_format());
+        assert!(!Language::Sql.supports_tree_sitter());
+        assert!(!Language::Rust.is_text_format());
+    }
+
+    #[test]
+    fn test_index_label() {
+        assert_eq!(Language::Rust.index_label(), "rust");
+        assert_eq!(Language::Unknown.index_label(), PLAIN_TEXT_LANGUAGE);
+    }
 }
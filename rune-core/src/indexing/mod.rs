@@ -1,21 +1,29 @@
+pub mod annotations;
 pub mod file_walker;
 pub mod language_detector;
 pub mod symbol_extractor;
 pub mod tantivy_indexer;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use anyhow::Result;
+use futures::StreamExt;
 use notify_debouncer_full::{Debouncer, FileIdMap};
 use rayon::prelude::*;
-use tokio::sync::mpsc;
+use tokio::sync::{Mutex, mpsc, watch};
 use tracing::{debug, error, info, warn};
 
 use self::file_walker::{FileEvent, FileWalker};
+use self::language_detector::Language;
+use self::symbol_extractor::ExtractionFilter;
 use self::tantivy_indexer::TantivyIndexer;
-use crate::{Config, storage::StorageBackend};
+use crate::{
+    Config, IndexEvictionPolicy, ReindexPolicy, RuneError,
+    cache::MultiTierCache,
+    storage::{CommitMarker, StorageBackend},
+};
 
 #[cfg(feature = "semantic")]
 use crate::search::semantic::SemanticSearcher;
@@ -23,24 +31,101 @@ use crate::search::semantic::SemanticSearcher;
 // Global counter to track indexing calls (for debugging duplicate issue)
 static INDEXING_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
+/// Maximum number of files read at once while indexing a batch
+const FILE_READ_CONCURRENCY: usize = 32;
+
 pub struct Indexer {
     config: Arc<Config>,
     storage: StorageBackend,
     tantivy_indexer: Arc<TantivyIndexer>,
     #[cfg(feature = "semantic")]
     semantic_searcher: Option<SemanticSearcher>,
+    /// Search result cache to invalidate as watched files change
+    result_cache: Option<Arc<MultiTierCache>>,
+    /// The search engine's handle on the index, when it isn't `tantivy_indexer`
+    search_index: Option<Arc<TantivyIndexer>>,
     file_walker: FileWalker,
     watcher_handles: Vec<tokio::task::JoinHandle<()>>,
     debouncer_handles: Vec<Debouncer<notify::RecommendedWatcher, FileIdMap>>,
     shutdown_tx: Option<mpsc::Sender<()>>,
     watching: Arc<AtomicBool>,
+    /// Number of full indexing passes run by this indexer
+    index_passes: AtomicUsize,
+    /// Pool for symbol extraction, sized by `indexing_threads`
+    thread_pool: Arc<rayon::ThreadPool>,
+    /// Held while an indexing pass runs, so passes never overlap
+    reindex_lock: Mutex<()>,
+    reindex_state: Mutex<ReindexState>,
+    /// The last finished reindex pass, for requests waiting on one
+    reindex_done: watch::Sender<FinishedPass>,
+}
+
+/// Number and outcome of a finished reindex pass
+#[derive(Debug, Clone, Default)]
+struct FinishedPass {
+    pass: u64,
+    /// What the pass failed with, including its causes
+    error: Option<String>,
+}
+
+/// Bookkeeping for coalescing `reindex` requests
+#[derive(Debug, Default)]
+struct ReindexState {
+    /// Number of the last reindex pass started
+    started: u64,
+    /// A request is waiting to run the next pass; later requests share it
+    follow_up_queued: bool,
 }
 
 impl Indexer {
     pub async fn new(config: Arc<Config>, storage: StorageBackend) -> Result<Self> {
+        let tantivy_indexer = Self::open_tantivy_indexer(&config).await?;
+        Self::with_tantivy_indexer(config, storage, tantivy_indexer).await
+    }
+
+    /// Open the writable Tantivy index under `cache_dir`, configured for indexing.
+    /// The handle can also be given to `SearchEngine::with_tantivy_indexer`.
+    pub async fn open_tantivy_indexer(config: &Config) -> Result<Arc<TantivyIndexer>> {
         let index_path = config.cache_dir.join("tantivy_index");
-        let tantivy_indexer = Arc::new(TantivyIndexer::new(&index_path).await?);
+        Ok(Arc::new(
+            TantivyIndexer::open(&index_path, config.store_content)
+                .await?
+                .with_extraction_filter(ExtractionFilter::from_config(config)?)
+                .with_reference_indexing(config.index_references)
+                .with_text_blocks(config.index_text_blocks)
+                .with_parser_pool_size(config.indexing_threads),
+        ))
+    }
+
+    /// Create an indexer that writes through an already opened Tantivy index
+    pub async fn with_tantivy_indexer(
+        config: Arc<Config>,
+        storage: StorageBackend,
+        tantivy_indexer: Arc<TantivyIndexer>,
+    ) -> Result<Self> {
         let file_walker = FileWalker::new(config.clone());
+        for root in &config.workspace_roots {
+            if !file_walker.excluded_dirs(root).is_empty() {
+                warn!(
+                    "Cache directory {:?} is inside workspace root {:?}; it will not be indexed or watched",
+                    config.cache_dir, root
+                );
+            }
+        }
+
+        let thread_pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(config.indexing_threads.max(1))
+                .thread_name(|i| format!("rune-indexer-{}", i))
+                .build()?,
+        );
+
+        // Stored hashes would make every file look unchanged to the fresh index
+        if tantivy_indexer.was_rebuilt() {
+            storage
+                .delete_file_metadata_batch(&storage.list_files().await?)
+                .await?;
+        }
 
         #[cfg(feature = "semantic")]
         let semantic_searcher = if config.enable_semantic {
@@ -61,15 +146,37 @@ impl Indexer {
             tantivy_indexer,
             #[cfg(feature = "semantic")]
             semantic_searcher,
+            result_cache: None,
+            search_index: None,
             file_walker,
             watcher_handles: Vec::new(),
             debouncer_handles: Vec::new(),
             shutdown_tx: None,
             watching: Arc::new(AtomicBool::new(false)),
+            index_passes: AtomicUsize::new(0),
+            thread_pool,
+            reindex_lock: Mutex::new(()),
+            reindex_state: Mutex::new(ReindexState::default()),
+            reindex_done: watch::Sender::new(FinishedPass::default()),
         })
     }
 
+    /// Invalidate `cache` entries for each file the watcher reindexes or removes.
+    /// Takes effect for watchers started after the call.
+    pub fn set_result_cache(&mut self, cache: Arc<MultiTierCache>) {
+        self.result_cache = Some(cache);
+    }
+
+    /// Reload `index`, a search engine's own handle on the index, after every commit
+    /// so changes are searchable without waiting for its reader to notice them.
+    /// Takes effect for watchers started after the call.
+    pub fn set_search_index(&mut self, index: Arc<TantivyIndexer>) {
+        self.search_index = Some(index);
+    }
+
     pub async fn start_watching(&mut self) -> Result<()> {
+        self.ensure_writable("watch files")?;
+
         if self.watching.load(Ordering::SeqCst) {
             warn!("File watchers already running");
             return Ok(());
@@ -108,6 +215,9 @@ impl Indexer {
         let storage = self.storage.clone();
         #[cfg(feature = "semantic")]
         let semantic_searcher = self.semantic_searcher.clone();
+        let result_cache = self.result_cache.clone();
+        let search_index = self.search_index.clone();
+        let config = self.config.clone();
         let mut shutdown_rx = shutdown_rx;
 
         let processor_handle = tokio::spawn(async move {
@@ -120,6 +230,9 @@ impl Indexer {
                             &storage,
                             #[cfg(feature = "semantic")]
                             semantic_searcher.as_ref(),
+                            result_cache.as_deref(),
+                            search_index.as_deref(),
+                            &config,
                         ).await {
                             error!("Failed to process file event: {}", e);
                         }
@@ -140,6 +253,38 @@ impl Indexer {
         Ok(())
     }
 
+    /// Index one file and commit right away, without waiting out the watcher's
+    /// debounce, so an editor's index-on-save sees its change on the next search.
+    /// A file that no longer exists is removed from the index, and one a workspace walk
+    /// would skip (ignored, hidden, oversized or binary) is left alone. Background
+    /// filesystem events are still debounced; the one this save triggers finds the
+    /// file unchanged.
+    pub async fn index_single_file(&self, path: &Path) -> Result<()> {
+        self.ensure_writable("index a file")?;
+
+        let event = if tokio::fs::try_exists(path).await? {
+            if !self.file_walker.admits(path) {
+                debug!("Skipping {:?}: a workspace walk wouldn't index it", path);
+                return Ok(());
+            }
+            FileEvent::Modified(path.to_path_buf())
+        } else {
+            FileEvent::Deleted(path.to_path_buf())
+        };
+
+        Self::process_file_event(
+            event,
+            &self.tantivy_indexer,
+            &self.storage,
+            #[cfg(feature = "semantic")]
+            self.semantic_searcher.as_ref(),
+            self.result_cache.as_deref(),
+            self.search_index.as_deref(),
+            &self.config,
+        )
+        .await
+    }
+
     pub async fn stop_watching(&mut self) -> Result<()> {
         if !self.watching.load(Ordering::SeqCst) {
             return Ok(());
@@ -166,14 +311,27 @@ impl Indexer {
         Ok(())
     }
 
+    /// Index every workspace root. Waits for any indexing or reindex pass already
+    /// running.
     pub async fn index_workspaces(&self) -> Result<()> {
+        self.ensure_writable("index workspaces")?;
+
+        let _running = self.reindex_lock.lock().await;
+        self.index_all_workspaces().await
+    }
+
+    /// `index_workspaces` for a caller already holding `reindex_lock`
+    async fn index_all_workspaces(&self) -> Result<()> {
         let call_count = INDEXING_COUNTER.fetch_add(1, Ordering::SeqCst) + 1;
+        self.index_passes.fetch_add(1, Ordering::SeqCst);
         info!(
             "[INDEXING START #{}] Indexing {} workspace roots",
             call_count,
             self.config.workspace_roots.len()
         );
 
+        self.release_evicted_files().await?;
+
         for root in &self.config.workspace_roots {
             info!(
                 "[INDEXING #{}] Processing workspace root: {:?}",
@@ -185,15 +343,121 @@ impl Indexer {
         // Commit all changes
         self.tantivy_indexer.commit().await?;
 
+        if let Some(max_bytes) = self.config.max_index_bytes {
+            self.enforce_index_size(max_bytes).await?;
+        }
+        if let Some(ref index) = self.search_index {
+            index.reload()?;
+        }
+
         info!("[INDEXING COMPLETE #{}] Finished indexing", call_count);
         Ok(())
     }
 
+    /// Evict files per `Config::index_eviction_policy` until the index fits in
+    /// `max_bytes`, returning the evicted paths. Evicted files are remembered, and
+    /// indexing passes skip them until they change or `Config::max_index_bytes` is
+    /// raised or removed.
+    ///
+    /// Deleted documents only free disk space once their segments are merged, so files
+    /// are evicted in rounds: each round removes files whose estimated share of the
+    /// index covers the excess, compacts the index and measures it again.
+    pub async fn enforce_index_size(&self, max_bytes: u64) -> Result<Vec<PathBuf>> {
+        self.ensure_writable("evict files from the index")?;
+
+        let initial_bytes = self.storage.get_index_size().await?;
+        if initial_bytes <= max_bytes {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        for metadata in self.storage.list_file_metadata().await? {
+            let indexed_ms = metadata.indexed_at.saturating_mul(1000);
+            let rank = match self.config.index_eviction_policy {
+                IndexEvictionPolicy::LeastRecentlyAccessed => self
+                    .storage
+                    .get_last_accessed(&metadata.path)
+                    .await?
+                    .map_or(indexed_ms, |accessed| accessed.max(indexed_ms)),
+                IndexEvictionPolicy::Oldest => indexed_ms,
+            };
+            files.push((rank, metadata));
+        }
+        files.sort_by(|(a_rank, a), (b_rank, b)| a_rank.cmp(b_rank).then(a.path.cmp(&b.path)));
+        let mut remaining = files.into_iter().map(|(_, metadata)| metadata).peekable();
+
+        let mut index_bytes = initial_bytes;
+        let mut evicted = Vec::new();
+        while index_bytes > max_bytes && remaining.peek().is_some() {
+            let remaining_source_bytes: u64 = remaining.clone().map(|m| m.size.max(1)).sum();
+            let excess = index_bytes - max_bytes;
+            let mut estimated = 0u64;
+            let mut round = Vec::new();
+
+            while estimated < excess
+                && let Some(metadata) = remaining.next()
+            {
+                self.tantivy_indexer.delete_file(&metadata.path).await?;
+                estimated += (index_bytes as u128 * metadata.size.max(1) as u128
+                    / remaining_source_bytes as u128) as u64;
+
+                info!(
+                    "Evicted file from index to stay under size cap: {:?}",
+                    metadata.path
+                );
+                round.push(metadata.path);
+            }
+
+            self.storage.delete_file_metadata_batch(&round).await?;
+            self.storage
+                .mark_evicted(&round, self.config.max_index_bytes.unwrap_or(max_bytes))
+                .await?;
+            self.tantivy_indexer.commit().await?;
+            self.tantivy_indexer.compact().await?;
+
+            index_bytes = self.storage.get_index_size().await?;
+            evicted.extend(round);
+        }
+
+        info!(
+            "Index was {} bytes (cap {}); evicted {} files, reclaiming {} bytes",
+            initial_bytes,
+            max_bytes,
+            evicted.len(),
+            initial_bytes.saturating_sub(index_bytes)
+        );
+        Ok(evicted)
+    }
+
+    /// Let evicted files back into the index once `Config::max_index_bytes` is larger
+    /// than the cap they were evicted under, or no longer set
+    async fn release_evicted_files(&self) -> Result<()> {
+        let Some(evicted_under) = self.storage.get_eviction_cap().await? else {
+            return Ok(());
+        };
+        if self
+            .config
+            .max_index_bytes
+            .is_none_or(|max_bytes| max_bytes > evicted_under)
+        {
+            info!(
+                "Index size cap is no longer {} bytes; reindexing evicted files",
+                evicted_under
+            );
+            self.storage.clear_evicted().await?;
+        }
+        Ok(())
+    }
+
     async fn index_directory(&self, path: &Path) -> Result<()> {
         let call_count = INDEXING_COUNTER.load(Ordering::SeqCst);
         info!("[INDEXING #{}] Indexing directory: {:?}", call_count, path);
 
-        let files = self.file_walker.walk_directory(path).await?;
+        let mut files = self.file_walker.walk_directory(path).await?;
+        if self.config.max_index_bytes.is_some() {
+            let evicted = self.storage.list_evicted().await?;
+            files.retain(|file| !evicted.contains(file));
+        }
         let total_files = files.len();
 
         info!("Found {} files to index", total_files);
@@ -204,130 +468,212 @@ impl Indexer {
             .and_then(|n| n.to_str())
             .unwrap_or("unknown");
 
+        #[cfg(feature = "semantic")]
+        let embed_repository = Self::embeds_repository(&self.config, repository);
+
         // Process files in parallel batches
         let batch_size = 100;
+        let commit_interval = self.config.commit_interval_batches.max(1);
+        let total_batches = total_files.div_ceil(batch_size);
         let tantivy_indexer = self.tantivy_indexer.clone();
         let storage = self.storage.clone();
 
+        // Files written to Tantivy since the last coordinated flush
+        let mut pending_files: Vec<PathBuf> = Vec::new();
+
         for (batch_num, batch) in files.chunks(batch_size).enumerate() {
             let batch_files: Vec<_> = batch.to_vec();
 
-            // Process batch in parallel using rayon
-            let results: Vec<_> = batch_files
-                .par_iter()
-                .map(|file_path| {
-                    // Read file content
-                    match std::fs::read_to_string(file_path) {
-                        Ok(content) => {
-                            // Create a future for indexing
-                            (file_path.clone(), repository.to_string(), content)
-                        },
+            // Read the batch concurrently without tying up the extraction pool
+            let results: Vec<_> = futures::stream::iter(batch_files)
+                .map(|file_path| async move {
+                    match tokio::fs::read_to_string(&file_path).await {
+                        Ok(content) => (file_path, repository.to_string(), content),
                         Err(e) => {
                             warn!("Failed to read file {:?}: {}", file_path, e);
-                            (file_path.clone(), repository.to_string(), String::new())
+                            (file_path, repository.to_string(), String::new())
                         },
                     }
                 })
-                .collect();
+                .buffer_unordered(FILE_READ_CONCURRENCY)
+                .collect()
+                .await;
 
-            // Index all files in the batch
+            // Work out which files changed; unchanged ones only get their timestamp bumped
+            let mut changed = Vec::new();
             for (file_path, repo, content) in results {
-                if !content.is_empty() {
-                    // Compute hash of the content
-                    let content_hash = blake3::hash(content.as_bytes()).to_string();
-
-                    // Check if file has changed by comparing hashes
-                    let should_index = match storage.get_file_metadata(&file_path).await {
-                        Ok(Some(existing_metadata)) => {
-                            // Only index if the hash has changed
-                            if existing_metadata.hash != content_hash {
-                                debug!("File {:?} has changed, reindexing", file_path);
-                                true
-                            } else {
-                                debug!("File {:?} unchanged, skipping reindex", file_path);
-                                // Update only the indexed_at timestamp
-                                let mut updated_metadata = existing_metadata;
-                                updated_metadata.indexed_at = std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap_or_default()
-                                    .as_secs();
-                                if let Err(e) = storage
-                                    .store_file_metadata(&file_path, updated_metadata)
-                                    .await
-                                {
-                                    error!(
-                                        "Failed to update metadata timestamp for {:?}: {}",
-                                        file_path, e
-                                    );
-                                }
-                                false
-                            }
-                        },
-                        Ok(None) => {
-                            debug!("File {:?} is new, indexing", file_path);
-                            true // New file, needs indexing
-                        },
-                        Err(e) => {
-                            warn!(
-                                "Failed to get metadata for {:?}: {}, indexing anyway",
-                                file_path, e
-                            );
-                            true // Error getting metadata, index to be safe
-                        },
-                    };
+                if content.is_empty() {
+                    continue;
+                }
 
-                    if should_index {
-                        if let Err(e) = tantivy_indexer
-                            .index_file(&file_path, &repo, &content)
-                            .await
-                        {
-                            error!("Failed to index file {:?}: {}", file_path, e);
-                        }
+                let language =
+                    language_detector::LanguageDetector::detect(&file_path, Some(&content));
+                if !Self::language_enabled(&self.config, language) {
+                    debug!(
+                        "Skipping {:?}: {} is not enabled",
+                        file_path,
+                        language.to_str()
+                    );
+                    continue;
+                }
 
-                        // Index for semantic search if enabled
-                        #[cfg(feature = "semantic")]
-                        if let Some(ref semantic_searcher) = self.semantic_searcher
-                            && let Err(e) = semantic_searcher
-                                .index_file(&file_path.to_string_lossy(), &content)
+                // Compute hash of the content
+                let content_hash = blake3::hash(content.as_bytes()).to_string();
+
+                // Check if file has changed by comparing hashes
+                let should_index = match storage.get_file_metadata(&file_path).await {
+                    Ok(Some(existing_metadata)) => {
+                        // Only index if the hash has changed
+                        if existing_metadata.hash != content_hash {
+                            debug!("File {:?} has changed, reindexing", file_path);
+                            true
+                        } else {
+                            debug!("File {:?} unchanged, skipping reindex", file_path);
+                            // Update only the indexed_at timestamp
+                            let mut updated_metadata = existing_metadata;
+                            updated_metadata.indexed_at = std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs();
+                            if let Err(e) = storage
+                                .store_file_metadata(&file_path, updated_metadata)
                                 .await
+                            {
+                                error!(
+                                    "Failed to update metadata timestamp for {:?}: {}",
+                                    file_path, e
+                                );
+                            }
+                            false
+                        }
+                    },
+                    Ok(None) => {
+                        debug!("File {:?} is new, indexing", file_path);
+                        true // New file, needs indexing
+                    },
+                    Err(e) => {
+                        warn!(
+                            "Failed to get metadata for {:?}: {}, indexing anyway",
+                            file_path, e
+                        );
+                        true // Error getting metadata, index to be safe
+                    },
+                };
+
+                if should_index {
+                    changed.push((file_path, repo, content, language, content_hash));
+                }
+            }
+
+            // Parse symbols for the changed files in parallel, off the async runtime
+            let pool = self.thread_pool.clone();
+            let indexer = tantivy_indexer.clone();
+            let (changed, symbols): (Vec<_>, Vec<_>) = tokio::task::spawn_blocking(move || {
+                let symbols = pool.install(|| {
+                    changed
+                        .par_iter()
+                        .map(|(file_path, _, content, _, _)| {
+                            indexer.extract_symbols(file_path, content)
+                        })
+                        .collect()
+                });
+                (changed, symbols)
+            })
+            .await?;
+
+            // Files whose chunks are embedded once the batch is in Tantivy
+            #[cfg(feature = "semantic")]
+            let mut to_embed: Vec<(PathBuf, String)> = Vec::new();
+
+            // Index all changed files in the batch
+            for ((file_path, repo, content, language, content_hash), symbols) in
+                changed.into_iter().zip(symbols)
+            {
+                let (indexed, symbol_count) = match symbols {
+                    Ok(symbols) => (
+                        tantivy_indexer
+                            .index_file_with_symbols(&file_path, &repo, &content, &symbols)
+                            .await,
+                        symbols.len(),
+                    ),
+                    Err(e) => (Err(e), 0),
+                };
+                if let Err(e) = indexed {
+                    error!("Failed to index file {:?}: {}", file_path, e);
+                }
+
+                // Store metadata with new hash
+                let metadata = crate::storage::FileMetadata {
+                    path: file_path.clone(),
+                    size: content.len() as u64,
+                    modified: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs(),
+                    language: language.index_label().to_string(),
+                    hash: content_hash,
+                    indexed_at: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs(),
+                    symbol_count,
+                };
+
+                if let Err(e) = storage.store_file_metadata(&file_path, metadata).await {
+                    error!("Failed to store metadata for {:?}: {}", file_path, e);
+                }
+
+                if let Err(e) =
+                    Self::record_annotations(&self.config, &storage, &file_path, &content).await
+                {
+                    error!("Failed to store annotations for {:?}: {}", file_path, e);
+                }
+
+                // Plain-text files are only searchable through Tantivy
+                #[cfg(feature = "semantic")]
+                if embed_repository
+                    && language != Language::Unknown
+                    && self.semantic_searcher.is_some()
+                {
+                    to_embed.push((file_path.clone(), content));
+                }
+
+                pending_files.push(file_path);
+            }
+
+            // Queue the batch for semantic search, several files at a time; vectors
+            // are written at the next flush
+            #[cfg(feature = "semantic")]
+            if let Some(ref semantic_searcher) = self.semantic_searcher {
+                futures::stream::iter(to_embed)
+                    .map(|(file_path, content)| async move {
+                        if let Err(e) = semantic_searcher
+                            .queue_file(&file_path.to_string_lossy(), &content)
+                            .await
                         {
                             error!(
                                 "Failed to index file for semantic search {:?}: {}",
                                 file_path, e
                             );
                         }
-
-                        // Store metadata with new hash
-                        let metadata = crate::storage::FileMetadata {
-                            path: file_path.clone(),
-                            size: content.len() as u64,
-                            modified: std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs(),
-                            language: language_detector::LanguageDetector::detect(
-                                &file_path,
-                                Some(&content),
-                            )
-                            .to_str()
-                            .to_string(),
-                            hash: content_hash,
-                            indexed_at: std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs(),
-                        };
-
-                        if let Err(e) = storage.store_file_metadata(&file_path, metadata).await {
-                            error!("Failed to store metadata for {:?}: {}", file_path, e);
-                        }
-                    }
-                }
+                    })
+                    .buffer_unordered(self.config.max_concurrent_embeddings.max(1))
+                    .collect::<Vec<()>>()
+                    .await;
             }
-
-            // Commit periodically
-            if batch_num % 10 == 0 {
-                tantivy_indexer.commit().await?;
+            // Flush Tantivy and Qdrant together at batch boundaries, or early once
+            // the queued embeddings grow too large to hold
+            let flush_due =
+                (batch_num + 1) % commit_interval == 0 || batch_num + 1 == total_batches;
+            #[cfg(feature = "semantic")]
+            let flush_due = flush_due
+                || match self.semantic_searcher {
+                    Some(ref semantic_searcher) => semantic_searcher.pending_full().await,
+                    None => false,
+                };
+            if flush_due {
+                self.coordinated_flush(std::mem::take(&mut pending_files))
+                    .await?;
                 debug!(
                     "Indexed {} / {} files",
                     (batch_num + 1) * batch_size,
@@ -339,17 +685,178 @@ impl Indexer {
         Ok(())
     }
 
+    /// Commit Tantivy and flush pending Qdrant upserts as one unit.
+    ///
+    /// The commit marker is written after each half, so a crash between the two
+    /// leaves the generations out of step and `verify_index` can find the files
+    /// whose vectors never landed.
+    async fn coordinated_flush(&self, pending_files: Vec<PathBuf>) -> Result<()> {
+        let mut marker = self.storage.get_commit_marker().await?.unwrap_or_default();
+
+        // An earlier flush that never completed stays flagged until it is repaired
+        let drifted = !marker.is_consistent();
+        let already_pending = marker.pending_files.len();
+
+        self.tantivy_indexer.commit().await?;
+        if !drifted {
+            marker.tantivy_generation += 1;
+        }
+        marker.pending_files.extend(pending_files);
+        marker.updated_at = unix_now();
+        self.storage.store_commit_marker(&marker).await?;
+
+        #[cfg(feature = "semantic")]
+        if let Some(ref semantic_searcher) = self.semantic_searcher
+            && let Err(e) = semantic_searcher.flush_pending().await
+        {
+            // Leave the marker inconsistent so the drift is visible to verify_index
+            error!("Failed to flush pending embeddings: {}", e);
+            return Ok(());
+        }
+
+        marker.pending_files.truncate(already_pending);
+        if !drifted {
+            marker.vector_generation = marker.tantivy_generation;
+        }
+        marker.updated_at = unix_now();
+        self.storage.store_commit_marker(&marker).await?;
+
+        Ok(())
+    }
+
+    /// Check that the text and vector indexes were last flushed together.
+    ///
+    /// With `repair`, files from the incomplete flush have their metadata dropped so
+    /// the next indexing pass re-embeds them, and the marker is reset.
+    pub async fn verify_index(&self, repair: bool) -> Result<IndexVerification> {
+        let marker = self.storage.get_commit_marker().await?.unwrap_or_default();
+        let consistent = marker.is_consistent();
+
+        let mut verification = IndexVerification {
+            consistent,
+            tantivy_generation: marker.tantivy_generation,
+            vector_generation: marker.vector_generation,
+            pending_files: marker.pending_files.clone(),
+            repaired: false,
+        };
+
+        if repair {
+            self.ensure_writable("repair the index")?;
+
+            // The running symbol total can drift if a write was interrupted
+            self.storage.recompute_symbol_count().await?;
+        }
+
+        if consistent || !repair {
+            return Ok(verification);
+        }
+
+        warn!(
+            "Repairing index drift: {} files missing from the vector index",
+            marker.pending_files.len()
+        );
+        self.storage
+            .delete_file_metadata_batch(&marker.pending_files)
+            .await?;
+
+        let repaired = CommitMarker {
+            tantivy_generation: marker.tantivy_generation,
+            vector_generation: marker.tantivy_generation,
+            pending_files: Vec::new(),
+            updated_at: unix_now(),
+        };
+        self.storage.store_commit_marker(&repaired).await?;
+        verification.repaired = true;
+
+        Ok(verification)
+    }
+
+    /// Fail with `RuneError::ReadOnly` when the engine was opened with `Config::read_only`
+    fn ensure_writable(&self, operation: &str) -> Result<()> {
+        if self.config.read_only {
+            return Err(RuneError::ReadOnly(operation.to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Whether files from `repository` get embedded, per `Config::semantic_repositories`
+    #[cfg(feature = "semantic")]
+    fn embeds_repository(config: &Config, repository: &str) -> bool {
+        config
+            .semantic_repositories
+            .as_ref()
+            .is_none_or(|repositories| repositories.iter().any(|r| r == repository))
+    }
+
+    /// Name of the innermost workspace root containing `path`, as used for the
+    /// repository of files indexed from that root
+    #[cfg(feature = "semantic")]
+    fn root_repository<'a>(config: &'a Config, path: &Path) -> &'a str {
+        config
+            .workspace_roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .and_then(|root| root.file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or("unknown")
+    }
+
+    /// Whether files in `language` get indexed. Undetected files follow
+    /// `index_unknown_languages`; text formats must be listed in `languages`.
+    fn language_enabled(config: &Config, language: Language) -> bool {
+        if language == Language::Unknown {
+            config.index_unknown_languages
+        } else if language.is_text_format() {
+            config
+                .languages
+                .iter()
+                .any(|name| name.parse::<Language>() == Ok(language))
+        } else {
+            true
+        }
+    }
+
+    /// Store the TODO/FIXME-style comments in `content`, if `Config::extract_annotations`
+    async fn record_annotations(
+        config: &Config,
+        storage: &StorageBackend,
+        path: &Path,
+        content: &str,
+    ) -> Result<()> {
+        if !config.extract_annotations {
+            return Ok(());
+        }
+        let annotations = annotations::extract_annotations(path, content);
+        storage.store_annotations(path, &annotations).await
+    }
+
     async fn process_file_event(
         event: FileEvent,
         tantivy_indexer: &TantivyIndexer,
         storage: &StorageBackend,
         #[cfg(feature = "semantic")] semantic_searcher: Option<&SemanticSearcher>,
+        result_cache: Option<&MultiTierCache>,
+        search_index: Option<&TantivyIndexer>,
+        config: &Config,
     ) -> Result<()> {
+        // A file that changed or went away since it was evicted is no longer skipped
+        let path = match &event {
+            FileEvent::Created(path) | FileEvent::Modified(path) | FileEvent::Deleted(path) => path,
+        };
+        storage.unmark_evicted(path).await?;
+
         match event {
             FileEvent::Created(path) | FileEvent::Modified(path) => {
                 // Read file content
                 let content = tokio::fs::read_to_string(&path).await?;
 
+                let language = language_detector::LanguageDetector::detect(&path, Some(&content));
+                if !Self::language_enabled(config, language) {
+                    debug!("Skipping {:?}: {} is not enabled", path, language.to_str());
+                    return Ok(());
+                }
+
                 // Compute hash of the content
                 let content_hash = blake3::hash(content.as_bytes()).to_string();
 
@@ -394,13 +901,16 @@ impl Indexer {
                         .unwrap_or("unknown");
 
                     // Index file
+                    let symbols = tantivy_indexer.extract_symbols(&path, &content)?;
                     tantivy_indexer
-                        .index_file(&path, repository, &content)
+                        .index_file_with_symbols(&path, repository, &content, &symbols)
                         .await?;
 
                     // Index for semantic search if enabled
                     #[cfg(feature = "semantic")]
-                    if let Some(searcher) = semantic_searcher
+                    if language != Language::Unknown
+                        && Self::embeds_repository(config, Self::root_repository(config, &path))
+                        && let Some(searcher) = semantic_searcher
                         && let Err(e) = searcher.index_file(&path.to_string_lossy(), &content).await
                     {
                         error!("Failed to index file for semantic search {:?}: {}", path, e);
@@ -414,23 +924,28 @@ impl Indexer {
                             .duration_since(std::time::UNIX_EPOCH)
                             .unwrap_or_default()
                             .as_secs(),
-                        language: language_detector::LanguageDetector::detect(
-                            &path,
-                            Some(&content),
-                        )
-                        .to_str()
-                        .to_string(),
+                        language: language.index_label().to_string(),
                         hash: content_hash,
                         indexed_at: std::time::SystemTime::now()
                             .duration_since(std::time::UNIX_EPOCH)
                             .unwrap_or_default()
                             .as_secs(),
+                        symbol_count: symbols.len(),
                     };
 
                     storage.store_file_metadata(&path, metadata).await?;
+                    Self::record_annotations(config, storage, &path, &content).await?;
 
                     // Commit changes
                     tantivy_indexer.commit().await?;
+                    if let Some(index) = search_index {
+                        index.reload()?;
+                    }
+                    // A new or edited file can match any cached query, not just ones
+                    // whose results cite it
+                    if let Some(cache) = result_cache {
+                        cache.clear().await;
+                    }
 
                     info!("Indexed file: {:?}", path);
                 } else {
@@ -441,9 +956,15 @@ impl Indexer {
                 // Remove from index
                 tantivy_indexer.delete_file(&path).await?;
                 tantivy_indexer.commit().await?;
+                if let Some(index) = search_index {
+                    index.reload()?;
+                }
 
                 // Remove from storage
                 storage.delete_file_metadata(&path).await?;
+                if let Some(cache) = result_cache {
+                    cache.invalidate_file(&path);
+                }
 
                 info!("Removed file from index: {:?}", path);
             },
@@ -457,14 +978,114 @@ impl Indexer {
         self.watching.load(Ordering::SeqCst)
     }
 
-    pub async fn reindex(&self) -> Result<()> {
-        info!("Reindexing all workspaces");
+    /// Number of full workspace indexing passes this indexer has run
+    pub fn index_pass_count(&self) -> usize {
+        self.index_passes.load(Ordering::SeqCst)
+    }
+
+    /// Number of live documents in the Tantivy index
+    pub async fn document_count(&self) -> Result<usize> {
+        self.tantivy_indexer.get_document_count().await
+    }
+
+    /// Repositories in the text index with their document counts
+    pub async fn list_repositories(&self) -> Result<Vec<(String, usize)>> {
+        self.tantivy_indexer.list_repositories().await
+    }
+
+    /// Entries and estimated bytes in the indexing-side embedding cache
+    pub fn embedding_cache_usage(&self) -> (usize, usize) {
+        #[cfg(feature = "semantic")]
+        if let Some(ref semantic_searcher) = self.semantic_searcher {
+            return semantic_searcher.embedding_cache_usage();
+        }
+        (0, 0)
+    }
+
+    /// Most frequently defined symbol names with their counts
+    pub async fn top_symbols(
+        &self,
+        kind: Option<symbol_extractor::SymbolKind>,
+        n: usize,
+    ) -> Result<Vec<(String, usize)>> {
+        self.tantivy_indexer.top_symbols(kind, n).await
+    }
+
+    /// Indexed TODO/FIXME/HACK/XXX comments, optionally of one kind, ordered by file
+    /// then line
+    pub async fn list_annotations(
+        &self,
+        kind: Option<annotations::AnnotationKind>,
+    ) -> Result<Vec<annotations::Annotation>> {
+        let mut annotations = self.storage.list_annotations().await?;
+        if let Some(kind) = kind {
+            annotations.retain(|a| a.kind == kind);
+        }
+        Ok(annotations)
+    }
+
+    /// Reindex every workspace. A request made while a pass is running doesn't start
+    /// a second one alongside it; depending on `Config::reindex_policy` it waits for
+    /// the running pass or for a single follow-up pass, and gets that pass's outcome:
+    /// if it failed, so does the request.
+    pub async fn reindex(&self) -> Result<ReindexStatus> {
+        self.ensure_writable("reindex")?;
+
+        let wait_for = {
+            let mut state = self.reindex_state.lock().await;
+            let in_flight = state.started > self.reindex_done.borrow().pass;
+            if in_flight && self.config.reindex_policy == ReindexPolicy::JoinInFlight {
+                Some((state.started, ReindexStatus::JoinedInFlight))
+            } else if state.follow_up_queued {
+                Some((state.started + 1, ReindexStatus::Coalesced))
+            } else {
+                state.follow_up_queued = true;
+                None
+            }
+        };
+
+        if let Some((pass, status)) = wait_for {
+            debug!("Reindex request waiting for pass {}", pass);
+            let finished = self
+                .reindex_done
+                .subscribe()
+                .wait_for(|done| done.pass >= pass)
+                .await?
+                .clone();
+            return match finished.error {
+                Some(error) => Err(anyhow::anyhow!(
+                    "Reindex pass {} failed: {}",
+                    finished.pass,
+                    error
+                )),
+                None => Ok(status),
+            };
+        }
+
+        let _running = self.reindex_lock.lock().await;
+        let pass = {
+            let mut state = self.reindex_state.lock().await;
+            state.follow_up_queued = false;
+            state.started += 1;
+            state.started
+        };
+
+        let result = self.run_reindex(pass).await;
+        self.reindex_done.send_replace(FinishedPass {
+            pass,
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+        });
+        result.map(|()| ReindexStatus::Completed)
+    }
+
+    async fn run_reindex(&self, pass: u64) -> Result<()> {
+        info!("Reindexing all workspaces (pass {})", pass);
 
         // Clear existing index
         // Note: In production, you might want to build a new index and swap
 
         // Reindex everything
-        self.index_workspaces().await?;
+        self.index_all_workspaces().await?;
 
         // Optimize index after bulk reindexing
         self.tantivy_indexer.optimize().await?;
@@ -473,8 +1094,40 @@ impl Indexer {
     }
 }
 
+/// How a `reindex` request was served
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReindexStatus {
+    /// This request ran a full pass
+    Completed,
+    /// Waited for the pass that was already running
+    JoinedInFlight,
+    /// Covered by a follow-up pass another waiting request ran
+    Coalesced,
+}
+
+/// Result of comparing the Tantivy and Qdrant flush generations
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IndexVerification {
+    pub consistent: bool,
+    pub tantivy_generation: u64,
+    pub vector_generation: u64,
+    /// Files whose vectors may be missing after an incomplete flush
+    pub pending_files: Vec<PathBuf>,
+    /// Whether the drift was repaired by this call
+    pub repaired: bool,
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 #[cfg(test)]
 mod tests {
+    use super::annotations::AnnotationKind;
     use super::*;
     use tempfile::tempdir;
 
@@ -504,4 +1157,680 @@ mod tests {
         let doc_count = indexer.tantivy_indexer.get_document_count().await.unwrap();
         assert_eq!(doc_count, 2);
     }
+
+    #[tokio::test]
+    async fn test_annotations_are_extracted() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        let rust_file = workspace.join("lib.rs");
+        let python_file = workspace.join("tool.py");
+        std::fs::write(&rust_file, "fn main() {\n    // TODO: x\n}\n").unwrap();
+        std::fs::write(&python_file, "# FIXME: y\ndef main():\n    pass\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let indexer = Indexer::new(config, storage).await.unwrap();
+        indexer.index_workspaces().await.unwrap();
+
+        let found: Vec<_> = indexer
+            .list_annotations(None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.kind, a.file_path, a.line, a.message))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (AnnotationKind::Todo, rust_file.clone(), 2, "x".to_string()),
+                (
+                    AnnotationKind::Fixme,
+                    python_file.clone(),
+                    1,
+                    "y".to_string()
+                ),
+            ]
+        );
+
+        let fixmes = indexer
+            .list_annotations(Some(AnnotationKind::Fixme))
+            .await
+            .unwrap();
+        assert_eq!(fixmes.len(), 1);
+        assert_eq!(fixmes[0].file_path, python_file);
+
+        // Resolved and deleted files drop their annotations
+        std::fs::write(&rust_file, "fn main() {}\n").unwrap();
+        indexer.index_single_file(&rust_file).await.unwrap();
+        std::fs::remove_file(&python_file).unwrap();
+        indexer.index_single_file(&python_file).await.unwrap();
+        assert!(indexer.list_annotations(None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_parallel_symbol_extraction() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+
+        // Enough files for more than one batch, across several languages
+        for i in 0..150 {
+            let (name, source) = match i % 3 {
+                0 => (
+                    format!("mod_{i}.rs"),
+                    format!("pub fn rust_item_{i}() {{}}\n"),
+                ),
+                1 => (
+                    format!("mod_{i}.py"),
+                    format!("def python_item_{i}():\n    pass\n"),
+                ),
+                _ => (
+                    format!("mod_{i}.js"),
+                    format!("function javascript_item_{i}() {{}}\n"),
+                ),
+            };
+            std::fs::write(workspace.join(name), source).unwrap();
+        }
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            indexing_threads: 4,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let indexer = Indexer::new(config, storage).await.unwrap();
+        indexer.index_workspaces().await.unwrap();
+
+        let tantivy_indexer = &indexer.tantivy_indexer;
+        assert_eq!(tantivy_indexer.get_document_count().await.unwrap(), 150);
+
+        let names = tantivy_indexer.symbol_names().await.unwrap();
+        assert_eq!(names.len(), 150);
+        for name in [
+            "rust_item_0",
+            "python_item_1",
+            "javascript_item_2",
+            "rust_item_147",
+        ] {
+            assert!(names.iter().any(|n| n == name), "missing symbol {}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_reads_store_metadata() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+
+        let mut sources = Vec::new();
+        for i in 0..120 {
+            let path = workspace.join(format!("file_{i}.rs"));
+            let source = format!("pub fn item_{i}() -> usize {{ {i} }}\n");
+            std::fs::write(&path, &source).unwrap();
+            sources.push((path, source));
+        }
+        // Unreadable as UTF-8, so it's skipped rather than indexed empty
+        std::fs::write(workspace.join("binary.rs"), [0xff, 0xfe, 0x00, 0x01]).unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let indexer = Indexer::new(config, storage.clone()).await.unwrap();
+        indexer.index_workspaces().await.unwrap();
+
+        assert_eq!(
+            indexer.tantivy_indexer.get_document_count().await.unwrap(),
+            120
+        );
+        assert_eq!(storage.get_file_count().await.unwrap(), 120);
+
+        for (path, source) in &sources {
+            let metadata = storage.get_file_metadata(path).await.unwrap().unwrap();
+            assert_eq!(metadata.hash, blake3::hash(source.as_bytes()).to_string());
+            assert_eq!(metadata.size, source.len() as u64);
+            assert_eq!(metadata.language, "rust");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_index_detects_interrupted_flush() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        let file = workspace.join("lib.rs");
+        std::fs::write(&file, "pub fn add(a: i32, b: i32) -> i32 { a + b }").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let indexer = Indexer::new(config, storage.clone()).await.unwrap();
+        indexer.index_workspaces().await.unwrap();
+
+        let verification = indexer.verify_index(false).await.unwrap();
+        assert!(verification.consistent);
+        assert!(verification.tantivy_generation > 0);
+
+        // Simulate a crash after the Tantivy commit but before the Qdrant flush
+        let marker = CommitMarker {
+            tantivy_generation: verification.tantivy_generation + 1,
+            vector_generation: verification.vector_generation,
+            pending_files: vec![file.clone()],
+            updated_at: 0,
+        };
+        storage.store_commit_marker(&marker).await.unwrap();
+
+        let verification = indexer.verify_index(false).await.unwrap();
+        assert!(!verification.consistent);
+        assert!(!verification.repaired);
+        assert_eq!(verification.pending_files, vec![file.clone()]);
+
+        let verification = indexer.verify_index(true).await.unwrap();
+        assert!(verification.repaired);
+        assert!(storage.get_file_metadata(&file).await.unwrap().is_none());
+        assert!(indexer.verify_index(false).await.unwrap().consistent);
+    }
+
+    #[tokio::test]
+    async fn test_text_formats_follow_config_languages() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        let readme = workspace.join("README.md");
+        std::fs::write(&readme, "# Setup\n\nRun the quokka migration first.\n").unwrap();
+        let manifest = workspace.join("deploy.toml");
+        std::fs::write(&manifest, "[deploy]\nregion = \"wombat-east\"\n").unwrap();
+        let migration = workspace.join("001_init.sql");
+        std::fs::write(&migration, "CREATE TABLE platypus (id INTEGER);\n").unwrap();
+
+        // SQL left out of the language list
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            languages: vec![
+                "rust".to_string(),
+                "markdown".to_string(),
+                "toml".to_string(),
+            ],
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let indexer = Indexer::new(config, storage).await.unwrap();
+        indexer.index_workspaces().await.unwrap();
+
+        let query_parser = tantivy::query::QueryParser::for_index(
+            indexer.tantivy_indexer.get_searcher().index(),
+            vec![indexer.tantivy_indexer.get_content_field()],
+        );
+        let search = |text: &str| {
+            let query = query_parser.parse_query(text).unwrap();
+            let indexer = &indexer;
+            async move {
+                indexer
+                    .tantivy_indexer
+                    .search_documents(query.as_ref(), 10)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let results = search("quokka").await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, readme);
+        assert_eq!(results[0].language, "markdown");
+
+        let results = search("wombat").await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, manifest);
+        assert_eq!(results[0].language, "toml");
+
+        assert!(search("platypus").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_language_files_follow_config() {
+        for index_unknown_languages in [true, false] {
+            let temp_dir = tempdir().unwrap();
+            let workspace = temp_dir.path().join("workspace");
+            std::fs::create_dir(&workspace).unwrap();
+            std::fs::write(workspace.join("main.rs"), "fn main() {}").unwrap();
+            let data_file = workspace.join("settings.xyz");
+            std::fs::write(&data_file, "retry_budget = frobnicate").unwrap();
+
+            let config = Arc::new(Config {
+                workspace_roots: vec![workspace],
+                cache_dir: temp_dir.path().join("cache"),
+                enable_semantic: false,
+                index_unknown_languages,
+                ..Default::default()
+            });
+
+            let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+            let indexer = Indexer::new(config, storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+
+            let query_parser = tantivy::query::QueryParser::for_index(
+                indexer.tantivy_indexer.get_searcher().index(),
+                vec![indexer.tantivy_indexer.get_content_field()],
+            );
+            let query = query_parser.parse_query("frobnicate").unwrap();
+            let results = indexer
+                .tantivy_indexer
+                .search_documents(query.as_ref(), 10)
+                .await
+                .unwrap();
+            let metadata = storage.get_file_metadata(&data_file).await.unwrap();
+
+            if index_unknown_languages {
+                assert_eq!(results.len(), 1);
+                assert_eq!(results[0].path, data_file);
+                assert_eq!(results[0].language, language_detector::PLAIN_TEXT_LANGUAGE);
+                assert_eq!(
+                    metadata.unwrap().language,
+                    language_detector::PLAIN_TEXT_LANGUAGE
+                );
+            } else {
+                assert!(results.is_empty());
+                assert!(metadata.is_none());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plain_text_files_indexed_by_default() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("notes.txt"), "remember to frobnicate").unwrap();
+        std::fs::write(workspace.join("README"), "frobnicate before release").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let indexer = Indexer::new(config, storage).await.unwrap();
+        indexer.index_workspaces().await.unwrap();
+
+        let query_parser = tantivy::query::QueryParser::for_index(
+            indexer.tantivy_indexer.get_searcher().index(),
+            vec![indexer.tantivy_indexer.get_content_field()],
+        );
+        let query = query_parser.parse_query("frobnicate").unwrap();
+        let mut paths: Vec<PathBuf> = indexer
+            .tantivy_indexer
+            .search_documents(query.as_ref(), 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|doc| doc.path)
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![workspace.join("README"), workspace.join("notes.txt")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_reindex_requests_coalesce() {
+        for (policy, expected_passes) in [
+            (ReindexPolicy::QueueFollowUp, 2),
+            (ReindexPolicy::JoinInFlight, 1),
+        ] {
+            let temp_dir = tempdir().unwrap();
+            let workspace = temp_dir.path().join("workspace");
+            std::fs::create_dir(&workspace).unwrap();
+            for i in 0..20 {
+                std::fs::write(
+                    workspace.join(format!("item_{i}.rs")),
+                    format!("pub fn item_{i}() {{}}\n"),
+                )
+                .unwrap();
+            }
+
+            let config = Arc::new(Config {
+                workspace_roots: vec![workspace],
+                cache_dir: temp_dir.path().join("cache"),
+                enable_semantic: false,
+                reindex_policy: policy,
+                ..Default::default()
+            });
+
+            let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+            let indexer = Indexer::new(config, storage.clone()).await.unwrap();
+
+            let (first, second, third) =
+                tokio::join!(indexer.reindex(), indexer.reindex(), indexer.reindex());
+            let statuses = [first.unwrap(), second.unwrap(), third.unwrap()];
+
+            assert_eq!(statuses[0], ReindexStatus::Completed);
+            match policy {
+                ReindexPolicy::QueueFollowUp => assert_eq!(
+                    statuses[1..],
+                    [ReindexStatus::Completed, ReindexStatus::Coalesced]
+                ),
+                ReindexPolicy::JoinInFlight => assert_eq!(
+                    statuses[1..],
+                    [ReindexStatus::JoinedInFlight, ReindexStatus::JoinedInFlight]
+                ),
+            }
+            assert_eq!(indexer.index_pass_count(), expected_passes);
+
+            assert_eq!(indexer.document_count().await.unwrap(), 20);
+            assert_eq!(storage.get_file_count().await.unwrap(), 20);
+            assert!(indexer.verify_index(false).await.unwrap().consistent);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindex_waiters_receive_pass_errors() {
+        for policy in [ReindexPolicy::QueueFollowUp, ReindexPolicy::JoinInFlight] {
+            let temp_dir = tempdir().unwrap();
+            let workspace = temp_dir.path().join("workspace");
+            std::fs::create_dir(&workspace).unwrap();
+            std::fs::write(workspace.join("lib.rs"), "pub fn item() {}\n").unwrap();
+
+            let config = Arc::new(Config {
+                workspace_roots: vec![workspace],
+                cache_dir: temp_dir.path().join("cache"),
+                enable_semantic: false,
+                reindex_policy: policy,
+                ..Default::default()
+            });
+
+            let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+            let indexer = Indexer::new(config.clone(), storage).await.unwrap();
+
+            // Every pass fails to commit into a missing index directory
+            std::fs::remove_dir_all(config.cache_dir.join("tantivy_index")).unwrap();
+
+            let (first, second, third) =
+                tokio::join!(indexer.reindex(), indexer.reindex(), indexer.reindex());
+            assert!(first.is_err());
+            assert!(second.is_err(), "{policy:?}: {second:?}");
+            assert!(third.is_err(), "{policy:?}: {third:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_over_size_cap_evicts_oldest_files() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+
+        // Distinct, incompressible identifiers, so each file's share of the index
+        // outweighs the index's fixed overhead
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let names = ["old_a", "old_b", "new_a", "new_b"];
+        for name in names {
+            let mut content = String::new();
+            for _ in 0..4000 {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                content.push_str(&format!("let v{:016x} = {};\n", seed, name.len()));
+            }
+            std::fs::write(
+                workspace.join(format!("{}.rs", name)),
+                format!("fn {}() {{\n{}}}\n", name, content),
+            )
+            .unwrap();
+        }
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            // Large enough never to trigger eviction by itself
+            max_index_bytes: Some(u64::MAX),
+            ..Default::default()
+        });
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let indexer = Indexer::new(config, storage.clone()).await.unwrap();
+        indexer.index_workspaces().await.unwrap();
+
+        // Pretend the old files were indexed an hour before the new ones
+        for name in ["old_a", "old_b"] {
+            let path = workspace.join(format!("{}.rs", name));
+            let mut metadata = storage.get_file_metadata(&path).await.unwrap().unwrap();
+            metadata.indexed_at -= 3600;
+            storage.store_file_metadata(&path, metadata).await.unwrap();
+        }
+
+        let index_bytes = storage.get_index_size().await.unwrap();
+        let evicted = indexer
+            .enforce_index_size(index_bytes * 6 / 10)
+            .await
+            .unwrap();
+
+        let mut evicted_names: Vec<_> = evicted
+            .iter()
+            .map(|p| p.file_stem().unwrap().to_string_lossy().to_string())
+            .collect();
+        evicted_names.sort();
+        assert_eq!(evicted_names, vec!["old_a", "old_b"]);
+
+        let remaining = storage.list_files().await.unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.contains(&workspace.join("new_a.rs")));
+        assert!(remaining.contains(&workspace.join("new_b.rs")));
+        assert_eq!(indexer.document_count().await.unwrap(), 2);
+        assert!(storage.get_index_size().await.unwrap() <= index_bytes * 6 / 10);
+
+        // Evicted files stay out of later passes
+        indexer.index_workspaces().await.unwrap();
+        assert_eq!(storage.list_files().await.unwrap().len(), 2);
+        assert_eq!(indexer.document_count().await.unwrap(), 2);
+
+        // Already under the cap
+        assert!(
+            indexer
+                .enforce_index_size(u64::MAX)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_evicted_files_return_when_changed_or_cap_raised() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        for name in ["alpha", "beta", "gamma"] {
+            std::fs::write(
+                workspace.join(format!("{}.rs", name)),
+                format!("fn {}() {{}}\n", name),
+            )
+            .unwrap();
+        }
+
+        let config_with_cap = |max_index_bytes| {
+            Arc::new(Config {
+                workspace_roots: vec![workspace.clone()],
+                cache_dir: temp_dir.path().join("cache"),
+                enable_semantic: false,
+                max_index_bytes,
+                ..Default::default()
+            })
+        };
+        let storage = StorageBackend::new(&temp_dir.path().join("cache"))
+            .await
+            .unwrap();
+        let file_count = || async { storage.list_files().await.unwrap().len() };
+
+        {
+            let indexer = Indexer::new(config_with_cap(Some(1 << 40)), storage.clone())
+                .await
+                .unwrap();
+            indexer.index_workspaces().await.unwrap();
+            assert_eq!(indexer.enforce_index_size(1).await.unwrap().len(), 3);
+            assert_eq!(file_count().await, 0);
+
+            // Editing an evicted file brings it back, for later passes too
+            let alpha = workspace.join("alpha.rs");
+            std::fs::write(&alpha, "fn alpha_edited() {}\n").unwrap();
+            indexer.index_single_file(&alpha).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+            assert_eq!(storage.list_files().await.unwrap(), vec![alpha]);
+
+            // The same cap keeps the others out
+            drop(indexer);
+            let indexer = Indexer::new(config_with_cap(Some(1 << 40)), storage.clone())
+                .await
+                .unwrap();
+            indexer.index_workspaces().await.unwrap();
+            assert_eq!(file_count().await, 1);
+        }
+
+        // Raising the cap lets every evicted file back in
+        {
+            let indexer = Indexer::new(config_with_cap(Some(1 << 41)), storage.clone())
+                .await
+                .unwrap();
+            indexer.index_workspaces().await.unwrap();
+            assert_eq!(file_count().await, 3);
+            assert!(storage.list_evicted().await.unwrap().is_empty());
+
+            indexer.enforce_index_size(1).await.unwrap();
+            assert_eq!(file_count().await, 0);
+        }
+
+        // So does removing it
+        let indexer = Indexer::new(config_with_cap(None), storage.clone())
+            .await
+            .unwrap();
+        indexer.index_workspaces().await.unwrap();
+        assert_eq!(file_count().await, 3);
+        assert_eq!(storage.get_eviction_cap().await.unwrap(), None);
+    }
+
+    #[cfg(feature = "semantic")]
+    #[tokio::test]
+    async fn test_semantic_repositories_limits_embedding() {
+        let temp_dir = tempdir().unwrap();
+        let alpha = temp_dir.path().join("alpha");
+        let beta = temp_dir.path().join("beta");
+        std::fs::create_dir(&alpha).unwrap();
+        std::fs::create_dir(&beta).unwrap();
+        std::fs::write(alpha.join("lib.rs"), "fn alpha_entry() {}\n").unwrap();
+        std::fs::write(beta.join("lib.rs"), "fn beta_entry() {}\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![alpha.clone(), beta.clone()],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            semantic_repositories: Some(vec!["alpha".to_string()]),
+            ..Default::default()
+        });
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let pipeline = Arc::new(crate::embedding::EmbeddingPipeline::in_memory_stand_in(
+            config.clone(),
+        ));
+        let searcher = SemanticSearcher::with_pipeline(config.clone(), storage.clone(), pipeline);
+
+        let mut indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+        indexer.semantic_searcher = Some(searcher.clone());
+        indexer.index_workspaces().await.unwrap();
+
+        let embedded = |path: PathBuf| {
+            let searcher = searcher.clone();
+            let dimension = config.embedding_dimension;
+            async move {
+                let filter = crate::embedding::VectorFilter {
+                    file_paths: vec![path.to_string_lossy().to_string()],
+                    language: None,
+                };
+                !searcher
+                    .search_by_vector(vec![1.0; dimension], 10, Some(filter))
+                    .await
+                    .unwrap()
+                    .is_empty()
+            }
+        };
+        assert!(embedded(alpha.join("lib.rs")).await);
+        assert!(!embedded(beta.join("lib.rs")).await);
+
+        // Both repositories are still in the text index
+        assert_eq!(indexer.document_count().await.unwrap(), 2);
+    }
+
+    #[cfg(feature = "semantic")]
+    #[tokio::test]
+    async fn test_parallel_embedding_matches_serial() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        for i in 0..12 {
+            let mut content = String::new();
+            for j in 0..4 {
+                content.push_str(&format!(
+                    "fn handler_{i}_{j}(x: u32) -> u32 {{\n    x.wrapping_mul({j})\n}}\n\n"
+                ));
+            }
+            std::fs::write(workspace.join(format!("module_{i}.rs")), content).unwrap();
+        }
+
+        let stored_chunks = |concurrency: usize| {
+            let workspace = workspace.clone();
+            let cache_dir = temp_dir.path().join(format!("cache-{concurrency}"));
+            async move {
+                let config = Arc::new(Config {
+                    workspace_roots: vec![workspace],
+                    cache_dir,
+                    enable_semantic: false,
+                    max_concurrent_embeddings: concurrency,
+                    ..Default::default()
+                });
+                let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+                let pipeline = Arc::new(crate::embedding::EmbeddingPipeline::in_memory_stand_in(
+                    config.clone(),
+                ));
+                let searcher =
+                    SemanticSearcher::with_pipeline(config.clone(), storage.clone(), pipeline);
+
+                let mut indexer = Indexer::new(config.clone(), storage).await.unwrap();
+                indexer.semantic_searcher = Some(searcher.clone());
+                indexer.index_workspaces().await.unwrap();
+
+                let mut chunks: Vec<_> = searcher
+                    .search_by_vector(vec![1.0; config.embedding_dimension], 1000, None)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| (r.file_path, r.line_number, r.end_line, r.content))
+                    .collect();
+                chunks.sort();
+                chunks
+            }
+        };
+
+        let serial = stored_chunks(1).await;
+        let parallel = stored_chunks(8).await;
+        assert!(serial.len() >= 12);
+        assert_eq!(serial, parallel);
+    }
 }
@@ -1,9 +1,16 @@
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use anyhow::{Result, anyhow};
-use tree_sitter::{Language as TSLanguage, Node, Parser};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use parking_lot::Mutex;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Language as TSLanguage, Node, Parser, Query, QueryCursor, Tree};
 
 use super::language_detector::Language;
+use crate::Config;
 
 #[derive(Debug, Clone)]
 pub struct Symbol {
@@ -14,8 +21,38 @@ pub struct Symbol {
     pub start_col: usize,
     pub end_col: usize,
     pub signature: Option<String>,
+    /// Class or struct a method is declared in
+    pub container: Option<String>,
 }
 
+/// An identifier used outside of its own definition, e.g. a type in a parameter list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolReference {
+    pub name: String,
+    /// 0-indexed, like `Symbol::start_line`
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Tree-sitter node kinds that name something, across the supported grammars
+const IDENTIFIER_KINDS: &[&str] = &[
+    "identifier",
+    "type_identifier",
+    "field_identifier",
+    "property_identifier",
+    "namespace_identifier",
+];
+
+/// Suffixes of node kinds that introduce a name (`struct_item`, `class_declaration`,
+/// `type_spec`, ...), as opposed to using one (`struct_expression`)
+const DEFINITION_KIND_SUFFIXES: &[&str] = &[
+    "_item",
+    "_declaration",
+    "_definition",
+    "_specifier",
+    "_spec",
+];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SymbolKind {
     Function,
@@ -57,8 +94,105 @@ impl SymbolKind {
     }
 }
 
+/// Decides which files are too noisy for symbol extraction (minified or generated code)
+#[derive(Debug, Clone)]
+pub struct ExtractionFilter {
+    max_average_line_length: usize,
+    exclude: GlobSet,
+}
+
+impl ExtractionFilter {
+    pub fn new(max_average_line_length: usize, exclude_globs: &[String]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in exclude_globs {
+            builder.add(Glob::new(pattern)?);
+        }
+
+        Ok(Self {
+            max_average_line_length,
+            exclude: builder.build()?,
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Self::new(
+            config.symbol_extraction_max_avg_line_length,
+            &config.symbol_extraction_exclude_globs,
+        )
+    }
+
+    /// True if symbols should not be extracted from this file
+    pub fn should_skip(&self, path: &Path, content: &str) -> bool {
+        if self.exclude.is_match(path) {
+            return true;
+        }
+
+        let line_count = content.lines().count().max(1);
+        content.len() / line_count > self.max_average_line_length
+    }
+}
+
+impl Default for ExtractionFilter {
+    fn default() -> Self {
+        Self::from_config(&Config::default()).expect("default exclude globs are valid")
+    }
+}
+
+/// Tree-sitter query capturing a language's import statements as `@import`
+pub fn import_query_source(language: Language) -> Option<&'static str> {
+    match language {
+        Language::Rust => Some("(use_declaration) @import"),
+        Language::Python => Some("(import_statement) @import\n(import_from_statement) @import"),
+        Language::JavaScript | Language::TypeScript => Some("(import_statement) @import"),
+        Language::Go => Some("(import_declaration) @import"),
+        _ => None,
+    }
+}
+
+/// Parsers for one language. A parser is checked out for each parse, and a new one is
+/// created when all of them are busy, so files of the same language parse in parallel.
+#[derive(Default)]
+struct ParserPool {
+    idle: Mutex<Vec<Parser>>,
+    created: AtomicUsize,
+}
+
+/// A parser checked out of a `ParserPool`, returned to it on drop
+struct PooledParser {
+    parser: Option<Parser>,
+    pool: Arc<ParserPool>,
+    max_idle: usize,
+}
+
+impl Deref for PooledParser {
+    type Target = Parser;
+
+    fn deref(&self) -> &Parser {
+        self.parser.as_ref().expect("parser is present until drop")
+    }
+}
+
+impl DerefMut for PooledParser {
+    fn deref_mut(&mut self) -> &mut Parser {
+        self.parser.as_mut().expect("parser is present until drop")
+    }
+}
+
+impl Drop for PooledParser {
+    fn drop(&mut self) {
+        let mut idle = self.pool.idle.lock();
+        if idle.len() < self.max_idle
+            && let Some(parser) = self.parser.take()
+        {
+            idle.push(parser);
+        }
+    }
+}
+
 pub struct SymbolExtractor {
-    parsers: dashmap::DashMap<Language, Parser>,
+    parsers: dashmap::DashMap<Language, Arc<ParserPool>>,
+    /// Most parsers kept per language between parses
+    max_idle_parsers: usize,
 }
 
 impl Default for SymbolExtractor {
@@ -69,11 +203,25 @@ impl Default for SymbolExtractor {
 
 impl SymbolExtractor {
     pub fn new() -> Self {
+        Self::with_pool_size(num_cpus::get())
+    }
+
+    /// Keep up to `max_idle_parsers` parsers per language for reuse. More are created
+    /// while that many are busy, but dropped once done.
+    pub fn with_pool_size(max_idle_parsers: usize) -> Self {
         Self {
             parsers: dashmap::DashMap::new(),
+            max_idle_parsers: max_idle_parsers.max(1),
         }
     }
 
+    /// Number of parsers created so far for `language`
+    pub fn parsers_created(&self, language: Language) -> usize {
+        self.parsers
+            .get(&language)
+            .map_or(0, |pool| pool.created.load(Ordering::Relaxed))
+    }
+
     pub fn extract_symbols(
         &self,
         _path: &Path,
@@ -84,20 +232,7 @@ impl SymbolExtractor {
             return Ok(Vec::new());
         }
 
-        // Get or create parser for this language, then parse with exclusive access
-        self.ensure_parser_exists(language)?;
-
-        // Use DashMap's entry API to get exclusive mutable access to cached parser
-        let tree = {
-            let mut parser_ref = self
-                .parsers
-                .get_mut(&language)
-                .ok_or_else(|| anyhow!("Parser not found after creation"))?;
-            parser_ref
-                .parse(content, None)
-                .ok_or_else(|| anyhow!("Failed to parse file"))?
-        }; // parser_ref dropped here, releasing the lock
-
+        let tree = self.parse(content, language)?;
         let root = tree.root_node();
         let mut symbols = Vec::new();
 
@@ -119,19 +254,104 @@ impl SymbolExtractor {
         Ok(symbols)
     }
 
-    fn ensure_parser_exists(&self, language: Language) -> Result<()> {
-        // Check if parser already exists in cache
-        if self.parsers.contains_key(&language) {
-            return Ok(());
+    /// Every identifier usage in the file, skipping the names introduced by definitions.
+    /// One entry per name per line, in source order.
+    pub fn extract_references(
+        &self,
+        _path: &Path,
+        content: &str,
+        language: Language,
+    ) -> Result<Vec<SymbolReference>> {
+        if !language.supports_tree_sitter() {
+            return Ok(Vec::new());
         }
 
-        // Create and cache a new parser for this language
-        let mut parser = Parser::new();
-        let ts_language = self.get_tree_sitter_language(language)?;
-        parser.set_language(&ts_language)?;
-        self.parsers.insert(language, parser);
+        let tree = self.parse(content, language)?;
+        let mut references: Vec<SymbolReference> = Vec::new();
+        let mut seen = std::collections::HashSet::new();
 
-        Ok(())
+        // Walk iteratively; deeply nested expressions would overflow a recursive walk
+        let mut stack = vec![tree.root_node()];
+        while let Some(node) = stack.pop() {
+            if IDENTIFIER_KINDS.contains(&node.kind()) {
+                let is_definition = node.parent().is_some_and(|parent| {
+                    DEFINITION_KIND_SUFFIXES
+                        .iter()
+                        .any(|suffix| parent.kind().ends_with(suffix))
+                        && parent
+                            .child_by_field_name("name")
+                            .is_some_and(|name| name.id() == node.id())
+                });
+                if !is_definition {
+                    let name = node.utf8_text(content.as_bytes())?;
+                    let position = node.start_position();
+                    if seen.insert((name, position.row)) {
+                        references.push(SymbolReference {
+                            name: name.to_string(),
+                            line: position.row,
+                            column: position.column,
+                        });
+                    }
+                }
+                continue;
+            }
+
+            let mut cursor = node.walk();
+            let children: Vec<Node> = node.children(&mut cursor).collect();
+            stack.extend(children.into_iter().rev());
+        }
+
+        Ok(references)
+    }
+
+    /// Text of each import statement in the file (`use`, `import`, ...), in source order
+    pub fn extract_imports(&self, content: &str, language: Language) -> Result<Vec<String>> {
+        let Some(query_source) = import_query_source(language) else {
+            return Ok(Vec::new());
+        };
+
+        let tree = self.parse(content, language)?;
+        let query = Query::new(&self.get_tree_sitter_language(language)?, query_source)?;
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&query, tree.root_node(), content.as_bytes());
+
+        let mut imports = Vec::new();
+        while let Some(query_match) = matches.next() {
+            for capture in query_match.captures {
+                imports.push(capture.node.utf8_text(content.as_bytes())?.to_string());
+            }
+        }
+
+        Ok(imports)
+    }
+
+    /// Parse with a pooled parser for `language`
+    fn parse(&self, content: &str, language: Language) -> Result<Tree> {
+        self.checkout_parser(language)?
+            .parse(content, None)
+            .ok_or_else(|| anyhow!("Failed to parse file"))
+    }
+
+    /// An idle parser for `language`, or a new one if all are in use
+    fn checkout_parser(&self, language: Language) -> Result<PooledParser> {
+        // Clone the pool out so the map isn't locked while parsing
+        let pool = self.parsers.entry(language).or_default().clone();
+        let idle = pool.idle.lock().pop();
+        let parser = match idle {
+            Some(parser) => parser,
+            None => {
+                let mut parser = Parser::new();
+                parser.set_language(&self.get_tree_sitter_language(language)?)?;
+                pool.created.fetch_add(1, Ordering::Relaxed);
+                parser
+            },
+        };
+
+        Ok(PooledParser {
+            parser: Some(parser),
+            pool,
+            max_idle: self.max_idle_parsers,
+        })
     }
 
     fn get_tree_sitter_language(&self, language: Language) -> Result<TSLanguage> {
@@ -326,6 +546,22 @@ impl SymbolExtractor {
                         let name = name_node.utf8_text(source.as_bytes())?;
                         symbols.push(self.create_symbol(name, SymbolKind::Class, child, source)?);
                     }
+
+                    // Class attributes, but not assignments inside methods
+                    if let Some(body) = child.child_by_field_name("body") {
+                        let mut body_cursor = body.walk();
+                        for statement in body.children(&mut body_cursor) {
+                            if statement.kind() == "expression_statement" {
+                                let mut statement_cursor = statement.walk();
+                                for expression in statement.children(&mut statement_cursor) {
+                                    self.extract_python_assignment(expression, source, symbols)?;
+                                }
+                            }
+                        }
+                    }
+                },
+                "assignment" => {
+                    self.extract_python_assignment(child, source, symbols)?;
                 },
                 _ => {
                     self.extract_python_symbols(child, source, symbols)?;
@@ -336,6 +572,35 @@ impl SymbolExtractor {
         Ok(())
     }
 
+    /// A plain or annotated assignment to a single name (`MAX_SIZE = 100`,
+    /// `timeout: float = 1.5`). All-caps names are taken to be constants.
+    fn extract_python_assignment(
+        &self,
+        node: Node,
+        source: &str,
+        symbols: &mut Vec<Symbol>,
+    ) -> Result<()> {
+        if node.kind() != "assignment" {
+            return Ok(());
+        }
+
+        if let Some(left) = node.child_by_field_name("left")
+            && left.kind() == "identifier"
+        {
+            let name = left.utf8_text(source.as_bytes())?;
+            let kind = if name.chars().any(|c| c.is_uppercase())
+                && !name.chars().any(|c| c.is_lowercase())
+            {
+                SymbolKind::Constant
+            } else {
+                SymbolKind::Variable
+            };
+            symbols.push(self.create_symbol(name, kind, node, source)?);
+        }
+
+        Ok(())
+    }
+
     fn extract_go_symbols(
         &self,
         node: Node,
@@ -380,6 +645,12 @@ impl SymbolExtractor {
                         symbols.push(self.create_symbol(name, kind, child, source)?);
                     }
                 },
+                "const_declaration" => {
+                    self.extract_go_specs(child, source, SymbolKind::Constant, symbols)?;
+                },
+                "var_declaration" => {
+                    self.extract_go_specs(child, source, SymbolKind::Variable, symbols)?;
+                },
                 _ => {
                     self.extract_go_symbols(child, source, symbols)?;
                 },
@@ -389,6 +660,36 @@ impl SymbolExtractor {
         Ok(())
     }
 
+    /// Every name in a Go `const`/`var` declaration, including grouped
+    /// `const ( ... )` blocks and specs declaring several names (`var a, b int`)
+    fn extract_go_specs(
+        &self,
+        node: Node,
+        source: &str,
+        kind: SymbolKind,
+        symbols: &mut Vec<Symbol>,
+    ) -> Result<()> {
+        let mut cursor = node.walk();
+
+        for child in node.children(&mut cursor) {
+            match child.kind() {
+                "const_spec" | "var_spec" => {
+                    let mut names = child.walk();
+                    for name_node in child.children_by_field_name("name", &mut names) {
+                        let name = name_node.utf8_text(source.as_bytes())?;
+                        symbols.push(self.create_symbol(name, kind, child, source)?);
+                    }
+                },
+                "var_spec_list" => {
+                    self.extract_go_specs(child, source, kind, symbols)?;
+                },
+                _ => {},
+            }
+        }
+
+        Ok(())
+    }
+
     fn extract_java_symbols(
         &self,
         node: Node,
@@ -460,15 +761,19 @@ impl SymbolExtractor {
                         )?);
                     }
                 },
-                "struct_specifier" => {
+                "struct_specifier" | "class_specifier" => {
                     if let Some(name_node) = child.child_by_field_name("name") {
                         let name = name_node.utf8_text(source.as_bytes())?;
-                        symbols.push(self.create_symbol(
-                            name,
-                            SymbolKind::Struct,
-                            child,
-                            source,
-                        )?);
+                        let kind = if child.kind() == "class_specifier" {
+                            SymbolKind::Class
+                        } else {
+                            SymbolKind::Struct
+                        };
+                        symbols.push(self.create_symbol(name, kind, child, source)?);
+
+                        if let Some(body) = child.child_by_field_name("body") {
+                            self.extract_cpp_methods(body, name, source, symbols)?;
+                        }
                     }
                 },
                 "enum_specifier" => {
@@ -477,6 +782,21 @@ impl SymbolExtractor {
                         symbols.push(self.create_symbol(name, SymbolKind::Enum, child, source)?);
                     }
                 },
+                "namespace_definition" => {
+                    // Anonymous namespaces have no name but their contents still count
+                    if let Some(name_node) = child.child_by_field_name("name") {
+                        let name = name_node.utf8_text(source.as_bytes())?;
+                        symbols.push(self.create_symbol(
+                            name,
+                            SymbolKind::Namespace,
+                            child,
+                            source,
+                        )?);
+                    }
+                    if let Some(body) = child.child_by_field_name("body") {
+                        self.extract_c_symbols(body, source, symbols)?;
+                    }
+                },
                 _ => {
                     self.extract_c_symbols(child, source, symbols)?;
                 },
@@ -486,6 +806,63 @@ impl SymbolExtractor {
         Ok(())
     }
 
+    /// Methods defined inline in a C++ class or struct body, including templated ones
+    fn extract_cpp_methods(
+        &self,
+        body: Node,
+        class_name: &str,
+        source: &str,
+        symbols: &mut Vec<Symbol>,
+    ) -> Result<()> {
+        let mut cursor = body.walk();
+
+        for member in body.children(&mut cursor) {
+            match member.kind() {
+                "function_definition" => {
+                    if let Some(declarator) = member.child_by_field_name("declarator")
+                        && let Some(name_node) = Self::find_method_name(declarator)
+                    {
+                        let name = name_node.utf8_text(source.as_bytes())?;
+                        let mut symbol =
+                            self.create_symbol(name, SymbolKind::Method, member, source)?;
+                        symbol.container = Some(class_name.to_string());
+                        symbols.push(symbol);
+                    }
+                },
+                "template_declaration" => {
+                    self.extract_cpp_methods(member, class_name, source, symbols)?;
+                },
+                _ => {},
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Name of a method declarator: a field identifier, destructor or operator name
+    fn find_method_name(node: Node) -> Option<Node> {
+        if matches!(
+            node.kind(),
+            "field_identifier" | "identifier" | "destructor_name" | "operator_name"
+        ) {
+            return Some(node);
+        }
+
+        // The parameter list can't hold the name
+        if node.kind() == "parameter_list" {
+            return None;
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if let Some(name) = Self::find_method_name(child) {
+                return Some(name);
+            }
+        }
+
+        None
+    }
+
     fn extract_generic_symbols(
         &self,
         node: Node,
@@ -586,6 +963,7 @@ impl SymbolExtractor {
             start_col: start_pos.column,
             end_col: end_pos.column,
             signature,
+            container: None,
         })
     }
 }
@@ -633,4 +1011,186 @@ mod tests {
                 .any(|s| s.name == "MyStruct" && s.kind == SymbolKind::Implementation)
         );
     }
+
+    #[test]
+    fn test_extract_references_skips_definitions() {
+        let source = "struct Config {}\n\nfn load(path: &str) -> Config {\n    Config {}\n}\n";
+
+        let extractor = SymbolExtractor::new();
+        let references = extractor
+            .extract_references(Path::new("test.rs"), source, Language::Rust)
+            .unwrap();
+
+        let config_lines: Vec<usize> = references
+            .iter()
+            .filter(|r| r.name == "Config")
+            .map(|r| r.line)
+            .collect();
+        assert_eq!(config_lines, vec![2, 3]);
+        assert!(!references.iter().any(|r| r.name == "load"));
+        assert!(references.iter().any(|r| r.name == "path" && r.line == 2));
+    }
+
+    #[test]
+    fn test_extract_go_constants_and_vars() {
+        let source = r#"
+package config
+
+const (
+    DefaultPort = 8080
+    DefaultHost = "localhost"
+)
+
+var registry = map[string]int{}
+
+var (
+    minSize, maxSize int
+)
+
+func load() {
+    var local = 1
+    _ = local
+}
+"#;
+
+        let extractor = SymbolExtractor::new();
+        let symbols = extractor
+            .extract_symbols(Path::new("config.go"), source, Language::Go)
+            .unwrap();
+
+        let kind_of = |name: &str| symbols.iter().find(|s| s.name == name).map(|s| s.kind);
+        assert_eq!(kind_of("DefaultPort"), Some(SymbolKind::Constant));
+        assert_eq!(kind_of("DefaultHost"), Some(SymbolKind::Constant));
+        assert_eq!(kind_of("registry"), Some(SymbolKind::Variable));
+        assert_eq!(kind_of("minSize"), Some(SymbolKind::Variable));
+        assert_eq!(kind_of("maxSize"), Some(SymbolKind::Variable));
+        assert_eq!(kind_of("load"), Some(SymbolKind::Function));
+        assert_eq!(kind_of("local"), None);
+    }
+
+    #[test]
+    fn test_extract_python_assignments() {
+        let source = r#"
+MAX_SIZE = 100
+default_timeout: float = 1.5
+
+class Client:
+    RETRIES = 3
+    name = "client"
+
+    def connect(self):
+        attempts = 0
+        return attempts
+"#;
+
+        let extractor = SymbolExtractor::new();
+        let symbols = extractor
+            .extract_symbols(Path::new("client.py"), source, Language::Python)
+            .unwrap();
+
+        let kind_of = |name: &str| symbols.iter().find(|s| s.name == name).map(|s| s.kind);
+        assert_eq!(kind_of("MAX_SIZE"), Some(SymbolKind::Constant));
+        assert_eq!(kind_of("default_timeout"), Some(SymbolKind::Variable));
+        assert_eq!(kind_of("RETRIES"), Some(SymbolKind::Constant));
+        assert_eq!(kind_of("name"), Some(SymbolKind::Variable));
+        assert_eq!(kind_of("Client"), Some(SymbolKind::Class));
+        assert_eq!(kind_of("attempts"), None);
+    }
+
+    #[test]
+    fn test_extract_cpp_classes_and_namespaces() {
+        let source = r#"
+namespace geometry {
+
+class Shape {
+public:
+    Shape() {}
+    ~Shape() {}
+    double area() const { return 0.0; }
+
+    template <typename T>
+    T scaled(T factor) { return factor; }
+};
+
+int count_shapes() { return 0; }
+
+}
+"#;
+
+        let extractor = SymbolExtractor::new();
+        let symbols = extractor
+            .extract_symbols(Path::new("shape.cpp"), source, Language::Cpp)
+            .unwrap();
+
+        let find = |name: &str| symbols.iter().find(|s| s.name == name);
+        assert_eq!(
+            find("geometry").map(|s| s.kind),
+            Some(SymbolKind::Namespace)
+        );
+        assert_eq!(find("Shape").map(|s| s.kind), Some(SymbolKind::Class));
+        assert_eq!(
+            find("count_shapes").map(|s| s.kind),
+            Some(SymbolKind::Function)
+        );
+
+        for method in ["area", "scaled", "~Shape"] {
+            let symbol = find(method).unwrap_or_else(|| panic!("missing method {}", method));
+            assert_eq!(symbol.kind, SymbolKind::Method);
+            assert_eq!(symbol.container.as_deref(), Some("Shape"));
+        }
+        assert!(
+            symbols
+                .iter()
+                .any(|s| s.name == "Shape" && s.kind == SymbolKind::Method)
+        );
+    }
+
+    #[test]
+    fn test_parser_pool_grows_while_busy() {
+        let extractor = SymbolExtractor::with_pool_size(2);
+        let source = "fn alpha() {}\n";
+
+        // A parse while another parser is checked out gets a second parser instead of
+        // waiting on the first
+        let held = extractor.checkout_parser(Language::Rust).unwrap();
+        let symbols = extractor
+            .extract_symbols(Path::new("a.rs"), source, Language::Rust)
+            .unwrap();
+        assert_eq!(symbols[0].name, "alpha");
+        assert_eq!(extractor.parsers_created(Language::Rust), 2);
+        drop(held);
+
+        // Both are reused afterwards
+        for _ in 0..3 {
+            extractor
+                .extract_symbols(Path::new("a.rs"), source, Language::Rust)
+                .unwrap();
+        }
+        assert_eq!(extractor.parsers_created(Language::Rust), 2);
+        assert_eq!(extractor.parsers_created(Language::Python), 0);
+    }
+
+    #[test]
+    fn test_concurrent_extraction_same_language() {
+        let extractor = SymbolExtractor::with_pool_size(4);
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let extractor = &extractor;
+                scope.spawn(move || {
+                    let source: String = (0..200)
+                        .map(|j| format!("fn file{i}_fn{j}() {{}}\n"))
+                        .collect();
+                    let symbols = extractor
+                        .extract_symbols(Path::new("lib.rs"), &source, Language::Rust)
+                        .unwrap();
+                    assert_eq!(symbols.len(), 200);
+                    assert_eq!(symbols[0].name, format!("file{i}_fn0"));
+                    assert_eq!(symbols[199].name, format!("file{i}_fn199"));
+                });
+            }
+        });
+
+        let created = extractor.parsers_created(Language::Rust);
+        assert!((1..=8).contains(&created));
+    }
 }
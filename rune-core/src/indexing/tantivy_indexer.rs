@@ -1,19 +1,32 @@
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::{Result, anyhow};
 use tantivy::{
-    Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, doc,
-    schema::{FAST, Field, STORED, STRING, Schema, TEXT, Value},
+    Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument,
+    aggregation::{
+        AggregationCollector, Key,
+        agg_req::Aggregations,
+        agg_result::{AggregationResult, BucketResult},
+    },
+    collector::DocSetCollector,
+    doc,
+    query::{AllQuery, BooleanQuery, Occur, Query, RangeQuery},
+    schema::{FAST, Field, INDEXED, STORED, STRING, Schema, TEXT, Value},
+    snippet::SnippetGenerator,
 };
 use tokio::sync::RwLock;
 use tracing::{debug, info};
 
-use super::language_detector::LanguageDetector;
-use super::symbol_extractor::SymbolExtractor;
+use super::language_detector::{Language, LanguageDetector};
+use super::symbol_extractor::{ExtractionFilter, Symbol, SymbolExtractor, SymbolKind};
+
+/// Upper bound on buckets returned by `list_repositories`
+const MAX_LISTED_REPOSITORIES: u32 = 10_000;
 
 pub struct TantivyIndexer {
-    _index: Index, // Kept for directory lock ownership
+    index: Index,
     schema: Schema,
     writer: Option<Arc<RwLock<IndexWriter>>>,
     reader: IndexReader,
@@ -25,21 +38,49 @@ pub struct TantivyIndexer {
     symbols_field: Field,
     line_numbers_field: Field,
     repository_field: Field,
+    path_text_field: Field,
+    references_field: Field,
+    block_ranges_field: Field,
+    modified_field: Field,
+
+    // Set when an index with an older schema was discarded on open
+    rebuilt: bool,
 
     // Shared symbol extractor for all files
     symbol_extractor: Arc<SymbolExtractor>,
+    extraction_filter: ExtractionFilter,
+    // Whether identifier usages are recorded alongside definitions
+    index_references: bool,
+    // Whether text files get their blank-line-delimited blocks recorded
+    index_text_blocks: bool,
+    // Whether file content is stored in the index or read back from disk
+    store_content: bool,
+    // `symbol_names` as of the `index_version` it was built at; cleared on commit
+    symbol_names: parking_lot::Mutex<Option<(u64, Arc<Vec<String>>)>>,
 }
 
 impl TantivyIndexer {
     pub async fn new(index_path: &Path) -> Result<Self> {
-        Self::new_with_writer(index_path, true).await
+        Self::open(index_path, true).await
+    }
+
+    /// Open a writable index. Without `store_content` file content is only indexed
+    /// for search, not kept, and results read it from disk instead.
+    pub async fn open(index_path: &Path, store_content: bool) -> Result<Self> {
+        Self::new_with_writer(index_path, true, store_content).await
     }
 
-    pub async fn new_read_only(index_path: &Path) -> Result<Self> {
-        Self::new_with_writer(index_path, false).await
+    /// Open without a writer. `store_content` only matters if the index doesn't exist
+    /// yet; an existing one keeps the layout it was written with.
+    pub async fn new_read_only(index_path: &Path, store_content: bool) -> Result<Self> {
+        Self::new_with_writer(index_path, false, store_content).await
     }
 
-    async fn new_with_writer(index_path: &Path, create_writer: bool) -> Result<Self> {
+    async fn new_with_writer(
+        index_path: &Path,
+        create_writer: bool,
+        store_content: bool,
+    ) -> Result<Self> {
         // Create index directory
         tokio::fs::create_dir_all(index_path).await?;
 
@@ -47,21 +88,52 @@ impl TantivyIndexer {
         let mut schema_builder = Schema::builder();
 
         let path_field = schema_builder.add_text_field("path", STRING | STORED);
-        let content_field = schema_builder.add_text_field("content", TEXT | STORED);
+        let content_field = if store_content {
+            schema_builder.add_text_field("content", TEXT | STORED)
+        } else {
+            schema_builder.add_text_field("content", TEXT)
+        };
         let language_field = schema_builder.add_text_field("language", STRING | STORED | FAST);
         let symbols_field = schema_builder.add_text_field("symbols", TEXT | STORED);
         let line_numbers_field = schema_builder.add_text_field("line_numbers", STORED);
         let repository_field = schema_builder.add_text_field("repository", STRING | STORED | FAST);
+        // Tokenized copy of the path so file names can be searched by component
+        let path_text_field = schema_builder.add_text_field("path_text", TEXT);
+        // "<line> <name>" per identifier usage; empty unless reference indexing is on
+        let references_field = schema_builder.add_text_field("references", TEXT | STORED);
+        // "<start>-<end>" (1-based, inclusive) per block of a text file; empty unless
+        // text block indexing is on
+        let block_ranges_field = schema_builder.add_text_field("block_ranges", STORED);
+        // When the document was written (epoch seconds), for `modified_after`
+        let modified_field = schema_builder.add_u64_field("modified", INDEXED | FAST);
 
         let schema = schema_builder.build();
 
-        // Open or create index
+        // Open or create index. An index written with a different schema can't be
+        // updated in place, so the writer discards it and starts over.
+        let mut rebuilt = false;
         let index = if index_path.join("meta.json").exists() {
-            Index::open_in_dir(index_path)?
+            let index = Index::open_in_dir(index_path)?;
+            if index.schema() != schema && create_writer {
+                info!(
+                    "Tantivy schema changed, rebuilding index at {:?}",
+                    index_path
+                );
+                drop(index);
+                tokio::fs::remove_dir_all(index_path).await?;
+                tokio::fs::create_dir_all(index_path).await?;
+                rebuilt = true;
+                Index::create_in_dir(index_path, schema.clone())?
+            } else {
+                index
+            }
         } else {
             Index::create_in_dir(index_path, schema.clone())?
         };
 
+        // A read-only handle may be looking at an index written with the other setting
+        let store_content = index.schema().get_field_entry(content_field).is_stored();
+
         // Create writer with 100MB heap if requested
         let writer = if create_writer {
             Some(Arc::new(RwLock::new(index.writer(100_000_000)?)))
@@ -79,7 +151,7 @@ impl TantivyIndexer {
         let symbol_extractor = Arc::new(SymbolExtractor::new());
 
         Ok(Self {
-            _index: index,
+            index,
             schema,
             writer,
             reader,
@@ -89,26 +161,91 @@ impl TantivyIndexer {
             symbols_field,
             line_numbers_field,
             repository_field,
+            path_text_field,
+            references_field,
+            block_ranges_field,
+            modified_field,
+            rebuilt,
             symbol_extractor,
+            extraction_filter: ExtractionFilter::default(),
+            index_references: false,
+            index_text_blocks: false,
+            store_content,
+            symbol_names: parking_lot::Mutex::new(None),
         })
     }
 
+    /// Whether an index with an outdated schema was discarded when opening, in which
+    /// case every file needs to be indexed again
+    pub fn was_rebuilt(&self) -> bool {
+        self.rebuilt
+    }
+
+    /// Replace the filter that decides which files skip symbol extraction
+    pub fn with_extraction_filter(mut self, filter: ExtractionFilter) -> Self {
+        self.extraction_filter = filter;
+        self
+    }
+
+    /// Also record identifier usages in the `references` field. Costs a second tree walk
+    /// per file and a larger index.
+    pub fn with_reference_indexing(mut self, enabled: bool) -> Self {
+        self.index_references = enabled;
+        self
+    }
+
+    /// Keep up to `size` symbol parsers per language, normally one per indexing thread
+    pub fn with_parser_pool_size(mut self, size: usize) -> Self {
+        self.symbol_extractor = Arc::new(SymbolExtractor::with_pool_size(size));
+        self
+    }
+
+    /// Record the blank-line-delimited blocks of plain text and data files, so searches
+    /// can return a match's whole paragraph as context
+    pub fn with_text_blocks(mut self, enabled: bool) -> Self {
+        self.index_text_blocks = enabled;
+        self
+    }
+
     pub async fn index_file(
         &self,
         file_path: &Path,
         repository: &str,
         content: &str,
     ) -> Result<()> {
-        // Detect language
+        let symbols = self.extract_symbols(file_path, content)?;
+        self.index_file_with_symbols(file_path, repository, content, &symbols)
+            .await
+    }
+
+    /// Extract the symbols `index_file` would store for a file. Synchronous and safe to
+    /// call from several threads at once, so callers can parse files in parallel.
+    pub fn extract_symbols(&self, file_path: &Path, content: &str) -> Result<Vec<Symbol>> {
         let language = LanguageDetector::detect(file_path, Some(content));
 
-        // Extract symbols if supported (using shared extractor)
-        let symbols = if language.supports_tree_sitter() {
+        // Extract symbols if supported (using shared extractor). Minified and generated
+        // files are still indexed for their content, just without symbols.
+        if self.should_parse(file_path, content, language) {
             self.symbol_extractor
-                .extract_symbols(file_path, content, language)?
+                .extract_symbols(file_path, content, language)
         } else {
-            Vec::new()
-        };
+            Ok(Vec::new())
+        }
+    }
+
+    fn should_parse(&self, file_path: &Path, content: &str, language: Language) -> bool {
+        language.supports_tree_sitter() && !self.extraction_filter.should_skip(file_path, content)
+    }
+
+    /// Index a file using symbols already produced by `extract_symbols`
+    pub async fn index_file_with_symbols(
+        &self,
+        file_path: &Path,
+        repository: &str,
+        content: &str,
+        symbols: &[Symbol],
+    ) -> Result<()> {
+        let language = LanguageDetector::detect(file_path, Some(content));
 
         // Add symbols as searchable text
         let symbol_text = symbols
@@ -117,18 +254,49 @@ impl TantivyIndexer {
             .collect::<Vec<_>>()
             .join("\n");
 
+        let reference_text =
+            if self.index_references && self.should_parse(file_path, content, language) {
+                self.symbol_extractor
+                    .extract_references(file_path, content, language)?
+                    .iter()
+                    .map(|r| format!("{} {}", r.line, r.name))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            } else {
+                String::new()
+            };
+
+        let block_text = if self.index_text_blocks
+            && (language.is_text_format() || language == Language::Unknown)
+        {
+            text_blocks(content)
+                .iter()
+                .map(|block| format!("{}-{}", block.start + 1, block.end))
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            String::new()
+        };
+
         // Add line numbers for quick lookup
         let line_count = content.lines().count();
         let line_numbers = format!("1-{}", line_count);
 
         // Create document using the doc! macro
         let doc = doc!(
-            self.path_field => file_path.to_string_lossy().as_ref(),
+            self.path_field => path_term(file_path),
+            self.path_text_field => file_path.to_string_lossy().as_ref(),
             self.content_field => content,
-            self.language_field => language.to_str(),
+            self.language_field => language.index_label(),
             self.repository_field => repository,
             self.symbols_field => symbol_text.as_str(),
-            self.line_numbers_field => line_numbers.as_str()
+            self.references_field => reference_text.as_str(),
+            self.block_ranges_field => block_text.as_str(),
+            self.line_numbers_field => line_numbers.as_str(),
+            self.modified_field => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
         );
 
         // Delete old version if exists and add new document
@@ -136,7 +304,7 @@ impl TantivyIndexer {
             let writer = writer_arc.write().await;
             writer.delete_term(tantivy::Term::from_field_text(
                 self.path_field,
-                file_path.to_string_lossy().as_ref(),
+                &path_term(file_path),
             ));
 
             // Add new document
@@ -159,7 +327,7 @@ impl TantivyIndexer {
             let writer = writer_arc.write().await;
             writer.delete_term(tantivy::Term::from_field_text(
                 self.path_field,
-                file_path.to_string_lossy().as_ref(),
+                &path_term(file_path),
             ));
         } else {
             return Err(anyhow!("Cannot delete file: indexer is read-only"));
@@ -177,11 +345,39 @@ impl TantivyIndexer {
 
         // Reload the reader to see the latest changes
         self.reader.reload()?;
+        *self.symbol_names.lock() = None;
 
         info!("Committed index changes");
         Ok(())
     }
 
+    /// Merge all segments and delete unused files, so space held by deleted documents
+    /// is actually freed
+    pub async fn compact(&self) -> Result<()> {
+        let Some(ref writer_arc) = self.writer else {
+            return Err(anyhow!("Cannot compact index: indexer is read-only"));
+        };
+
+        {
+            let mut writer = writer_arc.write().await;
+            let segment_ids = self.index.searchable_segment_ids()?;
+            if !segment_ids.is_empty() {
+                writer.merge(&segment_ids).wait()?;
+            }
+            writer.garbage_collect_files().wait()?;
+        }
+
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    /// Pick up commits made through another handle on the same index right away,
+    /// instead of after the reader's reload delay
+    pub fn reload(&self) -> Result<()> {
+        self.reader.reload()?;
+        Ok(())
+    }
+
     pub async fn optimize(&self) -> Result<()> {
         // For now, just commit to ensure index is optimized
         // wait_merging_threads may not be available in this context
@@ -219,6 +415,39 @@ impl TantivyIndexer {
         self.repository_field
     }
 
+    pub fn get_path_text_field(&self) -> Field {
+        self.path_text_field
+    }
+
+    pub fn get_references_field(&self) -> Field {
+        self.references_field
+    }
+
+    /// `query` limited to documents indexed after `since` (epoch seconds), if set, so
+    /// the filter applies before any result limit
+    pub fn modified_after(&self, query: Box<dyn Query>, since: Option<u64>) -> Box<dyn Query> {
+        let Some(since) = since else {
+            return query;
+        };
+        let range = RangeQuery::new(
+            std::ops::Bound::Excluded(tantivy::Term::from_field_u64(self.modified_field, since)),
+            std::ops::Bound::Unbounded,
+        );
+        Box::new(BooleanQuery::new(vec![
+            (Occur::Must, query),
+            (Occur::Must, Box::new(range)),
+        ]))
+    }
+
+    /// Highlights the terms of `query` that occur in the index in `content` text
+    pub fn snippet_generator(&self, query: &dyn tantivy::query::Query) -> Result<SnippetGenerator> {
+        Ok(SnippetGenerator::create(
+            &self.get_searcher(),
+            query,
+            self.content_field,
+        )?)
+    }
+
     pub async fn search_documents(
         &self,
         query: &dyn tantivy::query::Query,
@@ -232,17 +461,27 @@ impl TantivyIndexer {
         for (_score, doc_address) in top_docs {
             let doc: TantivyDocument = searcher.doc(doc_address)?;
 
-            let path = doc
+            let term = doc
                 .get_first(self.path_field)
                 .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
                 .ok_or_else(|| anyhow!("Missing path field"))?;
-
-            let content = doc
-                .get_first(self.content_field)
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-                .ok_or_else(|| anyhow!("Missing content field"))?;
+            let path = path_from_term(term);
+
+            let content = if self.store_content {
+                doc.get_first(self.content_field)
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| anyhow!("Missing content field"))?
+            } else {
+                match tokio::fs::read_to_string(&path).await {
+                    Ok(content) => content,
+                    Err(e) => {
+                        // Deleted or unreadable since it was indexed
+                        debug!("Skipping {:?} whose content can't be read: {}", path, e);
+                        continue;
+                    },
+                }
+            };
 
             let language = doc
                 .get_first(self.language_field)
@@ -256,32 +495,226 @@ impl TantivyIndexer {
                 .map(|s| s.to_string())
                 .unwrap_or_default();
 
+            let references = doc
+                .get_first(self.references_field)
+                .and_then(|v| v.as_str())
+                .map(|text| {
+                    text.lines()
+                        .filter_map(|entry| {
+                            let (line, name) = entry.split_once(' ')?;
+                            Some((line.parse().ok()?, name.to_string()))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let blocks = doc
+                .get_first(self.block_ranges_field)
+                .and_then(|v| v.as_str())
+                .map(|text| {
+                    text.lines()
+                        .filter_map(|entry| {
+                            let (start, end) = entry.split_once('-')?;
+                            Some(start.parse::<usize>().ok()?.checked_sub(1)?..end.parse().ok()?)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
             results.push(SearchResult {
-                path: PathBuf::from(path),
+                path,
                 content,
                 language,
                 repository,
                 score: _score,
+                references,
+                blocks,
             });
         }
 
         Ok(results)
     }
 
+    /// Identifies the searchable state of the index; changes whenever a commit adds or
+    /// deletes documents
+    pub fn index_version(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for segment in self.get_searcher().segment_readers() {
+            segment.segment_id().uuid_string().hash(&mut hasher);
+            segment.num_deleted_docs().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
     pub async fn get_document_count(&self) -> Result<usize> {
         let searcher = self.get_searcher();
         let count = searcher.num_docs() as usize;
         Ok(count)
     }
+
+    /// Distinct names of every symbol stored in the index, sorted. Built once per
+    /// index version, since it scans every stored document.
+    pub async fn symbol_names(&self) -> Result<Arc<Vec<String>>> {
+        // The reader can also reload on its own after another process commits
+        let version = self.index_version();
+        if let Some((built_at, names)) = self.symbol_names.lock().as_ref()
+            && *built_at == version
+        {
+            return Ok(names.clone());
+        }
+
+        let mut names = std::collections::BTreeSet::new();
+        self.for_each_symbol(|_, name| {
+            names.insert(name.to_string());
+        })?;
+
+        let names = Arc::new(names.into_iter().collect::<Vec<_>>());
+        *self.symbol_names.lock() = Some((version, names.clone()));
+        Ok(names)
+    }
+
+    /// The `n` most frequently defined symbol names, optionally of one kind, with how
+    /// many times each is defined. Ties are ordered by name. The symbols field is
+    /// tokenized text rather than a FAST field, so this scans the stored entries.
+    pub async fn top_symbols(
+        &self,
+        kind: Option<SymbolKind>,
+        n: usize,
+    ) -> Result<Vec<(String, usize)>> {
+        let mut counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        self.for_each_symbol(|symbol_kind, name| {
+            if kind.is_none_or(|k| k.to_str() == symbol_kind) {
+                *counts.entry(name.to_string()).or_default() += 1;
+            }
+        })?;
+
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(n);
+        Ok(counts)
+    }
+
+    /// Call `f(kind, name)` for every symbol entry stored in the index
+    fn for_each_symbol(&self, mut f: impl FnMut(&str, &str)) -> Result<()> {
+        let searcher = self.get_searcher();
+        let addresses = searcher.search(&AllQuery, &DocSetCollector)?;
+
+        for address in addresses {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let Some(symbols) = doc.get_first(self.symbols_field).and_then(|v| v.as_str()) else {
+                continue;
+            };
+
+            // One "<kind> <name>" entry per line
+            for line in symbols.lines() {
+                if let Some((kind, name)) = line.split_once(' ') {
+                    f(kind, name);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// List every repository in the index with its document count, sorted by name.
+    /// Uses a terms aggregation over the FAST `repository` field.
+    pub async fn list_repositories(&self) -> Result<Vec<(String, usize)>> {
+        let aggs: Aggregations = serde_json::from_value(serde_json::json!({
+            "repositories": {
+                "terms": {
+                    "field": "repository",
+                    "size": MAX_LISTED_REPOSITORIES,
+                    "segment_size": MAX_LISTED_REPOSITORIES
+                }
+            }
+        }))?;
+
+        let searcher = self.get_searcher();
+        let collector = AggregationCollector::from_aggs(aggs, Default::default());
+        let results = searcher.search(&AllQuery, &collector)?;
+
+        let mut repositories = match results.0.get("repositories") {
+            Some(AggregationResult::BucketResult(BucketResult::Terms { buckets, .. })) => buckets
+                .iter()
+                .filter_map(|bucket| match &bucket.key {
+                    Key::Str(name) => Some((name.clone(), bucket.doc_count as usize)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        repositories.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(repositories)
+    }
+}
+
+/// Value of the `path` field for `path`. UTF-8 paths are stored as-is; others get
+/// their lossy form (for display) followed by a NUL, which no real path contains, and
+/// the hex of their raw bytes, so distinct paths never share a term.
+fn path_term(path: &Path) -> String {
+    match path.to_str() {
+        Some(path) => path.to_string(),
+        None => {
+            let hex: String = crate::storage::path_key(path)
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect();
+            format!("{}\0{}", path.to_string_lossy(), hex)
+        },
+    }
+}
+
+/// The real path encoded in a `path` field value
+fn path_from_term(term: &str) -> PathBuf {
+    let Some((display, hex)) = term.split_once('\0') else {
+        return PathBuf::from(term);
+    };
+
+    let bytes: Option<Vec<u8>> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect();
+    bytes.map_or_else(|| PathBuf::from(display), |b| crate::storage::key_path(&b))
 }
 
 #[derive(Debug, Clone)]
 pub struct SearchResult {
+    /// The file's real path, which may not be valid UTF-8
     pub path: PathBuf,
     pub content: String,
     pub language: String,
     pub repository: String,
     pub score: f32,
+    /// 0-indexed line and name of each identifier usage, when references are indexed
+    pub references: Vec<(usize, String)>,
+    /// 0-indexed line ranges of the file's blank-line-delimited blocks, when text
+    /// blocks are indexed
+    pub blocks: Vec<Range<usize>>,
+}
+
+/// Line ranges (0-based, end exclusive) of the runs of non-blank lines in `content`
+pub fn text_blocks(content: &str) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut start = None;
+    let mut line_count = 0;
+    for (i, line) in content.lines().enumerate() {
+        line_count = i + 1;
+        match (line.trim().is_empty(), start) {
+            (true, Some(s)) => {
+                blocks.push(s..i);
+                start = None;
+            },
+            (false, None) => start = Some(i),
+            _ => {},
+        }
+    }
+    if let Some(s) = start {
+        blocks.push(s..line_count);
+    }
+    blocks
 }
 
 #[cfg(test)]
@@ -312,11 +745,181 @@ mod tests {
 
         // Search for the document
         let query_parser =
-            tantivy::query::QueryParser::for_index(&indexer._index, vec![indexer.content_field]);
+            tantivy::query::QueryParser::for_index(&indexer.index, vec![indexer.content_field]);
         let query = query_parser.parse_query("main").unwrap();
 
         let results = indexer.search_documents(query.as_ref(), 10).await.unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].path, Path::new("test.rs"));
     }
+
+    #[tokio::test]
+    async fn test_list_repositories() {
+        let temp_dir = tempdir().unwrap();
+        let index_path = temp_dir.path().join("index");
+
+        let indexer = TantivyIndexer::new(&index_path).await.unwrap();
+        indexer
+            .index_file(Path::new("a/main.rs"), "alpha", "fn main() {}")
+            .await
+            .unwrap();
+        indexer
+            .index_file(Path::new("a/lib.rs"), "alpha", "pub fn lib() {}")
+            .await
+            .unwrap();
+        indexer
+            .index_file(Path::new("b/app.py"), "beta", "def app(): pass")
+            .await
+            .unwrap();
+        indexer.commit().await.unwrap();
+
+        let repositories = indexer.list_repositories().await.unwrap();
+        assert_eq!(
+            repositories,
+            vec![("alpha".to_string(), 2), ("beta".to_string(), 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_top_symbols() {
+        let temp_dir = tempdir().unwrap();
+        let index_path = temp_dir.path().join("index");
+
+        let indexer = TantivyIndexer::new(&index_path).await.unwrap();
+        for (path, content) in [
+            ("a.rs", "fn new() {}\nstruct Config;\n"),
+            ("b.rs", "fn new() {}\nfn parse() {}\n"),
+            ("c.rs", "fn new() {}\nfn parse() {}\nstruct Config;\n"),
+        ] {
+            indexer
+                .index_file(Path::new(path), "repo", content)
+                .await
+                .unwrap();
+        }
+        indexer.commit().await.unwrap();
+
+        let names = indexer.symbol_names().await.unwrap();
+        assert_eq!(*names, ["Config", "new", "parse"]);
+        assert!(Arc::ptr_eq(&names, &indexer.symbol_names().await.unwrap()));
+
+        let top = indexer.top_symbols(None, 2).await.unwrap();
+        assert_eq!(top, vec![("new".to_string(), 3), ("Config".to_string(), 2)]);
+
+        let structs = indexer
+            .top_symbols(Some(SymbolKind::Struct), 10)
+            .await
+            .unwrap();
+        assert_eq!(structs, vec![("Config".to_string(), 2)]);
+
+        // A commit invalidates the cached names
+        indexer
+            .index_file(Path::new("d.rs"), "repo", "fn render() {}\n")
+            .await
+            .unwrap();
+        indexer.commit().await.unwrap();
+        let names = indexer.symbol_names().await.unwrap();
+        assert_eq!(*names, ["Config", "new", "parse", "render"]);
+    }
+
+    #[tokio::test]
+    async fn test_minified_file_skips_symbols() {
+        let temp_dir = tempdir().unwrap();
+        let index_path = temp_dir.path().join("index");
+        let indexer = TantivyIndexer::new(&index_path).await.unwrap();
+
+        // One very long line, as produced by a minifier
+        let minified: String = (0..200)
+            .map(|i| format!("function fn{i}(a){{return a+{i}}}"))
+            .collect::<Vec<_>>()
+            .join(";");
+        indexer
+            .index_file(Path::new("bundle.js"), "web", &minified)
+            .await
+            .unwrap();
+        indexer
+            .index_file(
+                Path::new("app.min.js"),
+                "web",
+                "function tiny(a) {\n  return a;\n}\n",
+            )
+            .await
+            .unwrap();
+        indexer.commit().await.unwrap();
+
+        let searcher = indexer.get_searcher();
+        let symbols_parser =
+            tantivy::query::QueryParser::for_index(searcher.index(), vec![indexer.symbols_field]);
+        let content_parser =
+            tantivy::query::QueryParser::for_index(searcher.index(), vec![indexer.content_field]);
+
+        for term in ["fn42", "tiny"] {
+            let query = symbols_parser.parse_query(term).unwrap();
+            let results = indexer.search_documents(query.as_ref(), 10).await.unwrap();
+            assert!(results.is_empty(), "no symbols expected for {}", term);
+
+            let query = content_parser.parse_query(term).unwrap();
+            let results = indexer.search_documents(query.as_ref(), 10).await.unwrap();
+            assert_eq!(results.len(), 1, "content should still match {}", term);
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_non_utf8_paths_keep_separate_documents() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = tempdir().unwrap();
+        let indexer = TantivyIndexer::new(&temp_dir.path().join("index"))
+            .await
+            .unwrap();
+
+        let a = PathBuf::from(std::ffi::OsStr::from_bytes(b"src/caf\xe9.rs"));
+        let b = PathBuf::from(std::ffi::OsStr::from_bytes(b"src/caf\xff.rs"));
+        indexer
+            .index_file(&a, "repo", "fn brew() {}")
+            .await
+            .unwrap();
+        indexer
+            .index_file(&b, "repo", "fn brew() {}")
+            .await
+            .unwrap();
+        indexer.commit().await.unwrap();
+        assert_eq!(indexer.get_document_count().await.unwrap(), 2);
+
+        // Deleting one leaves the other, and results carry the real path
+        indexer.delete_file(&a).await.unwrap();
+        indexer.commit().await.unwrap();
+        assert_eq!(indexer.get_document_count().await.unwrap(), 1);
+
+        let query = tantivy::query::AllQuery;
+        let results = indexer.search_documents(&query, 10).await.unwrap();
+        assert_eq!(results[0].path, b);
+    }
+
+    #[tokio::test]
+    async fn test_schema_change_rebuilds_index() {
+        let temp_dir = tempdir().unwrap();
+        let index_path = temp_dir.path().join("index");
+
+        // An index written before the path_text field existed
+        {
+            std::fs::create_dir_all(&index_path).unwrap();
+            let mut schema_builder = Schema::builder();
+            schema_builder.add_text_field("path", STRING | STORED);
+            Index::create_in_dir(&index_path, schema_builder.build()).unwrap();
+        }
+
+        let indexer = TantivyIndexer::new(&index_path).await.unwrap();
+        assert!(indexer.was_rebuilt());
+        indexer
+            .index_file(Path::new("src/config.rs"), "repo", "struct Config;")
+            .await
+            .unwrap();
+        indexer.commit().await.unwrap();
+        assert_eq!(indexer.get_document_count().await.unwrap(), 1);
+        drop(indexer);
+
+        let indexer = TantivyIndexer::new(&index_path).await.unwrap();
+        assert!(!indexer.was_rebuilt());
+    }
 }
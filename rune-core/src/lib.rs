@@ -12,7 +12,7 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tracing::{info, warn};
 
 pub use error::RuneError;
 
@@ -36,11 +36,225 @@ pub struct Config {
     /// Enable semantic search
     pub enable_semantic: bool,
 
-    /// Languages to support
+    /// Repositories (workspace root directory names) whose files are embedded for
+    /// semantic search. All of them when unset; every repository still gets text and
+    /// symbol indexing.
+    pub semantic_repositories: Option<Vec<String>>,
+
+    /// When a file is re-embedded, skip chunks that are unchanged since its last
+    /// embedding (same content at the same lines), as their vectors are still stored
+    pub incremental_embedding: bool,
+
+    /// Number of files whose chunks are embedded at once during bulk indexing, and of
+    /// ONNX sessions loaded so their inference can run in parallel
+    pub max_concurrent_embeddings: usize,
+
+    /// Rewrite each chunk's leading indentation to a canonical form (four spaces per
+    /// level, tabs expanded) before embedding it, so code that differs only in
+    /// indentation style embeds alike. Stored chunk content keeps the original text.
+    pub normalize_whitespace_for_embedding: bool,
+
+    /// Languages to support. Text formats (markdown, yaml, toml, sql) are only
+    /// indexed when listed here.
     pub languages: Vec<String>,
 
     /// File watching debounce delay in milliseconds
     pub file_watch_debounce_ms: u64,
+
+    /// Number of file batches between coordinated Tantivy/Qdrant flushes
+    pub commit_interval_batches: usize,
+
+    /// Similarity metric for vector search (Qdrant and the in-memory store)
+    pub vector_distance: VectorDistance,
+
+    /// Vector size of the Qdrant collection. Embeddings of any other length are
+    /// rejected before they are sent.
+    pub embedding_dimension: usize,
+
+    /// Where embeddings come from: the bundled ONNX model or an HTTP endpoint. A
+    /// remote model must produce `embedding_dimension`-sized vectors.
+    pub embedding_provider: EmbeddingProvider,
+
+    /// Second embedding model that re-scores the top semantic candidates, e.g.
+    /// `all-mpnet-base-v2`. Reranking is off when unset.
+    pub rerank_model: Option<String>,
+
+    /// How many of the primary model's top candidates the rerank model re-scores
+    pub rerank_candidates: usize,
+
+    /// ONNX Runtime graph optimization level for the embedding model. Lower levels
+    /// load faster at some cost in inference speed.
+    pub onnx_optimization_level: OnnxOptimizationLevel,
+
+    /// Only index files that resolve (following symlinks and `..`) under one of
+    /// `workspace_roots`. Anything else found by the walker or watcher is skipped with
+    /// a warning.
+    pub enforce_root_containment: bool,
+
+    /// Index dotfiles and hidden directories (e.g. `.vscode`, `.github`)
+    pub index_hidden: bool,
+
+    /// Extra `.gitignore`-style patterns, relative to each workspace root, for paths
+    /// that are never indexed or watched. A `!` pattern re-includes a path an earlier
+    /// one in this list excluded.
+    pub ignore_patterns: Vec<String>,
+
+    /// Index files with no detected language as plain text (Tantivy only, no symbols
+    /// or embeddings). Set to false to skip such files entirely.
+    pub index_unknown_languages: bool,
+
+    /// Skip symbol extraction for files whose average line length exceeds this
+    /// (minified bundles). Content is still indexed.
+    pub symbol_extraction_max_avg_line_length: usize,
+
+    /// Glob patterns for generated files that get no symbol extraction
+    pub symbol_extraction_exclude_globs: Vec<String>,
+
+    /// Extra attempts for a failed Qdrant search before reporting it unavailable
+    pub qdrant_search_retries: u32,
+
+    /// Extra attempts for each embedding model file whose download fails with a
+    /// network or server error, before falling back to hash embeddings
+    pub model_download_retries: u32,
+
+    /// Maximum number of searches a `SearchEngine` runs at once (cache hits excluded)
+    pub max_concurrent_searches: usize,
+
+    /// Fail searches beyond `max_concurrent_searches` with `RuneError::Overloaded`
+    /// instead of queueing them
+    pub reject_when_overloaded: bool,
+
+    /// Longest query term or symbol name (in bytes) compared for "did you mean"
+    /// suggestions. Longer tokens, e.g. from minified code, are skipped.
+    pub fuzzy_max_token_len: usize,
+
+    /// How long (in seconds) past its normal expiry a cached semantic response may still
+    /// be served, flagged `SearchResponse::stale`, while the vector store is unreachable.
+    /// 0 disables stale responses.
+    pub semantic_stale_grace_secs: u64,
+
+    /// How long (in seconds) search responses are kept in the metadata store, so they
+    /// survive restarts. 0 keeps the result cache in memory only.
+    pub result_cache_l2_ttl_secs: u64,
+
+    /// Candidates each searcher keeps per query, as a multiple of the query's `limit`,
+    /// unless the query sets `candidate_limit`
+    pub candidate_multiplier: usize,
+
+    /// Multiplier on the number of candidates fetched when a query filters by
+    /// repository or file pattern, so filtered-out matches don't leave the page short
+    pub filter_oversample_factor: usize,
+
+    /// Maximum number of characters in a `SearchResult::body`; longer symbols are cut off
+    pub max_content_chars: usize,
+
+    /// Maximum number of line matches a single file contributes to symbol search results
+    pub max_matches_per_file: usize,
+
+    /// Directory names treated as vendored or generated code by
+    /// `SearchQuery::exclude_vendored`
+    pub vendored_dirs: Vec<String>,
+
+    /// Open an existing cache without write access: search works over the prebuilt
+    /// index, while indexing and watching fail with `RuneError::ReadOnly`
+    pub read_only: bool,
+
+    /// When another instance holds the cache's storage lock, open it as if `read_only`
+    /// were set instead of failing with `RuneError::StorageLocked`. The read-only view
+    /// sees metadata as of when it opened.
+    pub read_only_when_locked: bool,
+
+    /// Record identifier usages (e.g. a type used as a parameter) alongside definitions,
+    /// so `SearchQuery::include_references` can return them. Slows indexing.
+    pub index_references: bool,
+
+    /// Record the blank-line-delimited blocks of plain text and data files, so literal
+    /// and regex searches with `ContextMode::Block` return a match's whole paragraph
+    pub index_text_blocks: bool,
+
+    /// Record TODO/FIXME/HACK/XXX comments while indexing, for `RuneEngine::list_annotations`
+    pub extract_annotations: bool,
+
+    /// How overlapping `reindex` requests are coalesced
+    pub reindex_policy: ReindexPolicy,
+
+    /// Cap on the Tantivy index size in bytes. When an indexing pass leaves the index
+    /// larger than this, files are evicted per `index_eviction_policy`. Unbounded when unset.
+    pub max_index_bytes: Option<u64>,
+
+    /// Which files are evicted first once the index exceeds `max_index_bytes`
+    pub index_eviction_policy: IndexEvictionPolicy,
+
+    /// Keep a copy of each file's content in the Tantivy index. When false content is
+    /// only indexed for search and read from disk when results are built, roughly
+    /// halving the index at the cost of extra file reads. Changing it rebuilds the index.
+    pub store_content: bool,
+
+    /// Share one Tantivy index handle between indexing and search, so searches see
+    /// each commit immediately instead of opening a second, lagging reader
+    pub share_tantivy_index: bool,
+}
+
+/// Distance metric used to compare embeddings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VectorDistance {
+    #[default]
+    Cosine,
+    Dot,
+    Euclidean,
+}
+
+/// What `Indexer::reindex` does when asked to reindex while a pass is already running.
+/// Passes never overlap either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReindexPolicy {
+    /// Wait for the running pass and return when it finishes
+    JoinInFlight,
+    /// Run one more pass once the current one finishes, shared by every request that
+    /// arrives in the meantime, so changes made during the running pass are picked up
+    #[default]
+    QueueFollowUp,
+}
+
+/// Order in which files are dropped from an index that has outgrown `Config::max_index_bytes`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexEvictionPolicy {
+    /// Files that haven't appeared in search results for the longest go first. Files
+    /// never returned by a search count as accessed when they were indexed.
+    #[default]
+    LeastRecentlyAccessed,
+    /// Files indexed longest ago go first
+    Oldest,
+}
+
+/// Source of the embeddings behind semantic search
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EmbeddingProvider {
+    /// ONNX model run in process, with hash-based vectors if it can't be loaded
+    #[default]
+    Local,
+    /// OpenAI-compatible embeddings API. Requests go to `{url}/v1/embeddings`.
+    Remote {
+        url: String,
+        model: String,
+        /// Environment variable holding the API key, sent as a bearer token
+        api_key_env: Option<String>,
+    },
+}
+
+/// Graph optimization applied when loading the ONNX embedding model
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnnxOptimizationLevel {
+    Disable,
+    Level1,
+    Level2,
+    #[default]
+    Level3,
 }
 
 impl Default for Config {
@@ -54,6 +268,10 @@ impl Default for Config {
             max_file_size: 10 * 1024 * 1024, // 10MB
             indexing_threads: num_cpus::get(),
             enable_semantic: true,
+            semantic_repositories: None,
+            normalize_whitespace_for_embedding: false,
+            max_concurrent_embeddings: 4,
+            incremental_embedding: true,
             languages: vec![
                 "rust".to_string(),
                 "javascript".to_string(),
@@ -62,15 +280,199 @@ impl Default for Config {
                 "go".to_string(),
                 "java".to_string(),
                 "cpp".to_string(),
+                "markdown".to_string(),
+                "yaml".to_string(),
+                "toml".to_string(),
+                "sql".to_string(),
             ],
             file_watch_debounce_ms: 500, // Default 500ms debounce
+            commit_interval_batches: 10,
+            vector_distance: VectorDistance::Cosine,
+            embedding_dimension: 384,
+            embedding_provider: EmbeddingProvider::Local,
+            onnx_optimization_level: OnnxOptimizationLevel::Level3,
+            rerank_model: None,
+            rerank_candidates: 50,
+            enforce_root_containment: true,
+            index_hidden: false,
+            ignore_patterns: Vec::new(),
+            index_unknown_languages: true,
+            symbol_extraction_max_avg_line_length: 250,
+            symbol_extraction_exclude_globs: vec![
+                "*.min.js".to_string(),
+                "*.min.css".to_string(),
+                "*.pb.go".to_string(),
+                "*_pb2.py".to_string(),
+                "*.generated.*".to_string(),
+            ],
+            qdrant_search_retries: 2,
+            model_download_retries: 3,
+            max_concurrent_searches: 16,
+            reject_when_overloaded: false,
+            max_matches_per_file: 20,
+            semantic_stale_grace_secs: 0,
+            result_cache_l2_ttl_secs: 0,
+            candidate_multiplier: search::DEFAULT_CANDIDATE_MULTIPLIER,
+            filter_oversample_factor: 4,
+            max_content_chars: 10_000,
+            fuzzy_max_token_len: search::fuzzy::DEFAULT_MAX_TOKEN_LEN,
+            vendored_dirs: vec![
+                "node_modules".to_string(),
+                "vendor".to_string(),
+                "target".to_string(),
+                "dist".to_string(),
+                "build".to_string(),
+                ".venv".to_string(),
+            ],
+            share_tantivy_index: false,
+            read_only: false,
+            index_references: false,
+            index_text_blocks: false,
+            extract_annotations: true,
+            read_only_when_locked: false,
+            reindex_policy: ReindexPolicy::QueueFollowUp,
+            max_index_bytes: None,
+            store_content: true,
+            index_eviction_policy: IndexEvictionPolicy::LeastRecentlyAccessed,
+        }
+    }
+}
+
+impl Config {
+    /// Start building a config from the defaults
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Check invariants that the engine relies on
+    pub fn validate(&self) -> error::Result<()> {
+        let invalid = |reason: &str| Err(RuneError::InvalidConfig(reason.to_string()));
+
+        if self.workspace_roots.is_empty() {
+            return invalid("workspace_roots must not be empty");
+        }
+        if self.indexing_threads == 0 {
+            return invalid("indexing_threads must be at least 1");
         }
+        if self.max_file_size == 0 {
+            return invalid("max_file_size must be greater than 0");
+        }
+        if self.commit_interval_batches == 0 {
+            return invalid("commit_interval_batches must be at least 1");
+        }
+        if self.max_concurrent_searches == 0 {
+            return invalid("max_concurrent_searches must be at least 1");
+        }
+        if self.embedding_dimension == 0 {
+            return invalid("embedding_dimension must be greater than 0");
+        }
+        if self.max_index_bytes == Some(0) {
+            return invalid("max_index_bytes must be greater than 0");
+        }
+        if let EmbeddingProvider::Remote { url, model, .. } = &self.embedding_provider
+            && (url.is_empty() || model.is_empty())
+        {
+            return invalid("remote embedding_provider needs a url and a model");
+        }
+        let mut patterns = ignore::gitignore::GitignoreBuilder::new("");
+        for pattern in &self.ignore_patterns {
+            if let Err(e) = patterns.add_line(None, pattern) {
+                return invalid(&format!("invalid ignore pattern {:?}: {}", pattern, e));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Fluent construction of a validated `Config`. Unset fields keep their defaults.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// Set the workspace roots; `workspace_dir` follows the first one
+    pub fn workspace_roots(mut self, roots: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        self.config.workspace_roots = roots.into_iter().map(Into::into).collect();
+        if let Some(first) = self.config.workspace_roots.first() {
+            self.config.workspace_dir = first.to_string_lossy().to_string();
+        }
+        self
+    }
+
+    pub fn cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.config.cache_dir = cache_dir.into();
+        self
+    }
+
+    pub fn max_file_size(mut self, bytes: usize) -> Self {
+        self.config.max_file_size = bytes;
+        self
+    }
+
+    pub fn indexing_threads(mut self, threads: usize) -> Self {
+        self.config.indexing_threads = threads;
+        self
+    }
+
+    pub fn enable_semantic(mut self, enable: bool) -> Self {
+        self.config.enable_semantic = enable;
+        self
+    }
+
+    pub fn languages(mut self, languages: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.languages = languages.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn file_watch_debounce_ms(mut self, ms: u64) -> Self {
+        self.config.file_watch_debounce_ms = ms;
+        self
+    }
+
+    pub fn commit_interval_batches(mut self, batches: usize) -> Self {
+        self.config.commit_interval_batches = batches;
+        self
+    }
+
+    pub fn vector_distance(mut self, distance: VectorDistance) -> Self {
+        self.config.vector_distance = distance;
+        self
+    }
+
+    pub fn index_hidden(mut self, index_hidden: bool) -> Self {
+        self.config.index_hidden = index_hidden;
+        self
+    }
+
+    pub fn index_unknown_languages(mut self, index: bool) -> Self {
+        self.config.index_unknown_languages = index;
+        self
+    }
+
+    pub fn ignore_patterns(
+        mut self,
+        patterns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.config.ignore_patterns = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn max_concurrent_searches(mut self, max: usize) -> Self {
+        self.config.max_concurrent_searches = max;
+        self
+    }
+
+    /// Validate and return the config, or `RuneError::InvalidConfig`
+    pub fn build(self) -> error::Result<Config> {
+        self.config.validate()?;
+        Ok(self.config)
     }
 }
 
 /// Main engine for the Rune code search system
 pub struct RuneEngine {
-    #[allow(dead_code)]
     config: Arc<Config>,
     search_engine: search::SearchEngine,
     indexer: indexing::Indexer,
@@ -85,16 +487,79 @@ impl RuneEngine {
             config.workspace_roots.len()
         );
 
-        let config = Arc::new(config);
+        let mut config = config;
 
         // Initialize storage backend
-        let storage = storage::StorageBackend::new(&config.cache_dir).await?;
+        let storage = if config.read_only {
+            storage::StorageBackend::open_read_only(&config.cache_dir).await?
+        } else {
+            match storage::StorageBackend::new(&config.cache_dir).await {
+                Err(e)
+                    if config.read_only_when_locked
+                        && matches!(
+                            e.downcast_ref::<RuneError>(),
+                            Some(RuneError::StorageLocked { .. })
+                        ) =>
+                {
+                    warn!("{}; continuing read-only", e);
+                    config.read_only = true;
+                    storage::StorageBackend::open_read_only(&config.cache_dir).await?
+                },
+                result => result?,
+            }
+        };
+
+        let config = Arc::new(config);
 
-        // Initialize search engine
-        let search_engine = search::SearchEngine::new(config.clone(), storage.clone()).await?;
+        let (search_engine, mut indexer) = if config.read_only {
+            // Both sides share the writer-less index; the indexer only rejects writes
+            let index_path = config.cache_dir.join("tantivy_index");
+            let tantivy_indexer = Arc::new(
+                indexing::tantivy_indexer::TantivyIndexer::new_read_only(
+                    &index_path,
+                    config.store_content,
+                )
+                .await?,
+            );
+            let indexer = indexing::Indexer::with_tantivy_indexer(
+                config.clone(),
+                storage.clone(),
+                tantivy_indexer.clone(),
+            )
+            .await?;
+            let search_engine = search::SearchEngine::with_tantivy_indexer(
+                config.clone(),
+                storage.clone(),
+                tantivy_indexer,
+            )
+            .await?;
+            (search_engine, indexer)
+        } else if config.share_tantivy_index {
+            // Open the writer first so a schema rebuild happens before anything reads
+            let tantivy_indexer = indexing::Indexer::open_tantivy_indexer(&config).await?;
+            let indexer = indexing::Indexer::with_tantivy_indexer(
+                config.clone(),
+                storage.clone(),
+                tantivy_indexer.clone(),
+            )
+            .await?;
+            let search_engine = search::SearchEngine::with_tantivy_indexer(
+                config.clone(),
+                storage.clone(),
+                tantivy_indexer,
+            )
+            .await?;
+            (search_engine, indexer)
+        } else {
+            // Initialize search engine
+            let search_engine = search::SearchEngine::new(config.clone(), storage.clone()).await?;
 
-        // Initialize indexer
-        let indexer = indexing::Indexer::new(config.clone(), storage.clone()).await?;
+            // Initialize indexer, reloading the search engine's reader as it commits
+            let mut indexer = indexing::Indexer::new(config.clone(), storage.clone()).await?;
+            indexer.set_search_index(search_engine.tantivy_indexer());
+            (search_engine, indexer)
+        };
+        indexer.set_result_cache(search_engine.result_cache());
 
         Ok(Self {
             config,
@@ -105,13 +570,22 @@ impl RuneEngine {
     }
 
     /// Start the engine (begins file watching and indexing)
+    ///
+    /// This is the single source of truth for the initial index: callers should not
+    /// follow `start` with `reindex`. Watchers are started before indexing so that
+    /// changes made during the initial pass are not missed.
     pub async fn start(&mut self) -> Result<()> {
+        if self.config.read_only {
+            info!("[ENGINE START] Read-only engine, serving the existing index");
+            return Ok(());
+        }
+
         info!("[ENGINE START] Starting Rune engine - will trigger initial indexing");
 
-        // Start file watcher
+        // Start file watcher first so no events are missed during the initial pass
         self.indexer.start_watching().await?;
 
-        // Initial index of workspace
+        // Initial index of workspace (the only indexing pass on startup)
         info!("[ENGINE START] Triggering initial workspace indexing");
         self.indexer.index_workspaces().await?;
 
@@ -141,23 +615,122 @@ impl RuneEngine {
         self.indexer.is_watching()
     }
 
-    /// Get engine statistics
-    pub async fn stats(&self) -> Result<EngineStats> {
+    /// List known repositories with their indexed document counts
+    pub async fn list_repositories(&self) -> Result<Vec<(String, usize)>> {
+        self.indexer.list_repositories().await
+    }
+
+    /// The `n` most frequently defined symbol names, optionally of one kind
+    pub async fn top_symbols(
+        &self,
+        kind: Option<indexing::symbol_extractor::SymbolKind>,
+        n: usize,
+    ) -> Result<Vec<(String, usize)>> {
+        self.indexer.top_symbols(kind, n).await
+    }
+
+    /// TODO/FIXME/HACK/XXX comments across indexed files, optionally of one kind
+    pub async fn list_annotations(
+        &self,
+        kind: Option<indexing::annotations::AnnotationKind>,
+    ) -> Result<Vec<indexing::annotations::Annotation>> {
+        self.indexer.list_annotations(kind).await
+    }
+
+    /// Number of documents in the Tantivy index
+    pub async fn document_count(&self) -> Result<usize> {
+        self.indexer.document_count().await
+    }
+
+    /// Find the chunks nearest to a precomputed embedding, e.g. one produced by another
+    /// embedding service. The vector must have `Config::embedding_dimension` entries.
+    #[cfg(feature = "semantic")]
+    pub async fn search_by_embedding(
+        &self,
+        vector: Vec<f32>,
+        limit: usize,
+        filter: Option<embedding::VectorFilter>,
+    ) -> Result<Vec<search::SearchResult>> {
+        self.search_engine
+            .search_by_embedding(vector, limit, filter)
+            .await
+    }
+
+    /// Estimate how much memory the in-memory caches are using
+    pub fn memory_report(&self) -> MemoryReport {
+        let (search_entries, search_bytes) = self.search_engine.embedding_cache_usage();
+        let (index_entries, index_bytes) = self.indexer.embedding_cache_usage();
+        let (l1_cache_entries, l1_cache_estimated_bytes) = self.search_engine.result_cache_usage();
+
+        MemoryReport {
+            embedding_cache_bytes: search_bytes + index_bytes,
+            embedding_cache_entries: search_entries + index_entries,
+            l1_cache_entries,
+            l1_cache_estimated_bytes,
+        }
+    }
+
+    /// Get engine statistics. Directory sizes come from a periodically refreshed
+    /// cache; pass `refresh` to rescan the index and cache directories now.
+    pub async fn stats(&self, refresh: bool) -> Result<EngineStats> {
+        let sizes = self.storage.get_directory_sizes(refresh).await?;
         Ok(EngineStats {
             indexed_files: self.storage.get_file_count().await?,
+            tantivy_documents: self.document_count().await?,
             total_symbols: self.storage.get_symbol_count().await?,
-            index_size_bytes: self.storage.get_index_size().await?,
-            cache_size_bytes: self.storage.get_cache_size().await?,
+            index_size_bytes: sizes.index_bytes,
+            cache_size_bytes: sizes.cache_bytes,
+            embedding_fallback: self.embedding_status().fallback,
+            file_watching_active: self.is_watching(),
         })
     }
+
+    /// Which embedding model backs semantic search. `fallback` means the model
+    /// couldn't be loaded and semantic results are degraded.
+    pub fn embedding_status(&self) -> EmbeddingStatus {
+        self.search_engine.embedding_status()
+    }
+}
+
+/// Estimated memory held by the engine's in-memory caches. Tantivy's reader caches
+/// and the ONNX session aren't included; their size depends on the index and model
+/// rather than on usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryReport {
+    /// Cached embedding vectors and their content-hash keys, indexing and search combined
+    pub embedding_cache_bytes: usize,
+    pub embedding_cache_entries: usize,
+    /// Cached search responses
+    pub l1_cache_entries: usize,
+    pub l1_cache_estimated_bytes: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EngineStats {
+    /// Files with stored metadata
     pub indexed_files: usize,
+    /// Documents in the Tantivy index; differs from `indexed_files` if the stores drift
+    pub tantivy_documents: usize,
     pub total_symbols: usize,
     pub index_size_bytes: u64,
     pub cache_size_bytes: u64,
+    /// Semantic search runs on hash-based fallback embeddings, see `EmbeddingStatus`
+    #[serde(default)]
+    pub embedding_fallback: bool,
+    #[serde(default)]
+    pub file_watching_active: bool,
+}
+
+/// State of the embedding model behind semantic search
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingStatus {
+    /// The model loaded and produces real embeddings
+    pub model_loaded: bool,
+    /// The model failed to load, so vectors are derived from a content hash. They
+    /// carry no meaning, and semantic results are little better than arbitrary.
+    pub fallback: bool,
+    pub dimension: usize,
+    pub model_name: String,
 }
 
 #[cfg(test)]
@@ -165,6 +738,65 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_config_builder() {
+        let config = Config::builder()
+            .workspace_roots(["/tmp/project"])
+            .cache_dir("/tmp/project/.rune_cache")
+            .indexing_threads(2)
+            .enable_semantic(false)
+            .build()
+            .unwrap();
+        assert_eq!(config.workspace_dir, "/tmp/project");
+        assert_eq!(config.indexing_threads, 2);
+        assert!(!config.enable_semantic);
+        assert_eq!(config.max_file_size, Config::default().max_file_size);
+
+        let err = Config::builder().indexing_threads(0).build().unwrap_err();
+        assert!(matches!(err, RuneError::InvalidConfig(ref m) if m.contains("indexing_threads")));
+
+        let err = Config::builder()
+            .workspace_roots(Vec::<PathBuf>::new())
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, RuneError::InvalidConfig(ref m) if m.contains("workspace_roots")));
+
+        let err = Config::builder()
+            .ignore_patterns(["build/", "{unclosed"])
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, RuneError::InvalidConfig(ref m) if m.contains("{unclosed")));
+
+        let config = Config {
+            embedding_provider: EmbeddingProvider::Remote {
+                url: "https://api.example.com".to_string(),
+                model: String::new(),
+                api_key_env: None,
+            },
+            ..Default::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, RuneError::InvalidConfig(ref m) if m.contains("embedding_provider")));
+    }
+
+    #[test]
+    fn test_file_watch_debounce_config() {
+        assert_eq!(Config::default().file_watch_debounce_ms, 500);
+
+        let config = Config::builder()
+            .workspace_roots(["/tmp/project"])
+            .file_watch_debounce_ms(1_250)
+            .build()
+            .unwrap();
+        assert_eq!(config.file_watch_debounce_ms, 1_250);
+
+        let config = Config {
+            file_watch_debounce_ms: 50,
+            ..Default::default()
+        };
+        assert_eq!(config.file_watch_debounce_ms, 50);
+    }
+
     #[tokio::test]
     async fn test_engine_creation() {
         let tmp_dir = tempdir().unwrap();
@@ -181,4 +813,499 @@ mod tests {
         }
         assert!(engine.is_ok());
     }
+
+    #[cfg(feature = "semantic")]
+    #[tokio::test]
+    async fn test_embedding_status_reports_fallback() {
+        let tmp_dir = tempdir().unwrap();
+        let cache_dir = tmp_dir.path().join(".cache");
+
+        // A file where the model directory belongs makes the download fail up front,
+        // without going to the network
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        std::fs::write(cache_dir.join("models"), "").unwrap();
+
+        let config = Config {
+            workspace_roots: vec![tmp_dir.path().to_path_buf()],
+            cache_dir,
+            enable_semantic: false,
+            embedding_dimension: 512,
+            ..Default::default()
+        };
+        let engine = RuneEngine::new(config).await.unwrap();
+
+        let status = engine.embedding_status();
+        assert!(status.fallback);
+        assert!(!status.model_loaded);
+        assert_eq!(status.dimension, 512);
+        assert_eq!(status.model_name, embedding::model_manager::MODEL_NAME);
+        assert!(engine.stats(false).await.unwrap().embedding_fallback);
+    }
+
+    #[tokio::test]
+    async fn test_start_indexes_once() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("main.rs"), "fn main() {}").unwrap();
+
+        let config = Config {
+            workspace_roots: vec![workspace],
+            cache_dir: tmp_dir.path().join(".cache"),
+            enable_semantic: false,
+            ..Default::default()
+        };
+
+        let mut engine = RuneEngine::new(config).await.unwrap();
+        engine.start().await.unwrap();
+
+        // A single start must produce exactly one full indexing pass
+        assert_eq!(engine.indexer().index_pass_count(), 1);
+
+        engine.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_document_count_matches_indexed_files() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("main.rs"), "fn main() {}").unwrap();
+        std::fs::write(workspace.join("util.py"), "def util(): pass").unwrap();
+
+        let config = Config {
+            workspace_roots: vec![workspace],
+            cache_dir: tmp_dir.path().join(".cache"),
+            enable_semantic: false,
+            ..Default::default()
+        };
+
+        let mut engine = RuneEngine::new(config).await.unwrap();
+        engine.start().await.unwrap();
+
+        let stats = engine.stats(true).await.unwrap();
+        assert_eq!(stats.indexed_files, 2);
+        assert_eq!(stats.tantivy_documents, stats.indexed_files);
+        assert_eq!(engine.document_count().await.unwrap(), 2);
+
+        engine.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stats_serves_cached_directory_sizes() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("main.rs"), "fn main() {}").unwrap();
+
+        let cache_dir = tmp_dir.path().join(".cache");
+        let config = Config {
+            workspace_roots: vec![workspace],
+            cache_dir: cache_dir.clone(),
+            enable_semantic: false,
+            ..Default::default()
+        };
+
+        let mut engine = RuneEngine::new(config).await.unwrap();
+        engine.start().await.unwrap();
+
+        let refreshed = engine.stats(true).await.unwrap();
+        let scans = engine.storage.size_scan_count();
+        assert_eq!(
+            refreshed.cache_size_bytes,
+            engine.storage.get_cache_size().await.unwrap()
+        );
+        assert_eq!(
+            refreshed.index_size_bytes,
+            engine.storage.get_index_size().await.unwrap()
+        );
+
+        // Serialized in field order, so clients can snapshot the JSON
+        let keys = |stats: &EngineStats| -> Vec<String> {
+            let json = serde_json::to_string(stats).unwrap();
+            json.trim_matches(['{', '}'])
+                .split(',')
+                .map(|field| {
+                    field
+                        .split(':')
+                        .next()
+                        .unwrap()
+                        .trim_matches('"')
+                        .to_string()
+                })
+                .collect()
+        };
+        assert_eq!(
+            keys(&refreshed),
+            [
+                "indexed_files",
+                "tantivy_documents",
+                "total_symbols",
+                "index_size_bytes",
+                "cache_size_bytes",
+                "embedding_fallback",
+                "file_watching_active",
+            ]
+        );
+        assert_eq!(keys(&engine.stats(false).await.unwrap()), keys(&refreshed));
+        assert!(refreshed.file_watching_active);
+
+        // Growing the cache dir isn't visible until an explicit refresh
+        std::fs::write(cache_dir.join("padding.bin"), vec![0u8; 4096]).unwrap();
+        for _ in 0..3 {
+            let cached = engine.stats(false).await.unwrap();
+            assert_eq!(cached.cache_size_bytes, refreshed.cache_size_bytes);
+        }
+        assert_eq!(engine.storage.size_scan_count(), scans);
+
+        let refreshed = engine.stats(true).await.unwrap();
+        assert_eq!(engine.storage.size_scan_count(), scans + 1);
+        assert_eq!(
+            refreshed.cache_size_bytes,
+            engine.storage.get_cache_size().await.unwrap()
+        );
+
+        engine.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shared_tantivy_index_sees_new_documents() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("first.rs"), "fn first_handler() {}").unwrap();
+
+        let config = Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: tmp_dir.path().join(".cache"),
+            enable_semantic: false,
+            share_tantivy_index: true,
+            ..Default::default()
+        };
+
+        let engine = RuneEngine::new(config).await.unwrap();
+        let symbol_query = |query: &str| search::SearchQuery {
+            query: query.to_string(),
+            mode: search::SearchMode::Symbol,
+            ..Default::default()
+        };
+
+        engine.indexer().index_workspaces().await.unwrap();
+        let response = engine.search().search(symbol_query("first_handler")).await;
+        assert_eq!(response.unwrap().results.len(), 1);
+
+        // A file indexed later is searchable as soon as the indexing pass returns
+        std::fs::write(workspace.join("second.rs"), "fn second_handler() {}").unwrap();
+        engine.indexer().index_workspaces().await.unwrap();
+        let response = engine.search().search(symbol_query("second_handler")).await;
+        assert_eq!(response.unwrap().results.len(), 1);
+        assert_eq!(engine.document_count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_index_single_file_skips_debounce() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("main.rs"), "fn main() {}").unwrap();
+
+        let config = Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: tmp_dir.path().join(".cache"),
+            enable_semantic: false,
+            share_tantivy_index: true,
+            // Far longer than the test runs
+            file_watch_debounce_ms: 60_000,
+            ..Default::default()
+        };
+
+        let mut engine = RuneEngine::new(config).await.unwrap();
+        engine.start().await.unwrap();
+        let symbol_query = |query: &str| search::SearchQuery {
+            query: query.to_string(),
+            mode: search::SearchMode::Symbol,
+            bypass_cache: true,
+            ..Default::default()
+        };
+
+        let saved = workspace.join("saved.rs");
+        std::fs::write(&saved, "fn saved_in_editor() {}").unwrap();
+        engine.indexer().index_single_file(&saved).await.unwrap();
+        let response = engine
+            .search()
+            .search(symbol_query("saved_in_editor"))
+            .await;
+        assert_eq!(response.unwrap().results.len(), 1);
+
+        // Saving a deleted file drops it
+        std::fs::remove_file(&saved).unwrap();
+        engine.indexer().index_single_file(&saved).await.unwrap();
+        let response = engine
+            .search()
+            .search(symbol_query("saved_in_editor"))
+            .await;
+        assert!(response.unwrap().results.is_empty());
+
+        engine.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_index_single_file_searchable_with_default_config() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("main.rs"), "fn main() {}").unwrap();
+
+        // The search engine reads through its own handle on the index
+        let config = Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: tmp_dir.path().join(".cache"),
+            enable_semantic: false,
+            file_watch_debounce_ms: 60_000,
+            ..Default::default()
+        };
+        assert!(!config.share_tantivy_index);
+
+        let mut engine = RuneEngine::new(config).await.unwrap();
+        engine.start().await.unwrap();
+        let symbol_query = || search::SearchQuery {
+            query: "saved_in_editor".to_string(),
+            mode: search::SearchMode::Symbol,
+            ..Default::default()
+        };
+
+        // Cache an empty response before the file exists
+        engine.search().search(symbol_query()).await.unwrap();
+        let response = engine.search().search(symbol_query()).await.unwrap();
+        assert_eq!(response.from_cache, Some(true));
+        assert!(response.results.is_empty());
+
+        let saved = workspace.join("saved.rs");
+        std::fs::write(&saved, "fn saved_in_editor() {}").unwrap();
+        engine.indexer().index_single_file(&saved).await.unwrap();
+        let response = engine.search().search(symbol_query()).await.unwrap();
+        assert_eq!(response.results.len(), 1);
+
+        std::fs::remove_file(&saved).unwrap();
+        engine.indexer().index_single_file(&saved).await.unwrap();
+        let response = engine.search().search(symbol_query()).await.unwrap();
+        assert!(response.results.is_empty());
+
+        engine.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_index_single_file_applies_walker_rules() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir_all(workspace.join(".hidden")).unwrap();
+        std::fs::create_dir_all(workspace.join("gen")).unwrap();
+        std::fs::write(workspace.join(".gitignore"), "ignored.rs\n").unwrap();
+
+        let config = Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: tmp_dir.path().join(".cache"),
+            enable_semantic: false,
+            ignore_patterns: vec!["gen/".to_string()],
+            max_file_size: 1024,
+            index_unknown_languages: true,
+            ..Default::default()
+        };
+
+        let engine = RuneEngine::new(config).await.unwrap();
+        let skipped = [
+            ("ignored.rs", b"fn skipped() {}".to_vec()),
+            ("gen/out.rs", b"fn skipped() {}".to_vec()),
+            (".hidden/secret.rs", b"fn skipped() {}".to_vec()),
+            (
+                "big.rs",
+                format!("fn skipped() {{}}\n{}", "// pad\n".repeat(200)).into_bytes(),
+            ),
+            ("blob.dat", b"fn skipped() {}\0\x01".to_vec()),
+        ];
+        for (name, content) in skipped {
+            let path = workspace.join(name);
+            std::fs::write(&path, content).unwrap();
+            engine.indexer().index_single_file(&path).await.unwrap();
+        }
+        assert_eq!(engine.document_count().await.unwrap(), 0);
+
+        let kept = workspace.join("kept.rs");
+        std::fs::write(&kept, "fn kept() {}").unwrap();
+        engine.indexer().index_single_file(&kept).await.unwrap();
+        assert_eq!(engine.document_count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reindexed_file_invalidates_cached_results() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        let edited = workspace.join("edited.rs");
+        std::fs::write(&edited, "fn cached_handler() {}").unwrap();
+
+        let config = Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: tmp_dir.path().join(".cache"),
+            enable_semantic: false,
+            share_tantivy_index: true,
+            ..Default::default()
+        };
+
+        let engine = RuneEngine::new(config).await.unwrap();
+        engine.indexer().index_workspaces().await.unwrap();
+        let symbol_query = || search::SearchQuery {
+            query: "cached_handler".to_string(),
+            mode: search::SearchMode::Symbol,
+            ..Default::default()
+        };
+
+        engine.search().search(symbol_query()).await.unwrap();
+        let response = engine.search().search(symbol_query()).await.unwrap();
+        assert_eq!(response.from_cache, Some(true));
+
+        std::fs::write(&edited, "fn cached_handler() {}\nfn added() {}").unwrap();
+        engine.indexer().index_single_file(&edited).await.unwrap();
+        let response = engine.search().search(symbol_query()).await.unwrap();
+        assert_eq!(response.from_cache, Some(false));
+        assert_eq!(response.results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_edited_file_matching_cached_query_invalidates_it() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("cached.rs"), "fn cached_handler() {}").unwrap();
+        let edited = workspace.join("edited.rs");
+        std::fs::write(&edited, "fn unrelated() {}").unwrap();
+
+        let config = Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: tmp_dir.path().join(".cache"),
+            enable_semantic: false,
+            ..Default::default()
+        };
+
+        let engine = RuneEngine::new(config).await.unwrap();
+        engine.indexer().index_workspaces().await.unwrap();
+        let symbol_query = || search::SearchQuery {
+            query: "cached_handler".to_string(),
+            mode: search::SearchMode::Symbol,
+            ..Default::default()
+        };
+
+        engine.search().search(symbol_query()).await.unwrap();
+        let response = engine.search().search(symbol_query()).await.unwrap();
+        assert_eq!(response.from_cache, Some(true));
+        assert_eq!(response.results.len(), 1);
+
+        // None of the cached results cite the edited file, which now matches too
+        std::fs::write(&edited, "fn unrelated() {}\nfn cached_handler() {}").unwrap();
+        engine.indexer().index_single_file(&edited).await.unwrap();
+        let response = engine.search().search(symbol_query()).await.unwrap();
+        assert_eq!(response.from_cache, Some(false));
+        assert_eq!(response.results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_engine_searches_prebuilt_index() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("lib.rs"), "fn prebuilt_handler() {}").unwrap();
+
+        let config = Config {
+            workspace_roots: vec![workspace],
+            cache_dir: tmp_dir.path().join(".cache"),
+            enable_semantic: false,
+            ..Default::default()
+        };
+
+        // Build the index, then release it
+        {
+            let mut engine = RuneEngine::new(config.clone()).await.unwrap();
+            engine.start().await.unwrap();
+            engine.stop().await.unwrap();
+        }
+
+        let mut engine = RuneEngine::new(Config {
+            read_only: true,
+            ..config
+        })
+        .await
+        .unwrap();
+        engine.start().await.unwrap();
+
+        let response = engine
+            .search()
+            .search(search::SearchQuery {
+                query: "prebuilt_handler".to_string(),
+                mode: search::SearchMode::Symbol,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.results.len(), 1);
+        assert_eq!(engine.stats(true).await.unwrap().indexed_files, 1);
+
+        let err = engine.indexer().reindex().await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RuneError>(),
+            Some(RuneError::ReadOnly(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_second_engine_on_locked_storage() {
+        let tmp_dir = tempdir().unwrap();
+        let workspace = tmp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(workspace.join("lib.rs"), "fn shared_handler() {}").unwrap();
+
+        let config = Config {
+            workspace_roots: vec![workspace],
+            cache_dir: tmp_dir.path().join(".cache"),
+            enable_semantic: false,
+            ..Default::default()
+        };
+
+        let mut first = RuneEngine::new(config.clone()).await.unwrap();
+        first.start().await.unwrap();
+
+        let err = RuneEngine::new(config.clone()).await.err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<RuneError>(),
+            Some(RuneError::StorageLocked { .. })
+        ));
+
+        // With the fallback the second engine serves the first one's index
+        let second = RuneEngine::new(Config {
+            read_only_when_locked: true,
+            ..config
+        })
+        .await
+        .unwrap();
+        let response = second
+            .search()
+            .search(search::SearchQuery {
+                query: "shared_handler".to_string(),
+                mode: search::SearchMode::Symbol,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.results.len(), 1);
+        assert!(matches!(
+            second
+                .indexer()
+                .reindex()
+                .await
+                .unwrap_err()
+                .downcast_ref::<RuneError>(),
+            Some(RuneError::ReadOnly(_))
+        ));
+
+        first.stop().await.unwrap();
+    }
 }
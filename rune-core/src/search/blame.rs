@@ -0,0 +1,122 @@
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::debug;
+
+use super::SearchResult;
+
+/// Lines a single commit last touched
+#[derive(Debug, Clone)]
+struct BlameHunk {
+    /// 1-based first line in the working tree file
+    start_line: usize,
+    lines: usize,
+    author: String,
+    commit: String,
+}
+
+/// Blame of one file, valid while its size and modification time are unchanged
+struct FileBlame {
+    modified: Option<SystemTime>,
+    len: u64,
+    /// Empty when the file isn't in a git repository
+    hunks: Arc<Vec<BlameHunk>>,
+}
+
+/// Per-file `git blame` results, so each file is blamed once however many of its
+/// lines match
+#[derive(Default)]
+pub struct BlameCache {
+    files: DashMap<PathBuf, FileBlame>,
+}
+
+impl BlameCache {
+    /// Set `last_author` and `last_commit` on each result from the blame of its line
+    pub async fn annotate(&self, mut results: Vec<SearchResult>) -> Vec<SearchResult> {
+        let mut file_hunks: HashMap<PathBuf, Arc<Vec<BlameHunk>>> = HashMap::new();
+
+        for result in &mut results {
+            if !file_hunks.contains_key(&result.file_path) {
+                let hunks = self.file_hunks(&result.file_path).await;
+                file_hunks.insert(result.file_path.clone(), hunks);
+            }
+
+            let hunk = file_hunks[&result.file_path].iter().find(|hunk| {
+                (hunk.start_line..hunk.start_line + hunk.lines).contains(&result.line_number)
+            });
+            if let Some(hunk) = hunk {
+                result.last_author = Some(hunk.author.clone());
+                result.last_commit = Some(hunk.commit.clone());
+            }
+        }
+
+        results
+    }
+
+    async fn file_hunks(&self, path: &Path) -> Arc<Vec<BlameHunk>> {
+        let metadata = tokio::fs::metadata(path).await.ok();
+        let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+        let len = metadata.as_ref().map_or(0, |m| m.len());
+
+        if let Some(cached) = self.files.get(path)
+            && cached.modified == modified
+            && cached.len == len
+        {
+            return cached.hunks.clone();
+        }
+
+        let owned = path.to_path_buf();
+        let hunks = match tokio::task::spawn_blocking(move || blame_file(&owned)).await {
+            Ok(Ok(hunks)) => hunks,
+            Ok(Err(e)) => {
+                debug!("No blame for {:?}: {}", path, e);
+                Vec::new()
+            },
+            Err(e) => {
+                debug!("Blame task for {:?} failed: {}", path, e);
+                Vec::new()
+            },
+        };
+
+        let hunks = Arc::new(hunks);
+        self.files.insert(
+            path.to_path_buf(),
+            FileBlame {
+                modified,
+                len,
+                hunks: hunks.clone(),
+            },
+        );
+        hunks
+    }
+}
+
+/// Blame the file as it is on disk, so line numbers match the index. Uncommitted
+/// lines are left out.
+fn blame_file(path: &Path) -> Result<Vec<BlameHunk>> {
+    let path = path.canonicalize()?;
+    let repo = git2::Repository::discover(&path)?;
+    let workdir = repo
+        .workdir()
+        .context("repository has no working tree")?
+        .canonicalize()?;
+    let relative = path.strip_prefix(&workdir)?;
+
+    let content = std::fs::read(&path)?;
+    let committed = repo.blame_file(relative, None)?;
+    let blame = committed.blame_buffer(&content)?;
+
+    Ok(blame
+        .iter()
+        .filter(|hunk| !hunk.final_commit_id().is_zero())
+        .map(|hunk| BlameHunk {
+            start_line: hunk.final_start_line(),
+            lines: hunk.lines_in_hunk(),
+            author: String::from_utf8_lossy(hunk.final_signature().name_bytes()).into_owned(),
+            commit: hunk.final_commit_id().to_string(),
+        })
+        .collect())
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use anyhow::Result;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::RuneError;
+
+/// `SearchQuery::file_patterns` compiled once and applied the same way by every searcher.
+///
+/// - Globs containing `/` (`src/**/*.ts`, `**/test_*.py`) match the whole path relative
+///   to its workspace root; `*` stops at `/` while `**` spans directories.
+/// - Globs without `/` (`*.rs`, `[ab].go`) match the file name, at any depth.
+/// - Patterns without glob characters match the whole path or its trailing components
+///   (`main.rs`, `src/lib.rs`). Substring matches must be asked for with a glob (`*main*`).
+#[derive(Debug, Clone)]
+pub struct FilePatterns {
+    path_globs: GlobSet,
+    name_globs: GlobSet,
+    literals: Vec<String>,
+}
+
+impl FilePatterns {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let mut path_globs = GlobSetBuilder::new();
+        let mut name_globs = GlobSetBuilder::new();
+        let mut literals = Vec::new();
+
+        for pattern in patterns {
+            if !pattern.contains(['*', '?', '[', '{']) {
+                literals.push(pattern.clone());
+                continue;
+            }
+
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| {
+                    RuneError::InvalidQuery(format!("Invalid file pattern '{}': {}", pattern, e))
+                })?;
+            if pattern.contains('/') {
+                path_globs.add(glob);
+            } else {
+                name_globs.add(glob);
+            }
+        }
+
+        Ok(Self {
+            path_globs: path_globs.build()?,
+            name_globs: name_globs.build()?,
+            literals,
+        })
+    }
+
+    /// Whether `relative_path` (relative to its workspace root) matches any pattern
+    pub fn matches(&self, relative_path: &Path) -> bool {
+        if self.path_globs.is_match(relative_path) {
+            return true;
+        }
+        if relative_path
+            .file_name()
+            .is_some_and(|name| self.name_globs.is_match(name))
+        {
+            return true;
+        }
+
+        self.literals
+            .iter()
+            .any(|literal| relative_path.ends_with(literal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        FilePatterns::new(&[pattern.to_string()])
+            .unwrap()
+            .matches(Path::new(path))
+    }
+
+    #[test]
+    fn test_single_star_matches_file_names() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("*.rs", "src/deep/lib.rs"));
+        assert!(!matches("*.rs", "src/lib.rsx"));
+        assert!(!matches("*.rs", "src/rs/readme.md"));
+
+        // With a directory, `*` stays within one path component
+        assert!(matches("src/*.rs", "src/lib.rs"));
+        assert!(!matches("src/*.rs", "src/nested/lib.rs"));
+        assert!(!matches("src/*.rs", "other/src/lib.rs"));
+    }
+
+    #[test]
+    fn test_double_star_spans_directories() {
+        assert!(matches("src/**/*.ts", "src/app.ts"));
+        assert!(matches("src/**/*.ts", "src/components/ui/button.ts"));
+        assert!(!matches("src/**/*.ts", "lib/src/app.ts"));
+
+        assert!(matches("**/test_*.py", "test_main.py"));
+        assert!(matches("**/test_*.py", "pkg/tests/test_api.py"));
+        assert!(!matches("**/test_*.py", "pkg/api_test.py"));
+    }
+
+    #[test]
+    fn test_character_classes_and_literals() {
+        assert!(matches("[ab].go", "cmd/a.go"));
+        assert!(matches("[ab].go", "b.go"));
+        assert!(!matches("[ab].go", "c.go"));
+        assert!(matches("file_?.txt", "docs/file_1.txt"));
+
+        assert!(matches("main.rs", "src/main.rs"));
+        assert!(matches("src/lib.rs", "src/lib.rs"));
+        assert!(matches("src/lib.rs", "crates/core/src/lib.rs"));
+        assert!(!matches("main", "src/lib.rs"));
+    }
+
+    #[test]
+    fn test_plain_patterns_are_not_substrings() {
+        assert!(!matches("main", "src/main.rs"));
+        assert!(!matches("lib.rs", "src/mylib.rs"));
+        assert!(!matches("src/lib.rs", "xsrc/lib.rs"));
+        assert!(!matches("src", "src/lib.rs"));
+
+        // Substring matching is opt-in through a glob
+        assert!(matches("*main*", "src/main.rs"));
+        assert!(matches("*lib.rs", "src/mylib.rs"));
+
+        assert!(FilePatterns::new(&["src/[".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+/// Tokens longer than this (in bytes) are never compared by default
+pub const DEFAULT_MAX_TOKEN_LEN: usize = 128;
+
+/// Case-insensitive edit-distance matching for approximate name lookups
+#[derive(Debug, Clone, Copy)]
+pub struct FuzzyMatcher {
+    max_distance: usize,
+    max_token_len: usize,
+}
+
+impl Default for FuzzyMatcher {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl FuzzyMatcher {
+    pub fn new(max_distance: usize) -> Self {
+        Self {
+            max_distance,
+            max_token_len: DEFAULT_MAX_TOKEN_LEN,
+        }
+    }
+
+    /// Skip terms and candidates longer than `max_token_len` bytes. Edit distance is
+    /// quadratic in length, so a single giant token (e.g. from minified code) would
+    /// otherwise stall the lookup.
+    pub fn with_max_token_len(mut self, max_token_len: usize) -> Self {
+        self.max_token_len = max_token_len;
+        self
+    }
+
+    /// Levenshtein distance between two strings, ignoring case
+    pub fn distance(a: &str, b: &str) -> usize {
+        let a: Vec<char> = a.to_lowercase().chars().collect();
+        let b: Vec<char> = b.to_lowercase().chars().collect();
+
+        let mut previous: Vec<usize> = (0..=b.len()).collect();
+        let mut current = vec![0; b.len() + 1];
+
+        for (i, ca) in a.iter().enumerate() {
+            current[0] = i + 1;
+            for (j, cb) in b.iter().enumerate() {
+                let substitution = previous[j] + usize::from(ca != cb);
+                current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            std::mem::swap(&mut previous, &mut current);
+        }
+
+        previous[b.len()]
+    }
+
+    /// Candidates within `max_distance` of `term`, closest first (ties by name).
+    /// Exact matches are excluded since they aren't corrections. A term longer than
+    /// `max_token_len` matches nothing.
+    pub fn closest<'a>(
+        &self,
+        term: &str,
+        candidates: impl IntoIterator<Item = &'a str>,
+        limit: usize,
+    ) -> Vec<String> {
+        if term.len() > self.max_token_len {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<(usize, &str)> = candidates
+            .into_iter()
+            .filter(|c| c.len() <= self.max_token_len)
+            .filter(|c| c.len().abs_diff(term.len()) <= self.max_distance)
+            .map(|c| (Self::distance(term, c), c))
+            .filter(|(d, _)| *d > 0 && *d <= self.max_distance)
+            .collect();
+
+        matches.sort();
+        matches.dedup_by(|a, b| a.1 == b.1);
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, c)| c.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(FuzzyMatcher::distance("parse_config", "parse_config"), 0);
+        assert_eq!(FuzzyMatcher::distance("parse_cofnig", "parse_config"), 2);
+        assert_eq!(FuzzyMatcher::distance("Search", "search"), 0);
+        assert_eq!(FuzzyMatcher::distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_closest() {
+        let candidates = ["load_config", "save_config", "parse_config", "unrelated"];
+        let matches = FuzzyMatcher::default().closest("laod_config", candidates, 5);
+        assert_eq!(matches, vec!["load_config"]);
+    }
+
+    #[test]
+    fn test_long_tokens_are_skipped() {
+        let giant = "a".repeat(1_000_000);
+        let near_giant = format!("{giant}b");
+        let candidates = [near_giant.as_str(), "load_config"];
+
+        let start = std::time::Instant::now();
+        let matcher = FuzzyMatcher::default();
+        assert!(matcher.closest(&giant, candidates, 5).is_empty());
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+        // Short terms still match, but not against candidates over the cap
+        let matcher = FuzzyMatcher::default().with_max_token_len(8);
+        assert!(matcher.closest("laod_config", candidates, 5).is_empty());
+        let matches = matcher.closest("laod", ["load", "loadconfig"], 5);
+        assert_eq!(matches, vec!["load"]);
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use tantivy::Term;
+use tantivy::query::{AllQuery, BooleanQuery, FuzzyTermQuery, Occur, Query, RegexQuery, TermQuery};
+use tantivy::schema::IndexRecordOption;
+use tantivy::snippet::SnippetGenerator;
+use tracing::debug;
+
+use super::{
+    ContextMode, MatchType, SearchBudget, SearchMode, SearchQuery, SearchResult,
+    apply_block_context, extract_context,
+    file_patterns::FilePatterns,
+    fuzzy::FuzzyMatcher,
+    relative_to_roots,
+    symbol::{FileMatches, SymbolMatches},
+};
+use crate::{
+    Config,
+    indexing::tantivy_indexer::{self, TantivyIndexer},
+};
+
+/// Tantivy's default tokenizer drops tokens longer than this, so they can't narrow
+/// the candidate documents
+const MAX_INDEXED_TOKEN_LEN: usize = 40;
+
+/// Shorter query words must match exactly, since a couple of edits turns them into
+/// almost anything
+const MIN_FUZZY_WORD_LEN: usize = 4;
+
+/// Edits allowed between a query word and a word in the file for a fuzzy match
+const MAX_FUZZY_DISTANCE: u8 = 2;
+
+/// Fuzzy matches rank below exact ones from equally relevant files
+const FUZZY_SCORE_FACTOR: f32 = 0.5;
+
+/// Exact, case-sensitive substring search over file content. The text index narrows
+/// the candidate files; each candidate is then scanned line by line.
+///
+/// A file without an exact match falls back to `MatchType::Fuzzy` lines: ones holding
+/// every word of the query in any order and case, each word either verbatim or within
+/// a couple of edits. When no file contains the query's words at all, files with
+/// similar words are scanned instead.
+#[derive(Clone)]
+pub struct LiteralSearcher {
+    config: Arc<Config>,
+    tantivy_indexer: Arc<TantivyIndexer>,
+}
+
+impl LiteralSearcher {
+    pub fn new(config: Arc<Config>, tantivy_indexer: Arc<TantivyIndexer>) -> Self {
+        Self {
+            config,
+            tantivy_indexer,
+        }
+    }
+
+    pub async fn search(
+        &self,
+        query: &SearchQuery,
+        budget: &SearchBudget,
+    ) -> Result<SymbolMatches> {
+        Ok(SymbolMatches::collect(
+            self.scan(query, budget).await?,
+            budget,
+        ))
+    }
+
+    /// Fetch the candidate documents, then find the matches in each one only as the
+    /// iterator reaches it
+    pub async fn scan(
+        &self,
+        query: &SearchQuery,
+        budget: &SearchBudget,
+    ) -> Result<Box<dyn Iterator<Item = FileMatches> + Send>> {
+        debug!("Performing literal search for: {}", query.query);
+
+        if query.query.is_empty() {
+            return Ok(Box::new(std::iter::empty()));
+        }
+
+        let mut docs = self
+            .tantivy_indexer
+            .search_documents(
+                self.tantivy_indexer
+                    .modified_after(self.candidate_query(&query.query), query.modified_since)
+                    .as_ref(),
+                budget.fetch_limit,
+            )
+            .await?;
+        if docs.is_empty()
+            && let Some(fuzzy) = self.fuzzy_candidate_query(&query.query)
+        {
+            debug!(
+                "No literal candidates for {}, trying similar words",
+                query.query
+            );
+            docs = self
+                .tantivy_indexer
+                .search_documents(
+                    self.tantivy_indexer
+                        .modified_after(fuzzy, query.modified_since)
+                        .as_ref(),
+                    budget.fetch_limit,
+                )
+                .await?;
+        }
+
+        let file_patterns = query
+            .file_patterns
+            .as_deref()
+            .map(FilePatterns::new)
+            .transpose()?;
+        let snippets = if query.highlight_snippets {
+            Some(
+                self.tantivy_indexer
+                    .snippet_generator(self.term_query(&query.query).as_ref())?,
+            )
+        } else {
+            None
+        };
+
+        let searcher = self.clone();
+        let query = query.clone();
+        Ok(Box::new(docs.into_iter().filter_map(move |doc| {
+            searcher.scan_document(&query, file_patterns.as_ref(), snippets.as_ref(), doc)
+        })))
+    }
+
+    /// Matches in one candidate, or `None` if the query's filters exclude it
+    fn scan_document(
+        &self,
+        query: &SearchQuery,
+        file_patterns: Option<&FilePatterns>,
+        snippets: Option<&SnippetGenerator>,
+        doc: tantivy_indexer::SearchResult,
+    ) -> Option<FileMatches> {
+        if let Some(repos) = &query.repositories
+            && !repos.contains(&doc.repository)
+        {
+            return None;
+        }
+
+        if let Some(patterns) = file_patterns {
+            let relative =
+                relative_to_roots(&self.config.workspace_roots, &doc.path).unwrap_or(&doc.path);
+            if !patterns.matches(relative) {
+                return None;
+            }
+        }
+
+        let (mut results, truncated) = self.find_matches_in_content(
+            &doc.path,
+            &doc.repository,
+            &doc.content,
+            &query.query,
+            doc.score,
+            query.context_mode.lines(),
+        );
+        if let Some(generator) = snippets {
+            for result in &mut results {
+                let snippet = generator.snippet(&result.content);
+                if !snippet.highlighted().is_empty() {
+                    result.match_ranges = snippet.highlighted().to_vec();
+                    result.snippet = Some(snippet.fragment().to_string());
+                }
+            }
+        }
+        if query.context_mode == ContextMode::Block {
+            apply_block_context(&mut results, &doc.content, &doc.blocks);
+        }
+
+        Some(FileMatches {
+            path: doc.path,
+            results,
+            truncated,
+        })
+    }
+
+    /// Documents containing every word of `text`, possibly as part of a longer token
+    fn candidate_query(&self, text: &str) -> Box<dyn Query> {
+        let content_field = self.tantivy_indexer.get_content_field();
+        let clauses: Vec<(Occur, Box<dyn Query>)> = words(text)
+            .filter_map(|word| {
+                let pattern = format!(".*{}.*", word.to_lowercase());
+                RegexQuery::from_pattern(&pattern, content_field).ok()
+            })
+            .map(|query| (Occur::Must, Box::new(query) as Box<dyn Query>))
+            .collect();
+
+        if clauses.is_empty() {
+            Box::new(AllQuery)
+        } else {
+            Box::new(BooleanQuery::new(clauses))
+        }
+    }
+
+    /// Documents with a word close to one of `text`'s, or `None` if every word is too
+    /// short to match fuzzily
+    fn fuzzy_candidate_query(&self, text: &str) -> Option<Box<dyn Query>> {
+        let content_field = self.tantivy_indexer.get_content_field();
+        let clauses: Vec<(Occur, Box<dyn Query>)> = words(text)
+            .filter(|word| word.chars().count() >= MIN_FUZZY_WORD_LEN)
+            .map(|word| {
+                let term = Term::from_field_text(content_field, &word.to_lowercase());
+                let query = FuzzyTermQuery::new(term, MAX_FUZZY_DISTANCE, true);
+                (Occur::Should, Box::new(query) as Box<dyn Query>)
+            })
+            .collect();
+
+        (!clauses.is_empty()).then(|| Box::new(BooleanQuery::new(clauses)) as Box<dyn Query>)
+    }
+
+    /// The words of `text` as indexed, for highlighting
+    fn term_query(&self, text: &str) -> Box<dyn Query> {
+        let content_field = self.tantivy_indexer.get_content_field();
+        let clauses: Vec<(Occur, Box<dyn Query>)> = words(text)
+            .map(|word| {
+                let term = Term::from_field_text(content_field, &word.to_lowercase());
+                let query = TermQuery::new(term, IndexRecordOption::Basic);
+                (Occur::Should, Box::new(query) as Box<dyn Query>)
+            })
+            .collect();
+        Box::new(BooleanQuery::new(clauses))
+    }
+
+    /// One result per line containing `text` with `context_lines` of context on each
+    /// side, capped at `Config::max_matches_per_file`. Lines that only match fuzzily are
+    /// returned when no line contains `text` exactly. Returns true alongside the results
+    /// if the cap cut them short.
+    pub fn find_matches_in_content(
+        &self,
+        file_path: &Path,
+        repository: &str,
+        content: &str,
+        text: &str,
+        score: f32,
+        context_lines: usize,
+    ) -> (Vec<SearchResult>, bool) {
+        let lines: Vec<&str> = content.lines().collect();
+        let max_matches = self.config.max_matches_per_file;
+
+        let mut match_type = MatchType::Exact;
+        let mut matches: Vec<(usize, usize)> = lines
+            .iter()
+            .enumerate()
+            .filter_map(|(line_idx, line)| line.find(text).map(|column| (line_idx, column)))
+            .take(max_matches.saturating_add(1))
+            .collect();
+        if matches.is_empty() {
+            let matcher = FuzzyMatcher::new(MAX_FUZZY_DISTANCE.into())
+                .with_max_token_len(self.config.fuzzy_max_token_len);
+            match_type = MatchType::Fuzzy;
+            matches = lines
+                .iter()
+                .enumerate()
+                .filter_map(|(line_idx, line)| {
+                    fuzzy_column(line, text, &matcher).map(|column| (line_idx, column))
+                })
+                .take(max_matches.saturating_add(1))
+                .collect();
+        }
+        let truncated = matches.len() > max_matches;
+        matches.truncate(max_matches);
+
+        let score = match match_type {
+            MatchType::Fuzzy => score * FUZZY_SCORE_FACTOR,
+            _ => score,
+        };
+        let mut results = Vec::with_capacity(matches.len());
+        for (line_idx, column) in matches {
+            let line = lines[line_idx];
+            let (context_before, context_after) =
+                extract_context(&lines, line_idx, context_lines, context_lines);
+            results.push(SearchResult {
+                file_path: file_path.to_path_buf(),
+                repository: repository.to_string(),
+                line_number: line_idx + 1,
+                end_line: line_idx + 1,
+                column,
+                content: line.to_string(),
+                context_before,
+                context_after,
+                score,
+                raw_score: score,
+                relative_path: None,
+                match_type: match_type.clone(),
+                source_mode: Some(SearchMode::Literal),
+                body: None,
+                relevant_imports: Vec::new(),
+                snippet: None,
+                match_ranges: Vec::new(),
+                last_author: None,
+                last_commit: None,
+                enclosing_signature: None,
+            });
+        }
+
+        (results, truncated)
+    }
+}
+
+/// Byte offset of the first of `text`'s words on `line`, if every word is on it. Words
+/// match ignoring ASCII case, or as a word of the line within `matcher`'s distance.
+fn fuzzy_column(line: &str, text: &str, matcher: &FuzzyMatcher) -> Option<usize> {
+    let lowered = line.to_ascii_lowercase();
+    let mut column = None::<usize>;
+
+    for word in words(text) {
+        let found = lowered.find(&word.to_ascii_lowercase()).or_else(|| {
+            if word.chars().count() < MIN_FUZZY_WORD_LEN {
+                return None;
+            }
+            words(line).find_map(|candidate| {
+                (!matcher.closest(word, [candidate], 1).is_empty())
+                    .then(|| candidate.as_ptr() as usize - line.as_ptr() as usize)
+            })
+        })?;
+        column = Some(column.map_or(found, |column| column.min(found)));
+    }
+
+    column
+}
+
+/// Alphanumeric runs of `text` short enough to be indexed
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && word.len() <= MAX_INDEXED_TOKEN_LEN)
+}
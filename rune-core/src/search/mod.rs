@@ -1,23 +1,208 @@
+#[cfg(feature = "git-blame")]
+pub mod blame;
+pub mod file_patterns;
+pub mod fuzzy;
+pub mod literal;
+pub mod query_parser;
+pub mod regex;
+#[cfg(feature = "semantic")]
 pub mod semantic;
 pub mod symbol;
 
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
+use tokio::sync::{Semaphore, SemaphorePermit};
 
+use self::file_patterns::FilePatterns;
+use self::fuzzy::FuzzyMatcher;
 use crate::{
-    Config,
+    Config, IndexEvictionPolicy, RuneError,
     cache::{CacheConfig, MultiTierCache},
-    indexing::tantivy_indexer::TantivyIndexer,
+    indexing::{
+        language_detector::LanguageDetector,
+        symbol_extractor::{Symbol, SymbolExtractor},
+        tantivy_indexer::TantivyIndexer,
+    },
     storage::StorageBackend,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SearchMode {
     Symbol,
+    /// Fails with `RuneError::SemanticDisabled` if built without the `semantic` feature
+    #[default]
     Semantic,
+    /// Exact, case-sensitive occurrences of the query text
+    Literal,
+    /// Lines matching the query as a regular expression
+    Regex,
+    /// Symbol and semantic results merged by reciprocal rank fusion
+    Hybrid,
+}
+
+/// Lines of context shown on each side of a match by default
+pub const DEFAULT_CONTEXT_LINES: usize = 2;
+
+/// Default for `Config::candidate_multiplier`
+pub const DEFAULT_CANDIDATE_MULTIPLIER: usize = 3;
+
+/// Rank offset `k` in reciprocal rank fusion, where a result scores `1 / (k + rank)`
+/// per list it appears in
+const RRF_K: f32 = 60.0;
+
+/// Maximum number of queries run at once by `SearchEngine::warm_cache`
+const WARM_CACHE_CONCURRENCY: usize = 4;
+
+/// Maximum number of "did you mean" suggestions returned for an empty search
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Words in import statements that aren't names being imported
+const IMPORT_KEYWORDS: &[&str] = &[
+    "use", "pub", "crate", "self", "super", "import", "from", "as", "type", "static", "_",
+];
+
+/// How much surrounding code to attach to each result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ContextMode {
+    /// A fixed number of lines before and after the match
+    Lines(usize),
+    /// The full line range of the enclosing symbol (e.g. the whole function)
+    Symbol,
+    /// The blank-line-delimited block around a literal or regex match, in files indexed
+    /// with `Config::index_text_blocks`. Elsewhere the usual fixed context is used.
+    Block,
+}
+
+impl Default for ContextMode {
+    fn default() -> Self {
+        ContextMode::Lines(DEFAULT_CONTEXT_LINES)
+    }
+}
+
+impl ContextMode {
+    /// Lines of context on each side of a match: `n` for `Lines(n)`, otherwise
+    /// `DEFAULT_CONTEXT_LINES`, which `Symbol` and `Block` widen where they apply
+    pub fn lines(&self) -> usize {
+        match self {
+            ContextMode::Lines(n) => *n,
+            ContextMode::Symbol | ContextMode::Block => DEFAULT_CONTEXT_LINES,
+        }
+    }
+}
+
+/// Up to `before` lines preceding and `after` lines following the 0-based `line_idx`,
+/// clamped to the start and end of `lines`
+pub fn extract_context(
+    lines: &[&str],
+    line_idx: usize,
+    before: usize,
+    after: usize,
+) -> (Vec<String>, Vec<String>) {
+    let len = lines.len();
+    let before_range = line_idx.saturating_sub(before).min(len)..line_idx.min(len);
+    let after_start = line_idx.saturating_add(1).min(len);
+    let after_range = after_start..after_start.saturating_add(after).min(len);
+
+    let to_owned = |slice: &[&str]| slice.iter().map(|s| s.to_string()).collect();
+    (
+        to_owned(&lines[before_range]),
+        to_owned(&lines[after_range]),
+    )
+}
+
+/// Replace the context of line matches in `content` with the rest of the block from
+/// `blocks` that contains each match
+pub fn apply_block_context(results: &mut [SearchResult], content: &str, blocks: &[Range<usize>]) {
+    if blocks.is_empty() {
+        return;
+    }
+    let lines: Vec<&str> = content.lines().collect();
+    for result in results {
+        let line_idx = result.line_number.saturating_sub(1);
+        if let Some(block) = blocks.iter().find(|block| block.contains(&line_idx)) {
+            (result.context_before, result.context_after) = extract_context(
+                &lines,
+                line_idx,
+                line_idx - block.start,
+                block.end - line_idx - 1,
+            );
+        }
+    }
+}
+
+/// `file_path` relative to the innermost of `roots` that contains it
+pub fn relative_to_roots<'a>(roots: &[PathBuf], file_path: &'a Path) -> Option<&'a Path> {
+    roots
+        .iter()
+        .filter_map(|root| file_path.strip_prefix(root).ok())
+        .min_by_key(|relative| relative.components().count())
+}
+
+/// Innermost of `symbols` spanning the 1-indexed `line_number`
+fn enclosing_symbol(symbols: &[Symbol], line_number: usize) -> Option<&Symbol> {
+    // Symbol lines are 0-indexed
+    let line_idx = line_number.saturating_sub(1);
+    symbols
+        .iter()
+        .filter(|s| s.start_line <= line_idx && s.end_line >= line_idx)
+        .min_by_key(|s| s.end_line - s.start_line)
+}
+
+/// Identifier-like words in `text`
+fn identifiers(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.starts_with(|c: char| c.is_alphabetic() || c == '_'))
+}
+
+/// Names an import statement brings into scope: the leaf of each imported path, or its
+/// alias (`HashMap` for `use std::collections::HashMap`, `np` for `import numpy as np`,
+/// `fmt` for Go's `import "fmt"`). Glob imports and path prefixes such as `std` or
+/// `crate` bind nothing.
+fn imported_names(import: &str) -> HashSet<&str> {
+    let mut names = HashSet::new();
+
+    for clause in import.split([',', '{', '}', '(', ')', ';', '\n']) {
+        let clause = clause.trim();
+        // `use a::{...}` leaves the `use a::` prefix as its own clause
+        if clause.is_empty() || clause.ends_with("::") || clause.ends_with('*') {
+            continue;
+        }
+
+        let name = match clause.split_once(['"', '\'', '`']) {
+            Some((before, quoted)) => {
+                // `import X from 'mod'` binds X; Go's `import f "fmt"` binds f
+                let specifier = before.trim_end();
+                let named = specifier.strip_suffix("from");
+                match identifiers(named.unwrap_or(specifier)).last() {
+                    Some(name) => Some(name),
+                    // Go's `import "net/http"` binds the package's last path segment
+                    None if named.is_none() => quoted
+                        .trim_end_matches(['"', '\'', '`'])
+                        .rsplit('/')
+                        .next()
+                        .and_then(|segment| identifiers(segment).last()),
+                    None => None,
+                }
+            },
+            // The last word is the leaf (`a::b::Leaf`, `from a import leaf`) or the alias
+            None => identifiers(clause).last(),
+        };
+
+        if let Some(name) = name.filter(|name| !IMPORT_KEYWORDS.contains(name)) {
+            names.insert(name);
+        }
+    }
+
+    names
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +213,120 @@ pub struct SearchQuery {
     pub file_patterns: Option<Vec<String>>,
     pub limit: usize,
     pub offset: usize,
+    #[serde(default)]
+    pub context_mode: ContextMode,
+    /// Collapse results from different paths whose files have identical content
+    #[serde(default)]
+    pub dedup_by_content_hash: bool,
+    /// How many candidates each searcher fetches before filtering and ranking.
+    /// Defaults to `Config::candidate_multiplier * limit`, oversampled when filtering
+    /// (see `SearchBudget`); `limit` still caps what is returned.
+    #[serde(default)]
+    pub candidate_limit: Option<usize>,
+    /// Min-max normalize each mode's scores to 0..1 so they can be compared across modes.
+    /// The searcher's own score stays available in `SearchResult::raw_score`. Hybrid
+    /// results keep their fused scores, which are already comparable.
+    #[serde(default)]
+    pub normalize_scores: bool,
+    /// When nothing matches, suggest indexed symbol names close to the query terms
+    #[serde(default)]
+    pub suggest_on_empty: bool,
+    /// Also match the query against file path components (e.g. `config` finds `src/config.rs`)
+    #[serde(default)]
+    pub search_paths: bool,
+    /// Trim semantic chunk content to its first N lines. `line_number`/`end_line` still
+    /// describe the whole chunk.
+    #[serde(default)]
+    pub chunk_preview_lines: Option<usize>,
+    /// Only return results from files modified after this time (epoch seconds)
+    #[serde(default)]
+    pub modified_since: Option<u64>,
+    /// Drop results under vendored or generated directories (`Config::vendored_dirs`)
+    #[serde(default)]
+    pub exclude_vendored: bool,
+    /// Skip the cache lookup but still cache the fresh response, so later queries
+    /// without the flag get the refreshed result
+    #[serde(default)]
+    pub bypass_cache: bool,
+    /// In symbol mode, also return places the name is used, not just where it's
+    /// defined. Needs `Config::index_references`.
+    #[serde(default)]
+    pub include_references: bool,
+    /// Drop results whose matched content is shorter than this once trimmed, e.g. a
+    /// lone `}` or a single word
+    #[serde(default)]
+    pub min_line_length: Option<usize>,
+    /// Attach the full text of the enclosing symbol (e.g. the whole function) to
+    /// symbol results as `SearchResult::body`
+    #[serde(default)]
+    pub include_symbol_body: bool,
+    /// Attach the file's import statements that mention an identifier from the
+    /// matched content as `SearchResult::relevant_imports`
+    #[serde(default)]
+    pub include_imports: bool,
+    /// For literal search, attach a fragment of each matched line with the query terms
+    /// highlighted (`SearchResult::snippet` and `match_ranges`). Terms are highlighted
+    /// where they match whole indexed words.
+    #[serde(default)]
+    pub highlight_snippets: bool,
+    /// Set `SearchResult::last_author` and `last_commit` from `git blame` for results
+    /// in a git repository. Costly on large histories, and ignored unless built with
+    /// the `git-blame` feature.
+    #[serde(default)]
+    pub include_blame: bool,
+    /// For literal and regex results, attach the signature of the innermost symbol
+    /// the match falls in as `SearchResult::enclosing_signature`
+    #[serde(default)]
+    pub include_enclosing_signature: bool,
+}
+
+/// How much work every searcher does for one query. Computed once per search, so
+/// each mode fetches and keeps the same number of candidates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchBudget {
+    /// Documents (semantic chunks in semantic mode) requested from the index
+    pub fetch_limit: usize,
+    /// Candidates handed to the reranker, when one is configured
+    pub max_expansions: usize,
+    /// Results a searcher keeps once its filters are applied
+    pub candidate_limit: usize,
+}
+
+impl SearchBudget {
+    /// A query's own `candidate_limit` is fetched as is. Otherwise the searchers keep
+    /// `Config::candidate_multiplier` times the limit, and fetch
+    /// `Config::filter_oversample_factor` times that when the query filters by
+    /// repository or file pattern, or semantically by modification time. Never less
+    /// than the requested page needs.
+    pub fn new(query: &SearchQuery, config: &Config) -> Self {
+        let page = query.limit.saturating_add(query.offset);
+        let (candidate_limit, fetch_limit) = match query.candidate_limit {
+            Some(limit) => (limit.max(page), limit.max(page)),
+            None => {
+                let candidate_limit = query
+                    .limit
+                    .saturating_mul(config.candidate_multiplier)
+                    .max(page);
+                // Text searches filter by modification time in the index query itself
+                let filtered = query.repositories.is_some()
+                    || query.file_patterns.is_some()
+                    || (query.modified_since.is_some()
+                        && matches!(query.mode, SearchMode::Semantic | SearchMode::Hybrid));
+                let fetch_limit = if filtered {
+                    candidate_limit.saturating_mul(config.filter_oversample_factor.max(1))
+                } else {
+                    candidate_limit
+                };
+                (candidate_limit, fetch_limit)
+            },
+        };
+
+        Self {
+            fetch_limit,
+            max_expansions: fetch_limit.max(config.rerank_candidates),
+            candidate_limit,
+        }
+    }
 }
 
 impl Default for SearchQuery {
@@ -39,29 +338,133 @@ impl Default for SearchQuery {
             file_patterns: None,
             limit: 50,
             offset: 0,
+            context_mode: ContextMode::default(),
+            dedup_by_content_hash: false,
+            candidate_limit: None,
+            normalize_scores: false,
+            suggest_on_empty: false,
+            search_paths: false,
+            chunk_preview_lines: None,
+            modified_since: None,
+            exclude_vendored: false,
+            bypass_cache: false,
+            include_references: false,
+            min_line_length: None,
+            include_symbol_body: false,
+            include_imports: false,
+            highlight_snippets: false,
+            include_blame: false,
+            include_enclosing_signature: false,
+        }
+    }
+}
+
+/// Paths serialize as strings, lossily when they aren't valid UTF-8 (which serde
+/// refuses), while results keep the real path for reading the file
+mod lossy_path {
+    use serde::Serializer;
+    use std::path::{Path, PathBuf};
+
+    pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&path.to_string_lossy())
+    }
+
+    pub fn serialize_option<S: Serializer>(
+        path: &Option<PathBuf>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match path {
+            Some(path) => serializer.serialize_some(&path.to_string_lossy()),
+            None => serializer.serialize_none(),
         }
     }
+
+    pub fn serialize_all<S: Serializer>(
+        paths: &[PathBuf],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(paths.iter().map(|path| path.to_string_lossy()))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
+    /// The file's real path. Serialized lossily if it isn't valid UTF-8.
+    #[serde(serialize_with = "lossy_path::serialize")]
     pub file_path: PathBuf,
+    /// `file_path` relative to the workspace root containing it, if any
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "lossy_path::serialize_option"
+    )]
+    pub relative_path: Option<PathBuf>,
     pub repository: String,
     pub line_number: usize,
+    /// Last line of the matched span (equal to `line_number` for single-line matches)
+    #[serde(default)]
+    pub end_line: usize,
     pub column: usize,
     pub content: String,
     pub context_before: Vec<String>,
     pub context_after: Vec<String>,
     pub score: f32,
+    /// Score as produced by the searcher (BM25 or vector similarity), before normalization
+    #[serde(default)]
+    pub raw_score: f32,
     pub match_type: MatchType,
+    /// Search mode whose searcher produced this result. Path matches count as
+    /// `Symbol`, since they come from the same text index. `None` for results
+    /// serialized before this was recorded.
+    #[serde(default)]
+    pub source_mode: Option<SearchMode>,
+    /// Full text of the symbol the match falls in, from `SearchQuery::include_symbol_body`.
+    /// Capped at `Config::max_content_chars`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Imports referencing identifiers in `content`, from `SearchQuery::include_imports`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relevant_imports: Vec<String>,
+    /// Fragment of `content` around the match, from `SearchQuery::highlight_snippets`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// Byte ranges of the highlighted query terms within `snippet`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub match_ranges: Vec<Range<usize>>,
+    /// Author of the last commit touching the matched line, from
+    /// `SearchQuery::include_blame`. Unset for uncommitted lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_author: Option<String>,
+    /// Id of that commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_commit: Option<String>,
+    /// First line of the symbol a literal or regex match falls in, e.g.
+    /// `fn parse(input: &str) -> Config {`, from `SearchQuery::include_enclosing_signature`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enclosing_signature: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum MatchType {
     Semantic,
     Symbol,
+    /// The file's path matched the query
+    Path,
+    /// A usage of the queried symbol, from `SearchQuery::include_references`
+    Reference,
+    /// The query text appears verbatim on the line
+    Exact,
+    /// The line matches the query as a regular expression
+    Regex,
+    /// The line contains a close but inexact match for the query, e.g. with a typo,
+    /// different case or its words apart
+    Fuzzy,
 }
 
+/// Version of the serialized `SearchResponse` shape. Bump whenever fields are added,
+/// removed or change meaning, so clients can branch on `SearchResponse::schema_version`.
+pub const SEARCH_RESPONSE_SCHEMA_VERSION: u32 = 8;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResponse {
     pub query: SearchQuery,
@@ -71,70 +474,438 @@ pub struct SearchResponse {
     /// Whether this response was served from cache
     #[serde(skip_serializing_if = "Option::is_none")]
     pub from_cache: Option<bool>,
+    /// "Did you mean" symbol names, only filled for empty results with `suggest_on_empty`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+    /// Files whose line matches were capped at `Config::max_matches_per_file`
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "lossy_path::serialize_all"
+    )]
+    pub truncated_files: Vec<PathBuf>,
+    /// `SEARCH_RESPONSE_SCHEMA_VERSION` at the time this response was produced.
+    /// Payloads from before versioning deserialize as 0.
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Set on a page split off with `take_page` by callers that hold on to the rest
+    /// (e.g. the Node bridge); passing it back fetches the next page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<String>,
+    /// Served from an expired cache entry because the vector store was unreachable,
+    /// within `Config::semantic_stale_grace_secs`
+    #[serde(default)]
+    pub stale: bool,
+}
+
+impl SearchResponse {
+    /// Results as JSON Lines: one `SearchResult` object per line, each newline-terminated
+    pub fn to_jsonl(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        self.write_jsonl(&mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    /// Split off the leading results whose serialized size (as a JSON array) fits in
+    /// `max_bytes` as a page carrying this response's metadata, leaving the rest in
+    /// `self`. A page always takes at least one result, so repeated calls drain the
+    /// response.
+    pub fn take_page(&mut self, max_bytes: usize) -> Result<SearchResponse> {
+        // Brackets plus one separator per result
+        let mut used = 1;
+        let mut count = 0;
+        for result in &self.results {
+            let mut counter = ByteCounter(0);
+            serde_json::to_writer(&mut counter, result)?;
+            used += counter.0 + 1;
+            if count > 0 && used > max_bytes {
+                break;
+            }
+            count += 1;
+        }
+
+        // Swap the remainder out first so cloning the metadata doesn't copy it
+        let rest = self.results.split_off(count);
+        let results = std::mem::take(&mut self.results);
+        let page = SearchResponse {
+            results,
+            ..self.clone()
+        };
+        self.results = rest;
+        Ok(page)
+    }
+
+    /// Stream the results to `writer` as JSON Lines
+    pub fn write_jsonl(&self, mut writer: impl std::io::Write) -> Result<()> {
+        for result in &self.results {
+            serde_json::to_writer(&mut writer, result)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// `io::Write` sink that only counts bytes, for measuring serialized sizes
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Search results that fall inside the same symbol (e.g. one method)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolGroup {
+    /// Name of the innermost enclosing symbol, or `None` for matches outside any symbol
+    pub symbol: Option<String>,
+    #[serde(serialize_with = "lossy_path::serialize")]
+    pub file_path: PathBuf,
+    pub results: Vec<SearchResult>,
+}
+
+/// A contiguous run of lines covering one or more nearby matches in a file, like a
+/// unified diff hunk. Line numbers are 1-indexed and inclusive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hunk {
+    #[serde(serialize_with = "lossy_path::serialize")]
+    pub file_path: PathBuf,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Text of every line from `start_line` to `end_line`, context included
+    pub lines: Vec<String>,
+    /// Lines within the hunk that matched, ascending
+    pub match_lines: Vec<usize>,
 }
 
 pub struct SearchEngine {
-    _config: Arc<Config>,
-    _storage: StorageBackend,
-    _tantivy_indexer: Arc<TantivyIndexer>,
+    config: Arc<Config>,
+    storage: StorageBackend,
+    tantivy_indexer: Arc<TantivyIndexer>,
     symbol_searcher: symbol::SymbolSearcher,
+    literal_searcher: literal::LiteralSearcher,
+    regex_searcher: regex::RegexSearcher,
     #[cfg(feature = "semantic")]
     semantic_searcher: semantic::SemanticSearcher,
     cache: Arc<MultiTierCache>,
+    /// Hash of the configuration, part of the result cache's L2 scope
+    config_fingerprint: u64,
+    symbol_extractor: Arc<SymbolExtractor>,
+    #[cfg(feature = "git-blame")]
+    blame_cache: blame::BlameCache,
+    /// Limits concurrent searches to `Config::max_concurrent_searches`
+    search_permits: Arc<Semaphore>,
 }
 
 impl SearchEngine {
     pub async fn new(config: Arc<Config>, storage: StorageBackend) -> Result<Self> {
         // Create tantivy indexer for search operations (read-only)
         let index_path = config.cache_dir.join("tantivy_index");
-        let tantivy_indexer = Arc::new(TantivyIndexer::new_read_only(&index_path).await?);
+        let tantivy_indexer =
+            Arc::new(TantivyIndexer::new_read_only(&index_path, config.store_content).await?);
+
+        Self::with_tantivy_indexer(config, storage, tantivy_indexer).await
+    }
 
+    /// Create a search engine over an already opened Tantivy index, e.g. the one the
+    /// `Indexer` writes to, so each commit is searchable right away
+    pub async fn with_tantivy_indexer(
+        config: Arc<Config>,
+        storage: StorageBackend,
+        tantivy_indexer: Arc<TantivyIndexer>,
+    ) -> Result<Self> {
         let symbol_searcher =
             symbol::SymbolSearcher::new(config.clone(), storage.clone(), tantivy_indexer.clone())
                 .await?;
 
+        let literal_searcher =
+            literal::LiteralSearcher::new(config.clone(), tantivy_indexer.clone());
+        let regex_searcher = regex::RegexSearcher::new(config.clone(), tantivy_indexer.clone());
+
         #[cfg(feature = "semantic")]
         let semantic_searcher =
             semantic::SemanticSearcher::new(config.clone(), storage.clone()).await?;
 
-        // Initialize cache with default config
-        let cache_config = CacheConfig::default();
+        // Keep expired entries around for semantic queries that come in while the vector
+        // store is down, and persist responses if configured
+        let cache_config = CacheConfig {
+            stale_grace: Duration::from_secs(config.semantic_stale_grace_secs),
+            l2_ttl: Duration::from_secs(config.result_cache_l2_ttl_secs),
+            ..CacheConfig::default()
+        };
+        // A read-only store can't hold the L2 tier
         let cache = Arc::new(MultiTierCache::new(
             cache_config,
-            Some(Arc::new(storage.clone())),
+            (!config.read_only).then(|| Arc::new(storage.clone())),
         ));
 
         Ok(Self {
-            _config: config,
-            _storage: storage,
-            _tantivy_indexer: tantivy_indexer,
+            storage,
+            tantivy_indexer,
             symbol_searcher,
+            literal_searcher,
+            regex_searcher,
             #[cfg(feature = "semantic")]
             semantic_searcher,
             cache,
+            config_fingerprint: Self::config_fingerprint(&config),
+            symbol_extractor: Arc::new(SymbolExtractor::new()),
+            #[cfg(feature = "git-blame")]
+            blame_cache: blame::BlameCache::default(),
+            search_permits: Arc::new(Semaphore::new(config.max_concurrent_searches.max(1))),
+            config,
         })
     }
 
     pub async fn search(&self, query: SearchQuery) -> Result<SearchResponse> {
-        let start = std::time::Instant::now();
-
-        // Check cache first
-        if let Some(mut cached_response) = self.cache.get(&query).await {
+        // Check cache first. Disk-cached responses only count if the index and
+        // configuration are the same as when they were stored.
+        if !query.bypass_cache
+            && let Some(mut cached_response) = self.cache.get(&query, self.cache_scope()).await
+        {
             cached_response.from_cache = Some(true);
             tracing::debug!("Serving search from cache for query: {}", query.query);
             return Ok(cached_response);
         }
 
         // Cache miss - perform actual search
+        let stale_allowed = query.mode == SearchMode::Semantic
+            && !query.bypass_cache
+            && self.config.semantic_stale_grace_secs > 0;
+        if stale_allowed
+            && self.vector_store_unavailable()
+            && let Some(response) = self.stale_response(&query).await
+        {
+            return Ok(response);
+        }
+
+        match self.with_search_permit(self.execute(query.clone())).await {
+            Err(e)
+                if stale_allowed
+                    && matches!(
+                        e.downcast_ref::<RuneError>(),
+                        Some(RuneError::QdrantUnavailable { .. })
+                    ) =>
+            {
+                self.stale_response(&query).await.ok_or(e)
+            },
+            result => result,
+        }
+    }
+
+    /// Whether semantic queries currently can't reach their vector store
+    fn vector_store_unavailable(&self) -> bool {
+        #[cfg(feature = "semantic")]
+        {
+            !self.semantic_searcher.is_available()
+        }
+        #[cfg(not(feature = "semantic"))]
+        {
+            false
+        }
+    }
+
+    /// The last cached response for `query` within the stale grace period, flagged stale
+    async fn stale_response(&self, query: &SearchQuery) -> Option<SearchResponse> {
+        let mut response = self.cache.get_stale(query).await?;
+        tracing::warn!(
+            "Vector store unavailable, serving stale cached results for query: {}",
+            query.query
+        );
+        response.from_cache = Some(true);
+        response.stale = true;
+        Some(response)
+    }
+
+    /// Yield results as they are found instead of collecting them first, honoring
+    /// `offset` and `limit` along the way. Literal and regex searches fetch their
+    /// candidate documents (at most the search budget's fetch limit) up front, then
+    /// match them one file at a time as the stream is polled. Other modes, and options
+    /// that need every result at once (`search_paths`, `normalize_scores`,
+    /// `dedup_by_content_hash` and the `include_*` attachments), run `search` and
+    /// stream its results. Streamed scans don't go through the result cache.
+    ///
+    /// Nothing runs except while the stream is polled, so dropping it (e.g. when a
+    /// client disconnects) cancels the search, including a pending `search`. A
+    /// streamed scan holds its search permit until it ends or is dropped.
+    pub fn search_stream(&self, query: SearchQuery) -> BoxStream<'_, Result<SearchResult>> {
+        let streamable = matches!(query.mode, SearchMode::Literal | SearchMode::Regex)
+            && !query.search_paths
+            && !query.normalize_scores
+            && !query.dedup_by_content_hash
+            && !query.include_symbol_body
+            && !query.include_imports
+            && !query.include_blame
+            && !query.include_enclosing_signature;
+
+        if !streamable {
+            return futures::stream::once(self.search(query))
+                .map_ok(|response| futures::stream::iter(response.results.into_iter().map(Ok)))
+                .try_flatten()
+                .boxed();
+        }
+
+        let (offset, limit) = (query.offset, query.limit);
+        let budget = SearchBudget::new(&query, &self.config);
+        let scan = async move {
+            let permit = self.acquire_search_permit().await?;
+            let files = match query.mode {
+                SearchMode::Regex => self.regex_searcher.scan(&query, &budget).await?,
+                _ => self.literal_searcher.scan(&query, &budget).await?,
+            };
+            let results = files
+                .flat_map(|file| file.results)
+                .take(budget.candidate_limit)
+                .map(|mut r| {
+                    r.relative_path = self.relative_path(&r.file_path);
+                    r
+                })
+                .filter(move |r| !query.exclude_vendored || !self.is_vendored(r))
+                .filter(move |r| {
+                    query
+                        .min_line_length
+                        .is_none_or(|min| r.content.trim().chars().count() >= min)
+                })
+                .skip(offset)
+                .take(limit)
+                .map(move |r| {
+                    let _held = &permit;
+                    Ok(r)
+                });
+            Ok::<_, anyhow::Error>(futures::stream::iter(results))
+        };
+
+        futures::stream::once(scan).try_flatten().boxed()
+    }
+
+    /// Pre-populate the cache by running each query, ignoring any cached result.
+    /// Queries run concurrently, at most `WARM_CACHE_CONCURRENCY` at a time.
+    /// Returns how many queries were cached; failures are logged and skipped.
+    pub async fn warm_cache(&self, queries: Vec<SearchQuery>) -> usize {
+        futures::stream::iter(queries)
+            .map(|query| async move {
+                let text = query.query.clone();
+                self.with_search_permit(self.execute(query))
+                    .await
+                    .inspect_err(|e| tracing::warn!("Failed to warm cache for '{}': {}", text, e))
+                    .is_ok()
+            })
+            .buffer_unordered(WARM_CACHE_CONCURRENCY)
+            .filter(|warmed| futures::future::ready(*warmed))
+            .count()
+            .await
+    }
+
+    /// Run `operation` once a search permit is free. When the engine is configured to
+    /// reject excess load, fail with `RuneError::Overloaded` instead of waiting.
+    async fn with_search_permit<T>(
+        &self,
+        operation: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let _permit = self.acquire_search_permit().await?;
+        operation.await
+    }
+
+    async fn acquire_search_permit(&self) -> Result<SemaphorePermit<'_>> {
+        if self.config.reject_when_overloaded {
+            Ok(self
+                .search_permits
+                .try_acquire()
+                .map_err(|_| RuneError::Overloaded {
+                    max_concurrent: self.config.max_concurrent_searches,
+                })?)
+        } else {
+            Ok(self.search_permits.acquire().await?)
+        }
+    }
+
+    /// Run a search without consulting the cache, then cache the response
+    async fn execute(&self, query: SearchQuery) -> Result<SearchResponse> {
+        let start = std::time::Instant::now();
+        let budget = SearchBudget::new(&query, &self.config);
+        // Taken before searching, so a commit during the search can't pass the
+        // response off as current
+        let scope = self.cache_scope();
+
+        let mut truncated_files = Vec::new();
         let results = match query.mode {
-            SearchMode::Symbol => self.symbol_searcher.search(&query).await?,
-            #[cfg(feature = "semantic")]
-            SearchMode::Semantic => self.semantic_searcher.search(&query).await?,
-            #[cfg(not(feature = "semantic"))]
-            SearchMode::Semantic => {
-                tracing::warn!("Semantic search requested but semantic feature is disabled");
-                vec![]
+            SearchMode::Symbol => {
+                let matches = self.symbol_searcher.search(&query, &budget).await?;
+                truncated_files = matches.truncated_files;
+                matches.results
+            },
+            SearchMode::Literal => {
+                let matches = self.literal_searcher.search(&query, &budget).await?;
+                truncated_files = matches.truncated_files;
+                matches.results
+            },
+            SearchMode::Regex => {
+                let matches = self.regex_searcher.search(&query, &budget).await?;
+                truncated_files = matches.truncated_files;
+                matches.results
             },
+            SearchMode::Semantic => self.semantic_results(&query, &budget).await?,
+            SearchMode::Hybrid => {
+                let (symbol, semantic) = tokio::join!(
+                    self.symbol_searcher.search(&query, &budget),
+                    self.semantic_results(&query, &budget)
+                );
+                let symbol = symbol?;
+                // Symbol matches alone are still useful while the vector store is down
+                let semantic = semantic.unwrap_or_else(|e| {
+                    tracing::warn!("Semantic half of hybrid search failed: {}", e);
+                    Vec::new()
+                });
+                truncated_files = symbol.truncated_files;
+                Self::reciprocal_rank_fusion(vec![symbol.results, semantic])
+            },
+        };
+
+        let results = if query.search_paths {
+            let mut path_results = self.path_matches(&query, &budget).await?;
+            path_results.retain(|p| !results.iter().any(|r| r.file_path == p.file_path));
+            path_results.extend(results);
+            path_results
+        } else {
+            results
+        };
+
+        let mut results = results;
+        for result in &mut results {
+            result.relative_path = self.relative_path(&result.file_path);
+        }
+
+        if query.exclude_vendored {
+            results.retain(|r| !self.is_vendored(r));
+        }
+
+        if let Some(min_length) = query.min_line_length {
+            results.retain(|r| r.content.trim().chars().count() >= min_length);
+        }
+
+        // Fused scores already combine the modes; rescaling by each result's own
+        // match type would undo that
+        let results = if query.normalize_scores && query.mode != SearchMode::Hybrid {
+            Self::normalize_scores(results)
+        } else {
+            results
+        };
+
+        let results = if query.dedup_by_content_hash {
+            self.dedup_by_content_hash(results).await
+        } else {
+            results
+        };
+
+        let suggestions = if query.suggest_on_empty && results.is_empty() {
+            self.suggest(&query.query).await
+        } else {
+            Vec::new()
         };
 
         let total_matches = results.len();
@@ -143,6 +914,30 @@ impl SearchEngine {
             .skip(query.offset)
             .take(query.limit)
             .collect();
+        let results = if query.include_symbol_body {
+            self.attach_symbol_bodies(results).await
+        } else {
+            results
+        };
+
+        let results = if query.include_imports {
+            self.attach_relevant_imports(results).await
+        } else {
+            results
+        };
+
+        let results = if query.include_enclosing_signature {
+            self.attach_enclosing_signatures(results).await
+        } else {
+            results
+        };
+
+        #[cfg(feature = "git-blame")]
+        let results = if query.include_blame {
+            self.blame_cache.annotate(results).await
+        } else {
+            results
+        };
 
         let response = SearchResponse {
             query: query.clone(),
@@ -150,123 +945,849 @@ impl SearchEngine {
             total_matches,
             search_time_ms: start.elapsed().as_millis() as u64,
             from_cache: Some(false),
+            suggestions,
+            truncated_files,
+            schema_version: SEARCH_RESPONSE_SCHEMA_VERSION,
+            continuation_token: None,
+            stale: false,
         };
 
+        // Recently returned files are the last to go under `Config::max_index_bytes`
+        if self.config.max_index_bytes.is_some()
+            && self.config.index_eviction_policy == IndexEvictionPolicy::LeastRecentlyAccessed
+            && !self.config.read_only
+            && !response.results.is_empty()
+        {
+            let mut accessed: Vec<PathBuf> = response
+                .results
+                .iter()
+                .map(|r| r.file_path.clone())
+                .collect();
+            accessed.sort();
+            accessed.dedup();
+            if let Err(e) = self.storage.record_access(&accessed).await {
+                tracing::warn!("Failed to record file access: {}", e);
+            }
+        }
+
         // Store in cache for future queries
-        if let Err(e) = self.cache.put(&query, response.clone()).await {
+        if let Err(e) = self.cache.put(&query, response.clone(), scope).await {
             tracing::warn!("Failed to cache search result: {}", e);
         }
 
         Ok(response)
     }
 
-    /// Get cache metrics for monitoring
-    pub fn cache_metrics(&self) -> Arc<crate::cache::CacheMetrics> {
-        self.cache.metrics()
-    }
+    /// Run a search and cluster the results under the symbol each one falls in.
+    /// Groups keep the order in which their first result was ranked.
+    pub async fn search_grouped_by_symbol(&self, query: SearchQuery) -> Result<Vec<SymbolGroup>> {
+        let response = self.search(query).await?;
 
-    /// Clear the search cache
-    pub async fn clear_cache(&self) {
-        self.cache.clear().await;
-    }
-}
+        let mut file_symbols: HashMap<PathBuf, Vec<Symbol>> = HashMap::new();
+        let mut groups: Vec<SymbolGroup> = Vec::new();
+        let mut group_index: HashMap<(PathBuf, Option<String>, usize), usize> = HashMap::new();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::indexing::Indexer;
-    use std::fs;
-    use tempfile::tempdir;
+        for result in response.results {
+            if !file_symbols.contains_key(&result.file_path) {
+                let symbols = self.file_symbols(&result.file_path).await;
+                file_symbols.insert(result.file_path.clone(), symbols);
+            }
 
-    #[tokio::test]
-    async fn test_symbol_search() {
-        let temp_dir = tempdir().unwrap();
-        let workspace = temp_dir.path().join("workspace");
-        fs::create_dir(&workspace).unwrap();
+            // Result line numbers are 1-indexed, symbol lines 0-indexed
+            let line_idx = result.line_number.saturating_sub(1);
+            let enclosing = file_symbols[&result.file_path]
+                .iter()
+                .filter(|s| s.start_line <= line_idx && s.end_line >= line_idx)
+                .min_by_key(|s| s.end_line - s.start_line);
 
-        // Create test file
-        fs::write(
-            workspace.join("test.rs"),
-            r#"
-struct MyStruct {
-    field: String,
-}
+            let symbol = enclosing.map(|s| s.name.clone());
+            // Same-named symbols in one file (e.g. overloads) stay separate
+            let key = (
+                result.file_path.clone(),
+                symbol.clone(),
+                enclosing.map_or(0, |s| s.start_line),
+            );
 
-impl MyStruct {
-    fn new() -> Self {
-        Self { field: String::new() }
+            match group_index.get(&key) {
+                Some(&idx) => groups[idx].results.push(result),
+                None => {
+                    group_index.insert(key, groups.len());
+                    groups.push(SymbolGroup {
+                        symbol,
+                        file_path: result.file_path.clone(),
+                        results: vec![result],
+                    });
+                },
+            }
+        }
+
+        Ok(groups)
     }
-}
 
-fn helper_function() {}
-            "#,
-        )
-        .unwrap();
+    /// Run a search and merge each file's matches into hunks. Matches whose context
+    /// overlaps or touches become one hunk, so shared context lines appear once.
+    /// Files keep the order in which their first result was ranked; hunks within a
+    /// file are in line order.
+    pub async fn search_hunks(&self, query: SearchQuery) -> Result<Vec<Hunk>> {
+        let response = self.search(query).await?;
 
-        let config = Arc::new(Config {
-            workspace_roots: vec![workspace],
-            cache_dir: temp_dir.path().join("cache"),
-            ..Default::default()
-        });
+        let mut file_order: Vec<PathBuf> = Vec::new();
+        let mut file_lines: HashMap<PathBuf, BTreeMap<usize, String>> = HashMap::new();
+        let mut match_spans: HashMap<PathBuf, Vec<(usize, usize)>> = HashMap::new();
 
-        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        for result in &response.results {
+            let lines = file_lines
+                .entry(result.file_path.clone())
+                .or_insert_with(|| {
+                    file_order.push(result.file_path.clone());
+                    BTreeMap::new()
+                });
 
-        // Index files first
-        {
-            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
-            indexer.index_workspaces().await.unwrap();
-            // Indexer is dropped here, releasing the writer lock
+            let start = result
+                .line_number
+                .saturating_sub(result.context_before.len());
+            let content = result.content.lines().map(str::to_string);
+            let text = result
+                .context_before
+                .iter()
+                .cloned()
+                .chain(content)
+                .chain(result.context_after.iter().cloned());
+            for (offset, line) in text.enumerate() {
+                lines.entry(start + offset).or_insert(line);
+            }
+
+            let end_line = result.end_line.max(result.line_number);
+            match_spans
+                .entry(result.file_path.clone())
+                .or_default()
+                .push((result.line_number, end_line));
         }
 
-        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let mut hunks = Vec::new();
+        for file_path in file_order {
+            let lines = file_lines.remove(&file_path).unwrap_or_default();
+            let spans = match_spans.remove(&file_path).unwrap_or_default();
 
-        // Test symbol search
-        let query = SearchQuery {
-            query: "MyStruct".to_string(),
-            mode: SearchMode::Symbol,
-            limit: 10,
-            ..Default::default()
-        };
+            let mut current: Option<Hunk> = None;
+            for (line_number, text) in lines {
+                let extends = current
+                    .as_ref()
+                    .is_some_and(|hunk| hunk.end_line + 1 == line_number);
+                if !extends {
+                    hunks.extend(current.take());
+                    current = Some(Hunk {
+                        file_path: file_path.clone(),
+                        start_line: line_number,
+                        end_line: line_number,
+                        lines: Vec::new(),
+                        match_lines: Vec::new(),
+                    });
+                }
 
-        let response = search_engine.search(query).await.unwrap();
-        assert!(response.total_matches > 0);
-        assert!(
-            response
-                .results
-                .iter()
-                .any(|r| r.match_type == MatchType::Symbol)
-        );
+                let hunk = current.as_mut().expect("hunk started above");
+                hunk.end_line = line_number;
+                hunk.lines.push(text);
+                if spans
+                    .iter()
+                    .any(|(start, end)| (*start..=*end).contains(&line_number))
+                {
+                    hunk.match_lines.push(line_number);
+                }
+            }
+            hunks.extend(current);
+        }
+
+        Ok(hunks)
     }
 
-    #[tokio::test]
-    async fn test_search_with_filters() {
-        let temp_dir = tempdir().unwrap();
-        let workspace = temp_dir.path().join("workspace");
-        fs::create_dir(&workspace).unwrap();
+    /// Fill `body` on symbol results with the innermost symbol enclosing each match
+    async fn attach_symbol_bodies(&self, mut results: Vec<SearchResult>) -> Vec<SearchResult> {
+        let mut files: HashMap<PathBuf, (Vec<String>, Vec<Symbol>)> = HashMap::new();
 
-        // Create test files
-        fs::write(workspace.join("main.rs"), "fn main() {}").unwrap();
-        fs::write(workspace.join("test.py"), "def main(): pass").unwrap();
-        fs::write(workspace.join("index.js"), "function main() {}").unwrap();
+        for result in &mut results {
+            if result.match_type != MatchType::Symbol {
+                continue;
+            }
 
-        let config = Arc::new(Config {
-            workspace_roots: vec![workspace],
-            cache_dir: temp_dir.path().join("cache"),
-            ..Default::default()
-        });
+            if !files.contains_key(&result.file_path) {
+                let lines = tokio::fs::read_to_string(&result.file_path)
+                    .await
+                    .map(|content| content.lines().map(str::to_string).collect())
+                    .unwrap_or_default();
+                let symbols = self.file_symbols(&result.file_path).await;
+                files.insert(result.file_path.clone(), (lines, symbols));
+            }
+            let (lines, symbols) = &files[&result.file_path];
+            let Some(symbol) = enclosing_symbol(symbols, result.line_number) else {
+                continue;
+            };
 
-        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+            let end = (symbol.end_line + 1).min(lines.len());
+            let body = lines
+                .get(symbol.start_line..end)
+                .unwrap_or_default()
+                .join("\n");
+            result.body = Some(
+                match body.char_indices().nth(self.config.max_content_chars) {
+                    Some((cut, _)) => body[..cut].to_string(),
+                    None => body,
+                },
+            );
+        }
 
-        // Index files first
-        {
-            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
-            indexer.index_workspaces().await.unwrap();
-            // Indexer is dropped here, releasing the writer lock
+        results
+    }
+
+    /// Fill `enclosing_signature` on literal and regex results from the innermost
+    /// symbol containing the matched line
+    async fn attach_enclosing_signatures(
+        &self,
+        mut results: Vec<SearchResult>,
+    ) -> Vec<SearchResult> {
+        let mut file_symbols: HashMap<PathBuf, Vec<Symbol>> = HashMap::new();
+
+        for result in &mut results {
+            if !matches!(result.match_type, MatchType::Exact | MatchType::Regex) {
+                continue;
+            }
+
+            if !file_symbols.contains_key(&result.file_path) {
+                let symbols = self.file_symbols(&result.file_path).await;
+                file_symbols.insert(result.file_path.clone(), symbols);
+            }
+            result.enclosing_signature =
+                enclosing_symbol(&file_symbols[&result.file_path], result.line_number)
+                    .and_then(|symbol| symbol.signature.clone());
         }
 
-        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        results
+    }
 
-        // Test search with file pattern filter using symbol mode
+    /// Fill `relevant_imports` with the imports of each result's file that bind a name
+    /// used in the result's content
+    async fn attach_relevant_imports(&self, mut results: Vec<SearchResult>) -> Vec<SearchResult> {
+        let mut file_imports: HashMap<PathBuf, Vec<(String, HashSet<String>)>> = HashMap::new();
+
+        for result in &mut results {
+            if !file_imports.contains_key(&result.file_path) {
+                let imports = match tokio::fs::read_to_string(&result.file_path).await {
+                    Ok(content) => {
+                        let language = LanguageDetector::detect(&result.file_path, Some(&content));
+                        self.symbol_extractor
+                            .extract_imports(&content, language)
+                            .unwrap_or_default()
+                    },
+                    Err(_) => Vec::new(),
+                };
+                let imports = imports
+                    .into_iter()
+                    .map(|import| {
+                        let names = imported_names(&import)
+                            .into_iter()
+                            .map(str::to_string)
+                            .collect();
+                        (import, names)
+                    })
+                    .collect();
+                file_imports.insert(result.file_path.clone(), imports);
+            }
+
+            let used: HashSet<&str> = identifiers(&result.content).collect();
+            result.relevant_imports = file_imports[&result.file_path]
+                .iter()
+                .filter(|(_, names)| names.iter().any(|name| used.contains(name.as_str())))
+                .map(|(import, _)| import.clone())
+                .collect();
+        }
+
+        results
+    }
+
+    /// Symbols defined in a file, or none if it can't be read or parsed
+    async fn file_symbols(&self, file_path: &Path) -> Vec<Symbol> {
+        let Ok(content) = tokio::fs::read_to_string(file_path).await else {
+            return Vec::new();
+        };
+
+        let language = LanguageDetector::detect(file_path, Some(&content));
+        self.symbol_extractor
+            .extract_symbols(file_path, &content, language)
+            .unwrap_or_default()
+    }
+
+    /// Path relative to the innermost workspace root that contains it
+    fn relative_path(&self, file_path: &Path) -> Option<PathBuf> {
+        relative_to_roots(&self.config.workspace_roots, file_path).map(Path::to_path_buf)
+    }
+
+    /// Whether the result sits under one of `Config::vendored_dirs`. Only directories
+    /// inside the workspace count, so a root that happens to live in `build/` is fine.
+    fn is_vendored(&self, result: &SearchResult) -> bool {
+        let path = result.relative_path.as_deref().unwrap_or(&result.file_path);
+        path.parent().is_some_and(|dir| {
+            dir.components().any(|c| {
+                self.config
+                    .vendored_dirs
+                    .iter()
+                    .any(|v| c.as_os_str() == v.as_str())
+            })
+        })
+    }
+
+    /// One result per file whose path components match the query, pointing at line 1
+    async fn path_matches(
+        &self,
+        query: &SearchQuery,
+        budget: &SearchBudget,
+    ) -> Result<Vec<SearchResult>> {
+        let query_parser = tantivy::query::QueryParser::for_index(
+            self.tantivy_indexer.get_searcher().index(),
+            vec![self.tantivy_indexer.get_path_text_field()],
+        );
+        // Lenient, so code-like text such as `foo(` or `a:b` doesn't fail the search
+        let (path_query, _) = query_parser.parse_query_lenient(&query.query);
+        let tantivy_query = self
+            .tantivy_indexer
+            .modified_after(path_query, query.modified_since);
+        let docs = self
+            .tantivy_indexer
+            .search_documents(tantivy_query.as_ref(), budget.fetch_limit)
+            .await?;
+        let file_patterns = query
+            .file_patterns
+            .as_deref()
+            .map(FilePatterns::new)
+            .transpose()?;
+
+        Ok(docs
+            .into_iter()
+            .filter(|doc| {
+                query
+                    .repositories
+                    .as_ref()
+                    .is_none_or(|repos| repos.contains(&doc.repository))
+            })
+            .filter(|doc| {
+                file_patterns.as_ref().is_none_or(|patterns| {
+                    patterns.matches(
+                        self.relative_path(&doc.path)
+                            .as_deref()
+                            .unwrap_or(&doc.path),
+                    )
+                })
+            })
+            .map(|doc| SearchResult {
+                content: doc.content.lines().next().unwrap_or_default().to_string(),
+                context_after: extract_context(
+                    &doc.content.lines().collect::<Vec<_>>(),
+                    0,
+                    0,
+                    query.context_mode.lines(),
+                )
+                .1,
+                file_path: doc.path,
+                repository: doc.repository,
+                line_number: 1,
+                end_line: 1,
+                column: 0,
+                context_before: vec![],
+                score: doc.score,
+                raw_score: doc.score,
+                relative_path: None,
+                match_type: MatchType::Path,
+                source_mode: Some(SearchMode::Symbol),
+                body: None,
+                relevant_imports: Vec::new(),
+                snippet: None,
+                match_ranges: Vec::new(),
+                last_author: None,
+                last_commit: None,
+                enclosing_signature: None,
+            })
+            .take(budget.candidate_limit)
+            .collect())
+    }
+
+    /// Indexed symbol names closest to any of the query's terms
+    async fn suggest(&self, query: &str) -> Vec<String> {
+        let names = match self.tantivy_indexer.symbol_names().await {
+            Ok(names) => names,
+            Err(e) => {
+                tracing::warn!("Failed to load symbol names for suggestions: {}", e);
+                return Vec::new();
+            },
+        };
+
+        let matcher = FuzzyMatcher::default().with_max_token_len(self.config.fuzzy_max_token_len);
+        let mut suggestions = Vec::new();
+        for term in query.split_whitespace() {
+            for name in matcher.closest(term, names.iter().map(String::as_str), MAX_SUGGESTIONS) {
+                if !suggestions.contains(&name) {
+                    suggestions.push(name);
+                }
+            }
+        }
+
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+
+    /// Fails with `RuneError::SemanticDisabled` when built without the `semantic`
+    /// feature, rather than returning no results
+    async fn semantic_results(
+        &self,
+        query: &SearchQuery,
+        budget: &SearchBudget,
+    ) -> Result<Vec<SearchResult>> {
+        #[cfg(feature = "semantic")]
+        {
+            self.semantic_searcher.search(query, budget).await
+        }
+        #[cfg(not(feature = "semantic"))]
+        {
+            let _ = (query, budget);
+            Err(RuneError::SemanticDisabled.into())
+        }
+    }
+
+    /// Merge ranked lists into one, scoring each (file, line) by the sum of
+    /// `1 / (RRF_K + rank)` over the lists it appears in. The first list's copy of a
+    /// duplicate is kept, and its own score stays in `raw_score`.
+    fn reciprocal_rank_fusion(lists: Vec<Vec<SearchResult>>) -> Vec<SearchResult> {
+        let mut fused: Vec<SearchResult> = Vec::new();
+        let mut positions: HashMap<(PathBuf, usize), usize> = HashMap::new();
+
+        for list in lists {
+            for (rank, result) in list.into_iter().enumerate() {
+                let contribution = 1.0 / (RRF_K + rank as f32 + 1.0);
+                let key = (result.file_path.clone(), result.line_number);
+                match positions.get(&key) {
+                    Some(&i) => fused[i].score += contribution,
+                    None => {
+                        positions.insert(key, fused.len());
+                        fused.push(SearchResult {
+                            score: contribution,
+                            ..result
+                        });
+                    },
+                }
+            }
+        }
+
+        fused.sort_by(|a, b| b.score.total_cmp(&a.score));
+        fused
+    }
+
+    /// Min-max scale scores to 0..1 separately for each match type, so BM25 and cosine
+    /// scores end up on the same scale. A mode whose results all tie scores 1.0.
+    fn normalize_scores(mut results: Vec<SearchResult>) -> Vec<SearchResult> {
+        let mut ranges: Vec<(MatchType, f32, f32)> = Vec::new();
+        for result in &results {
+            match ranges.iter_mut().find(|(t, _, _)| *t == result.match_type) {
+                Some((_, min, max)) => {
+                    *min = min.min(result.raw_score);
+                    *max = max.max(result.raw_score);
+                },
+                None => ranges.push((
+                    result.match_type.clone(),
+                    result.raw_score,
+                    result.raw_score,
+                )),
+            }
+        }
+
+        for result in &mut results {
+            if let Some((_, min, max)) = ranges.iter().find(|(t, _, _)| *t == result.match_type) {
+                result.score = if max > min {
+                    (result.raw_score - min) / (max - min)
+                } else {
+                    1.0
+                };
+            }
+        }
+
+        results
+    }
+
+    /// Keep the first result for each (content hash, line) pair so identical copies of a
+    /// file under different paths only show up once. Results without stored metadata
+    /// are kept as-is.
+    async fn dedup_by_content_hash(&self, results: Vec<SearchResult>) -> Vec<SearchResult> {
+        let mut seen = HashSet::new();
+        let mut deduped = Vec::with_capacity(results.len());
+
+        for result in results {
+            match self.storage.get_file_metadata(&result.file_path).await {
+                Ok(Some(metadata)) => {
+                    if seen.insert((metadata.hash, result.line_number)) {
+                        deduped.push(result);
+                    }
+                },
+                _ => deduped.push(result),
+            }
+        }
+
+        deduped
+    }
+
+    /// Semantic search with a precomputed query embedding instead of query text.
+    /// Bypasses the result cache.
+    #[cfg(feature = "semantic")]
+    pub async fn search_by_embedding(
+        &self,
+        vector: Vec<f32>,
+        limit: usize,
+        filter: Option<crate::embedding::VectorFilter>,
+    ) -> Result<Vec<SearchResult>> {
+        self.with_search_permit(
+            self.semantic_searcher
+                .search_by_vector(vector, limit, filter),
+        )
+        .await
+    }
+
+    /// Entries and estimated bytes in the query-side embedding cache
+    pub fn embedding_cache_usage(&self) -> (usize, usize) {
+        #[cfg(feature = "semantic")]
+        let usage = self.semantic_searcher.embedding_cache_usage();
+        #[cfg(not(feature = "semantic"))]
+        let usage = (0, 0);
+        usage
+    }
+
+    /// Which embeddings back semantic search; the default (nothing loaded) without
+    /// the `semantic` feature
+    pub fn embedding_status(&self) -> crate::EmbeddingStatus {
+        #[cfg(feature = "semantic")]
+        let status = self.semantic_searcher.embedding_status();
+        #[cfg(not(feature = "semantic"))]
+        let status = crate::EmbeddingStatus::default();
+        status
+    }
+
+    fn config_fingerprint(config: &Config) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        serde_json::to_string(config)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// What cached responses depend on besides the query
+    fn cache_scope(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.config_fingerprint.hash(&mut hasher);
+        self.tantivy_indexer.index_version().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Entries and estimated bytes in the L1 result cache
+    pub fn result_cache_usage(&self) -> (usize, usize) {
+        self.cache.l1_usage()
+    }
+
+    /// Get cache metrics for monitoring
+    pub fn cache_metrics(&self) -> Arc<crate::cache::CacheMetrics> {
+        self.cache.metrics()
+    }
+
+    /// Drop cached responses whose results reference `path`, after it was removed.
+    /// A created or edited file can match queries it didn't before; use `clear_cache`.
+    pub fn invalidate_file(&self, path: &Path) -> usize {
+        self.cache.invalidate_file(path)
+    }
+
+    /// The result cache, for an indexer to invalidate as files change
+    pub fn result_cache(&self) -> Arc<MultiTierCache> {
+        self.cache.clone()
+    }
+
+    /// The index searches read, for an indexer writing through its own handle to
+    /// reload after committing
+    pub fn tantivy_indexer(&self) -> Arc<TantivyIndexer> {
+        self.tantivy_indexer.clone()
+    }
+
+    /// Clear the search cache
+    pub async fn clear_cache(&self) {
+        self.cache.clear().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexing::Indexer;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_to_jsonl() {
+        let result = |line_number: usize, content: &str| SearchResult {
+            file_path: PathBuf::from("src/lib.rs"),
+            relative_path: Some(PathBuf::from("lib.rs")),
+            repository: "repo".to_string(),
+            line_number,
+            end_line: line_number,
+            column: 0,
+            content: content.to_string(),
+            context_before: vec![],
+            context_after: vec!["}".to_string()],
+            score: 1.5,
+            raw_score: 1.5,
+            match_type: MatchType::Symbol,
+            source_mode: Some(SearchMode::Symbol),
+            body: None,
+            relevant_imports: Vec::new(),
+            snippet: None,
+            match_ranges: Vec::new(),
+            last_author: None,
+            last_commit: None,
+            enclosing_signature: None,
+        };
+        let response = SearchResponse {
+            query: SearchQuery::default(),
+            results: vec![result(3, "fn a() {"), result(9, "fn b() {\n\"quoted\"")],
+            total_matches: 2,
+            search_time_ms: 1,
+            from_cache: Some(false),
+            suggestions: vec![],
+            truncated_files: vec![],
+            schema_version: SEARCH_RESPONSE_SCHEMA_VERSION,
+            continuation_token: None,
+            stale: false,
+        };
+
+        let jsonl = response.to_jsonl().unwrap();
+        assert!(jsonl.ends_with('\n'));
+
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), response.results.len());
+        for (line, expected) in lines.iter().zip(&response.results) {
+            let parsed: SearchResult = serde_json::from_str(line).unwrap();
+            assert_eq!(parsed.line_number, expected.line_number);
+            assert_eq!(parsed.content, expected.content);
+            assert_eq!(parsed.relative_path, expected.relative_path);
+        }
+
+        let empty = SearchResponse {
+            results: vec![],
+            ..response
+        };
+        assert_eq!(empty.to_jsonl().unwrap(), "");
+    }
+
+    #[test]
+    fn test_response_schema_version() {
+        let response = SearchResponse {
+            query: SearchQuery::default(),
+            results: vec![],
+            total_matches: 0,
+            search_time_ms: 0,
+            from_cache: None,
+            suggestions: vec![],
+            truncated_files: vec![],
+            schema_version: SEARCH_RESPONSE_SCHEMA_VERSION,
+            continuation_token: None,
+            stale: false,
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["schema_version"], SEARCH_RESPONSE_SCHEMA_VERSION);
+
+        // Payloads from before the field existed come back as version 0
+        let mut legacy = json;
+        legacy.as_object_mut().unwrap().remove("schema_version");
+        let parsed: SearchResponse = serde_json::from_value(legacy).unwrap();
+        assert_eq!(parsed.schema_version, 0);
+        assert_eq!(parsed.total_matches, 0);
+    }
+
+    #[test]
+    fn test_take_page_splits_large_responses() {
+        let result = |line_number: usize| SearchResult {
+            file_path: PathBuf::from("/workspace/src/lib.rs"),
+            relative_path: None,
+            repository: "workspace".to_string(),
+            line_number,
+            end_line: line_number,
+            column: 0,
+            content: "x".repeat(100),
+            context_before: vec![],
+            context_after: vec![],
+            score: 1.0,
+            raw_score: 1.0,
+            match_type: MatchType::Symbol,
+            source_mode: Some(SearchMode::Symbol),
+            body: None,
+            relevant_imports: Vec::new(),
+            snippet: None,
+            match_ranges: Vec::new(),
+            last_author: None,
+            last_commit: None,
+            enclosing_signature: None,
+        };
+        let mut response = SearchResponse {
+            query: SearchQuery::default(),
+            results: (1..=50).map(result).collect(),
+            total_matches: 50,
+            search_time_ms: 1,
+            from_cache: Some(false),
+            suggestions: vec![],
+            truncated_files: vec![],
+            schema_version: SEARCH_RESPONSE_SCHEMA_VERSION,
+            continuation_token: None,
+            stale: false,
+        };
+        let full_size = serde_json::to_string(&response).unwrap().len();
+
+        let mut pages = Vec::new();
+        while !response.results.is_empty() {
+            pages.push(response.take_page(2_000).unwrap());
+        }
+
+        // Every result arrives once, in order, across several bounded pages
+        assert!(pages.len() > 1);
+        let lines: Vec<usize> = pages
+            .iter()
+            .flat_map(|p| p.results.iter().map(|r| r.line_number))
+            .collect();
+        assert_eq!(lines, (1..=50).collect::<Vec<_>>());
+        for page in &pages {
+            assert_eq!(page.total_matches, 50);
+            let size = serde_json::to_string(&page.results).unwrap().len();
+            assert!(size <= 2_000, "page of {size} bytes");
+            assert!(size < full_size);
+        }
+
+        // A result larger than the budget still gets a page of its own
+        let mut response = SearchResponse {
+            results: vec![result(1), result(2)],
+            ..pages.pop().unwrap()
+        };
+        assert_eq!(response.take_page(10).unwrap().results.len(), 1);
+        assert_eq!(response.results.len(), 1);
+    }
+
+    #[test]
+    fn test_extract_context() {
+        let lines = ["one", "two", "three", "four", "five"];
+
+        // First line: nothing before, clamped count after
+        let (before, after) = extract_context(&lines, 0, 3, 3);
+        assert!(before.is_empty());
+        assert_eq!(after, vec!["two", "three", "four"]);
+
+        // Second line: only one line exists before it
+        let (before, after) = extract_context(&lines, 1, 3, 1);
+        assert_eq!(before, vec!["one"]);
+        assert_eq!(after, vec!["three"]);
+
+        // Middle
+        let (before, after) = extract_context(&lines, 2, 2, 2);
+        assert_eq!(before, vec!["one", "two"]);
+        assert_eq!(after, vec!["four", "five"]);
+
+        // Last line: nothing after
+        let (before, after) = extract_context(&lines, 4, 2, 3);
+        assert_eq!(before, vec!["three", "four"]);
+        assert!(after.is_empty());
+
+        // Out of range (the file shrank since indexing)
+        let (before, after) = extract_context(&lines, 9, 2, 2);
+        assert!(before.is_empty() && after.is_empty());
+        assert_eq!(extract_context(&[], 0, 3, 3), (vec![], vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_symbol_search() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+
+        // Create test file
+        fs::write(
+            workspace.join("test.rs"),
+            r#"
+struct MyStruct {
+    field: String,
+}
+
+impl MyStruct {
+    fn new() -> Self {
+        Self { field: String::new() }
+    }
+}
+
+fn helper_function() {}
+            "#,
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+
+        // Index files first
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+            // Indexer is dropped here, releasing the writer lock
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+
+        // Test symbol search
+        let query = SearchQuery {
+            query: "MyStruct".to_string(),
+            mode: SearchMode::Symbol,
+            limit: 10,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query).await.unwrap();
+        assert!(response.total_matches > 0);
+        assert!(
+            response
+                .results
+                .iter()
+                .any(|r| r.match_type == MatchType::Symbol)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_with_filters() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+
+        // Create test files
+        fs::write(workspace.join("main.rs"), "fn main() {}").unwrap();
+        fs::write(workspace.join("test.py"), "def main(): pass").unwrap();
+        fs::write(workspace.join("index.js"), "function main() {}").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+
+        // Index files first
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+            // Indexer is dropped here, releasing the writer lock
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+
+        // Test search with file pattern filter using symbol mode
         let query = SearchQuery {
             query: "main".to_string(),
             mode: SearchMode::Symbol,
@@ -285,4 +1806,2203 @@ fn helper_function() {}
                 .all(|r| r.file_path.extension().is_some_and(|e| e == "rs"))
         );
     }
+
+    #[tokio::test]
+    async fn test_dedup_by_content_hash() {
+        let temp_dir = tempdir().unwrap();
+        let source = "fn duplicated_helper() -> u32 {\n    42\n}\n";
+        let roots: Vec<_> = ["app", "vendor"]
+            .iter()
+            .map(|name| {
+                let root = temp_dir.path().join(name);
+                fs::create_dir(&root).unwrap();
+                fs::write(root.join("helper.rs"), source).unwrap();
+                root
+            })
+            .collect();
+
+        let config = Arc::new(Config {
+            workspace_roots: roots,
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+
+        let query = SearchQuery {
+            query: "duplicated_helper".to_string(),
+            mode: SearchMode::Symbol,
+            limit: 10,
+            ..Default::default()
+        };
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.results.len(), 2);
+
+        let response = search_engine
+            .search(SearchQuery {
+                dedup_by_content_hash: true,
+                ..query
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_grouped_by_symbol() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+
+        fs::write(
+            workspace.join("router.rs"),
+            r#"fn route_get() {
+    fn route_get_inner() {}
+    route_get_inner();
+}
+
+fn route_post() {
+    fn route_post_inner() {}
+    route_post_inner();
+}
+"#,
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+
+        let groups = search_engine
+            .search_grouped_by_symbol(SearchQuery {
+                query: "route".to_string(),
+                mode: SearchMode::Symbol,
+                limit: 10,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let group_for = |name: &str| {
+            groups
+                .iter()
+                .find(|g| g.symbol.as_deref() == Some(name))
+                .unwrap_or_else(|| panic!("missing group for {}", name))
+        };
+
+        // Nested fns aren't symbols of their own, so they fall under the outer function
+        let lines = |name: &str| {
+            group_for(name)
+                .results
+                .iter()
+                .map(|r| r.line_number)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(groups.len(), 2);
+        assert_eq!(lines("route_get"), vec![1, 2]);
+        assert_eq!(lines("route_post"), vec![6, 7]);
+    }
+
+    #[tokio::test]
+    async fn test_search_hunks() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+
+        let mut content = String::new();
+        for i in 1..=30 {
+            match i {
+                5 | 7 | 10 => content.push_str(&format!("fn retry_{i}() {{}}\n")),
+                25 => content.push_str("fn retry_later() {}\n"),
+                _ => content.push_str(&format!("const VALUE_{i}: u32 = {i};\n")),
+            }
+        }
+        fs::write(workspace.join("client.rs"), &content).unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let hunks = search_engine
+            .search_hunks(SearchQuery {
+                query: "retry".to_string(),
+                mode: SearchMode::Symbol,
+                limit: 10,
+                context_mode: ContextMode::Lines(3),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // With 3 lines of context: lines 5, 7 and 10 share theirs
+        // (2..=13), while line 25 stands alone (22..=28)
+        assert_eq!(hunks.len(), 2, "{:?}", hunks);
+        let first = &hunks[0];
+        assert_eq!(first.file_path, workspace.join("client.rs"));
+        assert_eq!((first.start_line, first.end_line), (2, 13));
+        assert_eq!(first.match_lines, vec![5, 7, 10]);
+        assert_eq!(first.lines.len(), 12);
+        assert_eq!(first.lines[0], "const VALUE_2: u32 = 2;");
+        assert_eq!(first.lines[3], "fn retry_5() {}");
+
+        let second = &hunks[1];
+        assert_eq!((second.start_line, second.end_line), (22, 28));
+        assert_eq!(second.match_lines, vec![25]);
+        assert_eq!(second.lines[3], "fn retry_later() {}");
+    }
+
+    #[tokio::test]
+    async fn test_relative_path() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir_all(workspace.join("src/net")).unwrap();
+        let file = workspace.join("src/net/client.rs");
+        fs::write(&file, "pub fn connect_client() {}\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let response = search_engine
+            .search(SearchQuery {
+                query: "connect_client".to_string(),
+                mode: SearchMode::Symbol,
+                limit: 10,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.results.len(), 1);
+        let result = &response.results[0];
+        assert_eq!(result.file_path, file);
+        assert!(result.file_path.is_absolute());
+        assert_eq!(
+            result.relative_path.as_deref(),
+            Some(Path::new("src/net/client.rs"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_paths() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir_all(workspace.join("src")).unwrap();
+        fs::write(
+            workspace.join("src/config.rs"),
+            "pub struct Settings {\n    pub verbose: bool,\n}\n",
+        )
+        .unwrap();
+        fs::write(workspace.join("src/main.rs"), "fn main() {}\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+
+        let query = SearchQuery {
+            query: "config".to_string(),
+            mode: SearchMode::Symbol,
+            limit: 10,
+            ..Default::default()
+        };
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.total_matches, 0);
+
+        let response = search_engine
+            .search(SearchQuery {
+                search_paths: true,
+                ..query.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.total_matches, 1);
+        let result = &response.results[0];
+        assert!(result.file_path.ends_with("src/config.rs"));
+        assert_eq!(result.match_type, MatchType::Path);
+        assert_eq!(result.line_number, 1);
+
+        // Query syntax in code-like text doesn't fail the path search
+        for text in ["config(", "src:config"] {
+            search_engine
+                .search(SearchQuery {
+                    query: text.to_string(),
+                    mode: SearchMode::Literal,
+                    search_paths: true,
+                    ..query.clone()
+                })
+                .await
+                .unwrap();
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_non_utf8_path_results() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        let file = workspace.join(std::ffi::OsStr::from_bytes(b"caf\xe9.rs"));
+        fs::write(&file, "fn brew_coffee() {\n    let cups = 2;\n}\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            store_content: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        // Content is read back, and the body found, through the real path
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let response = search_engine
+            .search(SearchQuery {
+                query: "brew_coffee".to_string(),
+                mode: SearchMode::Symbol,
+                include_symbol_body: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.results.len(), 1);
+        let result = &response.results[0];
+        assert_eq!(result.file_path, file);
+        assert!(result.body.as_deref().unwrap().contains("let cups = 2;"));
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            json["results"][0]["file_path"],
+            file.to_string_lossy().as_ref()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_suggest_on_empty() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(
+            workspace.join("lib.rs"),
+            "fn calculate_total() {}\n\nstruct InvoiceLine;\n",
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+
+        let query = SearchQuery {
+            query: "calcualte_total".to_string(),
+            mode: SearchMode::Symbol,
+            limit: 10,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.total_matches, 0);
+        assert!(response.suggestions.is_empty());
+
+        let response = search_engine
+            .search(SearchQuery {
+                suggest_on_empty: true,
+                ..query
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.total_matches, 0);
+        assert_eq!(response.suggestions, vec!["calculate_total"]);
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent_searches() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let temp_dir = tempdir().unwrap();
+        let config = Config {
+            workspace_roots: vec![temp_dir.path().to_path_buf()],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            max_concurrent_searches: 1,
+            ..Default::default()
+        };
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let search_engine = SearchEngine::new(Arc::new(config.clone()), storage.clone())
+            .await
+            .unwrap();
+
+        // A slow search that records how many run at the same time
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let slow_search = || async {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        };
+
+        let start = std::time::Instant::now();
+        let (first, second) = tokio::join!(
+            search_engine.with_search_permit(slow_search()),
+            search_engine.with_search_permit(slow_search())
+        );
+        first.unwrap();
+        second.unwrap();
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(100));
+        drop(search_engine);
+
+        // In reject mode the second search fails instead of waiting
+        let search_engine = SearchEngine::new(
+            Arc::new(Config {
+                reject_when_overloaded: true,
+                ..config
+            }),
+            storage,
+        )
+        .await
+        .unwrap();
+        let (first, second) = tokio::join!(
+            search_engine.with_search_permit(slow_search()),
+            search_engine.with_search_permit(slow_search())
+        );
+        first.unwrap();
+        let err = second.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RuneError>(),
+            Some(RuneError::Overloaded { max_concurrent: 1 })
+        ));
+    }
+
+    #[cfg(feature = "semantic")]
+    #[tokio::test]
+    async fn test_stale_semantic_results_during_outage() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        let file = workspace.join("auth.rs");
+        let content = "fn verify_token(token: &str) -> bool {\n    !token.is_empty()\n}\n";
+        fs::write(&file, content).unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            semantic_stale_grace_secs: 60,
+            ..Default::default()
+        });
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let mut search_engine = SearchEngine::new(config.clone(), storage.clone())
+            .await
+            .unwrap();
+
+        // Without Qdrant the engine's own searcher is unavailable; stand in an
+        // in-memory one for the healthy period
+        let offline = search_engine.semantic_searcher.clone();
+        assert!(!offline.is_available());
+        let pipeline = Arc::new(crate::embedding::EmbeddingPipeline::in_memory_stand_in(
+            config.clone(),
+        ));
+        pipeline
+            .process_file(&file.to_string_lossy(), content)
+            .await
+            .unwrap();
+        search_engine.semantic_searcher =
+            semantic::SemanticSearcher::with_pipeline(config.clone(), storage, pipeline);
+        search_engine.cache = Arc::new(MultiTierCache::new(
+            CacheConfig {
+                l1_ttl: Duration::from_millis(50),
+                stale_grace: Duration::from_secs(60),
+                ..Default::default()
+            },
+            None,
+        ));
+
+        let query = SearchQuery {
+            query: "verify token".to_string(),
+            mode: SearchMode::Semantic,
+            limit: 10,
+            ..Default::default()
+        };
+        let live = search_engine.search(query.clone()).await.unwrap();
+        assert!(!live.results.is_empty());
+        assert!(!live.stale);
+
+        // The entry expires while the vector store is down
+        search_engine.semantic_searcher = offline;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let stale = search_engine.search(query.clone()).await.unwrap();
+        assert!(stale.stale);
+        assert_eq!(stale.from_cache, Some(true));
+        assert_eq!(stale.results.len(), live.results.len());
+        assert_eq!(stale.results[0].file_path, file);
+
+        // Symbol queries are never served stale
+        let symbol = search_engine
+            .search(SearchQuery {
+                mode: SearchMode::Symbol,
+                ..query
+            })
+            .await
+            .unwrap();
+        assert!(!symbol.stale);
+    }
+
+    #[cfg(feature = "semantic")]
+    #[tokio::test]
+    async fn test_hybrid_search_mode() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        let files = [
+            (
+                workspace.join("auth.rs"),
+                "fn verify_token(token: &str) -> bool {\n    !token.is_empty()\n}\n",
+            ),
+            (
+                workspace.join("session.rs"),
+                "fn refresh_session(id: u64) -> u64 {\n    id + 1\n}\n",
+            ),
+        ];
+        for (path, content) in &files {
+            fs::write(path, content).unwrap();
+        }
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let mut search_engine = SearchEngine::new(config.clone(), storage.clone())
+            .await
+            .unwrap();
+        let pipeline = Arc::new(crate::embedding::EmbeddingPipeline::in_memory_stand_in(
+            config.clone(),
+        ));
+        for (path, content) in &files {
+            pipeline
+                .process_file(&path.to_string_lossy(), content)
+                .await
+                .unwrap();
+        }
+        search_engine.semantic_searcher =
+            semantic::SemanticSearcher::with_pipeline(config, storage, pipeline);
+
+        let query = SearchQuery {
+            query: "verify_token".to_string(),
+            mode: SearchMode::Hybrid,
+            limit: 10,
+            ..Default::default()
+        };
+        let response = search_engine.search(query.clone()).await.unwrap();
+
+        // The definition is found by both searchers and fused into one result that
+        // outranks anything only one of them found
+        let first = &response.results[0];
+        assert_eq!(first.file_path, files[0].0);
+        assert_eq!(first.line_number, 1);
+        assert!(first.score > 1.0 / (RRF_K + 1.0));
+        // Fused results keep the mode of the searcher that found them first
+        assert_eq!(first.source_mode, Some(SearchMode::Symbol));
+        assert!(
+            response
+                .results
+                .iter()
+                .any(|r| r.source_mode == Some(SearchMode::Semantic))
+        );
+        let keys: HashSet<_> = response
+            .results
+            .iter()
+            .map(|r| (r.file_path.clone(), r.line_number))
+            .collect();
+        assert_eq!(keys.len(), response.results.len());
+        assert!(
+            response
+                .results
+                .windows(2)
+                .all(|pair| pair[0].score >= pair[1].score)
+        );
+
+        // Normalizing leaves the fused scores alone
+        let normalized = search_engine
+            .search(SearchQuery {
+                normalize_scores: true,
+                ..query.clone()
+            })
+            .await
+            .unwrap();
+        let scores = |response: &SearchResponse| -> Vec<f32> {
+            response.results.iter().map(|r| r.score).collect()
+        };
+        assert_eq!(scores(&normalized), scores(&response));
+
+        // Paging applies to the fused list
+        let paged = search_engine
+            .search(SearchQuery {
+                offset: 1,
+                limit: 1,
+                ..query
+            })
+            .await
+            .unwrap();
+        assert_eq!(paged.total_matches, response.total_matches);
+        assert_eq!(paged.results.len(), 1);
+        assert_eq!(paged.results[0].file_path, response.results[1].file_path);
+        assert_eq!(
+            paged.results[0].line_number,
+            response.results[1].line_number
+        );
+    }
+
+    #[tokio::test]
+    async fn test_warm_cache() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(
+            workspace.join("lib.rs"),
+            "fn load_config() {}\n\nfn save_config() {}\n",
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            result_cache_l2_ttl_secs: 3600,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config.clone(), storage.clone())
+            .await
+            .unwrap();
+
+        let queries: Vec<_> = ["load_config", "save_config"]
+            .iter()
+            .map(|q| SearchQuery {
+                query: q.to_string(),
+                mode: SearchMode::Symbol,
+                limit: 10,
+                ..Default::default()
+            })
+            .collect();
+
+        assert_eq!(search_engine.warm_cache(queries.clone()).await, 2);
+
+        for query in &queries {
+            let response = search_engine.search(query.clone()).await.unwrap();
+            assert_eq!(response.from_cache, Some(true));
+            assert_eq!(response.total_matches, 1);
+        }
+
+        // Warmed responses were persisted under the current index's scope
+        let restarted = SearchEngine::new(config, storage).await.unwrap();
+        for query in queries {
+            let response = restarted.search(query).await.unwrap();
+            assert_eq!(response.from_cache, Some(true));
+        }
+        assert_eq!(
+            restarted
+                .cache_metrics()
+                .l2_hits
+                .load(std::sync::atomic::Ordering::Relaxed),
+            2
+        );
+    }
+
+    #[test]
+    fn test_normalize_scores() {
+        let result = |score: f32, match_type: MatchType| SearchResult {
+            file_path: PathBuf::from("lib.rs"),
+            repository: "repo".to_string(),
+            line_number: 1,
+            end_line: 1,
+            column: 0,
+            content: String::new(),
+            context_before: vec![],
+            context_after: vec![],
+            score,
+            raw_score: score,
+            relative_path: None,
+            source_mode: Some(if match_type == MatchType::Semantic {
+                SearchMode::Semantic
+            } else {
+                SearchMode::Symbol
+            }),
+            match_type,
+            body: None,
+            relevant_imports: Vec::new(),
+            snippet: None,
+            match_ranges: Vec::new(),
+            last_author: None,
+            last_commit: None,
+            enclosing_signature: None,
+        };
+
+        let results = SearchEngine::normalize_scores(vec![
+            result(12.5, MatchType::Symbol),
+            result(0.91, MatchType::Semantic),
+            result(7.0, MatchType::Symbol),
+            result(0.42, MatchType::Semantic),
+            result(3.1, MatchType::Symbol),
+            result(0.67, MatchType::Semantic),
+        ]);
+
+        assert!(results.iter().all(|r| (0.0..=1.0).contains(&r.score)));
+        for match_type in [MatchType::Symbol, MatchType::Semantic] {
+            let mode: Vec<_> = results
+                .iter()
+                .filter(|r| r.match_type == match_type)
+                .collect();
+            assert_eq!(mode[0].score, 1.0);
+            assert_eq!(
+                mode[1].score > mode[2].score,
+                mode[1].raw_score > mode[2].raw_score
+            );
+            assert!(mode.iter().any(|r| r.score == 0.0));
+        }
+        assert_eq!(results[0].raw_score, 12.5);
+    }
+
+    #[test]
+    fn test_match_type_serialization() {
+        // Clients match on these strings, so they must not change
+        let cases = [
+            (MatchType::Semantic, "\"Semantic\""),
+            (MatchType::Symbol, "\"Symbol\""),
+            (MatchType::Path, "\"Path\""),
+            (MatchType::Reference, "\"Reference\""),
+            (MatchType::Exact, "\"Exact\""),
+            (MatchType::Regex, "\"Regex\""),
+            (MatchType::Fuzzy, "\"Fuzzy\""),
+        ];
+        for (match_type, json) in cases {
+            assert_eq!(serde_json::to_string(&match_type).unwrap(), json);
+            assert_eq!(serde_json::from_str::<MatchType>(json).unwrap(), match_type);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_candidate_limit_surfaces_filtered_matches() {
+        let temp_dir = tempdir().unwrap();
+        let alpha = temp_dir.path().join("alpha");
+        let beta = temp_dir.path().join("beta");
+        fs::create_dir(&alpha).unwrap();
+        fs::create_dir(&beta).unwrap();
+
+        // Short files in alpha rank above the longer file in beta
+        for i in 0..5 {
+            fs::write(
+                alpha.join(format!("handler_{i}.rs")),
+                "fn request_handler() {}\n",
+            )
+            .unwrap();
+        }
+        let mut beta_source = String::from("fn request_handler() {}\n");
+        for i in 0..20 {
+            beta_source.push_str(&format!("fn helper_{i}() {{}}\n"));
+        }
+        fs::write(beta.join("handlers.rs"), beta_source).unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![alpha, beta],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+
+        let query = SearchQuery {
+            query: "request_handler".to_string(),
+            mode: SearchMode::Symbol,
+            repositories: Some(vec!["beta".to_string()]),
+            limit: 1,
+            candidate_limit: Some(1),
+            ..Default::default()
+        };
+
+        // Only the top candidate is fetched and it belongs to the filtered-out repository
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert!(response.results.is_empty());
+
+        let response = search_engine
+            .search(SearchQuery {
+                candidate_limit: Some(10),
+                ..query
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.results.len(), 1);
+        assert_eq!(response.results[0].repository, "beta");
+    }
+
+    #[test]
+    fn test_search_budget() {
+        let config = Config {
+            candidate_multiplier: 2,
+            filter_oversample_factor: 5,
+            rerank_candidates: 50,
+            ..Default::default()
+        };
+        let query = SearchQuery {
+            limit: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            SearchBudget::new(&query, &config),
+            SearchBudget {
+                fetch_limit: 20,
+                max_expansions: 50,
+                candidate_limit: 20,
+            }
+        );
+
+        // Filters oversample the fetch, not what is kept
+        let filtered = SearchQuery {
+            file_patterns: Some(vec!["*.rs".to_string()]),
+            ..query.clone()
+        };
+        let budget = SearchBudget::new(&filtered, &config);
+        assert_eq!((budget.fetch_limit, budget.candidate_limit), (100, 20));
+        assert_eq!(budget.max_expansions, 100);
+
+        // An explicit candidate limit is fetched as is, but never less than the page
+        let explicit = SearchQuery {
+            candidate_limit: Some(5),
+            offset: 5,
+            ..filtered
+        };
+        let budget = SearchBudget::new(&explicit, &config);
+        assert_eq!((budget.fetch_limit, budget.candidate_limit), (15, 15));
+    }
+
+    #[tokio::test]
+    async fn test_modes_share_search_budget() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        for i in 0..12 {
+            fs::write(
+                workspace.join(format!("marker_{i}.rs")),
+                "fn budget_marker() {}\n",
+            )
+            .unwrap();
+        }
+
+        let base = Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        };
+        let storage = StorageBackend::new(&base.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(Arc::new(base.clone()), storage.clone())
+                .await
+                .unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let query = SearchQuery {
+            query: "budget_marker".to_string(),
+            limit: 2,
+            ..Default::default()
+        };
+        for (multiplier, expected) in [(3, 6), (5, 10), (10, 12)] {
+            let config = Arc::new(Config {
+                candidate_multiplier: multiplier,
+                ..base.clone()
+            });
+            let search_engine = SearchEngine::new(config, storage.clone()).await.unwrap();
+
+            for mode in [SearchMode::Symbol, SearchMode::Literal, SearchMode::Regex] {
+                let response = search_engine
+                    .search(SearchQuery {
+                        mode: mode.clone(),
+                        ..query.clone()
+                    })
+                    .await
+                    .unwrap();
+                assert_eq!(response.total_matches, expected, "{mode:?} x{multiplier}");
+                assert_eq!(response.results.len(), 2);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_matches_per_file() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+
+        let many: String = (0..40)
+            .map(|i| format!("fn handler_{i}() {{}}\n"))
+            .collect();
+        fs::write(workspace.join("many.rs"), many).unwrap();
+        fs::write(workspace.join("few.rs"), "fn handler_main() {}\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            max_matches_per_file: 5,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let response = search_engine
+            .search(SearchQuery {
+                query: "handler".to_string(),
+                mode: SearchMode::Symbol,
+                limit: 100,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let from_file = |name: &str| {
+            response
+                .results
+                .iter()
+                .filter(|r| r.file_path.ends_with(name))
+                .count()
+        };
+        assert_eq!(from_file("many.rs"), 5);
+        assert_eq!(from_file("few.rs"), 1);
+        assert_eq!(response.total_matches, 6);
+        assert_eq!(response.truncated_files, vec![workspace.join("many.rs")]);
+    }
+
+    #[tokio::test]
+    async fn test_modified_since() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        // More old matches than the candidate limit, so filtering after it would
+        // leave nothing
+        for i in 0..50 {
+            fs::write(
+                workspace.join(format!("old_{i}.rs")),
+                format!("fn parse_old_{i}() {{}}\n"),
+            )
+            .unwrap();
+        }
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+        indexer.index_workspaces().await.unwrap();
+
+        // Index the new file in a later second than the old ones
+        let now = || {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+        let since = now();
+        while now() <= since {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        fs::write(workspace.join("new.rs"), "fn parse_new() {}\n").unwrap();
+        indexer.index_workspaces().await.unwrap();
+        drop(indexer);
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        for mode in [SearchMode::Symbol, SearchMode::Literal, SearchMode::Regex] {
+            let query = SearchQuery {
+                query: "parse".to_string(),
+                mode: mode.clone(),
+                limit: 1,
+                ..Default::default()
+            };
+
+            let response = search_engine.search(query.clone()).await.unwrap();
+            assert_eq!(response.results.len(), 1);
+
+            let query = SearchQuery {
+                modified_since: Some(since),
+                ..query
+            };
+            let response = search_engine.search(query.clone()).await.unwrap();
+            assert_eq!(response.results.len(), 1, "{mode:?}");
+            assert!(response.results[0].file_path.ends_with("new.rs"));
+            assert_eq!(response.from_cache, Some(false));
+
+            let streamed: Vec<_> = search_engine
+                .search_stream(query)
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(streamed.len(), 1, "{mode:?}");
+            assert!(streamed[0].file_path.ends_with("new.rs"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exclude_vendored() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        let vendored = workspace.join("node_modules").join("left-pad");
+        fs::create_dir_all(&vendored).unwrap();
+        fs::create_dir_all(workspace.join("src")).unwrap();
+        fs::write(vendored.join("index.js"), "function pad_left() {}\n").unwrap();
+        fs::write(workspace.join("src/pad.js"), "function pad_right() {}\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let query = SearchQuery {
+            query: "pad".to_string(),
+            mode: SearchMode::Symbol,
+            limit: 10,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.results.len(), 2);
+
+        let response = search_engine
+            .search(SearchQuery {
+                exclude_vendored: true,
+                ..query
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.results.len(), 1);
+        assert!(response.results[0].file_path.ends_with("src/pad.js"));
+    }
+
+    #[tokio::test]
+    async fn test_file_patterns_match_relative_paths() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir_all(workspace.join("src/net")).unwrap();
+        fs::create_dir_all(workspace.join("tests")).unwrap();
+        fs::write(workspace.join("main.rs"), "fn glob_probe_main() {}\n").unwrap();
+        fs::write(workspace.join("src/net/tcp.rs"), "fn glob_probe_tcp() {}\n").unwrap();
+        fs::write(
+            workspace.join("tests/test_a.py"),
+            "def glob_probe_a(): pass\n",
+        )
+        .unwrap();
+        fs::write(
+            workspace.join("tests/test_c.py"),
+            "def glob_probe_c(): pass\n",
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let search = |pattern: &str| {
+            let query = SearchQuery {
+                query: "glob_probe".to_string(),
+                mode: SearchMode::Symbol,
+                file_patterns: Some(vec![pattern.to_string()]),
+                limit: 10,
+                ..Default::default()
+            };
+            let search_engine = &search_engine;
+            async move {
+                let mut names: Vec<String> = search_engine
+                    .search(query)
+                    .await
+                    .unwrap()
+                    .results
+                    .iter()
+                    .map(|r| {
+                        r.file_path
+                            .file_name()
+                            .unwrap()
+                            .to_string_lossy()
+                            .into_owned()
+                    })
+                    .collect();
+                names.sort();
+                names
+            }
+        };
+
+        assert_eq!(search("*.rs").await, vec!["main.rs", "tcp.rs"]);
+        assert_eq!(search("src/**/*.rs").await, vec!["tcp.rs"]);
+        assert_eq!(search("src/*.rs").await, Vec::<String>::new());
+        assert_eq!(search("**/test_*.py").await, vec!["test_a.py", "test_c.py"]);
+        assert_eq!(search("tests/test_[ab].py").await, vec!["test_a.py"]);
+    }
+
+    #[tokio::test]
+    async fn test_include_references() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(workspace.join("model.rs"), "pub struct Settings {}\n").unwrap();
+        fs::write(
+            workspace.join("app.rs"),
+            "fn start(\n    name: &str,\n    settings: Settings,\n) {}\n",
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            index_references: true,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let query = SearchQuery {
+            query: "Settings".to_string(),
+            mode: SearchMode::Symbol,
+            limit: 10,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.results.len(), 1);
+        assert!(response.results[0].file_path.ends_with("model.rs"));
+
+        let response = search_engine
+            .search(SearchQuery {
+                include_references: true,
+                ..query
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.results.len(), 2);
+
+        let definition = response
+            .results
+            .iter()
+            .find(|r| r.match_type == MatchType::Symbol)
+            .unwrap();
+        assert!(definition.file_path.ends_with("model.rs"));
+        assert_eq!(definition.line_number, 1);
+
+        let usage = response
+            .results
+            .iter()
+            .find(|r| r.match_type == MatchType::Reference)
+            .unwrap();
+        assert!(usage.file_path.ends_with("app.rs"));
+        assert_eq!(usage.line_number, 3);
+        assert_eq!(usage.column, 14);
+    }
+
+    #[tokio::test]
+    async fn test_min_line_length_drops_short_lines() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(
+            workspace.join("lib.rs"),
+            "fn tiny() {}\n\nfn tiny_handler(request: Request) -> Response {\n    todo!()\n}\n",
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let query = SearchQuery {
+            query: "tiny".to_string(),
+            mode: SearchMode::Symbol,
+            limit: 10,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.results.len(), 2);
+
+        // Not served the unfiltered response from cache
+        let response = search_engine
+            .search(SearchQuery {
+                min_line_length: Some(20),
+                ..query
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.from_cache, Some(false));
+        assert_eq!(response.total_matches, 1);
+        assert_eq!(response.results[0].line_number, 3);
+    }
+
+    #[tokio::test]
+    async fn test_include_symbol_body() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        let function = "fn load_settings(path: &str) -> Settings {\n    let raw = read(path);\n    parse(raw)\n}";
+        fs::write(
+            workspace.join("settings.rs"),
+            format!("use std::fs::read;\n\n{function}\n\nfn unrelated() {{}}\n"),
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            max_content_chars: 30,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config.clone(), storage.clone())
+            .await
+            .unwrap();
+        let query = SearchQuery {
+            query: "load_settings".to_string(),
+            mode: SearchMode::Symbol,
+            limit: 10,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.results.len(), 1);
+        assert!(response.results[0].body.is_none());
+
+        let response = search_engine
+            .search(SearchQuery {
+                include_symbol_body: true,
+                ..query.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.from_cache, Some(false));
+        assert_eq!(response.results[0].body.as_deref(), Some(&function[..30]));
+
+        // With room to spare the whole function comes back
+        let config = Arc::new(Config {
+            max_content_chars: 10_000,
+            ..(*config).clone()
+        });
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let response = search_engine
+            .search(SearchQuery {
+                include_symbol_body: true,
+                ..query
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.results[0].body.as_deref(), Some(function));
+    }
+
+    #[tokio::test]
+    async fn test_unstored_content_is_read_from_disk() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        for i in 0..10 {
+            let body: String = (0..200)
+                .map(|j| {
+                    format!(
+                        "    let value_{j} = {};\n",
+                        (i * 7919 + j * 104729) % 1_000_003
+                    )
+                })
+                .collect();
+            fs::write(
+                workspace.join(format!("module_{i}.rs")),
+                format!("fn module_{i}_entry() {{\n{body}}}\n"),
+            )
+            .unwrap();
+        }
+
+        let mut index_sizes = Vec::new();
+        for store_content in [true, false] {
+            let config = Arc::new(Config {
+                workspace_roots: vec![workspace.clone()],
+                cache_dir: temp_dir.path().join(format!("cache_{store_content}")),
+                enable_semantic: false,
+                store_content,
+                ..Default::default()
+            });
+
+            let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+            {
+                let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+                indexer.index_workspaces().await.unwrap();
+            }
+            index_sizes.push(storage.get_index_size().await.unwrap());
+
+            let search_engine = SearchEngine::new(config, storage).await.unwrap();
+            let response = search_engine
+                .search(SearchQuery {
+                    query: "module_7_entry".to_string(),
+                    mode: SearchMode::Symbol,
+                    limit: 10,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+
+            assert_eq!(response.results.len(), 1);
+            let result = &response.results[0];
+            assert!(result.file_path.ends_with("module_7.rs"));
+            assert_eq!(result.content, "fn module_7_entry() {");
+            assert_eq!(result.context_after.len(), DEFAULT_CONTEXT_LINES);
+        }
+
+        assert!(
+            index_sizes[1] < index_sizes[0],
+            "unstored index ({} bytes) should be smaller than stored ({} bytes)",
+            index_sizes[1],
+            index_sizes[0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_results_record_source_mode() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(workspace.join("provenance.rs"), "fn unrelated() {}\n").unwrap();
+        fs::write(workspace.join("lib.rs"), "fn provenance() {}\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let response = search_engine
+            .search(SearchQuery {
+                query: "provenance".to_string(),
+                mode: SearchMode::Symbol,
+                search_paths: true,
+                limit: 10,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert!(
+            response
+                .results
+                .iter()
+                .any(|r| r.match_type == MatchType::Path)
+        );
+        assert!(
+            response
+                .results
+                .iter()
+                .any(|r| r.match_type == MatchType::Symbol)
+        );
+        assert!(
+            response
+                .results
+                .iter()
+                .all(|r| r.source_mode == Some(SearchMode::Symbol))
+        );
+
+        // Results serialized before the field existed still load
+        let mut json = serde_json::to_value(&response.results[0]).unwrap();
+        json.as_object_mut().unwrap().remove("source_mode");
+        let result: SearchResult = serde_json::from_value(json).unwrap();
+        assert_eq!(result.source_mode, None);
+    }
+
+    #[tokio::test]
+    async fn test_bypass_cache_refreshes_entry() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(workspace.join("one.rs"), "fn cache_probe_one() {}\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace.clone()],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let tantivy_indexer = Indexer::open_tantivy_indexer(&config).await.unwrap();
+        let indexer =
+            Indexer::with_tantivy_indexer(config.clone(), storage.clone(), tantivy_indexer.clone())
+                .await
+                .unwrap();
+        indexer.index_workspaces().await.unwrap();
+
+        let search_engine = SearchEngine::with_tantivy_indexer(config, storage, tantivy_indexer)
+            .await
+            .unwrap();
+        let query = SearchQuery {
+            query: "cache_probe".to_string(),
+            mode: SearchMode::Symbol,
+            limit: 10,
+            ..Default::default()
+        };
+        let bypass = SearchQuery {
+            bypass_cache: true,
+            ..query.clone()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.results.len(), 1);
+
+        // The index changes behind the cache's back
+        fs::write(workspace.join("two.rs"), "fn cache_probe_two() {}\n").unwrap();
+        indexer.index_workspaces().await.unwrap();
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.from_cache, Some(true));
+        assert_eq!(response.results.len(), 1);
+
+        for _ in 0..2 {
+            let response = search_engine.search(bypass.clone()).await.unwrap();
+            assert_eq!(response.from_cache, Some(false));
+            assert_eq!(response.results.len(), 2);
+        }
+
+        let response = search_engine.search(query).await.unwrap();
+        assert_eq!(response.from_cache, Some(true));
+        assert_eq!(response.results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_include_enclosing_signature() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(
+            workspace.join("lib.rs"),
+            r#"// A needle outside any function
+
+fn load_settings(path: &str) -> usize {
+    let raw = path.len();
+    // the needle is in here
+    raw + 1
+}
+"#,
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let query = SearchQuery {
+            query: "needle".to_string(),
+            mode: SearchMode::Literal,
+            limit: 10,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.results.len(), 2);
+        assert!(response.results[1].enclosing_signature.is_none());
+
+        for mode in [SearchMode::Literal, SearchMode::Regex] {
+            let response = search_engine
+                .search(SearchQuery {
+                    mode,
+                    include_enclosing_signature: true,
+                    ..query.clone()
+                })
+                .await
+                .unwrap();
+
+            let inside = &response.results[1];
+            assert_eq!(inside.line_number, 5);
+            assert_eq!(
+                inside.enclosing_signature.as_deref(),
+                Some("fn load_settings(path: &str) -> usize {")
+            );
+            assert_eq!(response.results[0].line_number, 1);
+            assert!(response.results[0].enclosing_signature.is_none());
+        }
+    }
+
+    #[test]
+    fn test_imported_names_are_leaves_or_aliases() {
+        let names = |import: &'static str| {
+            let mut names: Vec<_> = imported_names(import).into_iter().collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names("use std::collections::HashMap;"), vec!["HashMap"]);
+        assert_eq!(names("use super::config::Config as Cfg;"), vec!["Cfg"]);
+        assert_eq!(
+            names("pub use crate::search::{self, mod_a::Leaf, Other as O};"),
+            vec!["Leaf", "O"]
+        );
+        assert!(names("use crate::prelude::*;").is_empty());
+
+        assert_eq!(names("import numpy as np"), vec!["np"]);
+        assert_eq!(
+            names("from os.path import (join,\n    split)"),
+            vec!["join", "split"]
+        );
+        assert!(names("from typing import *").is_empty());
+
+        assert_eq!(
+            names("import React, { useState as useS } from 'react';"),
+            vec!["React", "useS"]
+        );
+        assert_eq!(names("import * as path from \"node:path\";"), vec!["path"]);
+        assert_eq!(
+            names("import type { Props } from './types';"),
+            vec!["Props"]
+        );
+
+        assert_eq!(
+            names("import (\n\t\"fmt\"\n\tsj \"encoding/json\"\n\t\"net/http\"\n)"),
+            vec!["fmt", "http", "sj"]
+        );
+        assert!(names("import _ \"embed\"").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_include_imports() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(
+            workspace.join("index.rs"),
+            "use std::collections::HashMap;\nuse std::sync::Arc;\n\nfn build_index(words: &[String]) -> std::collections::HashMap<String, usize> {\n    HashMap::new()\n}\n",
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let query = SearchQuery {
+            query: "build_index".to_string(),
+            mode: SearchMode::Symbol,
+            limit: 10,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert!(response.results[0].relevant_imports.is_empty());
+
+        let response = search_engine
+            .search(SearchQuery {
+                include_imports: true,
+                ..query
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.from_cache, Some(false));
+        let result = &response.results[0];
+        assert_eq!(result.line_number, 4);
+        assert_eq!(
+            result.relevant_imports,
+            vec!["use std::collections::HashMap;"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_literal_search() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(
+            workspace.join("settings.rs"),
+            "fn load() {\n    let path = \"app.config.toml\";\n    read(path)\n}\n\nfn save() {\n    write(\"app.config.toml\")\n}\n\nconst LEGACY: &str = \"APP.CONFIG.TOML\";\n",
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let query = SearchQuery {
+            query: "onfig.toml\"".to_string(),
+            mode: SearchMode::Literal,
+            limit: 10,
+            ..Default::default()
+        };
+
+        // Case-sensitive, and matches inside longer words
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.total_matches, 2);
+        let lines: Vec<usize> = response.results.iter().map(|r| r.line_number).collect();
+        assert_eq!(lines, vec![2, 7]);
+        let first = &response.results[0];
+        assert_eq!(first.match_type, MatchType::Exact);
+        assert_eq!(first.source_mode, Some(SearchMode::Literal));
+        assert_eq!(first.column, 21);
+        assert_eq!(first.context_before, vec!["fn load() {"]);
+
+        // Paging is applied after the searcher, like every other mode
+        let response = search_engine
+            .search(SearchQuery { offset: 1, ..query })
+            .await
+            .unwrap();
+        assert_eq!(response.total_matches, 2);
+        assert_eq!(response.results.len(), 1);
+        assert_eq!(response.results[0].line_number, 7);
+    }
+
+    #[tokio::test]
+    async fn test_literal_search_falls_back_to_fuzzy() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(
+            workspace.join("exact.rs"),
+            "// retry count is capped\nlet retry_count = 3;\n",
+        )
+        .unwrap();
+        fs::write(
+            workspace.join("apart.rs"),
+            "fn backoff() {}\nlet max_retry = 5; // count\nlet Retry_Count = 5;\n",
+        )
+        .unwrap();
+        fs::write(
+            workspace.join("loader.rs"),
+            "fn load_settings() {}\nfn save_settings() {}\n",
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let search = |text: &str| {
+            let query = SearchQuery {
+                query: text.to_string(),
+                mode: SearchMode::Literal,
+                limit: 10,
+                ..Default::default()
+            };
+            let search_engine = &search_engine;
+            async move {
+                let mut results = search_engine.search(query).await.unwrap().results;
+                results.sort_by_key(|r| (r.file_path.clone(), r.line_number));
+                results
+                    .into_iter()
+                    .map(|r| {
+                        let name = r.file_path.file_name().unwrap().to_string_lossy();
+                        (name.into_owned(), r.line_number, r.column, r.match_type)
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        // A file with the exact text keeps only its exact lines; the other file falls
+        // back to lines with both words, in any order and case
+        assert_eq!(
+            search("retry count").await,
+            vec![
+                ("apart.rs".to_string(), 2, 8, MatchType::Fuzzy),
+                ("apart.rs".to_string(), 3, 4, MatchType::Fuzzy),
+                ("exact.rs".to_string(), 1, 3, MatchType::Exact),
+            ]
+        );
+
+        // A typo finds the closest words when no file contains the query's words
+        assert_eq!(
+            search("laod_setings").await,
+            vec![("loader.rs".to_string(), 1, 3, MatchType::Fuzzy)]
+        );
+
+        // Short words have to match exactly
+        assert!(search("lod").await.is_empty());
+    }
+
+    #[cfg(feature = "git-blame")]
+    #[tokio::test]
+    async fn test_include_blame() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        let file = workspace.join("lib.rs");
+        fs::write(&file, "fn blamed_function() {}\n").unwrap();
+
+        let repo = git2::Repository::init(&workspace).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("lib.rs")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let author = git2::Signature::now("Ada Lovelace", "ada@example.com").unwrap();
+        let commit = repo
+            .commit(Some("HEAD"), &author, &author, "Add lib", &tree, &[])
+            .unwrap();
+
+        // Appended after the commit, so it has no blame
+        fs::write(&file, "fn blamed_function() {}\nfn blamed_later() {}\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let response = search_engine
+            .search(SearchQuery {
+                query: "blamed_".to_string(),
+                mode: SearchMode::Literal,
+                limit: 10,
+                include_blame: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.results.len(), 2);
+        let committed = &response.results[0];
+        assert_eq!(committed.line_number, 1);
+        assert_eq!(committed.last_author.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(committed.last_commit, Some(commit.to_string()));
+        assert!(response.results[1].last_author.is_none());
+    }
+
+    #[cfg(not(feature = "semantic"))]
+    #[tokio::test]
+    async fn test_semantic_search_without_feature() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(workspace.join("main.rs"), "fn main() {}\n").unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let query = |mode| SearchQuery {
+            query: "main".to_string(),
+            mode,
+            ..Default::default()
+        };
+
+        let err = search_engine
+            .search(query(SearchMode::Semantic))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RuneError>(),
+            Some(RuneError::SemanticDisabled)
+        ));
+
+        // Hybrid search still has its symbol half
+        let response = search_engine
+            .search(query(SearchMode::Hybrid))
+            .await
+            .unwrap();
+        assert_eq!(response.results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_stream_matches_search() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        for i in 0..6 {
+            let body: String = (0..i + 1)
+                .map(|n| format!("fn handler_{i}_{n}() {{ let retry_count = {n}; }}\n"))
+                .collect();
+            fs::write(workspace.join(format!("file_{i}.rs")), body).unwrap();
+        }
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            max_concurrent_searches: 1,
+            reject_when_overloaded: true,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let lines = |results: &[SearchResult]| -> Vec<(PathBuf, usize, Option<PathBuf>)> {
+            results
+                .iter()
+                .map(|r| (r.file_path.clone(), r.line_number, r.relative_path.clone()))
+                .collect()
+        };
+
+        let queries = [
+            ("retry_count", SearchMode::Literal, 0, 100),
+            ("retry_count", SearchMode::Literal, 4, 7),
+            (r"handler_\d_[02]", SearchMode::Regex, 1, 5),
+            ("handler_3_0", SearchMode::Symbol, 0, 10),
+        ];
+        for (text, mode, offset, limit) in queries {
+            let query = SearchQuery {
+                query: text.to_string(),
+                mode,
+                offset,
+                limit,
+                bypass_cache: true,
+                ..Default::default()
+            };
+            let expected = search_engine.search(query.clone()).await.unwrap().results;
+            assert!(!expected.is_empty(), "{text}");
+            let streamed: Vec<SearchResult> = search_engine
+                .search_stream(query)
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(lines(&streamed), lines(&expected), "{text}");
+        }
+
+        // A bad pattern fails the stream rather than ending it quietly
+        let mut stream = search_engine.search_stream(SearchQuery {
+            query: "(unclosed".to_string(),
+            mode: SearchMode::Regex,
+            offset: 3,
+            ..Default::default()
+        });
+        assert!(stream.next().await.unwrap().is_err());
+        drop(stream);
+
+        // An open stream keeps its permit, so the only slot stays taken
+        let literal = SearchQuery {
+            query: "retry_count".to_string(),
+            mode: SearchMode::Literal,
+            bypass_cache: true,
+            ..Default::default()
+        };
+        let mut stream = search_engine.search_stream(literal.clone());
+        assert!(stream.next().await.unwrap().is_ok());
+        let err = search_engine.search(literal.clone()).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RuneError>(),
+            Some(RuneError::Overloaded { max_concurrent: 1 })
+        ));
+        drop(stream);
+        search_engine.search(literal).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_literal_search_highlight_snippets() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        let filler = "lorem ipsum dolor sit amet ".repeat(10);
+        fs::write(
+            workspace.join("notes.rs"),
+            format!("// {filler}the Pool Size caps open connections {filler}\nfn main() {{}}\n"),
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let query = SearchQuery {
+            query: "Pool Size".to_string(),
+            mode: SearchMode::Literal,
+            limit: 10,
+            highlight_snippets: true,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.results.len(), 1);
+        let result = &response.results[0];
+        let snippet = result.snippet.as_deref().unwrap();
+        assert!(snippet.len() < result.content.len());
+        let highlighted: Vec<&str> = result
+            .match_ranges
+            .iter()
+            .map(|range| &snippet[range.clone()])
+            .collect();
+        assert_eq!(highlighted, vec!["Pool", "Size"]);
+
+        // Off by default
+        let response = search_engine
+            .search(SearchQuery {
+                highlight_snippets: false,
+                ..query
+            })
+            .await
+            .unwrap();
+        assert!(response.results[0].snippet.is_none());
+        assert!(response.results[0].match_ranges.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_regex_search() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(
+            workspace.join("queue.rs"),
+            "fn push() {\n    // TODO: fixme before release\n    todo!()\n}\n\n// TODO later, fixme too\n// fixme: TODO\n",
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let query = SearchQuery {
+            query: "TODO.*fixme".to_string(),
+            mode: SearchMode::Regex,
+            limit: 10,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        let lines: Vec<(usize, usize)> = response
+            .results
+            .iter()
+            .map(|r| (r.line_number, r.column))
+            .collect();
+        assert_eq!(lines, vec![(2, 7), (6, 3)]);
+        let first = &response.results[0];
+        assert_eq!(first.match_type, MatchType::Regex);
+        assert_eq!(first.context_before, vec!["fn push() {"]);
+        assert_eq!(first.context_after, vec!["    todo!()", "}"]);
+
+        let err = search_engine
+            .search(SearchQuery {
+                query: "TODO(".to_string(),
+                ..query
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RuneError>(),
+            Some(RuneError::InvalidQuery(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_block_context_for_text_files() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        fs::write(
+            workspace.join("NOTES.txt"),
+            "Release notes\n\nThe cache is flushed\nwhen the daemon restarts,\nso warm it first.\n\nUnrelated paragraph.\n",
+        )
+        .unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            index_unknown_languages: true,
+            index_text_blocks: true,
+            ..Default::default()
+        });
+
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+        let query = SearchQuery {
+            query: "daemon restarts".to_string(),
+            mode: SearchMode::Literal,
+            limit: 10,
+            context_mode: ContextMode::Block,
+            ..Default::default()
+        };
+
+        let response = search_engine.search(query.clone()).await.unwrap();
+        assert_eq!(response.results.len(), 1);
+        let result = &response.results[0];
+        assert_eq!(result.line_number, 4);
+        assert_eq!(result.context_before, vec!["The cache is flushed"]);
+        assert_eq!(result.context_after, vec!["so warm it first."]);
+
+        // Without block context the neighbouring paragraphs are included
+        let response = search_engine
+            .search(SearchQuery {
+                context_mode: ContextMode::default(),
+                ..query
+            })
+            .await
+            .unwrap();
+        let result = &response.results[0];
+        assert_eq!(result.context_before, vec!["", "The cache is flushed"]);
+    }
+
+    #[tokio::test]
+    async fn test_context_lines_apply_to_every_mode() {
+        let temp_dir = tempdir().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir(&workspace).unwrap();
+        let mut source = String::new();
+        for i in 0..7 {
+            source.push_str(&format!("const PAD_{i}: u8 = {i};\n"));
+        }
+        source.push_str("fn context_target() {}\n");
+        for i in 7..14 {
+            source.push_str(&format!("const PAD_{i}: u8 = {i};\n"));
+        }
+        fs::write(workspace.join("lib.rs"), source).unwrap();
+
+        let config = Arc::new(Config {
+            workspace_roots: vec![workspace],
+            cache_dir: temp_dir.path().join("cache"),
+            enable_semantic: false,
+            ..Default::default()
+        });
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        {
+            let indexer = Indexer::new(config.clone(), storage.clone()).await.unwrap();
+            indexer.index_workspaces().await.unwrap();
+        }
+        let search_engine = SearchEngine::new(config, storage).await.unwrap();
+
+        for (mode, text) in [
+            (SearchMode::Symbol, "context_target"),
+            (SearchMode::Literal, "fn context_target"),
+            (SearchMode::Regex, r"fn context_\w+"),
+        ] {
+            for n in [0, 5] {
+                let response = search_engine
+                    .search(SearchQuery {
+                        query: text.to_string(),
+                        mode: mode.clone(),
+                        limit: 10,
+                        context_mode: ContextMode::Lines(n),
+                        ..Default::default()
+                    })
+                    .await
+                    .unwrap();
+                let result = &response.results[0];
+                assert_eq!(result.line_number, 8, "{:?}", mode);
+                assert_eq!(result.context_before.len(), n, "{:?}", mode);
+                assert_eq!(result.context_after.len(), n, "{:?}", mode);
+            }
+        }
+    }
 }
@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+
+use super::SearchMode;
+use crate::indexing::language_detector::Language;
+
+/// What the user appears to be looking for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueryIntent {
+    /// Where something is declared ("where is X defined")
+    FindDefinition,
+    /// Where something is called or referenced ("who calls X")
+    FindUsages,
+    /// Code that performs some behavior ("functions that handle auth")
+    FindImplementation,
+    /// No specific intent detected
+    Explore,
+}
+
+/// Interpretation of a natural-language query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParsedQuery {
+    /// The query as typed
+    pub original: String,
+    pub intent: QueryIntent,
+    pub suggested_mode: SearchMode,
+    /// Query text with filler and filter words removed
+    pub search_text: String,
+    /// Languages mentioned in the query (canonical names, e.g. "rust")
+    pub languages: Vec<String>,
+    /// Explicit glob patterns or paths mentioned in the query
+    pub file_patterns: Vec<String>,
+    /// Symbol kinds mentioned in the query (e.g. "function", "class")
+    pub symbol_kinds: Vec<String>,
+}
+
+/// Rule-based parser for natural-language search queries. Doesn't touch the index.
+pub struct QueryParser;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "all", "an", "any", "are", "code", "does", "every", "file", "files", "find", "for", "get",
+    "give", "in", "is", "list", "me", "of", "show", "that", "the", "where", "which", "who", "with",
+    "written",
+];
+
+const DEFINITION_WORDS: &[&str] = &["defined", "definition", "declared", "declaration"];
+
+const USAGE_WORDS: &[&str] = &[
+    "calls",
+    "called",
+    "callers",
+    "usages",
+    "used",
+    "uses",
+    "references",
+    "referenced",
+];
+
+const IMPLEMENTATION_WORDS: &[&str] = &[
+    "handle",
+    "handles",
+    "handling",
+    "implement",
+    "implements",
+    "implementation",
+    "performs",
+    "validates",
+    "parses",
+];
+
+/// Languages whose names are also common English words; only treated as a language
+/// when followed by a code noun ("go functions", "c files").
+const AMBIGUOUS_LANGUAGES: &[&str] = &["go", "c", "sh"];
+
+impl QueryParser {
+    pub fn parse(query: &str) -> ParsedQuery {
+        let words: Vec<&str> = query.split_whitespace().collect();
+
+        let mut languages = Vec::new();
+        let mut file_patterns = Vec::new();
+        let mut symbol_kinds = Vec::new();
+        let mut intent = QueryIntent::Explore;
+        let mut remaining = Vec::new();
+
+        for (i, word) in words.iter().enumerate() {
+            let trimmed = word.trim_matches(|c: char| matches!(c, ',' | '?' | '"' | '\'' | '`'));
+            let lower = trimmed.to_lowercase();
+
+            if trimmed.contains('*') || trimmed.contains('/') {
+                file_patterns.push(trimmed.to_string());
+                continue;
+            }
+
+            if let Some(language) = Self::language(&lower, words.get(i + 1)) {
+                if !languages.contains(&language) {
+                    languages.push(language);
+                }
+                continue;
+            }
+
+            if let Some(kind) = Self::symbol_kind(&lower) {
+                if !symbol_kinds.iter().any(|k| k == kind) {
+                    symbol_kinds.push(kind.to_string());
+                }
+                continue;
+            }
+
+            if DEFINITION_WORDS.contains(&lower.as_str()) {
+                intent = QueryIntent::FindDefinition;
+                continue;
+            }
+
+            if USAGE_WORDS.contains(&lower.as_str()) {
+                intent = QueryIntent::FindUsages;
+                continue;
+            }
+
+            if IMPLEMENTATION_WORDS.contains(&lower.as_str()) && intent == QueryIntent::Explore {
+                intent = QueryIntent::FindImplementation;
+            }
+
+            if !STOP_WORDS.contains(&lower.as_str()) && !trimmed.is_empty() {
+                remaining.push(trimmed);
+            }
+        }
+
+        let search_text = remaining.join(" ");
+        let suggested_mode = Self::suggest_mode(intent, &remaining);
+
+        ParsedQuery {
+            original: query.to_string(),
+            intent,
+            suggested_mode,
+            search_text,
+            languages,
+            file_patterns,
+            symbol_kinds,
+        }
+    }
+
+    fn language(word: &str, next: Option<&&str>) -> Option<String> {
+        let language = word.parse::<Language>().ok()?;
+        if language == Language::Unknown {
+            return None;
+        }
+
+        if AMBIGUOUS_LANGUAGES.contains(&word) {
+            let next = next?.to_lowercase();
+            let next_is_code_noun =
+                next == "code" || next.starts_with("file") || Self::symbol_kind(&next).is_some();
+            if !next_is_code_noun {
+                return None;
+            }
+        }
+
+        Some(language.to_str().to_string())
+    }
+
+    fn symbol_kind(word: &str) -> Option<&'static str> {
+        match word {
+            "function" | "functions" | "fn" | "fns" | "func" | "funcs" => Some("function"),
+            "method" | "methods" => Some("method"),
+            "class" | "classes" => Some("class"),
+            "struct" | "structs" => Some("struct"),
+            "trait" | "traits" => Some("trait"),
+            "interface" | "interfaces" => Some("interface"),
+            "enum" | "enums" => Some("enum"),
+            "module" | "modules" => Some("module"),
+            _ => None,
+        }
+    }
+
+    /// Symbol search when the query boils down to identifiers, semantic otherwise
+    fn suggest_mode(intent: QueryIntent, remaining: &[&str]) -> SearchMode {
+        let identifier_like = !remaining.is_empty()
+            && remaining.iter().all(|w| {
+                w.chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == ':')
+                    && (w.contains('_') || w.contains("::") || Self::is_mixed_case(w))
+            });
+
+        match intent {
+            QueryIntent::FindDefinition | QueryIntent::FindUsages if remaining.len() == 1 => {
+                SearchMode::Symbol
+            },
+            _ if identifier_like => SearchMode::Symbol,
+            _ => SearchMode::Semantic,
+        }
+    }
+
+    fn is_mixed_case(word: &str) -> bool {
+        word.chars().any(|c| c.is_uppercase()) && word.chars().any(|c| c.is_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_natural_language_query() {
+        let parsed = QueryParser::parse("find all rust functions that handle auth");
+
+        assert_eq!(parsed.intent, QueryIntent::FindImplementation);
+        assert_eq!(parsed.suggested_mode, SearchMode::Semantic);
+        assert_eq!(parsed.languages, vec!["rust"]);
+        assert_eq!(parsed.symbol_kinds, vec!["function"]);
+        assert_eq!(parsed.search_text, "handle auth");
+
+        let json = serde_json::to_string(&parsed).unwrap();
+        assert!(json.contains("\"intent\":\"FindImplementation\""));
+        assert!(json.contains("\"suggested_mode\":\"Semantic\""));
+    }
+
+    #[test]
+    fn test_parse_identifier_queries() {
+        let parsed = QueryParser::parse("where is parse_config defined?");
+        assert_eq!(parsed.intent, QueryIntent::FindDefinition);
+        assert_eq!(parsed.suggested_mode, SearchMode::Symbol);
+        assert_eq!(parsed.search_text, "parse_config");
+
+        let parsed = QueryParser::parse("SearchEngine");
+        assert_eq!(parsed.intent, QueryIntent::Explore);
+        assert_eq!(parsed.suggested_mode, SearchMode::Symbol);
+    }
+
+    #[test]
+    fn test_ambiguous_language_words() {
+        assert!(
+            QueryParser::parse("go to the config loader")
+                .languages
+                .is_empty()
+        );
+        assert_eq!(
+            QueryParser::parse("go functions in src/*").languages,
+            vec!["go"]
+        );
+        assert_eq!(
+            QueryParser::parse("go functions in src/*").file_patterns,
+            vec!["src/*"]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, RegexQuery};
+use tracing::debug;
+
+use super::{
+    ContextMode, MatchType, SearchBudget, SearchMode, SearchQuery, SearchResult,
+    apply_block_context, extract_context,
+    file_patterns::FilePatterns,
+    relative_to_roots,
+    symbol::{FileMatches, SymbolMatches},
+};
+use crate::{
+    Config, RuneError,
+    indexing::tantivy_indexer::{self, TantivyIndexer},
+};
+
+/// Shortest literal run worth turning into a candidate clause
+const MIN_FRAGMENT_LEN: usize = 3;
+
+/// Tantivy's default tokenizer drops tokens longer than this
+const MAX_INDEXED_TOKEN_LEN: usize = 40;
+
+/// Regular expression search, matched line by line. Literal words the pattern
+/// requires are looked up with `RegexQuery` to narrow the candidate files first.
+#[derive(Clone)]
+pub struct RegexSearcher {
+    config: Arc<Config>,
+    tantivy_indexer: Arc<TantivyIndexer>,
+}
+
+impl RegexSearcher {
+    pub fn new(config: Arc<Config>, tantivy_indexer: Arc<TantivyIndexer>) -> Self {
+        Self {
+            config,
+            tantivy_indexer,
+        }
+    }
+
+    /// Fails with `RuneError::InvalidQuery` if the query isn't a valid pattern
+    pub async fn search(
+        &self,
+        query: &SearchQuery,
+        budget: &SearchBudget,
+    ) -> Result<SymbolMatches> {
+        Ok(SymbolMatches::collect(
+            self.scan(query, budget).await?,
+            budget,
+        ))
+    }
+
+    /// Fetch the candidate documents, then match each one only as the iterator
+    /// reaches it. Fails like `search`.
+    pub async fn scan(
+        &self,
+        query: &SearchQuery,
+        budget: &SearchBudget,
+    ) -> Result<Box<dyn Iterator<Item = FileMatches> + Send>> {
+        debug!("Performing regex search for: {}", query.query);
+
+        let pattern = ::regex::Regex::new(&query.query).map_err(|e| {
+            RuneError::InvalidQuery(format!("invalid regex {:?}: {}", query.query, e))
+        })?;
+
+        let docs = self
+            .tantivy_indexer
+            .search_documents(
+                self.tantivy_indexer
+                    .modified_after(self.candidate_query(&query.query), query.modified_since)
+                    .as_ref(),
+                budget.fetch_limit,
+            )
+            .await?;
+
+        let file_patterns = query
+            .file_patterns
+            .as_deref()
+            .map(FilePatterns::new)
+            .transpose()?;
+        let searcher = self.clone();
+        let query = query.clone();
+        Ok(Box::new(docs.into_iter().filter_map(move |doc| {
+            searcher.scan_document(&query, file_patterns.as_ref(), &pattern, doc)
+        })))
+    }
+
+    /// Matches in one candidate, or `None` if the query's filters exclude it
+    fn scan_document(
+        &self,
+        query: &SearchQuery,
+        file_patterns: Option<&FilePatterns>,
+        pattern: &::regex::Regex,
+        doc: tantivy_indexer::SearchResult,
+    ) -> Option<FileMatches> {
+        if let Some(repos) = &query.repositories
+            && !repos.contains(&doc.repository)
+        {
+            return None;
+        }
+
+        if let Some(patterns) = file_patterns {
+            let relative =
+                relative_to_roots(&self.config.workspace_roots, &doc.path).unwrap_or(&doc.path);
+            if !patterns.matches(relative) {
+                return None;
+            }
+        }
+
+        let (mut results, truncated) = self.find_matches_in_content(
+            &doc.path,
+            &doc.repository,
+            &doc.content,
+            pattern,
+            doc.score,
+            query.context_mode.lines(),
+        );
+        if query.context_mode == ContextMode::Block {
+            apply_block_context(&mut results, &doc.content, &doc.blocks);
+        }
+
+        Some(FileMatches {
+            path: doc.path,
+            results,
+            truncated,
+        })
+    }
+
+    /// Documents containing every literal fragment of `pattern`, or all documents when
+    /// it has none that are certain to appear (e.g. it uses alternation)
+    fn candidate_query(&self, pattern: &str) -> Box<dyn Query> {
+        let content_field = self.tantivy_indexer.get_content_field();
+        let clauses: Vec<(Occur, Box<dyn Query>)> = required_fragments(pattern)
+            .into_iter()
+            .filter(|fragment| fragment.len() <= MAX_INDEXED_TOKEN_LEN)
+            .filter_map(|fragment| {
+                RegexQuery::from_pattern(&format!(".*{}.*", fragment), content_field).ok()
+            })
+            .map(|query| (Occur::Must, Box::new(query) as Box<dyn Query>))
+            .collect();
+
+        if clauses.is_empty() {
+            Box::new(AllQuery)
+        } else {
+            Box::new(BooleanQuery::new(clauses))
+        }
+    }
+
+    /// One result per line `pattern` matches, at the first match, capped at
+    /// `Config::max_matches_per_file`. Returns true alongside the results if the cap
+    /// cut them short.
+    pub fn find_matches_in_content(
+        &self,
+        file_path: &Path,
+        repository: &str,
+        content: &str,
+        pattern: &::regex::Regex,
+        score: f32,
+        context_lines: usize,
+    ) -> (Vec<SearchResult>, bool) {
+        let mut results = Vec::new();
+        let lines: Vec<&str> = content.lines().collect();
+
+        for (line_idx, line) in lines.iter().enumerate() {
+            let Some(found) = pattern.find(line) else {
+                continue;
+            };
+            if results.len() >= self.config.max_matches_per_file {
+                return (results, true);
+            }
+
+            let (context_before, context_after) =
+                extract_context(&lines, line_idx, context_lines, context_lines);
+            results.push(SearchResult {
+                file_path: file_path.to_path_buf(),
+                repository: repository.to_string(),
+                line_number: line_idx + 1,
+                end_line: line_idx + 1,
+                column: found.start(),
+                content: line.to_string(),
+                context_before,
+                context_after,
+                score,
+                raw_score: score,
+                relative_path: None,
+                match_type: MatchType::Regex,
+                source_mode: Some(SearchMode::Regex),
+                body: None,
+                relevant_imports: Vec::new(),
+                snippet: None,
+                match_ranges: Vec::new(),
+                last_author: None,
+                last_commit: None,
+                enclosing_signature: None,
+            });
+        }
+
+        (results, false)
+    }
+}
+
+/// Lowercased alphanumeric runs every match of `pattern` must contain. Escapes,
+/// character classes, group flags and repetition counts are skipped, as is anything
+/// inside a group (it may be optional), and a character made optional by `?`, `*` or
+/// `{` is left off its run. Patterns with alternation have no required runs.
+fn required_fragments(pattern: &str) -> Vec<String> {
+    if pattern.contains('|') {
+        return Vec::new();
+    }
+
+    let mut fragments = Vec::new();
+    let mut run = String::new();
+    let mut depth = 0usize;
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() {
+            run.push(c);
+            continue;
+        }
+
+        if matches!(c, '?' | '*' | '{') {
+            run.pop();
+        }
+        if depth == 0 && run.len() >= MIN_FRAGMENT_LEN {
+            fragments.push(run.to_lowercase());
+        }
+        run.clear();
+
+        match c {
+            '\\' => {
+                chars.next();
+            },
+            '[' => {
+                // A leading `]` (after any `^`) is part of the class
+                chars.next_if_eq(&'^');
+                chars.next_if_eq(&']');
+                // Escaped characters don't close it, and classes can nest
+                let mut classes = 1usize;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        },
+                        '[' => classes += 1,
+                        ']' => {
+                            classes -= 1;
+                            if classes == 0 {
+                                break;
+                            }
+                        },
+                        _ => {},
+                    }
+                }
+            },
+            '{' => {
+                chars.by_ref().find(|&c| c == '}');
+            },
+            '(' if chars.peek() == Some(&'?') => {
+                // `(?i)` only sets flags; `(?:` and `(?P<name>` open a group
+                let end = chars.by_ref().find(|&c| matches!(c, ')' | ':' | '>'));
+                depth += usize::from(end != Some(')'));
+            },
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+    }
+    if depth == 0 && run.len() >= MIN_FRAGMENT_LEN {
+        fragments.push(run.to_lowercase());
+    }
+
+    fragments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_fragments() {
+        assert_eq!(required_fragments("TODO.*fixme"), vec!["todo", "fixme"]);
+        assert_eq!(required_fragments(r"colou?r_name\d+"), vec!["colo", "name"]);
+        assert_eq!(
+            required_fragments(r"(?i)hello[abcdef]{2,10}"),
+            vec!["hello"]
+        );
+        assert_eq!(required_fragments(r"(?P<word>hello)?world"), vec!["world"]);
+        assert_eq!(required_fragments(r"\u{1F600}smile"), vec!["smile"]);
+        assert_eq!(required_fragments(r"[\]xyz]+needle"), vec!["needle"]);
+        assert_eq!(
+            required_fragments(r"[^]abcd]x[[:alpha:]]word"),
+            vec!["word"]
+        );
+        assert!(required_fragments("alpha|beta").is_empty());
+    }
+}
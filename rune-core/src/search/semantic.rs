@@ -1,24 +1,41 @@
 use anyhow::Result;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::{debug, info, trace, warn};
 
-use super::{SearchQuery, SearchResult};
-use crate::{Config, embedding::EmbeddingPipeline, storage::StorageBackend};
+use super::{
+    ContextMode, SearchBudget, SearchQuery, SearchResult, extract_context,
+    file_patterns::FilePatterns, relative_to_roots,
+};
+use crate::{
+    Config, EmbeddingStatus,
+    embedding::{EmbeddingPipeline, Reranker, VectorFilter, qdrant::SemanticSearchResult},
+    indexing::{language_detector::LanguageDetector, symbol_extractor::SymbolExtractor},
+    storage::StorageBackend,
+};
 
 #[derive(Clone)]
 pub struct SemanticSearcher {
-    _config: Arc<Config>,     // Kept for potential future use
-    _storage: StorageBackend, // Kept for potential future use
+    config: Arc<Config>,
+    /// File metadata, for the `modified_since` filter
+    storage: StorageBackend,
     pipeline: Option<Arc<EmbeddingPipeline>>,
+    /// Second-stage scorer, present when `Config::rerank_model` is set and loads
+    reranker: Option<Arc<Reranker>>,
+    symbol_extractor: Arc<SymbolExtractor>,
+    /// Captured at startup, since the pipeline is dropped when Qdrant is unreachable
+    embedding_status: EmbeddingStatus,
 }
 
 impl SemanticSearcher {
     pub async fn new(config: Arc<Config>, storage: StorageBackend) -> Result<Self> {
         // Try to initialize the embedding pipeline
-        let pipeline = match EmbeddingPipeline::new(config.clone()).await {
+        let (pipeline, embedding_status) = match EmbeddingPipeline::new(config.clone()).await {
             Ok(p) => {
-                if p.is_available() {
+                let p = p.with_storage(storage.clone());
+                let status = p.embedding_status();
+                let pipeline = if p.is_available() {
                     info!(
                         "[SEMANTIC] Semantic search initialized successfully with Qdrant backend"
                     );
@@ -28,25 +45,84 @@ impl SemanticSearcher {
                         "[SEMANTIC] Embedding pipeline created but Qdrant is not available. Semantic search will be disabled."
                     );
                     None
-                }
+                };
+                (pipeline, status)
             },
             Err(e) => {
                 warn!(
                     "[SEMANTIC] Failed to initialize semantic search: {}. Feature will be disabled.",
                     e
                 );
-                None
+                // No model at all is reported like one that failed to load
+                let status = EmbeddingStatus {
+                    model_loaded: false,
+                    fallback: true,
+                    dimension: config.embedding_dimension,
+                    ..Default::default()
+                };
+                (None, status)
             },
         };
 
+        let reranker = match (&pipeline, &config.rerank_model) {
+            (Some(_), Some(model)) => Self::load_reranker(config.clone(), model).await,
+            _ => None,
+        };
+
         Ok(Self {
-            _config: config,
-            _storage: storage,
+            config,
+            storage,
             pipeline,
+            reranker,
+            symbol_extractor: Arc::new(SymbolExtractor::new()),
+            embedding_status,
         })
     }
 
-    pub async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+    /// A searcher over an already built pipeline, e.g. `EmbeddingPipeline::in_memory`.
+    /// No reranking.
+    pub fn with_pipeline(
+        config: Arc<Config>,
+        storage: StorageBackend,
+        pipeline: Arc<EmbeddingPipeline>,
+    ) -> Self {
+        Self {
+            config,
+            storage,
+            embedding_status: pipeline.embedding_status(),
+            pipeline: Some(pipeline),
+            reranker: None,
+            symbol_extractor: Arc::new(SymbolExtractor::new()),
+        }
+    }
+
+    /// Load the rerank model; reranking is skipped if it can't be loaded, since
+    /// hash-based fallback embeddings would only scramble the primary ranking
+    async fn load_reranker(config: Arc<Config>, model: &str) -> Option<Arc<Reranker>> {
+        match Reranker::new(config, model).await {
+            Ok(reranker) if reranker.is_available() => {
+                info!("[SEMANTIC] Reranking enabled with {}", model);
+                Some(Arc::new(reranker))
+            },
+            Ok(_) => {
+                warn!(
+                    "[SEMANTIC] Rerank model {} unavailable, reranking disabled",
+                    model
+                );
+                None
+            },
+            Err(e) => {
+                warn!("[SEMANTIC] Failed to load rerank model {}: {}", model, e);
+                None
+            },
+        }
+    }
+
+    pub async fn search(
+        &self,
+        query: &SearchQuery,
+        budget: &SearchBudget,
+    ) -> Result<Vec<SearchResult>> {
         if let Some(ref pipeline) = self.pipeline {
             if !pipeline.is_available() {
                 debug!("[SEMANTIC] Pipeline exists but is not available (Qdrant disconnected)");
@@ -55,12 +131,21 @@ impl SemanticSearcher {
 
             debug!("[SEMANTIC] Performing semantic search for: {}", query.query);
 
-            // Perform semantic search
-            let semantic_results = pipeline.search(&query.query, query.limit).await?;
-
-            // Convert to SearchResult format
-            let mut results = Vec::new();
-            for result in semantic_results.iter() {
+            // Perform semantic search. Filters are applied to the returned chunks, which
+            // the budget oversamples for. With a reranker, fetch enough candidates for it
+            // to choose from once filtered before re-scoring the top of the list.
+            let fetch = match self.reranker {
+                Some(_) => budget.max_expansions,
+                None => budget.fetch_limit,
+            };
+            let file_patterns = query
+                .file_patterns
+                .as_deref()
+                .map(FilePatterns::new)
+                .transpose()?;
+            let mut indexed_after: HashMap<String, bool> = HashMap::new();
+            let mut candidates = Vec::new();
+            for result in pipeline.search(&query.query, fetch).await? {
                 // Apply repository and file pattern filters if specified
                 if let Some(ref repos) = query.repositories {
                     let repo = self.extract_repo_from_path(&result.file_path);
@@ -69,36 +154,56 @@ impl SemanticSearcher {
                     }
                 }
 
-                if let Some(ref patterns) = query.file_patterns
-                    && !self.matches_patterns(&result.file_path, patterns)
+                if let Some(ref patterns) = file_patterns
+                    && !self.matches_file_patterns(&result.file_path, patterns)
                 {
                     continue;
                 }
 
+                if let Some(since) = query.modified_since {
+                    let recent = match indexed_after.get(&result.file_path) {
+                        Some(&recent) => recent,
+                        None => {
+                            let recent = self
+                                .storage
+                                .get_file_metadata(std::path::Path::new(&result.file_path))
+                                .await?
+                                .is_some_and(|metadata| metadata.modified > since);
+                            indexed_after.insert(result.file_path.clone(), recent);
+                            recent
+                        },
+                    };
+                    if !recent {
+                        continue;
+                    }
+                }
+
+                candidates.push(result);
+            }
+
+            let candidates = match self.reranker {
+                Some(ref reranker) => {
+                    reranker
+                        .rerank(&query.query, candidates, self.config.rerank_candidates)
+                        .await?
+                },
+                None => candidates,
+            };
+
+            // Convert to SearchResult format
+            let mut results = Vec::new();
+            for result in candidates.iter().take(budget.candidate_limit) {
                 // Extract context lines from the source file
                 let (context_before, context_after) = Self::extract_context(
+                    &self.symbol_extractor,
                     &result.file_path,
                     result.start_line,
                     result.end_line,
-                    2, // 2 lines of context before/after
+                    query.context_mode,
                 )
                 .await;
 
-                results.push(SearchResult {
-                    file_path: PathBuf::from(&result.file_path),
-                    repository: self.extract_repo_from_path(&result.file_path),
-                    line_number: result.start_line,
-                    column: 0,
-                    content: result.content.clone(),
-                    context_before,
-                    context_after,
-                    score: result.score,
-                    match_type: super::MatchType::Semantic,
-                });
-
-                if results.len() >= query.limit {
-                    break;
-                }
+                results.push(self.to_search_result(result, query, context_before, context_after));
             }
 
             debug!("[SEMANTIC] Found {} results after filtering", results.len());
@@ -109,6 +214,39 @@ impl SemanticSearcher {
         }
     }
 
+    /// Chunks nearest to a precomputed embedding, with default context. Returns nothing
+    /// when no pipeline is configured.
+    pub async fn search_by_vector(
+        &self,
+        vector: Vec<f32>,
+        limit: usize,
+        filter: Option<VectorFilter>,
+    ) -> Result<Vec<SearchResult>> {
+        let Some(ref pipeline) = self.pipeline else {
+            debug!("[SEMANTIC] Vector search skipped - pipeline not initialized");
+            return Ok(vec![]);
+        };
+
+        let query = SearchQuery {
+            limit,
+            ..Default::default()
+        };
+        let mut results = Vec::new();
+        for result in pipeline.search_by_vector(vector, limit, filter).await? {
+            let (context_before, context_after) = Self::extract_context(
+                &self.symbol_extractor,
+                &result.file_path,
+                result.start_line,
+                result.end_line,
+                query.context_mode,
+            )
+            .await;
+            results.push(self.to_search_result(&result, &query, context_before, context_after));
+        }
+
+        Ok(results)
+    }
+
     /// Process files for semantic indexing
     pub async fn index_file(&self, file_path: &str, content: &str) -> Result<()> {
         info!("[SEMANTIC] Attempting to index file: {}", file_path);
@@ -131,6 +269,33 @@ impl SemanticSearcher {
         Ok(())
     }
 
+    /// Embed a file but defer the Qdrant upsert until `flush_pending`
+    pub async fn queue_file(&self, file_path: &str, content: &str) -> Result<()> {
+        if let Some(ref pipeline) = self.pipeline
+            && pipeline.is_available()
+        {
+            pipeline.queue_file(file_path, content).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether enough embeddings are queued that they should be flushed early
+    pub async fn pending_full(&self) -> bool {
+        match self.pipeline {
+            Some(ref pipeline) => pipeline.pending_full().await,
+            None => false,
+        }
+    }
+
+    /// Write all queued embeddings to Qdrant
+    pub async fn flush_pending(&self) -> Result<usize> {
+        match self.pipeline {
+            Some(ref pipeline) => pipeline.flush_pending().await,
+            None => Ok(0),
+        }
+    }
+
     /// Clear semantic index
     pub async fn clear_index(&self) -> Result<()> {
         if let Some(ref pipeline) = self.pipeline {
@@ -139,6 +304,18 @@ impl SemanticSearcher {
         Ok(())
     }
 
+    /// Entries and estimated bytes held by the embedding cache, zero without a pipeline
+    pub fn embedding_cache_usage(&self) -> (usize, usize) {
+        self.pipeline
+            .as_ref()
+            .map_or((0, 0), |p| p.embedding_cache_usage())
+    }
+
+    /// Which embeddings the pipeline was built with, even if it was then dropped
+    pub fn embedding_status(&self) -> EmbeddingStatus {
+        self.embedding_status.clone()
+    }
+
     /// Check if semantic search is available
     pub fn is_available(&self) -> bool {
         self.pipeline.as_ref().is_some_and(|p| p.is_available())
@@ -152,34 +329,29 @@ impl SemanticSearcher {
         path.split('/').next().unwrap_or("unknown").to_string()
     }
 
+    #[cfg(test)]
     fn matches_patterns(&self, path: &str, patterns: &[String]) -> bool {
-        for pattern in patterns {
-            if pattern.contains('*') {
-                // Simple glob matching
-                let pattern = pattern.replace("**", ".*").replace('*', "[^/]*");
-                if let Ok(re) = regex::Regex::new(&pattern)
-                    && re.is_match(path)
-                {
-                    return true;
-                }
-            } else if path.contains(pattern) {
-                return true;
-            }
-        }
-        false
+        FilePatterns::new(patterns)
+            .is_ok_and(|patterns| self.matches_file_patterns(path, &patterns))
+    }
+
+    fn matches_file_patterns(&self, path: &str, patterns: &FilePatterns) -> bool {
+        let path = std::path::Path::new(path);
+        let relative = relative_to_roots(&self.config.workspace_roots, path).unwrap_or(path);
+        patterns.matches(relative)
     }
 
     /// Extract context lines before and after the match from a file.
-    /// Returns (context_before, context_after) with up to `context_lines` lines each.
+    /// Returns (context_before, context_after). `ContextMode::Lines(n)` yields up to `n`
+    /// lines each; `ContextMode::Symbol` extends to the enclosing symbol's boundaries and
+    /// falls back to the default line count when no symbol encloses the match.
     async fn extract_context(
+        symbol_extractor: &SymbolExtractor,
         file_path: &str,
         start_line: usize,
         end_line: usize,
-        context_lines: usize,
+        context_mode: ContextMode,
     ) -> (Vec<String>, Vec<String>) {
-        const MAX_CONTEXT_LINES: usize = 3;
-        let context_lines = context_lines.min(MAX_CONTEXT_LINES);
-
         // Try to read the file
         let content = match tokio::fs::read_to_string(file_path).await {
             Ok(c) => c,
@@ -196,35 +368,114 @@ impl SemanticSearcher {
         let start_idx = start_line.saturating_sub(1);
         let end_idx = end_line.min(total_lines);
 
-        // Extract context before
-        let context_before: Vec<String> = if start_idx > 0 {
-            let before_start = start_idx.saturating_sub(context_lines);
-            lines[before_start..start_idx]
-                .iter()
-                .map(|s| s.to_string())
-                .collect()
-        } else {
-            Vec::new()
+        // Resolve how far context extends on each side of the match
+        let symbol_range = match context_mode {
+            ContextMode::Symbol => Self::enclosing_symbol_range(
+                symbol_extractor,
+                file_path,
+                &content,
+                start_idx,
+                end_idx,
+            ),
+            ContextMode::Lines(_) | ContextMode::Block => None,
         };
-
-        // Extract context after
-        let context_after: Vec<String> = if end_idx < total_lines {
-            let after_end = (end_idx + context_lines).min(total_lines);
-            lines[end_idx..after_end]
-                .iter()
-                .map(|s| s.to_string())
-                .collect()
-        } else {
-            Vec::new()
+        let (before_start, after_end) = match symbol_range {
+            Some((symbol_start, symbol_end)) => (symbol_start, (symbol_end + 1).min(total_lines)),
+            None => {
+                let n = context_mode.lines();
+                (start_idx.saturating_sub(n), (end_idx + n).min(total_lines))
+            },
         };
 
+        // Context before the first line of the span and after its last line
+        let (context_before, _) =
+            extract_context(&lines, start_idx, start_idx.saturating_sub(before_start), 0);
+        let last_idx = end_idx.saturating_sub(1).max(start_idx);
+        let (_, context_after) =
+            extract_context(&lines, last_idx, 0, after_end.saturating_sub(last_idx + 1));
+
         (context_before, context_after)
     }
+
+    /// Convert a vector store hit into a search result, previewing the chunk if requested
+    fn to_search_result(
+        &self,
+        result: &SemanticSearchResult,
+        query: &SearchQuery,
+        context_before: Vec<String>,
+        context_after: Vec<String>,
+    ) -> SearchResult {
+        SearchResult {
+            file_path: PathBuf::from(&result.file_path),
+            repository: self.extract_repo_from_path(&result.file_path),
+            line_number: result.start_line,
+            end_line: result.end_line,
+            column: 0,
+            content: match query.chunk_preview_lines {
+                Some(lines) => Self::preview(&result.content, lines),
+                None => result.content.clone(),
+            },
+            context_before,
+            context_after,
+            score: result.score,
+            raw_score: result.score,
+            relative_path: None,
+            match_type: super::MatchType::Semantic,
+            source_mode: Some(super::SearchMode::Semantic),
+            body: None,
+            relevant_imports: Vec::new(),
+            snippet: None,
+            match_ranges: Vec::new(),
+            last_author: None,
+            last_commit: None,
+            enclosing_signature: None,
+        }
+    }
+
+    /// First `max_lines` lines of a chunk, with a marker noting how many were left out
+    fn preview(content: &str, max_lines: usize) -> String {
+        let total = content.lines().count();
+        if total <= max_lines {
+            return content.to_string();
+        }
+
+        let mut preview = content
+            .lines()
+            .take(max_lines)
+            .collect::<Vec<_>>()
+            .join("\n");
+        preview.push_str(&format!("\n... ({} more lines)", total - max_lines));
+        preview
+    }
+
+    /// Find the smallest symbol whose line range contains the 0-based `start_idx..end_idx`
+    /// span. Returns the symbol's 0-based (start, end) lines, both inclusive.
+    fn enclosing_symbol_range(
+        symbol_extractor: &SymbolExtractor,
+        file_path: &str,
+        content: &str,
+        start_idx: usize,
+        end_idx: usize,
+    ) -> Option<(usize, usize)> {
+        let path = std::path::Path::new(file_path);
+        let language = LanguageDetector::detect(path, Some(content));
+        let symbols = symbol_extractor
+            .extract_symbols(path, content, language)
+            .ok()?;
+
+        let last_idx = end_idx.saturating_sub(1).max(start_idx);
+        symbols
+            .iter()
+            .filter(|s| s.start_line <= start_idx && s.end_line >= last_idx)
+            .min_by_key(|s| s.end_line - s.start_line)
+            .map(|s| (s.start_line, s.end_line))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embedding::EmbeddingGenerator;
     use crate::storage::StorageBackend;
     use std::sync::Arc;
     use tempfile::{TempDir, tempdir};
@@ -240,10 +491,34 @@ mod tests {
             enable_semantic: true,
             languages: vec!["rust".to_string()],
             file_watch_debounce_ms: 500,
+            ..Default::default()
         });
         (config, temp_dir)
     }
 
+    #[tokio::test]
+    async fn test_failed_pipeline_reports_fallback() {
+        let (config, _temp_dir) = create_test_config();
+        let config = Arc::new(Config {
+            embedding_provider: crate::EmbeddingProvider::Remote {
+                url: "http://127.0.0.1:1".to_string(),
+                model: "small".to_string(),
+                api_key_env: Some("RUNE_TEST_UNSET_EMBEDDING_KEY".to_string()),
+            },
+            ..(*config).clone()
+        });
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+
+        let searcher = SemanticSearcher::new(config.clone(), storage)
+            .await
+            .unwrap();
+        let status = searcher.embedding_status();
+        assert!(!searcher.is_available());
+        assert!(!status.model_loaded);
+        assert!(status.fallback);
+        assert_eq!(status.dimension, config.embedding_dimension);
+    }
+
     #[tokio::test]
     async fn test_semantic_searcher_initialization_without_qdrant() {
         // When Qdrant is not available, the searcher should initialize but pipeline should be None
@@ -277,6 +552,7 @@ mod tests {
             enable_semantic: false, // Disable semantic to ensure no pipeline
             languages: vec![],
             file_watch_debounce_ms: 500,
+            ..Default::default()
         });
 
         let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
@@ -295,9 +571,13 @@ mod tests {
             file_patterns: None,
             limit: 10,
             offset: 0,
+            ..Default::default()
         };
 
-        let results = searcher.search(&query).await.unwrap();
+        let results = searcher
+            .search(&query, &SearchBudget::new(&query, &searcher.config))
+            .await
+            .unwrap();
         assert_eq!(
             results.len(),
             0,
@@ -319,9 +599,12 @@ mod tests {
         // We need to test synchronously, so we'll test the helper method directly
         // by creating a minimal searcher
         let searcher = SemanticSearcher {
-            _config: config,
-            _storage: storage,
+            config,
+            storage,
             pipeline: None,
+            reranker: None,
+            symbol_extractor: Arc::new(SymbolExtractor::new()),
+            embedding_status: EmbeddingStatus::default(),
         };
 
         assert_eq!(searcher.extract_repo_from_path("repo/path/file.rs"), "repo");
@@ -337,9 +620,12 @@ mod tests {
         let (config, _temp_dir) = create_test_config();
         let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
         let searcher = SemanticSearcher {
-            _config: config,
-            _storage: storage,
+            config,
+            storage,
             pipeline: None,
+            reranker: None,
+            symbol_extractor: Arc::new(SymbolExtractor::new()),
+            embedding_status: EmbeddingStatus::default(),
         };
 
         // Test exact match
@@ -350,8 +636,10 @@ mod tests {
         assert!(searcher.matches_patterns("src/lib.rs", &["src/*.rs".to_string()]));
         assert!(searcher.matches_patterns("deep/nested/file.rs", &["**/*.rs".to_string()]));
 
-        // Test partial match
-        assert!(searcher.matches_patterns("src/main.rs", &["main".to_string()]));
+        // Plain patterns match trailing path components; substrings need a glob
+        assert!(searcher.matches_patterns("src/main.rs", &["main.rs".to_string()]));
+        assert!(!searcher.matches_patterns("src/main.rs", &["main".to_string()]));
+        assert!(searcher.matches_patterns("src/main.rs", &["*main*".to_string()]));
 
         // Test no match
         assert!(!searcher.matches_patterns("test.py", &["*.rs".to_string()]));
@@ -372,10 +660,14 @@ mod tests {
             file_patterns: Some(vec!["*.rs".to_string()]),
             limit: 5,
             offset: 0,
+            ..Default::default()
         };
 
         // This should not panic even without pipeline
-        let results = searcher.search(&query).await.unwrap();
+        let results = searcher
+            .search(&query, &SearchBudget::new(&query, &searcher.config))
+            .await
+            .unwrap();
         assert_eq!(results.len(), 0);
     }
 
@@ -401,6 +693,7 @@ mod tests {
             enable_semantic: false,
             languages: vec![],
             file_watch_debounce_ms: 500,
+            ..Default::default()
         });
 
         let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
@@ -412,4 +705,206 @@ mod tests {
             .await
             .unwrap();
     }
+
+    #[tokio::test]
+    async fn test_chunk_preview_keeps_full_span() {
+        let (config, _temp_dir) = create_test_config();
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let searcher = SemanticSearcher {
+            config,
+            storage,
+            pipeline: None,
+            reranker: None,
+            symbol_extractor: Arc::new(SymbolExtractor::new()),
+            embedding_status: EmbeddingStatus::default(),
+        };
+
+        let chunk = SemanticSearchResult {
+            file_path: "/workspace/src/steps.rs".to_string(),
+            content: (1..=30)
+                .map(|i| format!("    let step_{i} = {i};"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            start_line: 11,
+            end_line: 40,
+            language: Some("rust".to_string()),
+            score: 0.8,
+        };
+
+        let query = SearchQuery {
+            query: "steps".to_string(),
+            chunk_preview_lines: Some(5),
+            ..Default::default()
+        };
+        let result = searcher.to_search_result(&chunk, &query, vec![], vec![]);
+        let lines: Vec<_> = result.content.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[4], "    let step_5 = 5;");
+        assert_eq!(lines[5], "... (25 more lines)");
+        assert_eq!(result.line_number, 11);
+        assert_eq!(result.end_line, 40);
+        assert_eq!(
+            result.source_mode,
+            Some(crate::search::SearchMode::Semantic)
+        );
+
+        // Without a preview limit the whole chunk is returned
+        let query = SearchQuery {
+            chunk_preview_lines: None,
+            ..query
+        };
+        let result = searcher.to_search_result(&chunk, &query, vec![], vec![]);
+        assert_eq!(result.content, chunk.content);
+    }
+
+    #[tokio::test]
+    async fn test_symbol_context_mode_covers_enclosing_function() {
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("lib.rs");
+        let source = "use std::fmt;\n\nfn handle(input: &str) -> usize {\n    let trimmed = input.trim();\n    let count = trimmed.len();\n    count * 2\n}\n\nfn other() {}\n";
+        std::fs::write(&file, source).unwrap();
+        let file_path = file.to_string_lossy();
+        let extractor = SymbolExtractor::new();
+
+        // Match on `let count = ...` (line 5) inside `handle` (lines 3-7)
+        let (before, after) =
+            SemanticSearcher::extract_context(&extractor, &file_path, 5, 5, ContextMode::Symbol)
+                .await;
+        assert_eq!(
+            before,
+            vec![
+                "fn handle(input: &str) -> usize {",
+                "    let trimmed = input.trim();"
+            ]
+        );
+        assert_eq!(after, vec!["    count * 2", "}"]);
+
+        // Line mode keeps the fixed window
+        let (before, after) =
+            SemanticSearcher::extract_context(&extractor, &file_path, 5, 5, ContextMode::Lines(1))
+                .await;
+        assert_eq!(before, vec!["    let trimmed = input.trim();"]);
+        assert_eq!(after, vec!["    count * 2"]);
+
+        // However many lines are asked for
+        let lines = |n| {
+            SemanticSearcher::extract_context(&extractor, &file_path, 5, 5, ContextMode::Lines(n))
+        };
+        assert_eq!(lines(0).await, (vec![], vec![]));
+        let (before, after) = lines(5).await;
+        assert_eq!(before.len(), 4);
+        assert_eq!(after.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_filtered_search_fills_limit() {
+        let (config, temp_dir) = create_test_config();
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let pipeline = Arc::new(EmbeddingPipeline::in_memory_stand_in(config.clone()));
+
+        // Chunks closest to the query are all filtered out by the file pattern
+        for i in 0..30 {
+            let path = temp_dir.path().join(format!("noise_{i}.rs"));
+            let content = format!("fn parse_config_{i}() {{\n    parse config file\n}}\n");
+            std::fs::write(&path, &content).unwrap();
+            pipeline
+                .process_file(&path.to_string_lossy(), &content)
+                .await
+                .unwrap();
+        }
+        for i in 0..5 {
+            let path = temp_dir.path().join(format!("keep_{i}.rs"));
+            let content = format!("fn render_widget_{i}() {{\n    draw pixels\n}}\n");
+            std::fs::write(&path, &content).unwrap();
+            pipeline
+                .process_file(&path.to_string_lossy(), &content)
+                .await
+                .unwrap();
+        }
+
+        let searcher = SemanticSearcher::with_pipeline(config, storage, pipeline);
+        let query = SearchQuery {
+            query: "parse config file".to_string(),
+            mode: super::super::SearchMode::Semantic,
+            file_patterns: Some(vec!["keep_*.rs".to_string()]),
+            limit: 5,
+            ..Default::default()
+        };
+
+        let results = searcher
+            .search(&query, &SearchBudget::new(&query, &searcher.config))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|r| {
+            r.file_path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("keep_"))
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_rerank_applies_to_filtered_candidates() {
+        let (config, temp_dir) = create_test_config();
+        let config = Arc::new(Config {
+            rerank_candidates: 3,
+            ..(*config).clone()
+        });
+        let storage = StorageBackend::new(&config.cache_dir).await.unwrap();
+        let pipeline = Arc::new(EmbeddingPipeline::in_memory_stand_in(config.clone()));
+        for (prefix, count) in [("noise", 30), ("keep", 5)] {
+            for i in 0..count {
+                let path = temp_dir.path().join(format!("{prefix}_{i}.rs"));
+                let content = format!("fn {prefix}_handler_{i}() {{\n    handle {i}\n}}\n");
+                std::fs::write(&path, &content).unwrap();
+                pipeline
+                    .process_file(&path.to_string_lossy(), &content)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        // A rerank model whose scores differ from the primary ones
+        let rerank_config = Arc::new(Config {
+            embedding_dimension: 16,
+            ..Default::default()
+        });
+        let rerank_generator = EmbeddingGenerator::fallback(rerank_config.clone());
+        let mut searcher = SemanticSearcher::with_pipeline(config, storage, pipeline);
+        searcher.reranker = Some(Arc::new(Reranker::with_generator(
+            EmbeddingGenerator::fallback(rerank_config),
+        )));
+
+        let query = SearchQuery {
+            query: "handle the request".to_string(),
+            mode: super::super::SearchMode::Semantic,
+            file_patterns: Some(vec!["keep_*.rs".to_string()]),
+            limit: 5,
+            ..Default::default()
+        };
+        let results = searcher
+            .search(&query, &SearchBudget::new(&query, &searcher.config))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 5);
+
+        // The top matching chunks carry rerank scores, however far down the primary
+        // ranking they were
+        let query_embedding = rerank_generator
+            .generate_embedding(&query.query)
+            .await
+            .unwrap();
+        for result in &results[..3] {
+            let embedding = rerank_generator
+                .generate_embedding(&result.content)
+                .await
+                .unwrap();
+            let expected: f32 = query_embedding
+                .iter()
+                .zip(&embedding)
+                .map(|(a, b)| a * b)
+                .sum();
+            assert!((result.score - expected).abs() < 1e-5, "{}", result.content);
+        }
+    }
 }
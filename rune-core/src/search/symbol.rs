@@ -1,18 +1,69 @@
 use anyhow::Result;
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tantivy::query::QueryParser;
 use tracing::debug;
 
-use super::{MatchType, SearchQuery, SearchResult};
-use crate::{Config, indexing::tantivy_indexer::TantivyIndexer, storage::StorageBackend};
+use super::{
+    MatchType, SearchBudget, SearchMode, SearchQuery, SearchResult, extract_context,
+    file_patterns::FilePatterns, relative_to_roots,
+};
+use crate::{
+    Config,
+    indexing::tantivy_indexer::{SearchResult as IndexedDocument, TantivyIndexer},
+    storage::StorageBackend,
+};
 
 #[derive(Clone)]
 pub struct SymbolSearcher {
-    _config: Arc<Config>,     // Kept for potential future use
+    config: Arc<Config>,
     _storage: StorageBackend, // Kept for potential future use
     tantivy_indexer: Arc<TantivyIndexer>,
 }
 
+/// Line matches from the text index (symbol or literal search) along with the files
+/// that hit the per-file match cap
+#[derive(Debug, Default)]
+pub struct SymbolMatches {
+    pub results: Vec<SearchResult>,
+    /// Files with more matching lines than `Config::max_matches_per_file`
+    pub truncated_files: Vec<PathBuf>,
+}
+
+/// Line matches within one file, as produced by a literal or regex scan
+#[derive(Debug)]
+pub struct FileMatches {
+    pub path: PathBuf,
+    pub results: Vec<SearchResult>,
+    /// The file had more matching lines than `Config::max_matches_per_file`
+    pub truncated: bool,
+}
+
+impl SymbolMatches {
+    /// Gather files' matches until there are `budget.candidate_limit` results
+    pub fn collect(files: impl IntoIterator<Item = FileMatches>, budget: &SearchBudget) -> Self {
+        let mut matches = SymbolMatches::default();
+        for file in files {
+            if !matches.push(file, budget) {
+                break;
+            }
+        }
+        matches
+    }
+
+    /// Add one file's matches, up to `budget.candidate_limit` results in total.
+    /// Returns false once the budget is used up.
+    fn push(&mut self, file: FileMatches, budget: &SearchBudget) -> bool {
+        if file.truncated {
+            self.truncated_files.push(file.path);
+        }
+        self.results.extend(file.results);
+        self.results.truncate(budget.candidate_limit);
+        self.results.len() < budget.candidate_limit
+    }
+}
+
 impl SymbolSearcher {
     pub async fn new(
         config: Arc<Config>,
@@ -20,34 +71,48 @@ impl SymbolSearcher {
         tantivy_indexer: Arc<TantivyIndexer>,
     ) -> Result<Self> {
         Ok(Self {
-            _config: config,
+            config,
             _storage: storage,
             tantivy_indexer,
         })
     }
 
-    pub async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+    pub async fn search(
+        &self,
+        query: &SearchQuery,
+        budget: &SearchBudget,
+    ) -> Result<SymbolMatches> {
         debug!("Performing symbol search for: {}", query.query);
 
         // Build Tantivy query specifically for symbols field
-        let query_parser = QueryParser::for_index(
-            self.tantivy_indexer.get_searcher().index(),
-            vec![self.tantivy_indexer.get_symbols_field()],
-        );
+        let mut fields = vec![self.tantivy_indexer.get_symbols_field()];
+        if query.include_references {
+            fields.push(self.tantivy_indexer.get_references_field());
+        }
+        let query_parser =
+            QueryParser::for_index(self.tantivy_indexer.get_searcher().index(), fields);
 
         // The query should match symbol names or types
         // For now, just search for the symbol name in the symbols field
         let search_query = query.query.clone();
 
-        let tantivy_query = query_parser.parse_query(&search_query)?;
+        let tantivy_query = self.tantivy_indexer.modified_after(
+            query_parser.parse_query(&search_query)?,
+            query.modified_since,
+        );
 
         // Search documents
         let docs = self
             .tantivy_indexer
-            .search_documents(tantivy_query.as_ref(), query.limit + query.offset)
+            .search_documents(tantivy_query.as_ref(), budget.fetch_limit)
             .await?;
 
-        let mut results = Vec::new();
+        let file_patterns = query
+            .file_patterns
+            .as_deref()
+            .map(FilePatterns::new)
+            .transpose()?;
+        let mut matches = SymbolMatches::default();
 
         for doc in docs {
             // Apply repository filter if specified
@@ -58,36 +123,108 @@ impl SymbolSearcher {
             }
 
             // Apply file pattern filter if specified
-            if let Some(patterns) = &query.file_patterns {
-                let file_name = doc.path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-                let matches_pattern = patterns.iter().any(|pattern| {
-                    if pattern.contains('*') {
-                        let pattern = pattern.replace("*", "");
-                        file_name.contains(&pattern)
-                    } else {
-                        file_name == pattern
-                    }
-                });
-
-                if !matches_pattern {
+            if let Some(patterns) = &file_patterns {
+                let relative =
+                    relative_to_roots(&self.config.workspace_roots, &doc.path).unwrap_or(&doc.path);
+                if !patterns.matches(relative) {
                     continue;
                 }
             }
 
             // Parse symbols from the content to find exact matches
-            let symbol_matches = self.find_symbol_matches(
+            let (mut symbol_matches, mut truncated) = self.find_symbol_matches(
                 &doc.path,
                 &doc.repository,
                 &doc.content,
                 &query.query,
                 doc.score,
+                query.context_mode.lines(),
             )?;
 
-            results.extend(symbol_matches);
+            if query.include_references && !truncated {
+                truncated = self.add_reference_matches(
+                    &doc,
+                    &query.query,
+                    query.context_mode.lines(),
+                    &mut symbol_matches,
+                );
+            }
+
+            let file = FileMatches {
+                path: doc.path.clone(),
+                results: symbol_matches,
+                truncated,
+            };
+            if !matches.push(file, budget) {
+                break;
+            }
         }
 
-        Ok(results)
+        Ok(matches)
+    }
+
+    /// Append usages of the queried names recorded for `doc`, skipping lines already
+    /// matched as definitions. Returns true if the per-file cap cut them short.
+    fn add_reference_matches(
+        &self,
+        doc: &IndexedDocument,
+        symbol_query: &str,
+        context_lines: usize,
+        results: &mut Vec<SearchResult>,
+    ) -> bool {
+        let names: Vec<&str> = symbol_query.split_whitespace().collect();
+        let lines: Vec<&str> = doc.content.lines().collect();
+        let definition_lines: HashSet<usize> = results.iter().map(|r| r.line_number).collect();
+        let mut reported = HashSet::new();
+
+        // Matching is case-insensitive, but when a line has both `settings` and
+        // `Settings`, report the column of the exact spelling
+        let exact = |name: &String| names.contains(&name.as_str());
+        let candidates = doc.references.iter().filter(|(_, name)| exact(name)).chain(
+            doc.references.iter().filter(|(_, name)| {
+                !exact(name) && names.iter().any(|n| n.eq_ignore_ascii_case(name))
+            }),
+        );
+
+        for (line_idx, name) in candidates {
+            if definition_lines.contains(&(line_idx + 1)) || !reported.insert(*line_idx) {
+                continue;
+            }
+            let Some(line) = lines.get(*line_idx) else {
+                continue;
+            };
+
+            if results.len() >= self.config.max_matches_per_file {
+                return true;
+            }
+
+            let (context_before, context_after) =
+                extract_context(&lines, *line_idx, context_lines, context_lines);
+            results.push(SearchResult {
+                file_path: doc.path.clone(),
+                repository: doc.repository.clone(),
+                line_number: line_idx + 1,
+                end_line: line_idx + 1,
+                column: line.find(name.as_str()).unwrap_or(0),
+                content: line.to_string(),
+                context_before,
+                context_after,
+                score: doc.score,
+                raw_score: doc.score,
+                relative_path: None,
+                match_type: MatchType::Reference,
+                source_mode: Some(SearchMode::Symbol),
+                body: None,
+                relevant_imports: Vec::new(),
+                snippet: None,
+                match_ranges: Vec::new(),
+                last_author: None,
+                last_commit: None,
+                enclosing_signature: None,
+            });
+        }
+
+        false
     }
 
     fn find_symbol_matches(
@@ -97,8 +234,10 @@ impl SymbolSearcher {
         content: &str,
         symbol_query: &str,
         score: f32,
-    ) -> Result<Vec<SearchResult>> {
+        context_lines: usize,
+    ) -> Result<(Vec<SearchResult>, bool)> {
         let mut results = Vec::new();
+        let max_matches = self.config.max_matches_per_file;
         let lines: Vec<&str> = content.lines().collect();
 
         // Simple heuristic: look for the symbol name in function/class/struct definitions
@@ -123,38 +262,41 @@ impl SymbolSearcher {
                 || (line_lower.contains("enum ") && line_lower.contains(&symbol_lower));
 
             if is_symbol_def {
+                if results.len() >= max_matches {
+                    return Ok((results, true));
+                }
+
                 // Find the column where the symbol name appears
                 let column = line_lower.find(&symbol_lower).unwrap_or(0);
 
-                // Get context lines (3 before, 3 after)
-                let context_before: Vec<String> = lines
-                    .iter()
-                    .skip(line_idx.saturating_sub(3))
-                    .take(line_idx.min(3))
-                    .map(|s| s.to_string())
-                    .collect();
-
-                let context_after: Vec<String> = lines
-                    .iter()
-                    .skip(line_idx + 1)
-                    .take(3)
-                    .map(|s| s.to_string())
-                    .collect();
+                let (context_before, context_after) =
+                    extract_context(&lines, line_idx, context_lines, context_lines);
 
                 results.push(SearchResult {
                     file_path: file_path.to_path_buf(),
                     repository: repository.to_string(),
                     line_number: line_idx + 1, // 1-indexed
+                    end_line: line_idx + 1,
                     column,
                     content: line.to_string(),
                     context_before,
                     context_after,
                     score,
+                    raw_score: score,
+                    relative_path: None,
                     match_type: MatchType::Symbol,
+                    source_mode: Some(SearchMode::Symbol),
+                    body: None,
+                    relevant_imports: Vec::new(),
+                    snippet: None,
+                    match_ranges: Vec::new(),
+                    last_author: None,
+                    last_commit: None,
+                    enclosing_signature: None,
                 });
             }
         }
 
-        Ok(results)
+        Ok((results, false))
     }
 }
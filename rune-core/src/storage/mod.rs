@@ -1,16 +1,90 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use bincode::{Decode, Encode};
 use parking_lot::RwLock;
-use rocksdb::{DB, Options};
+use rocksdb::{DB, Options, WriteBatch};
 use serde::{Deserialize, Serialize};
 
+use crate::RuneError;
+use crate::indexing::annotations::Annotation;
+
+/// Prefix for internal (non-file) keys. A NUL byte can never appear in a file path,
+/// so these keys can't collide with file metadata entries.
+const INTERNAL_KEY_PREFIX: &[u8] = b"\0rune:";
+
+/// Key holding the Tantivy/Qdrant commit coordination marker
+const COMMIT_MARKER_KEY: &[u8] = b"\0rune:commit_marker";
+
+/// Key holding the running total of `FileMetadata::symbol_count` across all files
+const SYMBOL_COUNT_KEY: &[u8] = b"\0rune:symbol_count";
+
+/// Prefix of the per-file keys holding when a file last appeared in search results
+const ACCESS_KEY_PREFIX: &[u8] = b"\0rune:accessed:";
+
+/// Prefix of the keys marking files evicted to stay under `Config::max_index_bytes`
+const EVICTED_KEY_PREFIX: &[u8] = b"\0rune:evicted:";
+
+/// Key holding the size cap files were last evicted to fit under
+const EVICTION_CAP_KEY: &[u8] = b"\0rune:eviction_cap";
+
+/// Prefix of the per-file keys holding a file's TODO/FIXME annotations
+const ANNOTATION_KEY_PREFIX: &[u8] = b"\0rune:annotations:";
+
+/// Prefix of the per-file keys holding the ids of a file's embedded chunks
+const CHUNK_IDS_KEY_PREFIX: &[u8] = b"\0rune:chunks:";
+
+/// Prefix of the keys holding serialized search responses, the result cache's L2 tier
+const RESULT_CACHE_KEY_PREFIX: &[u8] = b"\0rune:cache:";
+
+/// Lossless metadata key for `path`: its raw OS bytes on Unix, so distinct non-UTF-8
+/// paths never share a key. UTF-8 paths produce the same key as their string form.
+pub fn path_key(path: &Path) -> Vec<u8> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    }
+    #[cfg(not(unix))]
+    {
+        path.to_string_lossy().as_bytes().to_vec()
+    }
+}
+
+/// The path a `path_key` was made from
+pub fn key_path(key: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(key))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(key).into_owned())
+    }
+}
+
+/// How long cached directory sizes are served before a rescan
+const DIRECTORY_SIZES_TTL: Duration = Duration::from_secs(60);
+
 #[derive(Clone)]
 pub struct StorageBackend {
     db: Arc<RwLock<DB>>,
     cache_dir: PathBuf,
+    directory_sizes: Arc<RwLock<Option<DirectorySizes>>>,
+    size_scans: Arc<AtomicUsize>,
+}
+
+/// Result of the last directory size scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirectorySizes {
+    pub index_bytes: u64,
+    pub cache_bytes: u64,
+    scanned_at: Instant,
 }
 
 impl StorageBackend {
@@ -20,9 +94,6 @@ impl StorageBackend {
 
         let db_path = cache_dir.join("metadata.db");
 
-        // Try to recover from stale lock if necessary
-        Self::try_recover_lock(&db_path)?;
-
         // Open RocksDB
         let mut opts = Options::default();
         opts.create_if_missing(true);
@@ -30,11 +101,36 @@ impl StorageBackend {
         opts.set_use_fsync(false);
         opts.set_bytes_per_sync(1048576);
 
-        let db = DB::open(&opts, db_path)?;
+        // The OS drops RocksDB's lock when its holder exits, so failing to take it
+        // means another live instance has the database open
+        let db = DB::open(&opts, &db_path).map_err(|e| {
+            if Self::is_lock_error(&e) {
+                anyhow::Error::new(RuneError::StorageLocked {
+                    path: db_path.display().to_string(),
+                })
+            } else {
+                e.into()
+            }
+        })?;
+
+        Ok(Self {
+            db: Arc::new(RwLock::new(db)),
+            cache_dir: cache_dir.to_path_buf(),
+            directory_sizes: Arc::new(RwLock::new(None)),
+            size_scans: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Open an existing metadata database without write access, e.g. a prebuilt cache
+    /// on a read-only mount. Writes through this backend fail.
+    pub async fn open_read_only(cache_dir: &Path) -> Result<Self> {
+        let db = DB::open_for_read_only(&Options::default(), cache_dir.join("metadata.db"), false)?;
 
         Ok(Self {
             db: Arc::new(RwLock::new(db)),
             cache_dir: cache_dir.to_path_buf(),
+            directory_sizes: Arc::new(RwLock::new(None)),
+            size_scans: Arc::new(AtomicUsize::new(0)),
         })
     }
 
@@ -45,9 +141,9 @@ impl StorageBackend {
 
         for item in iter {
             if let Ok((key, _)) = item
-                && let Ok(path_str) = std::str::from_utf8(&key)
+                && !key.starts_with(INTERNAL_KEY_PREFIX)
             {
-                files.push(PathBuf::from(path_str));
+                files.push(key_path(&key));
             }
         }
 
@@ -58,16 +154,61 @@ impl StorageBackend {
     /// Uses iterator counting instead of collecting all files for better performance.
     pub async fn get_file_count(&self) -> Result<usize> {
         let db = self.db.read();
-        let count = db.iterator(rocksdb::IteratorMode::Start).count();
+        let count = db
+            .iterator(rocksdb::IteratorMode::Start)
+            .filter(|item| {
+                item.as_ref()
+                    .is_ok_and(|(key, _)| !key.starts_with(INTERNAL_KEY_PREFIX))
+            })
+            .count();
         Ok(count)
     }
 
-    /// Get an estimated symbol count.
-    /// This is an approximation based on average symbols per file (~20).
-    /// For exact counts, symbols would need to be stored in metadata.
+    /// Total symbols across all indexed files. Reads the running total kept up to date
+    /// by `store_file_metadata`/`delete_file_metadata`, recounting only if it's missing.
     pub async fn get_symbol_count(&self) -> Result<usize> {
-        let file_count = self.get_file_count().await?;
-        Ok(file_count * 20)
+        let db = self.db.read();
+        Ok(Self::symbol_total(&db)? as usize)
+    }
+
+    /// Rebuild the running symbol total by summing every file's metadata
+    pub async fn recompute_symbol_count(&self) -> Result<usize> {
+        let db = self.db.write();
+        let total = Self::sum_symbol_counts(&db);
+        db.put(SYMBOL_COUNT_KEY, total.to_le_bytes())?;
+
+        Ok(total as usize)
+    }
+
+    /// The stored running total, or a full recount if none has been stored yet
+    fn symbol_total(db: &DB) -> Result<u64> {
+        match db.get(SYMBOL_COUNT_KEY)? {
+            Some(value) => {
+                let bytes = value
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Corrupt symbol count entry"))?;
+                Ok(u64::from_le_bytes(bytes))
+            },
+            None => Ok(Self::sum_symbol_counts(db)),
+        }
+    }
+
+    fn sum_symbol_counts(db: &DB) -> u64 {
+        db.iterator(rocksdb::IteratorMode::Start)
+            .flatten()
+            .filter(|(key, _)| !key.starts_with(INTERNAL_KEY_PREFIX))
+            .filter_map(|(_, value)| FileMetadata::decode(&value).ok())
+            .map(|metadata| metadata.symbol_count as u64)
+            .sum()
+    }
+
+    /// Symbol count recorded for `key`, or 0 if it has no (readable) metadata
+    fn stored_symbol_count(db: &DB, key: &[u8]) -> Result<u64> {
+        Ok(db
+            .get(key)?
+            .and_then(|value| FileMetadata::decode(&value).ok())
+            .map_or(0, |metadata| metadata.symbol_count as u64))
     }
 
     pub async fn get_index_size(&self) -> Result<u64> {
@@ -83,39 +224,453 @@ impl StorageBackend {
         Ok(size)
     }
 
+    /// Index and cache directory sizes. Served from the last scan unless `refresh` is
+    /// set or the scan is older than `DIRECTORY_SIZES_TTL`.
+    pub async fn get_directory_sizes(&self, refresh: bool) -> Result<DirectorySizes> {
+        if !refresh
+            && let Some(sizes) = *self.directory_sizes.read()
+            && sizes.scanned_at.elapsed() < DIRECTORY_SIZES_TTL
+        {
+            return Ok(sizes);
+        }
+
+        self.size_scans.fetch_add(1, Ordering::Relaxed);
+        let sizes = DirectorySizes {
+            index_bytes: self.get_index_size().await?,
+            cache_bytes: self.get_cache_size().await?,
+            scanned_at: Instant::now(),
+        };
+        *self.directory_sizes.write() = Some(sizes);
+
+        Ok(sizes)
+    }
+
+    /// Number of directory size scans run by `get_directory_sizes`
+    pub fn size_scan_count(&self) -> usize {
+        self.size_scans.load(Ordering::Relaxed)
+    }
+
     pub async fn store_file_metadata(
         &self,
         file_path: &Path,
         metadata: FileMetadata,
     ) -> Result<()> {
-        let key = file_path.to_string_lossy().as_bytes().to_vec();
+        let key = path_key(file_path);
         let config = bincode::config::standard();
+        // bincode can't encode a non-UTF-8 path; the key keeps the exact one and
+        // replaces this display form when the metadata is read back
+        let mut metadata = metadata;
+        if metadata.path.to_str().is_none() {
+            metadata.path = PathBuf::from(metadata.path.to_string_lossy().into_owned());
+        }
         let value = bincode::encode_to_vec(&metadata, config)?;
 
         let db = self.db.write();
-        db.put(key, value)?;
+        let total = Self::symbol_total(&db)?.saturating_sub(Self::stored_symbol_count(&db, &key)?)
+            + metadata.symbol_count as u64;
+
+        let mut batch = WriteBatch::default();
+        batch.put(key, value);
+        batch.put(SYMBOL_COUNT_KEY, total.to_le_bytes());
+        db.write(batch)?;
 
         Ok(())
     }
 
     pub async fn delete_file_metadata(&self, file_path: &Path) -> Result<()> {
-        let key = file_path.to_string_lossy().as_bytes().to_vec();
+        let key = path_key(file_path);
+
+        let db = self.db.write();
+        let total = Self::symbol_total(&db)?.saturating_sub(Self::stored_symbol_count(&db, &key)?);
+
+        let mut batch = WriteBatch::default();
+        batch.delete(Self::access_key(file_path));
+        batch.delete(Self::annotation_key(file_path));
+        batch.delete(Self::chunk_ids_key(file_path));
+        batch.delete(key);
+        batch.put(SYMBOL_COUNT_KEY, total.to_le_bytes());
+        db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Delete the metadata of every path in `paths` in one write, like repeated
+    /// `delete_file_metadata` calls
+    pub async fn delete_file_metadata_batch(&self, paths: &[PathBuf]) -> Result<()> {
+        // A repeated path must only come off the symbol total once
+        let mut paths: Vec<(Vec<u8>, &PathBuf)> =
+            paths.iter().map(|path| (path_key(path), path)).collect();
+        paths.sort();
+        paths.dedup_by(|a, b| a.0 == b.0);
+
+        let db = self.db.write();
+        let mut total = Self::symbol_total(&db)?;
+        let mut batch = WriteBatch::default();
+        for (key, path) in paths {
+            total = total.saturating_sub(Self::stored_symbol_count(&db, &key)?);
+            batch.delete(Self::access_key(path));
+            batch.delete(Self::annotation_key(path));
+            batch.delete(Self::chunk_ids_key(path));
+            batch.delete(key);
+        }
+        batch.put(SYMBOL_COUNT_KEY, total.to_le_bytes());
+        db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Metadata of every indexed file
+    pub async fn list_file_metadata(&self) -> Result<Vec<FileMetadata>> {
+        let db = self.db.read();
+
+        Ok(db
+            .iterator(rocksdb::IteratorMode::Start)
+            .flatten()
+            .filter(|(key, _)| !key.starts_with(INTERNAL_KEY_PREFIX))
+            .filter_map(|(key, value)| {
+                let mut metadata = FileMetadata::decode(&value).ok()?;
+                metadata.path = key_path(&key);
+                Some(metadata)
+            })
+            .collect())
+    }
+
+    /// Record that `paths` were just returned by a search
+    pub async fn record_access(&self, paths: &[PathBuf]) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let mut batch = WriteBatch::default();
+        for path in paths {
+            batch.put(Self::access_key(path), now.to_le_bytes());
+        }
+
+        let db = self.db.write();
+        db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// When `file_path` last appeared in search results (epoch milliseconds), if ever
+    pub async fn get_last_accessed(&self, file_path: &Path) -> Result<Option<u64>> {
+        let db = self.db.read();
+        match db.get(Self::access_key(file_path))? {
+            Some(value) => {
+                let bytes = value
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Corrupt access time entry"))?;
+                Ok(Some(u64::from_le_bytes(bytes)))
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Remember that `paths` were evicted from the index to fit under `max_bytes`, so
+    /// indexing passes skip them
+    pub async fn mark_evicted(&self, paths: &[PathBuf], max_bytes: u64) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for path in paths {
+            batch.put(Self::evicted_key(path), []);
+        }
+        batch.put(EVICTION_CAP_KEY, max_bytes.to_le_bytes());
+
+        let db = self.db.write();
+        db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Files evicted to stay under the index size cap
+    pub async fn list_evicted(&self) -> Result<HashSet<PathBuf>> {
+        let db = self.db.read();
+        let from = rocksdb::IteratorMode::From(EVICTED_KEY_PREFIX, rocksdb::Direction::Forward);
+
+        Ok(db
+            .iterator(from)
+            .flatten()
+            .map_while(|(key, _)| key.strip_prefix(EVICTED_KEY_PREFIX).map(key_path))
+            .collect())
+    }
+
+    /// Forget that `file_path` was evicted, so indexing passes pick it up again
+    pub async fn unmark_evicted(&self, file_path: &Path) -> Result<()> {
+        let db = self.db.write();
+        db.delete(Self::evicted_key(file_path))?;
+
+        Ok(())
+    }
+
+    /// The cap passed to the latest `mark_evicted`, if any files are marked
+    pub async fn get_eviction_cap(&self) -> Result<Option<u64>> {
+        let db = self.db.read();
+        match db.get(EVICTION_CAP_KEY)? {
+            Some(value) => {
+                let bytes = value
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Corrupt eviction cap entry"))?;
+                Ok(Some(u64::from_le_bytes(bytes)))
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Forget every evicted file along with the cap they were evicted under
+    pub async fn clear_evicted(&self) -> Result<()> {
+        let db = self.db.write();
+        let from = rocksdb::IteratorMode::From(EVICTED_KEY_PREFIX, rocksdb::Direction::Forward);
+
+        let mut batch = WriteBatch::default();
+        for (key, _) in db.iterator(from).flatten() {
+            if !key.starts_with(EVICTED_KEY_PREFIX) {
+                break;
+            }
+            batch.delete(key);
+        }
+        batch.delete(EVICTION_CAP_KEY);
+        db.write(batch)?;
+
+        Ok(())
+    }
+
+    fn evicted_key(file_path: &Path) -> Vec<u8> {
+        let mut key = EVICTED_KEY_PREFIX.to_vec();
+        key.extend_from_slice(&path_key(file_path));
+        key
+    }
+
+    fn access_key(file_path: &Path) -> Vec<u8> {
+        let mut key = ACCESS_KEY_PREFIX.to_vec();
+        key.extend_from_slice(&path_key(file_path));
+        key
+    }
 
+    /// Replace the annotations recorded for `file_path`
+    pub async fn store_annotations(
+        &self,
+        file_path: &Path,
+        annotations: &[Annotation],
+    ) -> Result<()> {
+        let key = Self::annotation_key(file_path);
+        let db = self.db.write();
+        if annotations.is_empty() {
+            db.delete(key)?;
+            return Ok(());
+        }
+
+        // As with metadata, the key keeps the exact path for non-UTF-8 names
+        let annotations: Vec<Annotation> = annotations
+            .iter()
+            .cloned()
+            .map(|mut annotation| {
+                annotation.file_path = PathBuf::from(file_path.to_string_lossy().into_owned());
+                annotation
+            })
+            .collect();
+        db.put(
+            key,
+            bincode::encode_to_vec(&annotations, bincode::config::standard())?,
+        )?;
+
+        Ok(())
+    }
+
+    /// Annotations across all files, ordered by path then line
+    pub async fn list_annotations(&self) -> Result<Vec<Annotation>> {
+        let config = bincode::config::standard();
+        let db = self.db.read();
+        let mut annotations = Vec::new();
+
+        let from = rocksdb::IteratorMode::From(ANNOTATION_KEY_PREFIX, rocksdb::Direction::Forward);
+        for (key, value) in db.iterator(from).flatten() {
+            let Some(path_bytes) = key.strip_prefix(ANNOTATION_KEY_PREFIX) else {
+                break;
+            };
+            let file_path = key_path(path_bytes);
+            let (file_annotations, _) =
+                bincode::decode_from_slice::<Vec<Annotation>, _>(&value, config)?;
+            annotations.extend(file_annotations.into_iter().map(|mut annotation| {
+                annotation.file_path = file_path.clone();
+                annotation
+            }));
+        }
+
+        Ok(annotations)
+    }
+
+    fn annotation_key(file_path: &Path) -> Vec<u8> {
+        let mut key = ANNOTATION_KEY_PREFIX.to_vec();
+        key.extend_from_slice(&path_key(file_path));
+        key
+    }
+
+    /// Replace the ids of the chunks embedded for `file_path`
+    pub async fn store_chunk_ids(&self, file_path: &Path, ids: &[String]) -> Result<()> {
+        let key = Self::chunk_ids_key(file_path);
+        let db = self.db.write();
+        if ids.is_empty() {
+            db.delete(key)?;
+        } else {
+            db.put(
+                key,
+                bincode::encode_to_vec(ids, bincode::config::standard())?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Ids of the chunks embedded for `file_path`, empty if none were recorded
+    pub async fn get_chunk_ids(&self, file_path: &Path) -> Result<Vec<String>> {
+        let db = self.db.read();
+        let Some(value) = db.get(Self::chunk_ids_key(file_path))? else {
+            return Ok(Vec::new());
+        };
+        let (ids, _) = bincode::decode_from_slice(&value, bincode::config::standard())?;
+        Ok(ids)
+    }
+
+    /// Forget the embedded chunk ids of every file
+    pub async fn clear_chunk_ids(&self) -> Result<()> {
+        let db = self.db.write();
+        let mut batch = WriteBatch::default();
+        let from = rocksdb::IteratorMode::From(CHUNK_IDS_KEY_PREFIX, rocksdb::Direction::Forward);
+        for (key, _) in db.iterator(from).flatten() {
+            if !key.starts_with(CHUNK_IDS_KEY_PREFIX) {
+                break;
+            }
+            batch.delete(key);
+        }
+        db.write(batch)?;
+        Ok(())
+    }
+
+    fn chunk_ids_key(file_path: &Path) -> Vec<u8> {
+        let mut key = CHUNK_IDS_KEY_PREFIX.to_vec();
+        key.extend_from_slice(&path_key(file_path));
+        key
+    }
+
+    /// Serialized search response stored under a result cache key, and when it was
+    /// stored (epoch milliseconds)
+    pub async fn get_cached_response(&self, key: u64) -> Result<Option<(u64, Vec<u8>)>> {
+        let db = self.db.read();
+        let Some(value) = db.get(Self::result_cache_key(key))? else {
+            return Ok(None);
+        };
+        let (stored_at, response) = Self::split_cached_response(&value)?;
+        Ok(Some((stored_at, response.to_vec())))
+    }
+
+    pub async fn store_cached_response(
+        &self,
+        key: u64,
+        stored_at: u64,
+        value: &[u8],
+    ) -> Result<()> {
+        let mut bytes = stored_at.to_le_bytes().to_vec();
+        bytes.extend_from_slice(value);
+
+        let db = self.db.write();
+        db.put(Self::result_cache_key(key), bytes)?;
+        Ok(())
+    }
+
+    /// Remove stored search responses older than `cutoff` (epoch milliseconds),
+    /// returning how many were removed
+    pub async fn prune_cached_responses(&self, cutoff: u64) -> Result<usize> {
         let db = self.db.write();
-        db.delete(key)?;
+        let mut batch = WriteBatch::default();
+        let from =
+            rocksdb::IteratorMode::From(RESULT_CACHE_KEY_PREFIX, rocksdb::Direction::Forward);
+        for (key, value) in db.iterator(from).flatten() {
+            if !key.starts_with(RESULT_CACHE_KEY_PREFIX) {
+                break;
+            }
+            if Self::split_cached_response(&value)
+                .ok()
+                .is_none_or(|(stored_at, _)| stored_at < cutoff)
+            {
+                batch.delete(key);
+            }
+        }
 
+        let removed = batch.len();
+        db.write(batch)?;
+        Ok(removed)
+    }
+
+    pub async fn delete_cached_response(&self, key: u64) -> Result<()> {
+        let db = self.db.write();
+        db.delete(Self::result_cache_key(key))?;
         Ok(())
     }
 
+    /// Remove every stored search response, returning how many there were
+    pub async fn clear_cached_responses(&self) -> Result<usize> {
+        let db = self.db.write();
+        let mut batch = WriteBatch::default();
+        let from =
+            rocksdb::IteratorMode::From(RESULT_CACHE_KEY_PREFIX, rocksdb::Direction::Forward);
+        for (key, _) in db.iterator(from).flatten() {
+            if !key.starts_with(RESULT_CACHE_KEY_PREFIX) {
+                break;
+            }
+            batch.delete(key);
+        }
+
+        let removed = batch.len();
+        db.write(batch)?;
+        Ok(removed)
+    }
+
+    fn split_cached_response(value: &[u8]) -> Result<(u64, &[u8])> {
+        let (stored_at, response) = value
+            .split_first_chunk::<8>()
+            .ok_or_else(|| anyhow::anyhow!("Corrupt result cache entry"))?;
+        Ok((u64::from_le_bytes(*stored_at), response))
+    }
+
+    fn result_cache_key(key: u64) -> Vec<u8> {
+        let mut bytes = RESULT_CACHE_KEY_PREFIX.to_vec();
+        bytes.extend_from_slice(&key.to_be_bytes());
+        bytes
+    }
+
     pub async fn get_file_metadata(&self, file_path: &Path) -> Result<Option<FileMetadata>> {
-        let key = file_path.to_string_lossy().as_bytes().to_vec();
+        let db = self.db.read();
+        match db.get(path_key(file_path))? {
+            Some(value) => {
+                let mut metadata = FileMetadata::decode(&value)?;
+                metadata.path = file_path.to_path_buf();
+                Ok(Some(metadata))
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Store the Tantivy/Qdrant commit coordination marker
+    pub async fn store_commit_marker(&self, marker: &CommitMarker) -> Result<()> {
+        let config = bincode::config::standard();
+        let value = bincode::encode_to_vec(StoredCommitMarker::from(marker), config)?;
+
+        let db = self.db.write();
+        db.put(COMMIT_MARKER_KEY, value)?;
+
+        Ok(())
+    }
 
+    /// Get the commit coordination marker, if one has been recorded
+    pub async fn get_commit_marker(&self) -> Result<Option<CommitMarker>> {
         let db = self.db.read();
-        match db.get(key)? {
+        match db.get(COMMIT_MARKER_KEY)? {
             Some(value) => {
                 let config = bincode::config::standard();
-                let (metadata, _) = bincode::decode_from_slice(&value, config)?;
-                Ok(Some(metadata))
+                let (marker, _): (StoredCommitMarker, _) =
+                    bincode::decode_from_slice(&value, config)?;
+                Ok(Some(marker.into()))
             },
             None => Ok(None),
         }
@@ -145,30 +700,10 @@ impl StorageBackend {
         Ok(total_size)
     }
 
-    /// Try to recover from a stale lock file
-    pub fn try_recover_lock(db_path: &Path) -> Result<()> {
-        use tracing::{debug, warn};
-
-        let lock_path = db_path.join("LOCK");
-
-        if lock_path.exists() {
-            // Check if we can open the database - if another process has it, this will fail
-            match DB::open_for_read_only(&Options::default(), db_path, false) {
-                Ok(_) => {
-                    // Database is not actually locked, remove stale LOCK file
-                    warn!("Removing stale RocksDB LOCK file at {:?}", lock_path);
-                    std::fs::remove_file(&lock_path)?;
-                    Ok(())
-                },
-                Err(_) => {
-                    // Database is genuinely locked by another process
-                    debug!("RocksDB is locked by another process");
-                    Ok(())
-                },
-            }
-        } else {
-            Ok(())
-        }
+    /// Whether `DB::open` failed because another handle holds the database's LOCK file
+    fn is_lock_error(error: &rocksdb::Error) -> bool {
+        let message = error.to_string();
+        message.starts_with("IO error") && message.contains("lock")
     }
 }
 
@@ -189,4 +724,296 @@ pub struct FileMetadata {
     pub language: String,
     pub hash: String,
     pub indexed_at: u64,
+    /// Number of symbols extracted from the file
+    pub symbol_count: usize,
+}
+
+/// `FileMetadata` as stored before `symbol_count` was added
+#[derive(Encode, Decode)]
+struct FileMetadataV0 {
+    path: PathBuf,
+    size: u64,
+    modified: u64,
+    language: String,
+    hash: String,
+    indexed_at: u64,
+}
+
+impl FileMetadata {
+    /// Decode a stored record. Records in an older layout are read as such, with the
+    /// fields they lack left empty; their running counts catch up on reindex.
+    fn decode(value: &[u8]) -> Result<Self> {
+        let config = bincode::config::standard();
+        let error = match bincode::decode_from_slice::<Self, _>(value, config) {
+            Ok((metadata, _)) => return Ok(metadata),
+            Err(e) => e,
+        };
+
+        match bincode::decode_from_slice::<FileMetadataV0, _>(value, config) {
+            Ok((old, read)) if read == value.len() => Ok(Self {
+                path: old.path,
+                size: old.size,
+                modified: old.modified,
+                language: old.language,
+                hash: old.hash,
+                indexed_at: old.indexed_at,
+                symbol_count: 0,
+            }),
+            _ => Err(error.into()),
+        }
+    }
+}
+
+/// Records how far the text (Tantivy) and vector (Qdrant) indexes have been flushed.
+///
+/// A coordinated flush bumps `tantivy_generation` after the Tantivy commit and
+/// `vector_generation` after the pending Qdrant upserts land. If the process dies in
+/// between, the generations differ and `pending_files` lists the files whose vectors
+/// may be missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitMarker {
+    pub tantivy_generation: u64,
+    pub vector_generation: u64,
+    pub pending_files: Vec<PathBuf>,
+    pub updated_at: u64,
+}
+
+/// `CommitMarker` as stored, with paths as `path_key` bytes since bincode can't
+/// encode a non-UTF-8 path. Encodes the same as a marker whose paths are all UTF-8.
+#[derive(Encode, Decode)]
+struct StoredCommitMarker {
+    tantivy_generation: u64,
+    vector_generation: u64,
+    pending_files: Vec<Vec<u8>>,
+    updated_at: u64,
+}
+
+impl From<&CommitMarker> for StoredCommitMarker {
+    fn from(marker: &CommitMarker) -> Self {
+        Self {
+            tantivy_generation: marker.tantivy_generation,
+            vector_generation: marker.vector_generation,
+            pending_files: marker.pending_files.iter().map(|p| path_key(p)).collect(),
+            updated_at: marker.updated_at,
+        }
+    }
+}
+
+impl From<StoredCommitMarker> for CommitMarker {
+    fn from(stored: StoredCommitMarker) -> Self {
+        Self {
+            tantivy_generation: stored.tantivy_generation,
+            vector_generation: stored.vector_generation,
+            pending_files: stored.pending_files.iter().map(|k| key_path(k)).collect(),
+            updated_at: stored.updated_at,
+        }
+    }
+}
+
+impl CommitMarker {
+    /// Both indexes have been flushed up to the same generation
+    pub fn is_consistent(&self) -> bool {
+        self.tantivy_generation == self.vector_generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn metadata(path: &str, symbol_count: usize) -> FileMetadata {
+        FileMetadata {
+            path: PathBuf::from(path),
+            size: 100,
+            modified: 1_000,
+            language: "rust".to_string(),
+            hash: format!("{path}-{symbol_count}"),
+            indexed_at: 1_000,
+            symbol_count,
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_non_utf8_paths_do_not_collide() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = tempdir().unwrap();
+        let storage = StorageBackend::new(temp_dir.path()).await.unwrap();
+
+        // Both render as "src/caf\u{FFFD}.rs" when converted lossily
+        let a = PathBuf::from(std::ffi::OsStr::from_bytes(b"src/caf\xe9.rs"));
+        let b = PathBuf::from(std::ffi::OsStr::from_bytes(b"src/caf\xff.rs"));
+        assert_eq!(a.to_string_lossy(), b.to_string_lossy());
+
+        let mut metadata_a = metadata("a", 3);
+        metadata_a.path = a.clone();
+        let mut metadata_b = metadata("b", 4);
+        metadata_b.path = b.clone();
+        storage.store_file_metadata(&a, metadata_a).await.unwrap();
+        storage.store_file_metadata(&b, metadata_b).await.unwrap();
+
+        let stored_a = storage.get_file_metadata(&a).await.unwrap().unwrap();
+        let stored_b = storage.get_file_metadata(&b).await.unwrap().unwrap();
+        assert_eq!((stored_a.path, stored_a.symbol_count), (a.clone(), 3));
+        assert_eq!((stored_b.path, stored_b.symbol_count), (b.clone(), 4));
+        assert_eq!(storage.get_symbol_count().await.unwrap(), 7);
+
+        let mut files = storage.list_files().await.unwrap();
+        files.sort();
+        assert_eq!(files, vec![a.clone(), b.clone()]);
+
+        storage.delete_file_metadata(&a).await.unwrap();
+        assert!(storage.get_file_metadata(&a).await.unwrap().is_none());
+        let listed: Vec<PathBuf> = storage
+            .list_file_metadata()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.path)
+            .collect();
+        assert_eq!(listed, vec![b]);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_from_before_symbol_counts() {
+        let temp_dir = tempdir().unwrap();
+        let storage = StorageBackend::new(temp_dir.path()).await.unwrap();
+        let path = Path::new("old.rs");
+        let old = FileMetadataV0 {
+            path: path.to_path_buf(),
+            size: 100,
+            modified: 1_000,
+            language: "rust".to_string(),
+            hash: "abc".to_string(),
+            indexed_at: 1_000,
+        };
+        let value = bincode::encode_to_vec(&old, bincode::config::standard()).unwrap();
+        storage.db.write().put(path_key(path), value).unwrap();
+
+        let stored = storage.get_file_metadata(path).await.unwrap().unwrap();
+        assert_eq!((stored.hash.as_str(), stored.symbol_count), ("abc", 0));
+        assert_eq!(storage.list_file_metadata().await.unwrap().len(), 1);
+        assert_eq!(storage.recompute_symbol_count().await.unwrap(), 0);
+
+        // Rewriting it moves it to the current layout
+        storage
+            .store_file_metadata(path, metadata("old.rs", 3))
+            .await
+            .unwrap();
+        assert_eq!(storage.get_symbol_count().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_running_symbol_count() {
+        let temp_dir = tempdir().unwrap();
+        let storage = StorageBackend::new(temp_dir.path()).await.unwrap();
+        assert_eq!(storage.get_symbol_count().await.unwrap(), 0);
+
+        let a = Path::new("a.rs");
+        let b = Path::new("b.rs");
+        storage
+            .store_file_metadata(a, metadata("a.rs", 5))
+            .await
+            .unwrap();
+        storage
+            .store_file_metadata(b, metadata("b.rs", 7))
+            .await
+            .unwrap();
+        assert_eq!(storage.get_symbol_count().await.unwrap(), 12);
+
+        // Re-storing a file replaces its old count rather than adding to it
+        storage
+            .store_file_metadata(a, metadata("a.rs", 2))
+            .await
+            .unwrap();
+        assert_eq!(storage.get_symbol_count().await.unwrap(), 9);
+
+        storage.delete_file_metadata(b).await.unwrap();
+        assert_eq!(storage.get_symbol_count().await.unwrap(), 2);
+
+        // Deleting an unknown file leaves the total alone
+        storage.delete_file_metadata(b).await.unwrap();
+        assert_eq!(storage.get_symbol_count().await.unwrap(), 2);
+
+        assert_eq!(storage.recompute_symbol_count().await.unwrap(), 2);
+        assert_eq!(storage.get_file_count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_file_metadata_batch() {
+        let temp_dir = tempdir().unwrap();
+        let storage = StorageBackend::new(temp_dir.path()).await.unwrap();
+
+        let paths: Vec<PathBuf> = (0..50)
+            .map(|i| PathBuf::from(format!("src/file_{i}.rs")))
+            .collect();
+        for (i, path) in paths.iter().enumerate() {
+            let name = path.to_string_lossy();
+            storage
+                .store_file_metadata(path, metadata(&name, i + 1))
+                .await
+                .unwrap();
+            storage
+                .record_access(std::slice::from_ref(path))
+                .await
+                .unwrap();
+        }
+        assert_eq!(storage.get_symbol_count().await.unwrap(), 1275);
+
+        // Repeats and unknown paths don't throw off the total
+        let mut doomed = paths[..40].to_vec();
+        doomed.push(paths[0].clone());
+        doomed.push(PathBuf::from("src/never_indexed.rs"));
+        storage.delete_file_metadata_batch(&doomed).await.unwrap();
+
+        for path in &paths[..40] {
+            assert!(storage.get_file_metadata(path).await.unwrap().is_none());
+            assert!(storage.get_last_accessed(path).await.unwrap().is_none());
+        }
+        assert_eq!(storage.get_file_count().await.unwrap(), 10);
+        let remaining: usize = (41..=50).sum();
+        assert_eq!(storage.get_symbol_count().await.unwrap(), remaining);
+        assert_eq!(storage.recompute_symbol_count().await.unwrap(), remaining);
+    }
+
+    #[tokio::test]
+    async fn test_evicted_paths_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let storage = StorageBackend::new(temp_dir.path()).await.unwrap();
+
+        let kept = PathBuf::from("src/kept.rs");
+        storage
+            .store_file_metadata(&kept, metadata("src/kept.rs", 1))
+            .await
+            .unwrap();
+        assert!(storage.list_evicted().await.unwrap().is_empty());
+        storage
+            .store_commit_marker(&CommitMarker {
+                tantivy_generation: 1,
+                vector_generation: 1,
+                pending_files: Vec::new(),
+                updated_at: 0,
+            })
+            .await
+            .unwrap();
+
+        let evicted = vec![PathBuf::from("src/a.rs"), PathBuf::from("src/b.rs")];
+        storage.mark_evicted(&evicted, 4096).await.unwrap();
+
+        let listed = storage.list_evicted().await.unwrap();
+        assert_eq!(listed, evicted.iter().cloned().collect());
+        assert_eq!(storage.get_eviction_cap().await.unwrap(), Some(4096));
+        assert_eq!(storage.list_files().await.unwrap(), vec![kept.clone()]);
+
+        storage.unmark_evicted(&evicted[0]).await.unwrap();
+        let listed = storage.list_evicted().await.unwrap();
+        assert_eq!(listed, HashSet::from([evicted[1].clone()]));
+
+        storage.clear_evicted().await.unwrap();
+        assert!(storage.list_evicted().await.unwrap().is_empty());
+        assert_eq!(storage.get_eviction_cap().await.unwrap(), None);
+        assert_eq!(storage.list_files().await.unwrap(), vec![kept]);
+    }
 }
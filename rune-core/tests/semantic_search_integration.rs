@@ -264,6 +264,7 @@ async fn test_semantic_search_with_real_qdrant() {
             "go".to_string(),
         ],
         file_watch_debounce_ms: 500,
+        ..Default::default()
     };
 
     // Set environment variable
@@ -287,6 +288,7 @@ async fn test_semantic_search_with_real_qdrant() {
         file_patterns: None,
         limit: 5,
         offset: 0,
+        ..Default::default()
     };
 
     let cache_results = engine.search().search(cache_query).await.unwrap();
@@ -310,6 +312,7 @@ async fn test_semantic_search_with_real_qdrant() {
         file_patterns: None,
         limit: 5,
         offset: 0,
+        ..Default::default()
     };
 
     let auth_results = engine.search().search(auth_query).await.unwrap();
@@ -333,6 +336,7 @@ async fn test_semantic_search_with_real_qdrant() {
         file_patterns: None,
         limit: 5,
         offset: 0,
+        ..Default::default()
     };
 
     let db_results = engine.search().search(db_query).await.unwrap();
@@ -349,6 +353,7 @@ async fn test_semantic_search_with_real_qdrant() {
         file_patterns: None,
         limit: 5,
         offset: 0,
+        ..Default::default()
     };
 
     let http_results = engine.search().search(http_query).await.unwrap();
@@ -365,6 +370,7 @@ async fn test_semantic_search_with_real_qdrant() {
         file_patterns: None,
         limit: 10,
         offset: 0,
+        ..Default::default()
     };
 
     let general_results = engine.search().search(general_query).await.unwrap();
@@ -381,6 +387,7 @@ async fn test_semantic_search_with_real_qdrant() {
         file_patterns: Some(vec!["*.rs".to_string()]),
         limit: 5,
         offset: 0,
+        ..Default::default()
     };
 
     let rust_results = engine.search().search(rust_only_query).await.unwrap();
@@ -415,6 +422,7 @@ async fn test_semantic_search_without_qdrant() {
         enable_semantic: false, // Disable semantic to avoid Qdrant
         languages: vec!["rust".to_string()],
         file_watch_debounce_ms: 500,
+        ..Default::default()
     };
 
     // Also set environment to disable semantic and use bad URL
@@ -435,6 +443,7 @@ async fn test_semantic_search_without_qdrant() {
         file_patterns: None,
         limit: 5,
         offset: 0,
+        ..Default::default()
     };
 
     let results = engine.search().search(query).await.unwrap();
@@ -477,6 +486,7 @@ async fn test_semantic_search_basic() {
             "go".to_string(),
         ],
         file_watch_debounce_ms: 500,
+        ..Default::default()
     };
 
     unsafe {
@@ -499,6 +509,7 @@ async fn test_semantic_search_basic() {
         file_patterns: None,
         limit: 10,
         offset: 0,
+        ..Default::default()
     };
 
     let semantic_results = engine.search().search(semantic_query).await.unwrap();
@@ -548,6 +559,7 @@ async fn test_semantic_search_pagination() {
         enable_semantic: true,
         languages: vec!["rust".to_string()],
         file_watch_debounce_ms: 500,
+        ..Default::default()
     };
 
     unsafe {
@@ -568,6 +580,7 @@ async fn test_semantic_search_pagination() {
         file_patterns: None,
         limit: 5,
         offset: 0,
+        ..Default::default()
     };
 
     let page1_results = engine.search().search(page1_query).await.unwrap();
@@ -581,6 +594,7 @@ async fn test_semantic_search_pagination() {
         file_patterns: None,
         limit: 5,
         offset: 5,
+        ..Default::default()
     };
 
     let page2_results = engine.search().search(page2_query).await.unwrap();